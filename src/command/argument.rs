@@ -0,0 +1,370 @@
+use std::sync::Arc;
+
+use rand::seq::SliceRandom;
+use rust_mc_proto::{DataReader, DataWriter, Packet};
+
+use crate::{
+	ServerError,
+	data::Position,
+	player::context::ClientContext,
+	protocol::codec::{Decode, Encode},
+};
+
+/// Вид строкового аргумента
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringKind {
+	/// Одно слово до пробела
+	Word,
+	/// Слово или строка в кавычках
+	Quotable,
+	/// Весь остаток команды
+	Greedy,
+}
+
+/// Парсер аргумента команды
+///
+/// Клиент знает про эти парсеры сам и подсвечивает по ним синтаксис,
+/// айди берутся из реестра minecraft:command_argument_type
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentParser {
+	Bool,
+	Double { min: Option<f64>, max: Option<f64> },
+	Integer { min: Option<i32>, max: Option<i32> },
+	String(StringKind),
+	Entity { single: bool, players_only: bool },
+	BlockPos,
+	GameMode,
+}
+
+/// Распарсенное значение аргумента
+#[derive(Clone)]
+pub enum ArgumentValue {
+	Bool(bool),
+	Double(f64),
+	Integer(i32),
+	String(String),
+	Entity(Vec<Arc<ClientContext>>),
+	BlockPos(Position),
+	GameMode(u8),
+}
+
+pub const GAME_MODES: [&str; 4] = ["survival", "creative", "adventure", "spectator"];
+
+const SELECTORS: [&str; 5] = ["@a", "@e", "@p", "@r", "@s"];
+
+fn command_error(message: String) -> ServerError {
+	ServerError::Command(message)
+}
+
+// Читалка по строке команды, курсор в байтах
+pub struct StringReader<'a> {
+	input: &'a str,
+	cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+	pub fn new(input: &'a str, cursor: usize) -> StringReader<'a> {
+		StringReader { input, cursor }
+	}
+
+	pub fn cursor(&self) -> usize {
+		self.cursor
+	}
+
+	pub fn remaining(&self) -> &'a str {
+		&self.input[self.cursor..]
+	}
+
+	pub fn at_end(&self) -> bool {
+		self.cursor >= self.input.len()
+	}
+
+	pub fn peek(&self) -> Option<char> {
+		self.remaining().chars().next()
+	}
+
+	pub fn skip(&mut self) {
+		if let Some(c) = self.peek() {
+			self.cursor += c.len_utf8();
+		}
+	}
+
+	/// Читает до пробела или конца строки
+	pub fn read_word(&mut self) -> &'a str {
+		let rest = self.remaining();
+		let length = rest.find(' ').unwrap_or(rest.len());
+		self.cursor += length;
+		&rest[..length]
+	}
+
+	/// Читает весь остаток
+	pub fn read_rest(&mut self) -> &'a str {
+		let rest = self.remaining();
+		self.cursor = self.input.len();
+		rest
+	}
+
+	/// Строка в двойных или одинарных кавычках с экранированием через \
+	pub fn read_quoted(&mut self) -> Result<String, ServerError> {
+		let Some(quote) = self.peek().filter(|o| *o == '"' || *o == '\'') else {
+			return Ok(self.read_word().to_string());
+		};
+		self.skip();
+
+		let mut result = String::new();
+		let mut escaped = false;
+
+		while let Some(c) = self.peek() {
+			self.skip();
+			if escaped {
+				if c != quote && c != '\\' {
+					return Err(command_error(format!("Invalid escape sequence \\{c}")));
+				}
+				result.push(c);
+				escaped = false;
+			} else if c == '\\' {
+				escaped = true;
+			} else if c == quote {
+				return Ok(result);
+			} else {
+				result.push(c);
+			}
+		}
+
+		Err(command_error("Unclosed quoted string".to_string()))
+	}
+}
+
+// Координата блока: число, либо ~ со смещением от игрока
+fn parse_coordinate(token: &str, base: f64) -> Result<i32, ServerError> {
+	let error = || command_error(format!("Invalid coordinate {token}"));
+
+	if let Some(offset) = token.strip_prefix('~') {
+		let offset = if offset.is_empty() {
+			0.0
+		} else {
+			offset.parse::<f64>().map_err(|_| error())?
+		};
+		Ok((base + offset).floor() as i32)
+	} else {
+		token.parse::<i32>().map_err(|_| error())
+	}
+}
+
+impl ArgumentParser {
+	/// Айди в реестре minecraft:command_argument_type
+	pub fn id(&self) -> i32 {
+		match self {
+			ArgumentParser::Bool => 0,
+			ArgumentParser::Double { .. } => 2,
+			ArgumentParser::Integer { .. } => 3,
+			ArgumentParser::String(_) => 5,
+			ArgumentParser::Entity { .. } => 6,
+			ArgumentParser::BlockPos => 8,
+			ArgumentParser::GameMode => 42,
+		}
+	}
+
+	/// Парсит аргумент с текущей позиции, ошибка показывается игроку
+	pub fn parse(
+		&self,
+		reader: &mut StringReader,
+		client: &Arc<ClientContext>,
+	) -> Result<ArgumentValue, ServerError> {
+		Ok(match self {
+			ArgumentParser::Bool => match reader.read_word() {
+				"true" => ArgumentValue::Bool(true),
+				"false" => ArgumentValue::Bool(false),
+				word => return Err(command_error(format!("Invalid boolean {word}"))),
+			},
+			ArgumentParser::Double { min, max } => {
+				let word = reader.read_word();
+				let value = word
+					.parse::<f64>()
+					.map_err(|_| command_error(format!("Invalid double {word}")))?;
+				if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+					return Err(command_error(format!("Double {value} is out of range")));
+				}
+				ArgumentValue::Double(value)
+			}
+			ArgumentParser::Integer { min, max } => {
+				let word = reader.read_word();
+				let value = word
+					.parse::<i32>()
+					.map_err(|_| command_error(format!("Invalid integer {word}")))?;
+				if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+					return Err(command_error(format!("Integer {value} is out of range")));
+				}
+				ArgumentValue::Integer(value)
+			}
+			ArgumentParser::String(kind) => ArgumentValue::String(match kind {
+				StringKind::Word => reader.read_word().to_string(),
+				StringKind::Quotable => reader.read_quoted()?,
+				StringKind::Greedy => reader.read_rest().to_string(),
+			}),
+			ArgumentParser::Entity {
+				single,
+				players_only: _, // Других сущностей пока нет
+			} => {
+				let word = reader.read_word();
+				let players = client.server.players();
+
+				let targets = match word {
+					"@a" | "@e" => players,
+					"@s" | "@p" => vec![client.clone()],
+					"@r" => players
+						.choose(&mut rand::thread_rng())
+						.cloned()
+						.into_iter()
+						.collect(),
+					_ if word.starts_with('@') => {
+						return Err(command_error(format!("Unknown selector {word}")));
+					}
+					_ => client.server.get_player_by_name(word).into_iter().collect(),
+				};
+
+				if targets.is_empty() {
+					return Err(command_error("No player was found".to_string()));
+				}
+				if *single && targets.len() > 1 {
+					return Err(command_error(
+						"Only one player is allowed, but the provided selector allows more than one"
+							.to_string(),
+					));
+				}
+
+				ArgumentValue::Entity(targets)
+			}
+			ArgumentParser::BlockPos => {
+				let (x, y, z) = client.entity_info().unwrap().position();
+
+				let x = parse_coordinate(reader.read_word(), x)?;
+				let mut coordinates = [x, 0, 0];
+				for (i, base) in [(1, y), (2, z)] {
+					if reader.peek() != Some(' ') {
+						return Err(command_error("Incomplete block position".to_string()));
+					}
+					reader.skip();
+					coordinates[i] = parse_coordinate(reader.read_word(), base)?;
+				}
+
+				ArgumentValue::BlockPos(Position::new(
+					coordinates[0],
+					coordinates[1],
+					coordinates[2],
+				))
+			}
+			ArgumentParser::GameMode => {
+				let word = reader.read_word();
+				match GAME_MODES.iter().position(|o| *o == word) {
+					Some(mode) => ArgumentValue::GameMode(mode as u8),
+					None => return Err(command_error(format!("Unknown game mode {word}"))),
+				}
+			}
+		})
+	}
+
+	/// Варианты для автодополнения, фильтруются по введенному уже снаружи
+	pub fn suggestions(&self, client: &Arc<ClientContext>) -> Vec<String> {
+		match self {
+			ArgumentParser::Bool => vec!["true".to_string(), "false".to_string()],
+			ArgumentParser::Entity { .. } => client
+				.server
+				.players()
+				.iter()
+				.filter_map(|o| o.player_info())
+				.map(|o| o.name)
+				.chain(SELECTORS.iter().map(|o| o.to_string()))
+				.collect(),
+			ArgumentParser::BlockPos => vec!["~ ~ ~".to_string()],
+			ArgumentParser::GameMode => GAME_MODES.iter().map(|o| o.to_string()).collect(),
+			_ => Vec::new(),
+		}
+	}
+}
+
+// Флаги границ у числовых парсеров: 0x01 - есть минимум, 0x02 - есть максимум
+fn bounds_flags<T>(min: &Option<T>, max: &Option<T>) -> u8 {
+	(min.is_some() as u8) | ((max.is_some() as u8) << 1)
+}
+
+impl Encode for ArgumentParser {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_varint(self.id())?;
+
+		match self {
+			ArgumentParser::Double { min, max } => {
+				packet.write_byte(bounds_flags(min, max))?;
+				if let Some(min) = min {
+					packet.write_double(*min)?;
+				}
+				if let Some(max) = max {
+					packet.write_double(*max)?;
+				}
+			}
+			ArgumentParser::Integer { min, max } => {
+				packet.write_byte(bounds_flags(min, max))?;
+				if let Some(min) = min {
+					packet.write_int(*min)?;
+				}
+				if let Some(max) = max {
+					packet.write_int(*max)?;
+				}
+			}
+			ArgumentParser::String(kind) => packet.write_varint(match kind {
+				StringKind::Word => 0,
+				StringKind::Quotable => 1,
+				StringKind::Greedy => 2,
+			})?,
+			ArgumentParser::Entity {
+				single,
+				players_only,
+			} => packet.write_byte((*single as u8) | ((*players_only as u8) << 1))?,
+			ArgumentParser::Bool | ArgumentParser::BlockPos | ArgumentParser::GameMode => {}
+		}
+
+		Ok(())
+	}
+}
+
+impl Decode for ArgumentParser {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(match packet.read_varint()? {
+			0 => ArgumentParser::Bool,
+			2 => {
+				let flags = packet.read_byte()?;
+				ArgumentParser::Double {
+					min: (flags & 0x01 != 0)
+						.then(|| packet.read_double())
+						.transpose()?,
+					max: (flags & 0x02 != 0)
+						.then(|| packet.read_double())
+						.transpose()?,
+				}
+			}
+			3 => {
+				let flags = packet.read_byte()?;
+				ArgumentParser::Integer {
+					min: (flags & 0x01 != 0).then(|| packet.read_int()).transpose()?,
+					max: (flags & 0x02 != 0).then(|| packet.read_int()).transpose()?,
+				}
+			}
+			5 => ArgumentParser::String(match packet.read_varint()? {
+				0 => StringKind::Word,
+				1 => StringKind::Quotable,
+				2 => StringKind::Greedy,
+				_ => return Err(ServerError::WrongPacket),
+			}),
+			6 => {
+				let flags = packet.read_byte()?;
+				ArgumentParser::Entity {
+					single: flags & 0x01 != 0,
+					players_only: flags & 0x02 != 0,
+				}
+			}
+			8 => ArgumentParser::BlockPos,
+			42 => ArgumentParser::GameMode,
+			_ => return Err(ServerError::WrongPacket),
+		})
+	}
+}
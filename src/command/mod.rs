@@ -0,0 +1,518 @@
+// Дерево команд в стиле Brigadier
+// Команды описываются узлами (литералы и аргументы), клиент получает дерево через пакет Commands
+// и сам подсвечивает синтаксис, а выполняем и проверяем права мы
+
+use std::{collections::HashMap, sync::Arc};
+
+use argument::{ArgumentParser, ArgumentValue, StringReader};
+
+use crate::{
+	ServerError,
+	data::Position,
+	player::context::ClientContext,
+	protocol::{
+		codec::VarInt,
+		packets::play::{CommandNodeData, SuggestionMatch, clientbound},
+	},
+};
+
+pub mod argument;
+
+/// Выполняет команду, ServerError::Command показывается игроку красным
+pub type CommandExecutor = Arc<dyn Fn(&CommandContext) -> Result<(), ServerError> + Send + Sync>;
+
+/// Свои подсказки для аргумента, получает уже введенную часть аргумента
+pub type SuggestionProvider = Arc<dyn Fn(&CommandContext, &str) -> Vec<String> + Send + Sync>;
+
+// Узел без прав доступен всем
+pub const DEFAULT_PERMISSION_LEVEL: u8 = 0;
+
+const NODE_ROOT: u8 = 0;
+const NODE_LITERAL: u8 = 1;
+const NODE_ARGUMENT: u8 = 2;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_SUGGESTIONS: u8 = 0x10;
+
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+	Root,
+	Literal(String),
+	Argument {
+		name: String,
+		parser: ArgumentParser,
+	},
+}
+
+/// Узел дерева команд
+///
+/// ```ignore
+/// CommandNode::literal("heal")
+///   .requires(2)
+///   .then(
+///     CommandNode::argument("amount", ArgumentParser::Integer { min: Some(1), max: None })
+///       .executes(|ctx| { ... }),
+///   )
+/// ```
+#[derive(Clone)]
+pub struct CommandNode {
+	kind: NodeKind,
+	children: Vec<CommandNode>,
+	executor: Option<CommandExecutor>,
+	suggestions: Option<SuggestionProvider>,
+	permission: u8,
+}
+
+impl CommandNode {
+	fn new(kind: NodeKind) -> CommandNode {
+		CommandNode {
+			kind,
+			children: Vec::new(),
+			executor: None,
+			suggestions: None,
+			permission: DEFAULT_PERMISSION_LEVEL,
+		}
+	}
+
+	pub fn root() -> CommandNode {
+		CommandNode::new(NodeKind::Root)
+	}
+
+	pub fn literal(name: &str) -> CommandNode {
+		CommandNode::new(NodeKind::Literal(name.to_string()))
+	}
+
+	pub fn argument(name: &str, parser: ArgumentParser) -> CommandNode {
+		CommandNode::new(NodeKind::Argument {
+			name: name.to_string(),
+			parser,
+		})
+	}
+
+	/// Минимальный уровень прав игрока от 0 до 4
+	pub fn requires(mut self, permission: u8) -> CommandNode {
+		self.permission = permission;
+		self
+	}
+
+	pub fn then(mut self, child: CommandNode) -> CommandNode {
+		self.children.push(child);
+		self
+	}
+
+	pub fn executes<F>(mut self, executor: F) -> CommandNode
+	where
+		F: Fn(&CommandContext) -> Result<(), ServerError> + Send + Sync + 'static,
+	{
+		self.executor = Some(Arc::new(executor));
+		self
+	}
+
+	/// Подсказки запрашиваются у сервера, только для аргументов
+	pub fn suggests<F>(mut self, provider: F) -> CommandNode
+	where
+		F: Fn(&CommandContext, &str) -> Vec<String> + Send + Sync + 'static,
+	{
+		self.suggestions = Some(Arc::new(provider));
+		self
+	}
+
+	pub fn kind(&self) -> &NodeKind {
+		&self.kind
+	}
+
+	pub fn children(&self) -> &[CommandNode] {
+		&self.children
+	}
+
+	pub fn permission(&self) -> u8 {
+		self.permission
+	}
+
+	fn name(&self) -> Option<&str> {
+		match &self.kind {
+			NodeKind::Root => None,
+			NodeKind::Literal(name) | NodeKind::Argument { name, .. } => Some(name),
+		}
+	}
+
+	// Дети которые доступны игроку с таким уровнем прав
+	fn allowed_children(&self, level: u8) -> impl Iterator<Item = &CommandNode> {
+		self.children.iter().filter(move |o| o.permission <= level)
+	}
+
+	// Добавляет детей другого узла с тем же именем, чтобы плагины могли дополнять чужие команды
+	fn merge(&mut self, other: CommandNode) {
+		if other.executor.is_some() {
+			self.executor = other.executor;
+		}
+		if other.suggestions.is_some() {
+			self.suggestions = other.suggestions;
+		}
+
+		for child in other.children {
+			match self
+				.children
+				.iter_mut()
+				.find(|o| o.name().is_some() && o.name() == child.name())
+			{
+				Some(existing) => existing.merge(child),
+				None => self.children.push(child),
+			}
+		}
+	}
+}
+
+/// То что получает исполнитель команды
+pub struct CommandContext {
+	pub client: Arc<ClientContext>,
+	/// Вся команда без слеша
+	pub input: String,
+	arguments: HashMap<String, ArgumentValue>,
+}
+
+impl CommandContext {
+	pub fn new(client: Arc<ClientContext>, input: &str) -> CommandContext {
+		CommandContext {
+			client,
+			input: input.to_string(),
+			arguments: HashMap::new(),
+		}
+	}
+
+	pub fn argument(&self, name: &str) -> Option<&ArgumentValue> {
+		self.arguments.get(name)
+	}
+
+	pub fn get_bool(&self, name: &str) -> Option<bool> {
+		match self.argument(name)? {
+			ArgumentValue::Bool(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn get_double(&self, name: &str) -> Option<f64> {
+		match self.argument(name)? {
+			ArgumentValue::Double(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn get_integer(&self, name: &str) -> Option<i32> {
+		match self.argument(name)? {
+			ArgumentValue::Integer(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn get_string(&self, name: &str) -> Option<&str> {
+		match self.argument(name)? {
+			ArgumentValue::String(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	pub fn get_entities(&self, name: &str) -> Option<&[Arc<ClientContext>]> {
+		match self.argument(name)? {
+			ArgumentValue::Entity(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	pub fn get_block_pos(&self, name: &str) -> Option<Position> {
+		match self.argument(name)? {
+			ArgumentValue::BlockPos(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	/// 0 - выживание, 1 - креатив, 2 - приключение, 3 - наблюдатель
+	pub fn get_game_mode(&self, name: &str) -> Option<u8> {
+		match self.argument(name)? {
+			ArgumentValue::GameMode(value) => Some(*value),
+			_ => None,
+		}
+	}
+}
+
+// Уровень прав игрока, у клиента без сущности прав нет
+fn permission_level(client: &Arc<ClientContext>) -> u8 {
+	client
+		.entity_info()
+		.map(|o| o.permission_level())
+		.unwrap_or_default()
+}
+
+/// Все команды сервера
+pub struct CommandDispatcher {
+	root: CommandNode,
+}
+
+impl Default for CommandDispatcher {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl CommandDispatcher {
+	pub fn new() -> CommandDispatcher {
+		CommandDispatcher {
+			root: CommandNode::root(),
+		}
+	}
+
+	pub fn root(&self) -> &CommandNode {
+		&self.root
+	}
+
+	/// Регистрирует команду, если команда с таким именем уже есть - узлы объединяются
+	pub fn register(&mut self, node: CommandNode) {
+		self.root.merge(CommandNode::root().then(node));
+	}
+
+	/// Выполняет команду без слеша в начале
+	pub fn execute(&self, client: Arc<ClientContext>, input: &str) -> Result<(), ServerError> {
+		let mut context = CommandContext::new(client, input);
+		let executor = Self::parse(&self.root, &mut context, 0)?;
+		executor(&context)
+	}
+
+	// Ищет подходящую ветку с откатом, как Brigadier
+	fn parse<'a>(
+		node: &'a CommandNode,
+		context: &mut CommandContext,
+		cursor: usize,
+	) -> Result<&'a CommandExecutor, ServerError> {
+		let input = context.input.clone();
+
+		if cursor >= input.len() {
+			return node
+				.executor
+				.as_ref()
+				.ok_or_else(|| ServerError::Command("Unknown or incomplete command".to_string()));
+		}
+
+		// Показываем команду до конца слова на котором споткнулись
+		let word_end = input[cursor..]
+			.find(' ')
+			.map_or(input.len(), |o| cursor + o);
+		let mut error = ServerError::Command(format!(
+			"Unknown or incomplete command: {}<--[HERE]",
+			&input[..word_end]
+		));
+
+		for child in node.allowed_children(permission_level(&context.client)) {
+			let mut reader = StringReader::new(&input, cursor);
+
+			let value = match &child.kind {
+				NodeKind::Root => continue,
+				NodeKind::Literal(name) => {
+					if reader.read_word() != name {
+						continue;
+					}
+					None
+				}
+				NodeKind::Argument { name, parser } => match parser.parse(&mut reader, &context.client) {
+					Ok(value) => Some((name.clone(), value)),
+					Err(e) => {
+						error = e;
+						continue;
+					}
+				},
+			};
+
+			// Между аргументами ровно один пробел
+			if !reader.at_end() {
+				if reader.peek() != Some(' ') {
+					continue;
+				}
+				reader.skip();
+			}
+
+			let previous = value
+				.as_ref()
+				.and_then(|(name, _)| context.arguments.get(name).cloned());
+			if let Some((name, value)) = &value {
+				context.arguments.insert(name.clone(), value.clone());
+			}
+
+			match Self::parse(child, context, reader.cursor()) {
+				Ok(executor) => return Ok(executor),
+				Err(e) => {
+					error = e;
+					if let Some((name, _)) = value {
+						match previous {
+							Some(previous) => context.arguments.insert(name, previous),
+							None => context.arguments.remove(&name),
+						};
+					}
+				}
+			}
+		}
+
+		Err(error)
+	}
+
+	/// Подсказки для текста без слеша, возвращает начало заменяемого текста и варианты
+	pub fn suggest(&self, client: Arc<ClientContext>, input: &str) -> (usize, Vec<String>) {
+		let mut context = CommandContext::new(client, input);
+		let mut suggestions = Vec::new();
+		Self::collect_suggestions(&self.root, &mut context, 0, &mut suggestions);
+
+		// Клиенту можно отдать только одно начало, берем самое глубокое
+		let start = suggestions
+			.iter()
+			.map(|(start, _)| *start)
+			.max()
+			.unwrap_or(input.len());
+
+		let mut matches = suggestions
+			.into_iter()
+			.filter(|(o, _)| *o == start)
+			.map(|(_, text)| text)
+			.collect::<Vec<String>>();
+		matches.sort();
+		matches.dedup();
+
+		(start, matches)
+	}
+
+	fn collect_suggestions(
+		node: &CommandNode,
+		context: &mut CommandContext,
+		cursor: usize,
+		suggestions: &mut Vec<(usize, String)>,
+	) {
+		let input = context.input.clone();
+		let typed = &input[cursor..];
+
+		for child in node.allowed_children(permission_level(&context.client)) {
+			let mut reader = StringReader::new(&input, cursor);
+
+			let value = match &child.kind {
+				NodeKind::Root => continue,
+				NodeKind::Literal(name) => {
+					if reader.read_word() == name {
+						Some(None)
+					} else {
+						None
+					}
+				}
+				NodeKind::Argument { name, parser } => parser
+					.parse(&mut reader, &context.client)
+					.ok()
+					.map(|o| Some((name.clone(), o))),
+			};
+
+			// Узел полностью введен и дальше есть пробел - идем глубже
+			if let Some(value) = value
+				&& reader.peek() == Some(' ')
+			{
+				reader.skip();
+				if let Some((name, value)) = value {
+					context.arguments.insert(name, value);
+				}
+				Self::collect_suggestions(child, context, reader.cursor(), suggestions);
+				continue;
+			}
+
+			let candidates = match &child.kind {
+				NodeKind::Root => Vec::new(),
+				NodeKind::Literal(name) => vec![name.clone()],
+				NodeKind::Argument { parser, .. } => match &child.suggestions {
+					Some(provider) => provider(context, typed),
+					None => parser.suggestions(&context.client),
+				},
+			};
+
+			let typed_lower = typed.to_lowercase();
+			suggestions.extend(
+				candidates
+					.into_iter()
+					.filter(|o| o.to_lowercase().starts_with(&typed_lower))
+					.map(|o| (cursor, o)),
+			);
+		}
+	}
+
+	/// Пакет Commands с теми узлами, которые доступны игроку
+	pub fn to_packet(&self, client: &Arc<ClientContext>) -> clientbound::Commands {
+		let mut nodes = Vec::new();
+		let root_index = Self::flatten(&self.root, permission_level(client), &mut nodes);
+
+		clientbound::Commands {
+			nodes,
+			root_index: VarInt(root_index),
+		}
+	}
+
+	// Раскладывает дерево в список, возвращает индекс узла
+	fn flatten(node: &CommandNode, level: u8, nodes: &mut Vec<CommandNodeData>) -> i32 {
+		let index = nodes.len();
+		nodes.push(CommandNodeData::default());
+
+		let children = node
+			.allowed_children(level)
+			.map(|o| VarInt(Self::flatten(o, level, nodes)))
+			.collect();
+
+		let mut flags = match node.kind {
+			NodeKind::Root => NODE_ROOT,
+			NodeKind::Literal(_) => NODE_LITERAL,
+			NodeKind::Argument { .. } => NODE_ARGUMENT,
+		};
+		if node.executor.is_some() {
+			flags |= FLAG_EXECUTABLE;
+		}
+
+		let (parser, suggestions_type) = match &node.kind {
+			NodeKind::Argument { parser, .. } => {
+				let suggestions_type = node
+					.suggestions
+					.as_ref()
+					.map(|_| "minecraft:ask_server".to_string());
+				if suggestions_type.is_some() {
+					flags |= FLAG_SUGGESTIONS;
+				}
+				(Some(parser.clone()), suggestions_type)
+			}
+			_ => (None, None),
+		};
+
+		nodes[index] = CommandNodeData {
+			flags,
+			children,
+			redirect_node: None,
+			name: node.name().map(|o| o.to_string()),
+			parser,
+			suggestions_type,
+		};
+
+		index as i32
+	}
+}
+
+/// Ответ на Command Suggestions Request, text - весь текст со слешем
+pub fn suggestions_response(
+	client: Arc<ClientContext>,
+	transaction_id: i32,
+	text: &str,
+) -> clientbound::CommandSuggestionsResponse {
+	let input = text.strip_prefix('/').unwrap_or(text);
+	let offset = text.len() - input.len();
+
+	let (start, matches) = client.server.commands().suggest(client.clone(), input);
+
+	// Клиент считает позиции в символах UTF-16, как в джаве
+	clientbound::CommandSuggestionsResponse {
+		transaction_id: VarInt(transaction_id),
+		start: VarInt(text[..start + offset].encode_utf16().count() as i32),
+		length: VarInt(input[start..].encode_utf16().count() as i32),
+		matches: matches
+			.into_iter()
+			.map(|text| SuggestionMatch {
+				text,
+				tooltip: None,
+			})
+			.collect(),
+	}
+}
@@ -2,6 +2,9 @@ use std::{fs, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_default::DefaultFromSerde;
+use uuid::Uuid;
+
+use crate::{ServerError, world::generator::GeneratorConfig};
 
 #[derive(Debug, DefaultFromSerde, Serialize, Deserialize, Clone)]
 pub struct BindConfig {
@@ -9,6 +12,31 @@ pub struct BindConfig {
 	pub host: String,
 	#[serde(default = "default_timeout")]
 	pub timeout: u64,
+	/// Сколько пакетов может ждать отправки одному клиенту
+	#[serde(default = "default_max_queue_size")]
+	pub max_queue_size: usize,
+	/// Что делать с клиентом который не успевает принимать пакеты
+	#[serde(default)]
+	pub queue_overflow: QueueOverflow,
+	/// Ждать заголовок HAProxy PROXY протокола (v1 или v2) перед рукопожатием
+	#[serde(default)]
+	pub proxy_protocol: bool,
+	/// Адреса или подсети балансировщиков, от которых принимаем заголовок,
+	/// с пустым списком сервер не запустится
+	#[serde(default)]
+	pub trusted_proxies: Vec<String>,
+}
+
+/// Политика при переполнении очереди отправки клиента
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOverflow {
+	/// Сразу отключать клиента
+	#[default]
+	Kick,
+	/// Выкидывать необязательные пакеты (анимации, звуки, повороты головы),
+	/// отключать только если очередь забита вдвое больше лимита
+	DropNonEssential,
 }
 
 #[derive(Debug, DefaultFromSerde, Serialize, Deserialize, Clone)]
@@ -17,6 +45,77 @@ pub struct ServerConfig {
 	pub online_mode: bool,
 	#[serde(default = "default_compression")]
 	pub compression_threshold: Option<usize>,
+	#[serde(default = "default_session_server")]
+	pub session_server: String,
+	#[serde(default)]
+	pub prevent_proxy_connections: bool,
+	/// Уровень прав игроков от 0 до 4, от него зависят доступные команды
+	#[serde(default)]
+	pub default_permission_level: u8,
+	/// Ники или UUID операторов, они получают op_permission_level
+	#[serde(default)]
+	pub operators: Vec<String>,
+	#[serde(default = "default_op_permission_level")]
+	pub op_permission_level: u8,
+}
+
+impl ServerConfig {
+	/// Уровень прав игрока при входе
+	pub fn permission_level(&self, name: &str, uuid: &Uuid) -> u8 {
+		let is_operator = self
+			.operators
+			.iter()
+			.any(|o| o.eq_ignore_ascii_case(name) || Uuid::parse_str(o).is_ok_and(|o| o == *uuid));
+
+		if is_operator {
+			self.op_permission_level.min(4)
+		} else {
+			self.default_permission_level.min(4)
+		}
+	}
+}
+
+#[derive(Debug, DefaultFromSerde, Serialize, Deserialize, Clone)]
+pub struct WorldConfig {
+	#[serde(default = "default_world_path")]
+	pub path: String,
+	#[serde(default = "default_blocks_report")]
+	pub blocks_report: String,
+	#[serde(default = "default_registries_report")]
+	pub registries_report: String,
+	/// Отчет items.json, из него берутся размеры стаков и слоты брони
+	#[serde(default = "default_items_report")]
+	pub items_report: String,
+	#[serde(default)]
+	pub generator: GeneratorConfig,
+	/// Папка с датапаками, их реестры и теги добавляются к ванильным
+	#[serde(default = "default_datapacks_path")]
+	pub datapacks: String,
+	/// Как часто сохранять мир и выгружать ненужные чанки, в секундах. 0 выключает автосохранение
+	#[serde(default = "default_autosave_interval")]
+	pub autosave_interval: u64,
+}
+
+/// Откуда прокси передает данные игрока
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardingMode {
+	/// Без прокси, игроков авторизуем сами
+	#[default]
+	None,
+	/// Legacy форвардинг, данные дописываются в адрес сервера в рукопожатии
+	Bungeecord,
+	/// Modern форвардинг через login plugin request с подписью
+	Velocity,
+}
+
+#[derive(Debug, DefaultFromSerde, Serialize, Deserialize, Clone)]
+pub struct ProxyConfig {
+	#[serde(default)]
+	pub forwarding: ForwardingMode,
+	/// Секрет из forwarding.secret у Velocity
+	#[serde(default)]
+	pub secret: String,
 }
 
 #[derive(Debug, DefaultFromSerde, Serialize, Deserialize, Clone)]
@@ -25,6 +124,10 @@ pub struct Config {
 	pub bind: BindConfig,
 	#[serde(default)]
 	pub server: ServerConfig,
+	#[serde(default)]
+	pub world: WorldConfig,
+	#[serde(default)]
+	pub proxy: ProxyConfig,
 }
 
 fn default_host() -> String {
@@ -33,15 +136,61 @@ fn default_host() -> String {
 fn default_timeout() -> u64 {
 	5
 }
+fn default_max_queue_size() -> usize {
+	8192
+}
 fn default_compression() -> Option<usize> {
 	Some(256)
 }
+fn default_session_server() -> String {
+	"https://sessionserver.mojang.com".to_string()
+}
+fn default_op_permission_level() -> u8 {
+	4
+}
+fn default_world_path() -> String {
+	"world".to_string()
+}
+fn default_blocks_report() -> String {
+	"blocks.json".to_string()
+}
+fn default_registries_report() -> String {
+	"registries.json".to_string()
+}
+fn default_items_report() -> String {
+	"items.json".to_string()
+}
+fn default_datapacks_path() -> String {
+	"datapacks".to_string()
+}
+fn default_autosave_interval() -> u64 {
+	300
+}
 
 impl Config {
+	/// Проверяет настройки, с которыми сервер был бы открыт для подделки игроков
+	pub fn validate(&self) -> Result<(), ServerError> {
+		if self.proxy.forwarding == ForwardingMode::Velocity && self.proxy.secret.is_empty() {
+			return Err(ServerError::Config(
+				"для форвардинга velocity нужен proxy.secret, иначе данные игрока подделает кто угодно"
+					.to_string(),
+			));
+		}
+
+		// Иначе любой клиент сам напишет себе адрес в заголовке
+		if self.bind.proxy_protocol && self.bind.trusted_proxies.is_empty() {
+			return Err(ServerError::Config(
+				"для proxy_protocol нужен список bind.trusted_proxies".to_string(),
+			));
+		}
+
+		Ok(())
+	}
+
 	pub fn load_from_file(path: PathBuf) -> Option<Config> {
 		if !fs::exists(&path).unwrap_or_default() {
 			let table = Config::default();
-			fs::create_dir_all(&path.parent()?).ok()?;
+			fs::create_dir_all(path.parent()?).ok()?;
 			fs::write(&path, toml::to_string_pretty(&table).ok()?).ok()?;
 			return Some(table);
 		}
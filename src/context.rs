@@ -1,16 +1,42 @@
 use std::{
+	collections::{HashMap, HashSet},
 	net::SocketAddr,
-	sync::{Arc, atomic::AtomicI32},
+	path::Path,
+	sync::{
+		Arc, RwLock,
+		atomic::{AtomicBool, AtomicI32, AtomicI64, Ordering},
+	},
+	time::Duration,
 };
 
 use dashmap::DashMap;
 use itertools::Itertools;
+use log::{error, warn};
 use uuid::Uuid;
 
 use super::{
-	config::Config,
+	ServerError,
+	command::{CommandDispatcher, CommandNode},
+	config::{Config, WorldConfig},
 	event::{Listener, PacketHandler},
+	play::{datapack::load_datapacks, planner::Planner, registry::Registries, tick::TickStats},
 	player::context::ClientContext,
+	protocol::{
+		encryption::ServerKeys,
+		session::{MojangSessionService, SessionService},
+		version::{ProtocolVersion, SUPPORTED_VERSIONS},
+	},
+	world::{
+		WORLD_HEIGHT, WORLD_MIN_Y,
+		anvil::RegionStorage,
+		block::BlockRegistry,
+		block_to_chunk,
+		chunk::{Chunk, PaletteKind},
+		entity::{EntityStorage, WorldEntity},
+		generator::{VoidGenerator, WorldGenerator, create_generator},
+		item::{DEFAULT_MAX_STACK_SIZE, ItemRegistry},
+		playerdata::PlayerDataStorage,
+	},
 };
 
 // Контекст сервера
@@ -19,19 +45,92 @@ pub struct ServerContext {
 	pub config: Arc<Config>,
 	pub clients: DashMap<SocketAddr, Arc<ClientContext>>,
 	pub world: WorldContext,
+	keys: Option<ServerKeys>,
+	session_service: Box<dyn SessionService>,
 	listeners: Vec<Box<dyn Listener>>,
 	handlers: Vec<Box<dyn PacketHandler>>,
+	commands: CommandDispatcher,
+	planner: Planner,
+	tick_stats: TickStats,
+	registries: HashMap<ProtocolVersion, Registries>,
 }
 
 impl ServerContext {
-	pub fn new(config: Arc<Config>) -> ServerContext {
-		ServerContext {
+	/// Ошибка запуска если конфиг небезопасен (см. Config::validate)
+	/// или в online-mode не сгенерировались RSA ключи, без них ни один игрок не зайдет
+	pub fn new(config: Arc<Config>) -> Result<ServerContext, ServerError> {
+		config.validate()?;
+
+		// Ключи нужны только для online-mode, генерировать их просто так долго
+		let keys = if config.server.online_mode {
+			match ServerKeys::generate() {
+				Ok(keys) => Some(keys),
+				Err(error) => {
+					error!("Не удалось сгенерировать RSA ключи: {error:?}");
+					return Err(error);
+				}
+			}
+		} else {
+			None
+		};
+
+		let registries = SUPPORTED_VERSIONS
+			.into_iter()
+			.map(|version| {
+				let mut registries = Registries::vanilla(version).unwrap_or_else(|error| {
+					error!("Не удалось загрузить реестры {}: {error:?}", version.name());
+					Registries::default()
+				});
+				load_datapacks(Path::new(&config.world.datapacks), version, &mut registries);
+				// Без отчета не знаем имен предметов и звуков, хеши в Hashed Slot не сойдутся
+				if let Err(error) = registries.load_report(Path::new(&config.world.registries_report)) {
+					warn!(
+						"Не удалось загрузить {}: {error:?}, хеши предметов от клиента не сойдутся",
+						config.world.registries_report
+					);
+				}
+				(version, registries)
+			})
+			.collect::<HashMap<ProtocolVersion, Registries>>();
+
+		// Айди биомов в чанках берутся из реестра последней версии
+		let biomes = registries[&ProtocolVersion::LATEST].entry_names("minecraft:worldgen/biome");
+
+		Ok(ServerContext {
+			session_service: Box::new(MojangSessionService::new(
+				&config.server.session_server,
+				Duration::from_secs(config.bind.timeout),
+			)),
+			world: WorldContext::new(&config.world, biomes),
 			config,
+			keys,
 			listeners: Vec::new(),
 			handlers: Vec::new(),
+			commands: CommandDispatcher::new(),
+			planner: Planner::new(),
+			tick_stats: TickStats::default(),
+			registries,
 			clients: DashMap::new(),
-			world: WorldContext::new(),
-		}
+		})
+	}
+
+	/// Ключи сервера, есть только в online-mode
+	pub fn keys(self: &Arc<Self>) -> Option<&ServerKeys> {
+		self.keys.as_ref()
+	}
+
+	pub fn session_service(self: &Arc<Self>) -> &dyn SessionService {
+		self.session_service.as_ref()
+	}
+
+	/// Заменяет генератор мира, например на свой из плагина
+	pub fn set_world_generator(&mut self, generator: Box<dyn WorldGenerator>) {
+		self.world.set_generator(generator);
+	}
+
+	/// Заменяет бэкенд проверки сессий, например на свой сервер авторизации
+	pub fn set_session_service(&mut self, service: Box<dyn SessionService>) {
+		self.session_service = service;
 	}
 
 	pub fn get_player_by_uuid(self: &Arc<Self>, uuid: Uuid) -> Option<Arc<ClientContext>> {
@@ -76,6 +175,69 @@ impl ServerContext {
 			.collect()
 	}
 
+	/// Все сущности мира вместе с игроками
+	pub fn entities(self: &Arc<Self>) -> Vec<WorldEntity> {
+		self
+			.players()
+			.into_iter()
+			.map(WorldEntity::Player)
+			.chain(
+				self
+					.world
+					.entities()
+					.all()
+					.into_iter()
+					.map(WorldEntity::Entity),
+			)
+			.collect()
+	}
+
+	/// Сущность или игрок по айди сущности
+	pub fn get_entity(self: &Arc<Self>, id: i32) -> Option<WorldEntity> {
+		if let Some(entity) = self.world.entities().get(id) {
+			return Some(WorldEntity::Entity(entity));
+		}
+
+		self
+			.players()
+			.into_iter()
+			.find(|o| o.entity_info().is_some_and(|o| o.entity_id == id))
+			.map(WorldEntity::Player)
+	}
+
+	/// Регистрирует команду, см. CommandNode
+	pub fn register_command(&mut self, node: CommandNode) {
+		self.commands.register(node);
+	}
+
+	pub fn commands(self: &Arc<Self>) -> &CommandDispatcher {
+		&self.commands
+	}
+
+	/// Планировщик задач, тикается в главном тике сервера
+	pub fn planner(self: &Arc<Self>) -> &Planner {
+		&self.planner
+	}
+
+	/// Синхронизируемые реестры и теги для версии клиента
+	pub fn registries(self: &Arc<Self>, version: ProtocolVersion) -> &Registries {
+		&self.registries[&version]
+	}
+
+	pub fn tick_stats(self: &Arc<Self>) -> &TickStats {
+		&self.tick_stats
+	}
+
+	/// Тиков в секунду за последние 5 секунд
+	pub fn tps(self: &Arc<Self>) -> f64 {
+		self.tick_stats.tps()
+	}
+
+	/// Среднее время тика в миллисекундах
+	pub fn mspt(self: &Arc<Self>) -> f64 {
+		self.tick_stats.mspt()
+	}
+
 	pub fn add_packet_handler(&mut self, handler: Box<dyn PacketHandler>) {
 		self.handlers.push(handler);
 	}
@@ -84,31 +246,322 @@ impl ServerContext {
 		self.listeners.push(listener);
 	}
 
-	pub fn packet_handlers<F, K>(self: &Arc<Self>, sort_by: F) -> Vec<&Box<dyn PacketHandler>>
+	pub fn packet_handlers<F, K>(self: &Arc<Self>, sort_by: F) -> Vec<&dyn PacketHandler>
 	where
 		K: Ord,
-		F: FnMut(&&Box<dyn PacketHandler>) -> K,
+		F: FnMut(&&dyn PacketHandler) -> K,
 	{
-		self.handlers.iter().sorted_by_key(sort_by).collect_vec()
+		self
+			.handlers
+			.iter()
+			.map(|o| o.as_ref())
+			.sorted_by_key(sort_by)
+			.collect_vec()
 	}
 
-	pub fn listeners<F, K>(self: &Arc<Self>, sort_by: F) -> Vec<&Box<dyn Listener>>
+	pub fn listeners<F, K>(self: &Arc<Self>, sort_by: F) -> Vec<&dyn Listener>
 	where
 		K: Ord,
-		F: FnMut(&&Box<dyn Listener>) -> K,
+		F: FnMut(&&dyn Listener) -> K,
 	{
-		self.listeners.iter().sorted_by_key(sort_by).collect_vec()
+		self
+			.listeners
+			.iter()
+			.map(|o| o.as_ref())
+			.sorted_by_key(sort_by)
+			.collect_vec()
 	}
 }
 
+// Длина игровых суток в тиках
+pub const DAY_LENGTH: i64 = 24000;
+
 pub struct WorldContext {
 	pub entity_id_counter: AtomicI32,
+	world_age: AtomicI64,
+	time_of_day: AtomicI64,
+	daylight_cycle: AtomicBool,
+	chunks: DashMap<(i32, i32), Arc<RwLock<Chunk>>>,
+	storage: RegionStorage,
+	player_data: PlayerDataStorage,
+	generator: Box<dyn WorldGenerator>,
+	items: ItemRegistry,
+	entities: EntityStorage,
+	biome_bits: u8,
 }
 
 impl WorldContext {
-	pub fn new() -> WorldContext {
+	/// biomes - имена биомов по их айди, см. ServerContext::registries
+	pub fn new(config: &WorldConfig, biomes: Vec<String>) -> WorldContext {
+		// Без отчета о блоках знаем только самые базовые блоки
+		let blocks = match BlockRegistry::load_report(Path::new(&config.blocks_report)) {
+			Ok(blocks) => blocks,
+			Err(error) => {
+				warn!(
+					"Не удалось загрузить {}: {error:?}, используется встроенный реестр блоков, мир не будет загружаться и сохраняться",
+					config.blocks_report
+				);
+				BlockRegistry::builtin()
+			}
+		};
+
+		let mut items = match ItemRegistry::load_report(Path::new(&config.registries_report)) {
+			Ok(items) => items,
+			Err(error) => {
+				warn!(
+					"Не удалось загрузить {}: {error:?}, используется встроенный реестр предметов",
+					config.registries_report
+				);
+				ItemRegistry::builtin()
+			}
+		};
+
+		if let Err(error) = items.load_components_report(Path::new(&config.items_report)) {
+			warn!(
+				"Не удалось загрузить {}: {error:?}, все предметы стакаются по {DEFAULT_MAX_STACK_SIZE}",
+				config.items_report
+			);
+		}
+
+		let generator =
+			match create_generator(&config.generator, Path::new(&config.path), &blocks, &biomes) {
+				Ok(generator) => generator,
+				Err(error) => {
+					error!("Не удалось создать генератор мира: {error:?}, мир будет пустым");
+					Box::new(VoidGenerator::new(0))
+				}
+			};
+
 		WorldContext {
+			biome_bits: PaletteKind::Biomes.registry_bits(biomes.len()),
 			entity_id_counter: AtomicI32::new(0),
+			world_age: AtomicI64::new(0),
+			time_of_day: AtomicI64::new(0),
+			daylight_cycle: AtomicBool::new(true),
+			chunks: DashMap::new(),
+			storage: RegionStorage::new(
+				Path::new(&config.path),
+				WORLD_MIN_Y,
+				WORLD_HEIGHT,
+				blocks,
+				biomes,
+			),
+			player_data: PlayerDataStorage::new(Path::new(&config.path)),
+			generator,
+			items,
+			entities: EntityStorage::default(),
+		}
+	}
+
+	pub fn blocks(&self) -> &BlockRegistry {
+		self.storage.blocks()
+	}
+
+	pub fn items(&self) -> &ItemRegistry {
+		&self.items
+	}
+
+	/// Битность прямой палитры биомов в пакете чанка
+	///
+	/// Клиент считает ее от размера своего реестра биомов, а датапаки его расширяют
+	pub fn biome_bits(&self) -> u8 {
+		self.biome_bits
+	}
+
+	/// Новый айди сущности, общий счетчик для игроков и остальных сущностей
+	pub fn next_entity_id(&self) -> i32 {
+		self.entity_id_counter.fetch_add(1, Ordering::SeqCst)
+	}
+
+	/// Сущности мира кроме игроков, спавнить и убирать их надо через play::entity
+	pub fn entities(&self) -> &EntityStorage {
+		&self.entities
+	}
+
+	/// Заменяет генератор мира, уже сгенерированные чанки не трогаются
+	pub fn set_generator(&mut self, generator: Box<dyn WorldGenerator>) {
+		self.generator = generator;
+	}
+
+	pub fn generator(&self) -> &dyn WorldGenerator {
+		self.generator.as_ref()
+	}
+
+	pub fn storage(&self) -> &RegionStorage {
+		&self.storage
+	}
+
+	/// Файлы игроков в playerdata/, см. play::playerdata
+	pub fn player_data(&self) -> &PlayerDataStorage {
+		&self.player_data
+	}
+
+	/// Сколько тиков прошло с создания мира
+	pub fn world_age(&self) -> i64 {
+		self.world_age.load(Ordering::SeqCst)
+	}
+
+	/// Время суток от 0 до 24000
+	pub fn time_of_day(&self) -> i64 {
+		self.time_of_day.load(Ordering::SeqCst)
+	}
+
+	pub fn set_time_of_day(&self, time: i64) {
+		self
+			.time_of_day
+			.store(time.rem_euclid(DAY_LENGTH), Ordering::SeqCst);
+	}
+
+	/// Идет ли время суток, как gamerule doDaylightCycle
+	pub fn daylight_cycle(&self) -> bool {
+		self.daylight_cycle.load(Ordering::SeqCst)
+	}
+
+	pub fn set_daylight_cycle(&self, enabled: bool) {
+		self.daylight_cycle.store(enabled, Ordering::SeqCst);
+	}
+
+	/// Сдвигает время на один тик, вызывается из главного тика
+	pub fn tick_time(&self) {
+		self.world_age.fetch_add(1, Ordering::SeqCst);
+		if self.daylight_cycle() {
+			self.set_time_of_day(self.time_of_day() + 1);
+		}
+	}
+
+	/// Сохраняет все измененные чанки в регионы
+	///
+	/// Один несохраненный чанк не мешает остальным, возвращается последняя ошибка
+	pub fn save(&self) -> Result<(), ServerError> {
+		// Пишем на диск не держа блокировок DashMap, иначе встанет всё что берет чанки
+		let chunks: Vec<_> = self.chunks.iter().map(|o| o.value().clone()).collect();
+
+		let mut result = Ok(());
+		for chunk in chunks {
+			let mut chunk = chunk.write().unwrap();
+			if !chunk.is_dirty() {
+				continue;
+			}
+			match self.storage.save_chunk(&chunk) {
+				Ok(()) => chunk.set_dirty(false),
+				Err(error) => {
+					error!(
+						"Не удалось сохранить чанк {} {}: {error:?}",
+						chunk.x, chunk.z
+					);
+					result = Err(error);
+				}
+			}
 		}
+		result
+	}
+
+	/// Чанк по его координатам, если его нет в памяти то он создается
+	pub fn get_chunk(&self, x: i32, z: i32) -> Arc<RwLock<Chunk>> {
+		if let Some(chunk) = self.get_loaded_chunk(x, z) {
+			return chunk;
+		}
+
+		// Загрузка и генерация идут без блокировки DashMap, чтобы не держать весь шард.
+		// Если чанк успели загрузить параллельно, то остается тот что вставлен первым
+		let chunk = match self.storage.load_chunk(x, z) {
+			Ok(Some(chunk)) => chunk,
+			Ok(None) => self.generator.generate(x, z),
+			Err(error) => {
+				error!("Не удалось загрузить чанк {x} {z}: {error:?}");
+				self.generator.generate(x, z)
+			}
+		};
+
+		self
+			.chunks
+			.entry((x, z))
+			.or_insert_with(|| Arc::new(RwLock::new(chunk)))
+			.clone()
+	}
+
+	/// Чанк по его координатам, только если он уже загружен
+	pub fn get_loaded_chunk(&self, x: i32, z: i32) -> Option<Arc<RwLock<Chunk>>> {
+		self.chunks.get(&(x, z)).map(|o| o.clone())
+	}
+
+	/// Заменяет чанк целиком, например если плагин сгенерировал его сам
+	pub fn set_chunk(&self, chunk: Chunk) {
+		self
+			.chunks
+			.insert((chunk.x, chunk.z), Arc::new(RwLock::new(chunk)));
+	}
+
+	/// Выгружает чанк из памяти, сохраняя его если он менялся
+	pub fn unload_chunk(&self, x: i32, z: i32) -> Result<Option<Arc<RwLock<Chunk>>>, ServerError> {
+		let Some((_, chunk)) = self.chunks.remove(&(x, z)) else {
+			return Ok(None);
+		};
+		{
+			let mut guard = chunk.write().unwrap();
+			if guard.is_dirty() {
+				if let Err(error) = self.storage.save_chunk(&guard) {
+					// Не сохранили - оставляем в памяти, иначе изменения пропадут
+					drop(guard);
+					self.chunks.entry((x, z)).or_insert(chunk);
+					return Err(error);
+				}
+				guard.set_dirty(false);
+			}
+		}
+		Ok(Some(chunk))
+	}
+
+	/// Выгружает все чанки кроме used, возвращает сколько выгружено
+	pub fn unload_unused_chunks(&self, used: &HashSet<(i32, i32)>) -> usize {
+		let unused: Vec<_> = self
+			.chunks
+			.iter()
+			.map(|o| *o.key())
+			.filter(|o| !used.contains(o))
+			.collect();
+
+		let mut count = 0;
+		for (x, z) in unused {
+			match self.unload_chunk(x, z) {
+				Ok(Some(_)) => count += 1,
+				Ok(None) => {}
+				Err(error) => error!("Не удалось выгрузить чанк {x} {z}: {error:?}"),
+			}
+		}
+		count
+	}
+
+	/// Айди состояния блока по мировым координатам
+	pub fn get_block(&self, x: i32, y: i32, z: i32) -> u32 {
+		let ((chunk_x, chunk_z), (x, z)) = block_to_chunk(x, z);
+		self
+			.get_chunk(chunk_x, chunk_z)
+			.read()
+			.unwrap()
+			.get_block(x, y, z)
+	}
+
+	/// Высота самого верхнего не-воздушного блока в столбе, None если столб пустой
+	pub fn get_highest_block(&self, x: i32, z: i32) -> Option<i32> {
+		let ((chunk_x, chunk_z), (x, z)) = block_to_chunk(x, z);
+		let chunk = self.get_chunk(chunk_x, chunk_z);
+		let chunk = chunk.read().unwrap();
+		match chunk.compute_heightmap()[z * 16 + x] {
+			0 => None,
+			height => Some(chunk.min_y() + height as i32 - 1),
+		}
+	}
+
+	/// Ставит блок по мировым координатам, возвращает предыдущий или None если y вне мира
+	///
+	/// Клиентам ничего не отправляется
+	pub fn set_block(&self, x: i32, y: i32, z: i32, state: u32) -> Option<u32> {
+		let ((chunk_x, chunk_z), (x, z)) = block_to_chunk(x, z);
+		self
+			.get_chunk(chunk_x, chunk_z)
+			.write()
+			.unwrap()
+			.set_block(x, y, z, state)
 	}
 }
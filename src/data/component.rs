@@ -112,6 +112,7 @@ impl Default for TextComponent {
 	}
 }
 
+#[derive(Default)]
 pub struct TextComponentBuilder {
 	text: String,
 	color: Option<String>,
@@ -125,16 +126,7 @@ pub struct TextComponentBuilder {
 
 impl TextComponentBuilder {
 	pub fn new() -> Self {
-		Self {
-			text: String::new(),
-			color: None,
-			bold: None,
-			italic: None,
-			underlined: None,
-			strikethrough: None,
-			obfuscated: None,
-			extra: None,
-		}
+		Self::default()
 	}
 
 	pub fn text(mut self, text: &str) -> Self {
@@ -0,0 +1,1488 @@
+// Хеши компонентов предметов для Hashed Slot, считаются как у ваниллы (HashOps)
+//
+// Компонент кодируется своим кодеком, тем же что пишет предметы в мир,
+// только вместо NBT получается дерево HashValue, которое хешируется CRC32C.
+// Ссылки на реестры в кодеках пишутся именами, поэтому нужны Registries с загруженным отчетом.
+// Если имя не нашлось, пишется айди числом - хеш не сойдется и слот просто пересинхронизируется
+
+use std::collections::HashMap;
+
+use craftflow_nbt::DynNBT;
+use enum_index::EnumIndex;
+use uuid::Uuid;
+
+use crate::play::registry::Registries;
+
+use super::{
+	IdOr, IdSet, Position, Property,
+	component::TextComponent,
+	slot::{
+		AttributeModifier, BannerLayer, BannerPattern, BlockPredicate, BlockPredicatePropertyMatch,
+		ChickenVariant, ConsumeEffect, DamageReduction, FireworkExplosion, HiveBee, Instrument,
+		InstrumentComponent, JukeboxPlayable, JukeboxSong, PaintingVariant, PotionEffect,
+		PotionEffectDetail, ProvidesTrimMaterial, SLOT_COMPONENT_LENGTH, Slot, StructuredComponent,
+		ToolRule, TrimMaterial, TrimPattern,
+	},
+	sound::SoundEvent,
+};
+
+const TAG_EMPTY: u8 = 1;
+const TAG_MAP_START: u8 = 2;
+const TAG_MAP_END: u8 = 3;
+const TAG_LIST_START: u8 = 4;
+const TAG_LIST_END: u8 = 5;
+const TAG_BYTE: u8 = 6;
+const TAG_SHORT: u8 = 7;
+const TAG_INT: u8 = 8;
+const TAG_LONG: u8 = 9;
+const TAG_FLOAT: u8 = 10;
+const TAG_DOUBLE: u8 = 11;
+const TAG_STRING: u8 = 12;
+const TAG_BOOLEAN: u8 = 13;
+const TAG_BYTE_ARRAY_START: u8 = 14;
+const TAG_BYTE_ARRAY_END: u8 = 15;
+const TAG_INT_ARRAY_START: u8 = 16;
+const TAG_INT_ARRAY_END: u8 = 17;
+const TAG_LONG_ARRAY_START: u8 = 18;
+const TAG_LONG_ARRAY_END: u8 = 19;
+
+/// Имена типов компонентов, индекс - айди компонента
+pub const COMPONENT_NAMES: [&str; SLOT_COMPONENT_LENGTH as usize] = [
+	"minecraft:custom_data",
+	"minecraft:max_stack_size",
+	"minecraft:max_damage",
+	"minecraft:damage",
+	"minecraft:unbreakable",
+	"minecraft:custom_name",
+	"minecraft:item_name",
+	"minecraft:item_model",
+	"minecraft:lore",
+	"minecraft:rarity",
+	"minecraft:enchantments",
+	"minecraft:can_place_on",
+	"minecraft:can_break",
+	"minecraft:attribute_modifiers",
+	"minecraft:custom_model_data",
+	"minecraft:tooltip_display",
+	"minecraft:repair_cost",
+	"minecraft:creative_slot_lock",
+	"minecraft:enchantment_glint_override",
+	"minecraft:intangible_projectile",
+	"minecraft:food",
+	"minecraft:consumable",
+	"minecraft:use_remainder",
+	"minecraft:use_cooldown",
+	"minecraft:damage_resistant",
+	"minecraft:tool",
+	"minecraft:weapon",
+	"minecraft:enchantable",
+	"minecraft:equippable",
+	"minecraft:repairable",
+	"minecraft:glider",
+	"minecraft:tooltip_style",
+	"minecraft:death_protection",
+	"minecraft:blocks_attacks",
+	"minecraft:stored_enchantments",
+	"minecraft:dyed_color",
+	"minecraft:map_color",
+	"minecraft:map_id",
+	"minecraft:map_decorations",
+	"minecraft:map_post_processing",
+	"minecraft:charged_projectiles",
+	"minecraft:bundle_contents",
+	"minecraft:potion_contents",
+	"minecraft:potion_duration_scale",
+	"minecraft:suspicious_stew_effects",
+	"minecraft:writable_book_content",
+	"minecraft:written_book_content",
+	"minecraft:trim",
+	"minecraft:debug_stick_state",
+	"minecraft:entity_data",
+	"minecraft:bucket_entity_data",
+	"minecraft:block_entity_data",
+	"minecraft:instrument",
+	"minecraft:provides_trim_material",
+	"minecraft:ominous_bottle_amplifier",
+	"minecraft:jukebox_playable",
+	"minecraft:provides_banner_patterns",
+	"minecraft:recipes",
+	"minecraft:lodestone_tracker",
+	"minecraft:firework_explosion",
+	"minecraft:fireworks",
+	"minecraft:profile",
+	"minecraft:note_block_sound",
+	"minecraft:banner_patterns",
+	"minecraft:base_color",
+	"minecraft:pot_decorations",
+	"minecraft:container",
+	"minecraft:block_state",
+	"minecraft:bees",
+	"minecraft:lock",
+	"minecraft:container_loot",
+	"minecraft:break_sound",
+	"minecraft:villager/variant",
+	"minecraft:wolf/variant",
+	"minecraft:wolf/sound_variant",
+	"minecraft:wolf/collar",
+	"minecraft:fox/variant",
+	"minecraft:salmon/size",
+	"minecraft:parrot/variant",
+	"minecraft:tropical_fish/pattern",
+	"minecraft:tropical_fish/base_color",
+	"minecraft:tropical_fish/pattern_color",
+	"minecraft:mooshroom/variant",
+	"minecraft:rabbit/variant",
+	"minecraft:pig/variant",
+	"minecraft:cow/variant",
+	"minecraft:chicken/variant",
+	"minecraft:frog/variant",
+	"minecraft:horse/variant",
+	"minecraft:painting/variant",
+	"minecraft:llama/variant",
+	"minecraft:axolotl/variant",
+	"minecraft:cat/variant",
+	"minecraft:cat/collar",
+	"minecraft:sheep/color",
+	"minecraft:shulker/color",
+];
+
+// Перечисления в кодеках пишутся строками, индекс - айди в протоколе
+pub(super) const DYE_COLORS: [&str; 16] = [
+	"white",
+	"orange",
+	"magenta",
+	"light_blue",
+	"yellow",
+	"lime",
+	"pink",
+	"gray",
+	"light_gray",
+	"cyan",
+	"purple",
+	"blue",
+	"brown",
+	"green",
+	"red",
+	"black",
+];
+pub(super) const RARITIES: [&str; 4] = ["common", "uncommon", "rare", "epic"];
+const USE_ANIMATIONS: [&str; 11] = [
+	"none",
+	"eat",
+	"drink",
+	"block",
+	"bow",
+	"spear",
+	"crossbow",
+	"spyglass",
+	"toot_horn",
+	"brush",
+	"bundle",
+];
+const ATTRIBUTE_OPERATIONS: [&str; 3] =
+	["add_value", "add_multiplied_base", "add_multiplied_total"];
+const EQUIPMENT_SLOT_GROUPS: [&str; 11] = [
+	"any", "mainhand", "offhand", "hand", "feet", "legs", "chest", "head", "armor", "body", "saddle",
+];
+const EQUIPMENT_SLOTS: [&str; 8] = [
+	"mainhand", "feet", "legs", "chest", "head", "offhand", "body", "saddle",
+];
+pub(super) const MAP_POST_PROCESSING: [&str; 2] = ["lock", "scale"];
+const FIREWORK_SHAPES: [&str; 5] = ["small_ball", "large_ball", "star", "creeper", "burst"];
+const FOX_VARIANTS: [&str; 2] = ["red", "snow"];
+const SALMON_SIZES: [&str; 3] = ["small", "medium", "large"];
+const PARROT_VARIANTS: [&str; 5] = ["red_blue", "blue", "green", "yellow_blue", "gray"];
+const TROPICAL_FISH_PATTERNS: [&str; 12] = [
+	"kob",
+	"sunstreak",
+	"snooper",
+	"dasher",
+	"brinely",
+	"spotty",
+	"flopper",
+	"stripey",
+	"glitter",
+	"blockfish",
+	"betty",
+	"clayfish",
+];
+const MOOSHROOM_VARIANTS: [&str; 2] = ["red", "brown"];
+const RABBIT_VARIANTS: [&str; 6] = ["brown", "white", "black", "white_splotched", "gold", "salt"];
+const HORSE_VARIANTS: [&str; 7] = [
+	"white",
+	"creamy",
+	"chestnut",
+	"brown",
+	"black",
+	"gray",
+	"dark_brown",
+];
+const LLAMA_VARIANTS: [&str; 4] = ["creamy", "white", "brown", "gray"];
+const AXOLOTL_VARIANTS: [&str; 5] = ["lucy", "wild", "gold", "cyan", "blue"];
+
+/// Значение в том виде, в каком его видит кодек ваниллы
+#[derive(Debug, Clone, PartialEq)]
+pub enum HashValue {
+	Empty,
+	Bool(bool),
+	Byte(i8),
+	Short(i16),
+	Int(i32),
+	Long(i64),
+	Float(f32),
+	Double(f64),
+	String(String),
+	List(Vec<HashValue>),
+	Map(Vec<(String, HashValue)>),
+	ByteArray(Vec<u8>),
+	IntArray(Vec<i32>),
+	LongArray(Vec<i64>),
+}
+
+impl HashValue {
+	/// CRC32C значения, числа пишутся в little endian как в guava
+	pub fn hash(&self) -> u32 {
+		let mut data = Vec::new();
+
+		match self {
+			HashValue::Empty => data.push(TAG_EMPTY),
+			HashValue::Bool(value) => data.extend([TAG_BOOLEAN, *value as u8]),
+			HashValue::Byte(value) => data.extend([TAG_BYTE, *value as u8]),
+			HashValue::Short(value) => {
+				data.push(TAG_SHORT);
+				data.extend(value.to_le_bytes());
+			}
+			HashValue::Int(value) => {
+				data.push(TAG_INT);
+				data.extend(value.to_le_bytes());
+			}
+			HashValue::Long(value) => {
+				data.push(TAG_LONG);
+				data.extend(value.to_le_bytes());
+			}
+			HashValue::Float(value) => {
+				data.push(TAG_FLOAT);
+				data.extend(value.to_bits().to_le_bytes());
+			}
+			HashValue::Double(value) => {
+				data.push(TAG_DOUBLE);
+				data.extend(value.to_bits().to_le_bytes());
+			}
+			// Длина и символы в UTF-16, как строки в джаве
+			HashValue::String(value) => {
+				let chars = value.encode_utf16().collect::<Vec<u16>>();
+				data.push(TAG_STRING);
+				data.extend((chars.len() as i32).to_le_bytes());
+				for char in chars {
+					data.extend(char.to_le_bytes());
+				}
+			}
+			HashValue::List(values) => {
+				data.push(TAG_LIST_START);
+				for value in values {
+					data.extend(value.hash().to_le_bytes());
+				}
+				data.push(TAG_LIST_END);
+			}
+			// Порядок полей не важен: пары сортируются по хешам ключа и значения
+			HashValue::Map(entries) => {
+				let mut entries = entries
+					.iter()
+					.map(|(key, value)| (HashValue::String(key.clone()).hash(), value.hash()))
+					.collect::<Vec<(u32, u32)>>();
+				entries.sort();
+
+				data.push(TAG_MAP_START);
+				for (key, value) in entries {
+					data.extend(key.to_le_bytes());
+					data.extend(value.to_le_bytes());
+				}
+				data.push(TAG_MAP_END);
+			}
+			HashValue::ByteArray(values) => {
+				data.push(TAG_BYTE_ARRAY_START);
+				data.extend(values);
+				data.push(TAG_BYTE_ARRAY_END);
+			}
+			HashValue::IntArray(values) => {
+				data.push(TAG_INT_ARRAY_START);
+				for value in values {
+					data.extend(value.to_le_bytes());
+				}
+				data.push(TAG_INT_ARRAY_END);
+			}
+			HashValue::LongArray(values) => {
+				data.push(TAG_LONG_ARRAY_START);
+				for value in values {
+					data.extend(value.to_le_bytes());
+				}
+				data.push(TAG_LONG_ARRAY_END);
+			}
+		}
+
+		crc32c::crc32c(&data)
+	}
+
+	/// NBT переводится один в один, как это делает NbtOps
+	pub fn from_nbt(nbt: &DynNBT) -> HashValue {
+		match nbt {
+			DynNBT::Byte(value) => HashValue::Byte(*value),
+			DynNBT::Short(value) => HashValue::Short(*value),
+			DynNBT::Int(value) => HashValue::Int(*value),
+			DynNBT::Long(value) => HashValue::Long(*value),
+			DynNBT::Float(value) => HashValue::Float(*value),
+			DynNBT::Double(value) => HashValue::Double(*value),
+			DynNBT::String(value) => HashValue::String(value.clone()),
+			DynNBT::List(values) => HashValue::List(values.iter().map(HashValue::from_nbt).collect()),
+			DynNBT::Compound(values) => HashValue::Map(
+				values
+					.iter()
+					.map(|(k, v)| (k.clone(), HashValue::from_nbt(v)))
+					.collect(),
+			),
+			DynNBT::ByteArray(values) => HashValue::ByteArray(values.clone()),
+			DynNBT::IntArray(values) => HashValue::IntArray(values.clone()),
+			DynNBT::LongArray(values) => HashValue::LongArray(values.clone()),
+		}
+	}
+
+	/// Обратно в NBT, как это делает NbtOps: bool становится байтом, пустое значение - пустым
+	/// компаундом, а списки байтов, интов и лонгов - массивами
+	pub fn to_nbt(&self) -> DynNBT {
+		match self {
+			HashValue::Empty => DynNBT::Compound(HashMap::new()),
+			HashValue::Bool(value) => DynNBT::Byte(*value as i8),
+			HashValue::Byte(value) => DynNBT::Byte(*value),
+			HashValue::Short(value) => DynNBT::Short(*value),
+			HashValue::Int(value) => DynNBT::Int(*value),
+			HashValue::Long(value) => DynNBT::Long(*value),
+			HashValue::Float(value) => DynNBT::Float(*value),
+			HashValue::Double(value) => DynNBT::Double(*value),
+			HashValue::String(value) => DynNBT::String(value.clone()),
+			HashValue::List(values) => {
+				let bytes = values
+					.iter()
+					.map(|o| match o {
+						HashValue::Byte(value) => Some(*value as u8),
+						HashValue::Bool(value) => Some(*value as u8),
+						_ => None,
+					})
+					.collect::<Option<Vec<u8>>>();
+				let ints = values
+					.iter()
+					.map(|o| match o {
+						HashValue::Int(value) => Some(*value),
+						_ => None,
+					})
+					.collect::<Option<Vec<i32>>>();
+				let longs = values
+					.iter()
+					.map(|o| match o {
+						HashValue::Long(value) => Some(*value),
+						_ => None,
+					})
+					.collect::<Option<Vec<i64>>>();
+
+				match (bytes, ints, longs) {
+					_ if values.is_empty() => DynNBT::List(Vec::new()),
+					(Some(bytes), _, _) => DynNBT::ByteArray(bytes),
+					(_, Some(ints), _) => DynNBT::IntArray(ints),
+					(_, _, Some(longs)) => DynNBT::LongArray(longs),
+					_ => DynNBT::List(values.iter().map(|o| o.to_nbt()).collect()),
+				}
+			}
+			HashValue::Map(entries) => DynNBT::Compound(
+				entries
+					.iter()
+					.map(|(k, v)| (k.clone(), v.to_nbt()))
+					.collect(),
+			),
+			HashValue::ByteArray(values) => DynNBT::ByteArray(values.clone()),
+			HashValue::IntArray(values) => DynNBT::IntArray(values.clone()),
+			HashValue::LongArray(values) => DynNBT::LongArray(values.clone()),
+		}
+	}
+
+	fn string(value: &str) -> HashValue {
+		HashValue::String(value.to_string())
+	}
+
+	// Айди перечисления строкой, неизвестный айди остается числом
+	fn named(names: &[&str], id: u8) -> HashValue {
+		match names.get(id as usize) {
+			Some(name) => HashValue::string(name),
+			None => HashValue::Int(id as i32),
+		}
+	}
+
+	fn uuid(uuid: &Uuid) -> HashValue {
+		let (most, least) = uuid.as_u64_pair();
+		HashValue::IntArray(vec![
+			(most >> 32) as i32,
+			most as i32,
+			(least >> 32) as i32,
+			least as i32,
+		])
+	}
+
+	fn position(position: &Position) -> HashValue {
+		HashValue::IntArray(vec![position.x, position.y, position.z])
+	}
+}
+
+// Поля записи, поля без значения и со значением по умолчанию кодек не пишет
+#[derive(Default)]
+struct Fields(Vec<(String, HashValue)>);
+
+impl Fields {
+	fn field(mut self, name: &str, value: HashValue) -> Fields {
+		self.0.push((name.to_string(), value));
+		self
+	}
+
+	fn optional(self, name: &str, value: Option<HashValue>) -> Fields {
+		match value {
+			Some(value) => self.field(name, value),
+			None => self,
+		}
+	}
+
+	fn or_default(self, name: &str, value: HashValue, default: HashValue) -> Fields {
+		if value == default {
+			self
+		} else {
+			self.field(name, value)
+		}
+	}
+
+	fn build(self) -> HashValue {
+		HashValue::Map(self.0)
+	}
+}
+
+fn empty_list() -> HashValue {
+	HashValue::List(Vec::new())
+}
+
+fn empty_map() -> HashValue {
+	HashValue::Map(Vec::new())
+}
+
+/// Переводит компоненты в HashValue, имена записей реестров берет из registries
+pub struct ComponentHasher<'a> {
+	registries: &'a Registries,
+}
+
+impl<'a> ComponentHasher<'a> {
+	pub fn new(registries: &'a Registries) -> ComponentHasher<'a> {
+		ComponentHasher { registries }
+	}
+
+	/// Хеш компонента для Hashed Slot
+	pub fn hash(&self, component: &StructuredComponent) -> i32 {
+		self.component(component).hash() as i32
+	}
+
+	fn name(&self, registry: &str, id: u32) -> HashValue {
+		match self.registries.entry_name(registry, id) {
+			Some(name) => HashValue::string(name),
+			None => HashValue::Int(id as i32),
+		}
+	}
+
+	// Ссылка на запись реестра: имя, либо само значение если оно передано целиком
+	fn holder<T>(
+		&self,
+		registry: &str,
+		holder: &IdOr<T>,
+		direct: impl Fn(&Self, &T) -> HashValue,
+	) -> HashValue {
+		match holder {
+			IdOr::Id(id) => self.name(registry, *id as u32),
+			IdOr::Or(value) => direct(self, value),
+		}
+	}
+
+	// Тег пишется с решеткой, одна запись - просто именем, несколько - списком
+	fn id_set(&self, registry: &str, set: &IdSet) -> HashValue {
+		match set {
+			IdSet::Tag(tag) => HashValue::String(format!("#{tag}")),
+			IdSet::Ids(ids) if ids.len() == 1 => self.name(registry, ids[0]),
+			IdSet::Ids(ids) => HashValue::List(ids.iter().map(|o| self.name(registry, *o)).collect()),
+		}
+	}
+
+	fn text(&self, text: &TextComponent) -> HashValue {
+		// Текст без стиля и детей кодек сворачивает в строку
+		if text.color.is_none()
+			&& text.bold.is_none()
+			&& text.italic.is_none()
+			&& text.underlined.is_none()
+			&& text.strikethrough.is_none()
+			&& text.obfuscated.is_none()
+			&& text.extra.is_none()
+		{
+			return HashValue::string(&text.text);
+		}
+
+		Fields::default()
+			.field("text", HashValue::string(&text.text))
+			.optional("color", text.color.as_deref().map(HashValue::string))
+			.optional("bold", text.bold.map(HashValue::Bool))
+			.optional("italic", text.italic.map(HashValue::Bool))
+			.optional("underlined", text.underlined.map(HashValue::Bool))
+			.optional("strikethrough", text.strikethrough.map(HashValue::Bool))
+			.optional("obfuscated", text.obfuscated.map(HashValue::Bool))
+			.optional(
+				"extra",
+				text
+					.extra
+					.as_ref()
+					.map(|o| HashValue::List(o.iter().map(|o| self.text(o)).collect())),
+			)
+			.build()
+	}
+
+	fn sound(&self, sound: &IdOr<SoundEvent>) -> HashValue {
+		self.holder("minecraft:sound_event", sound, |_, sound| {
+			Fields::default()
+				.field("sound_id", HashValue::string(&sound.sound_name))
+				.optional("range", sound.fixed_range.map(HashValue::Float))
+				.build()
+		})
+	}
+
+	/// Предмет кодеком ItemStack, в таком виде он и хранится в NBT
+	pub fn item(&self, item: &Slot) -> HashValue {
+		Fields::default()
+			.field("id", self.name("minecraft:item", item.id as u32))
+			.or_default("count", HashValue::Int(item.amount), HashValue::Int(1))
+			.or_default(
+				"components",
+				self.patch(&item.components, &item.removed_components),
+				empty_map(),
+			)
+			.build()
+	}
+
+	// Измененные компоненты по именам, удаленные - с восклицательным знаком и пустым значением
+	fn patch(&self, components: &[StructuredComponent], removed: &[u16]) -> HashValue {
+		let mut entries = components
+			.iter()
+			.map(|o| (o.type_name().to_string(), self.component(o)))
+			.collect::<Vec<(String, HashValue)>>();
+
+		for id in removed {
+			if let Some(name) = COMPONENT_NAMES.get(*id as usize) {
+				entries.push((format!("!{name}"), empty_map()));
+			}
+		}
+
+		HashValue::Map(entries)
+	}
+
+	fn enchantments(&self, enchantments: &[(u64, i32)]) -> HashValue {
+		HashValue::Map(
+			enchantments
+				.iter()
+				.filter_map(|(id, level)| {
+					let name = self
+						.registries
+						.entry_name("minecraft:enchantment", *id as u32)?;
+					Some((name.to_string(), HashValue::Int(*level)))
+				})
+				.collect(),
+		)
+	}
+
+	fn block_predicate(&self, predicate: &BlockPredicate) -> HashValue {
+		Fields::default()
+			.optional(
+				"blocks",
+				predicate
+					.blocks
+					.as_ref()
+					.map(|o| self.id_set("minecraft:block", o)),
+			)
+			.optional(
+				"state",
+				predicate.properties.as_ref().map(|properties| {
+					HashValue::Map(
+						properties
+							.iter()
+							.map(|property| {
+								let value = match &property.matches {
+									BlockPredicatePropertyMatch::Exact(value) => HashValue::string(value),
+									BlockPredicatePropertyMatch::Range(min, max) => Fields::default()
+										.optional("min", min.as_deref().map(HashValue::string))
+										.optional("max", max.as_deref().map(HashValue::string))
+										.build(),
+								};
+								(property.name.clone(), value)
+							})
+							.collect(),
+					)
+				}),
+			)
+			.optional("nbt", predicate.nbt.as_ref().map(HashValue::from_nbt))
+			.or_default(
+				"components",
+				self.patch(&predicate.exact_matchers, &[]),
+				empty_map(),
+			)
+			.or_default(
+				"predicates",
+				HashValue::Map(
+					predicate
+						.partial_matchers
+						.iter()
+						.filter_map(|o| {
+							let name = partial_predicate_name(o.type_id)?;
+							Some((name.to_string(), HashValue::from_nbt(&o.predicate)))
+						})
+						.collect(),
+				),
+				empty_map(),
+			)
+			.build()
+	}
+
+	// Один предикат пишется без списка
+	fn adventure_predicate(&self, predicates: &[BlockPredicate]) -> HashValue {
+		match predicates {
+			[predicate] => self.block_predicate(predicate),
+			predicates => HashValue::List(predicates.iter().map(|o| self.block_predicate(o)).collect()),
+		}
+	}
+
+	fn attribute_modifier(&self, modifier: &AttributeModifier) -> HashValue {
+		Fields::default()
+			.field(
+				"type",
+				self.name("minecraft:attribute", modifier.attribute_id as u32),
+			)
+			.field("id", HashValue::string(&modifier.modifier_id))
+			.field("amount", HashValue::Double(modifier.value))
+			.field(
+				"operation",
+				HashValue::named(&ATTRIBUTE_OPERATIONS, modifier.operation),
+			)
+			.or_default(
+				"slot",
+				HashValue::named(&EQUIPMENT_SLOT_GROUPS, modifier.slot),
+				HashValue::string("any"),
+			)
+			.build()
+	}
+
+	fn effect_detail(&self, detail: &PotionEffectDetail) -> Fields {
+		Fields::default()
+			.or_default(
+				"amplifier",
+				HashValue::Byte(detail.amplifier as i8),
+				HashValue::Byte(0),
+			)
+			.or_default(
+				"duration",
+				HashValue::Int(detail.duration),
+				HashValue::Int(0),
+			)
+			.or_default(
+				"ambient",
+				HashValue::Bool(detail.ambient),
+				HashValue::Bool(false),
+			)
+			.or_default(
+				"show_particles",
+				HashValue::Bool(detail.show_particles),
+				HashValue::Bool(true),
+			)
+			.field("show_icon", HashValue::Bool(detail.show_icon))
+			.optional(
+				"hidden_effect",
+				detail
+					.hidden_effect
+					.as_ref()
+					.map(|o| self.effect_detail(o).build()),
+			)
+	}
+
+	fn effect(&self, effect: &PotionEffect) -> HashValue {
+		self
+			.effect_detail(&effect.detail)
+			.field("id", self.name("minecraft:mob_effect", effect.type_id))
+			.build()
+	}
+
+	fn consume_effect(&self, effect: &ConsumeEffect) -> HashValue {
+		match effect {
+			ConsumeEffect::ApplyEffects(effects, probability) => Fields::default()
+				.field("type", HashValue::string("minecraft:apply_effects"))
+				.field(
+					"effects",
+					HashValue::List(effects.iter().map(|o| self.effect(o)).collect()),
+				)
+				.or_default(
+					"probability",
+					HashValue::Float(*probability),
+					HashValue::Float(1.0),
+				),
+			ConsumeEffect::RemoveEffects(effects) => Fields::default()
+				.field("type", HashValue::string("minecraft:remove_effects"))
+				.field("effects", self.id_set("minecraft:mob_effect", effects)),
+			ConsumeEffect::ClearAllEffects => {
+				Fields::default().field("type", HashValue::string("minecraft:clear_all_effects"))
+			}
+			ConsumeEffect::TeleportRandomly(diameter) => Fields::default()
+				.field("type", HashValue::string("minecraft:teleport_randomly"))
+				.or_default(
+					"diameter",
+					HashValue::Float(*diameter),
+					HashValue::Float(16.0),
+				),
+			ConsumeEffect::PlaySound(sound) => Fields::default()
+				.field("type", HashValue::string("minecraft:play_sound"))
+				.field("sound", self.sound(sound)),
+		}
+		.build()
+	}
+
+	fn consume_effects(&self, effects: &[ConsumeEffect]) -> HashValue {
+		HashValue::List(effects.iter().map(|o| self.consume_effect(o)).collect())
+	}
+
+	fn tool_rule(&self, rule: &ToolRule) -> HashValue {
+		Fields::default()
+			.field("blocks", self.id_set("minecraft:block", &rule.blocks))
+			.optional("speed", rule.speed.map(HashValue::Float))
+			.optional(
+				"correct_for_drops",
+				rule.correct_drop_for_blocks.map(HashValue::Bool),
+			)
+			.build()
+	}
+
+	fn damage_reduction(&self, reduction: &DamageReduction) -> HashValue {
+		Fields::default()
+			.or_default(
+				"horizontal_blocking_angle",
+				HashValue::Float(reduction.horizontal_blocking_angle),
+				HashValue::Float(90.0),
+			)
+			.optional(
+				"type",
+				reduction
+					.damage_kind
+					.as_ref()
+					.map(|o| self.id_set("minecraft:damage_type", o)),
+			)
+			.field("base", HashValue::Float(reduction.base))
+			.field("factor", HashValue::Float(reduction.factor))
+			.build()
+	}
+
+	fn filterable<T>(
+		&self,
+		raw: &T,
+		filtered: Option<&T>,
+		value: impl Fn(&T) -> HashValue,
+	) -> HashValue {
+		Fields::default()
+			.field("raw", value(raw))
+			.optional("filtered", filtered.map(value))
+			.build()
+	}
+
+	fn trim_material(&self, material: &TrimMaterial) -> HashValue {
+		Fields::default()
+			.field("asset_name", HashValue::string(&material.suffix))
+			.or_default(
+				"override_armor_assets",
+				HashValue::Map(
+					material
+						.overrides
+						.iter()
+						.map(|(k, v)| (k.clone(), HashValue::string(v)))
+						.collect(),
+				),
+				empty_map(),
+			)
+			.field("description", self.text(&material.description))
+			.build()
+	}
+
+	fn trim_pattern(&self, pattern: &TrimPattern) -> HashValue {
+		Fields::default()
+			.field("asset_id", HashValue::string(&pattern.asset_name))
+			.field("description", self.text(&pattern.description))
+			.or_default(
+				"decal",
+				HashValue::Bool(pattern.decal),
+				HashValue::Bool(false),
+			)
+			.build()
+	}
+
+	fn instrument(&self, instrument: &Instrument) -> HashValue {
+		Fields::default()
+			.field("sound_event", self.sound(&instrument.sound))
+			.field("use_duration", HashValue::Float(instrument.use_duration))
+			.field("range", HashValue::Float(instrument.range))
+			.field("description", self.text(&instrument.description))
+			.build()
+	}
+
+	fn jukebox_song(&self, song: &JukeboxSong) -> HashValue {
+		Fields::default()
+			.field("sound_event", self.sound(&song.sound))
+			.field("description", self.text(&song.description))
+			.field("length_in_seconds", HashValue::Float(song.duration))
+			.field("comparator_output", HashValue::Int(song.output))
+			.build()
+	}
+
+	fn painting_variant(&self, variant: &PaintingVariant) -> HashValue {
+		Fields::default()
+			.field("width", HashValue::Int(variant.width))
+			.field("height", HashValue::Int(variant.height))
+			.field("asset_id", HashValue::string(&variant.asset_id))
+			.optional("title", variant.title.as_ref().map(|o| self.text(o)))
+			.optional("author", variant.author.as_ref().map(|o| self.text(o)))
+			.build()
+	}
+
+	fn banner_layer(&self, layer: &BannerLayer) -> HashValue {
+		Fields::default()
+			.field(
+				"pattern",
+				self.holder(
+					"minecraft:banner_pattern",
+					&layer.pattern,
+					|_, pattern: &BannerPattern| {
+						Fields::default()
+							.field("asset_id", HashValue::string(&pattern.asset_id))
+							.field(
+								"translation_key",
+								HashValue::string(&pattern.translation_key),
+							)
+							.build()
+					},
+				),
+			)
+			.field("color", HashValue::named(&DYE_COLORS, layer.color))
+			.build()
+	}
+
+	fn firework_explosion(&self, explosion: &FireworkExplosion) -> HashValue {
+		let colors =
+			|colors: &[i32]| HashValue::List(colors.iter().map(|o| HashValue::Int(*o)).collect());
+
+		Fields::default()
+			.field("shape", HashValue::named(&FIREWORK_SHAPES, explosion.shape))
+			.or_default("colors", colors(&explosion.colors), empty_list())
+			.or_default("fade_colors", colors(&explosion.fade_colors), empty_list())
+			.or_default(
+				"has_trail",
+				HashValue::Bool(explosion.has_trail),
+				HashValue::Bool(false),
+			)
+			.or_default(
+				"has_twinkle",
+				HashValue::Bool(explosion.has_twinkle),
+				HashValue::Bool(false),
+			)
+			.build()
+	}
+
+	fn property(&self, property: &Property) -> HashValue {
+		Fields::default()
+			.field("name", HashValue::string(&property.name))
+			.field("value", HashValue::string(&property.value))
+			.optional(
+				"signature",
+				property.signature.as_deref().map(HashValue::string),
+			)
+			.build()
+	}
+
+	fn bee(&self, bee: &HiveBee) -> HashValue {
+		Fields::default()
+			.or_default(
+				"entity_data",
+				HashValue::from_nbt(&bee.entity_data),
+				empty_map(),
+			)
+			.field("ticks_in_hive", HashValue::Int(bee.ticks_in_hive))
+			.field("min_ticks_in_hive", HashValue::Int(bee.min_ticks_in_hive))
+			.build()
+	}
+
+	fn strings(&self, values: &[(String, String)]) -> HashValue {
+		HashValue::Map(
+			values
+				.iter()
+				.map(|(k, v)| (k.clone(), HashValue::string(v)))
+				.collect(),
+		)
+	}
+
+	/// Компонент в виде, в каком его пишет кодек
+	pub fn component(&self, component: &StructuredComponent) -> HashValue {
+		match component {
+			StructuredComponent::CustomData(nbt)
+			| StructuredComponent::MapDecorations(nbt)
+			| StructuredComponent::DebugStrickState(nbt)
+			| StructuredComponent::EntityData(nbt)
+			| StructuredComponent::BucketEntityData(nbt)
+			| StructuredComponent::BlockEntityData(nbt)
+			| StructuredComponent::Recipes(nbt)
+			| StructuredComponent::Lock(nbt)
+			| StructuredComponent::ContainerLoot(nbt) => HashValue::from_nbt(nbt),
+			StructuredComponent::MaxStackSize(value)
+			| StructuredComponent::MaxDamage(value)
+			| StructuredComponent::Damage(value)
+			| StructuredComponent::RepairCost(value)
+			| StructuredComponent::MapId(value)
+			| StructuredComponent::DyedColor(value)
+			| StructuredComponent::MapColor(value) => HashValue::Int(*value),
+			StructuredComponent::Unbreakable
+			| StructuredComponent::CreativeSlotLock
+			| StructuredComponent::IntangibleProjectile
+			| StructuredComponent::Glider => empty_map(),
+			StructuredComponent::CustomName(text) | StructuredComponent::ItemName(text) => {
+				self.text(text)
+			}
+			StructuredComponent::ItemModel(name)
+			| StructuredComponent::TooltipStyle(name)
+			| StructuredComponent::NoteBlockSound(name) => HashValue::string(name),
+			StructuredComponent::Lore(lines) => {
+				HashValue::List(lines.iter().map(|o| self.text(o)).collect())
+			}
+			StructuredComponent::Rarity(rarity) => HashValue::named(&RARITIES, *rarity),
+			StructuredComponent::Enchantments(enchantments)
+			| StructuredComponent::StoredEnchantments(enchantments) => self.enchantments(enchantments),
+			StructuredComponent::CanPlaceOn(predicates) | StructuredComponent::CanBreak(predicates) => {
+				self.adventure_predicate(predicates)
+			}
+			StructuredComponent::AttributeModifiers(modifiers) => HashValue::List(
+				modifiers
+					.iter()
+					.map(|o| self.attribute_modifier(o))
+					.collect(),
+			),
+			StructuredComponent::CustomModelData(floats, flags, strings, colors) => Fields::default()
+				.or_default(
+					"floats",
+					HashValue::List(floats.iter().map(|o| HashValue::Float(*o)).collect()),
+					empty_list(),
+				)
+				.or_default(
+					"flags",
+					HashValue::List(flags.iter().map(|o| HashValue::Bool(*o)).collect()),
+					empty_list(),
+				)
+				.or_default(
+					"strings",
+					HashValue::List(strings.iter().map(|o| HashValue::string(o)).collect()),
+					empty_list(),
+				)
+				.or_default(
+					"colors",
+					HashValue::List(colors.iter().map(|o| HashValue::Int(*o)).collect()),
+					empty_list(),
+				)
+				.build(),
+			StructuredComponent::TooltipDisplay(hide_tooltip, hidden) => Fields::default()
+				.or_default(
+					"hide_tooltip",
+					HashValue::Bool(*hide_tooltip),
+					HashValue::Bool(false),
+				)
+				.or_default(
+					"hidden_components",
+					HashValue::List(
+						hidden
+							.iter()
+							.map(|id| match COMPONENT_NAMES.get(*id as usize) {
+								Some(name) => HashValue::string(name),
+								None => HashValue::Int(*id as i32),
+							})
+							.collect(),
+					),
+					empty_list(),
+				)
+				.build(),
+			StructuredComponent::EnchantmentGlintOverride(value) => HashValue::Bool(*value),
+			StructuredComponent::Food(nutrition, saturation, can_always_eat) => Fields::default()
+				.field("nutrition", HashValue::Int(*nutrition as i32))
+				.field("saturation", HashValue::Float(*saturation))
+				.or_default(
+					"can_always_eat",
+					HashValue::Bool(*can_always_eat),
+					HashValue::Bool(false),
+				)
+				.build(),
+			StructuredComponent::Consumable {
+				consume_seconds,
+				animation,
+				sound,
+				has_particles,
+				effects,
+			} => Fields::default()
+				.or_default(
+					"consume_seconds",
+					HashValue::Float(*consume_seconds),
+					HashValue::Float(1.6),
+				)
+				.or_default(
+					"animation",
+					HashValue::named(&USE_ANIMATIONS, *animation),
+					HashValue::string("eat"),
+				)
+				.or_default(
+					"sound",
+					self.sound(sound),
+					HashValue::string("minecraft:entity.generic.eat"),
+				)
+				.or_default(
+					"has_consume_particles",
+					HashValue::Bool(*has_particles),
+					HashValue::Bool(true),
+				)
+				.or_default(
+					"on_consume_effects",
+					self.consume_effects(effects),
+					empty_list(),
+				)
+				.build(),
+			StructuredComponent::UseRemainder(item) => Fields::default()
+				.field("convert_into", self.item(item))
+				.build(),
+			StructuredComponent::UseCooldown { seconds, group } => Fields::default()
+				.field("seconds", HashValue::Float(*seconds))
+				.optional("cooldown_group", group.as_deref().map(HashValue::string))
+				.build(),
+			StructuredComponent::DamageResistant(tag) => Fields::default()
+				.field("types", HashValue::String(format!("#{tag}")))
+				.build(),
+			StructuredComponent::Tool {
+				rules,
+				default_mining_speed,
+				damage_per_block,
+				can_destroy_blocks_in_creative,
+			} => Fields::default()
+				.field(
+					"rules",
+					HashValue::List(rules.iter().map(|o| self.tool_rule(o)).collect()),
+				)
+				.or_default(
+					"default_mining_speed",
+					HashValue::Float(*default_mining_speed),
+					HashValue::Float(1.0),
+				)
+				.or_default(
+					"damage_per_block",
+					HashValue::Int(*damage_per_block),
+					HashValue::Int(1),
+				)
+				.or_default(
+					"can_destroy_blocks_in_creative",
+					HashValue::Bool(*can_destroy_blocks_in_creative),
+					HashValue::Bool(true),
+				)
+				.build(),
+			StructuredComponent::Weapon {
+				damage_per_attack,
+				disable_blocking_for_seconds,
+			} => Fields::default()
+				.or_default(
+					"item_damage_per_attack",
+					HashValue::Int(*damage_per_attack),
+					HashValue::Int(1),
+				)
+				.or_default(
+					"disable_blocking_for_seconds",
+					HashValue::Float(*disable_blocking_for_seconds),
+					HashValue::Float(0.0),
+				)
+				.build(),
+			StructuredComponent::Enchantable(value) => Fields::default()
+				.field("value", HashValue::Int(*value))
+				.build(),
+			StructuredComponent::Equippable {
+				slot,
+				equip_sound,
+				model,
+				camera_overlay,
+				allowed_entities,
+				dispensable,
+				swappable,
+				damage_on_hurt,
+				equip_on_interact,
+			} => Fields::default()
+				.field("slot", HashValue::named(&EQUIPMENT_SLOTS, *slot))
+				.or_default(
+					"equip_sound",
+					self.sound(equip_sound),
+					HashValue::string("minecraft:item.armor.equip_generic"),
+				)
+				.optional("asset_id", model.as_deref().map(HashValue::string))
+				.optional(
+					"camera_overlay",
+					camera_overlay.as_deref().map(HashValue::string),
+				)
+				.optional(
+					"allowed_entities",
+					allowed_entities
+						.as_ref()
+						.map(|o| self.id_set("minecraft:entity_type", o)),
+				)
+				.or_default(
+					"dispensable",
+					HashValue::Bool(*dispensable),
+					HashValue::Bool(true),
+				)
+				.or_default(
+					"swappable",
+					HashValue::Bool(*swappable),
+					HashValue::Bool(true),
+				)
+				.or_default(
+					"damage_on_hurt",
+					HashValue::Bool(*damage_on_hurt),
+					HashValue::Bool(true),
+				)
+				.or_default(
+					"equip_on_interact",
+					HashValue::Bool(*equip_on_interact),
+					HashValue::Bool(false),
+				)
+				.build(),
+			StructuredComponent::Repairable(items) => Fields::default()
+				.field("items", self.id_set("minecraft:item", items))
+				.build(),
+			StructuredComponent::DeathProtection(effects) => Fields::default()
+				.or_default("death_effects", self.consume_effects(effects), empty_list())
+				.build(),
+			StructuredComponent::BlockAttacks {
+				block_delay_seconds,
+				disable_cooldown_scale,
+				damage_reductions,
+				item_damage_threshold,
+				item_damage_base,
+				item_damage_factor,
+				bypassed_by,
+				block_sound,
+				disable_sound,
+			} => Fields::default()
+				.or_default(
+					"block_delay_seconds",
+					HashValue::Float(*block_delay_seconds),
+					HashValue::Float(0.0),
+				)
+				.or_default(
+					"disable_cooldown_scale",
+					HashValue::Float(*disable_cooldown_scale),
+					HashValue::Float(1.0),
+				)
+				.or_default(
+					"damage_reductions",
+					HashValue::List(
+						damage_reductions
+							.iter()
+							.map(|o| self.damage_reduction(o))
+							.collect(),
+					),
+					HashValue::List(vec![self.damage_reduction(&DamageReduction {
+						horizontal_blocking_angle: 90.0,
+						damage_kind: None,
+						base: 0.0,
+						factor: 1.0,
+					})]),
+				)
+				.or_default(
+					"item_damage",
+					Fields::default()
+						.field("threshold", HashValue::Float(*item_damage_threshold))
+						.field("base", HashValue::Float(*item_damage_base))
+						.field("factor", HashValue::Float(*item_damage_factor))
+						.build(),
+					Fields::default()
+						.field("threshold", HashValue::Float(1.0))
+						.field("base", HashValue::Float(0.0))
+						.field("factor", HashValue::Float(1.0))
+						.build(),
+				)
+				.optional(
+					"bypassed_by",
+					bypassed_by
+						.as_ref()
+						.map(|o| HashValue::String(format!("#{o}"))),
+				)
+				.optional("block_sound", block_sound.as_ref().map(|o| self.sound(o)))
+				.optional(
+					"disabled_sound",
+					disable_sound.as_ref().map(|o| self.sound(o)),
+				)
+				.build(),
+			StructuredComponent::MapPostProcessing(value) => {
+				HashValue::named(&MAP_POST_PROCESSING, *value)
+			}
+			StructuredComponent::ChargedProjectiles(items)
+			| StructuredComponent::BundleContents(items) => {
+				HashValue::List(items.iter().map(|o| self.item(o)).collect())
+			}
+			StructuredComponent::PotionContents {
+				potion_id,
+				custom_color,
+				custom_effects,
+				custom_name,
+			} => Fields::default()
+				.optional(
+					"potion",
+					potion_id.map(|o| self.name("minecraft:potion", o as u32)),
+				)
+				.optional("custom_color", custom_color.map(HashValue::Int))
+				.or_default(
+					"custom_effects",
+					HashValue::List(custom_effects.iter().map(|o| self.effect(o)).collect()),
+					empty_list(),
+				)
+				.optional("custom_name", custom_name.as_deref().map(HashValue::string))
+				.build(),
+			StructuredComponent::PotionDurationScale(scale) => HashValue::Float(*scale),
+			StructuredComponent::SuspiciousStewEffects(effects) => HashValue::List(
+				effects
+					.iter()
+					.map(|(id, duration)| {
+						Fields::default()
+							.field("id", self.name("minecraft:mob_effect", *id as u32))
+							.or_default("duration", HashValue::Int(*duration), HashValue::Int(160))
+							.build()
+					})
+					.collect(),
+			),
+			StructuredComponent::WritableBookContent(pages) => Fields::default()
+				.or_default(
+					"pages",
+					HashValue::List(
+						pages
+							.iter()
+							.map(|(raw, filtered)| {
+								self.filterable(raw, filtered.as_ref(), |o| HashValue::string(o))
+							})
+							.collect(),
+					),
+					empty_list(),
+				)
+				.build(),
+			StructuredComponent::WrittenBookContent {
+				raw_title,
+				filtered_title,
+				author,
+				generation,
+				pages,
+				resolved,
+			} => Fields::default()
+				.field(
+					"title",
+					self.filterable(raw_title, filtered_title.as_ref(), |o| HashValue::string(o)),
+				)
+				.field("author", HashValue::string(author))
+				.or_default("generation", HashValue::Int(*generation), HashValue::Int(0))
+				.or_default(
+					"pages",
+					HashValue::List(
+						pages
+							.iter()
+							.map(|(raw, filtered)| self.filterable(raw, filtered.as_ref(), |o| self.text(o)))
+							.collect(),
+					),
+					empty_list(),
+				)
+				.or_default(
+					"resolved",
+					HashValue::Bool(*resolved),
+					HashValue::Bool(false),
+				)
+				.build(),
+			StructuredComponent::Trim(material, pattern) => Fields::default()
+				.field(
+					"material",
+					self.holder("minecraft:trim_material", material, Self::trim_material),
+				)
+				.field(
+					"pattern",
+					self.holder("minecraft:trim_pattern", pattern, Self::trim_pattern),
+				)
+				.build(),
+			StructuredComponent::Instrument(instrument) => match instrument {
+				InstrumentComponent::ById(instrument) => {
+					self.holder("minecraft:instrument", instrument, Self::instrument)
+				}
+				InstrumentComponent::ByName(name) => HashValue::string(name),
+			},
+			StructuredComponent::ProvidesTrimMaterial(material) => match material {
+				ProvidesTrimMaterial::ById(material) => {
+					self.holder("minecraft:trim_material", material, Self::trim_material)
+				}
+				ProvidesTrimMaterial::ByName(name) => HashValue::string(name),
+			},
+			StructuredComponent::OminousBottleAmplifier(value) => HashValue::Int(*value as i32),
+			StructuredComponent::JukeboxPlayable(song) => match song {
+				JukeboxPlayable::ById(song) => {
+					self.holder("minecraft:jukebox_song", song, Self::jukebox_song)
+				}
+				JukeboxPlayable::ByName(name) => HashValue::string(name),
+			},
+			StructuredComponent::ProvidesBannerPatterns(tag) => {
+				HashValue::String(format!("#{}", tag.trim_start_matches('#')))
+			}
+			StructuredComponent::LodestoneTracker {
+				global_position,
+				tracked,
+			} => Fields::default()
+				.optional(
+					"target",
+					global_position.as_ref().map(|(dimension, position)| {
+						Fields::default()
+							.field("dimension", HashValue::string(dimension))
+							.field("pos", HashValue::position(position))
+							.build()
+					}),
+				)
+				.or_default("tracked", HashValue::Bool(*tracked), HashValue::Bool(true))
+				.build(),
+			StructuredComponent::FireworkExplosion(explosion) => self.firework_explosion(explosion),
+			StructuredComponent::Fireworks {
+				flight_duration,
+				explosions,
+			} => Fields::default()
+				.or_default(
+					"flight_duration",
+					HashValue::Byte(*flight_duration as i8),
+					HashValue::Byte(0),
+				)
+				.or_default(
+					"explosions",
+					HashValue::List(
+						explosions
+							.iter()
+							.map(|o| self.firework_explosion(o))
+							.collect(),
+					),
+					empty_list(),
+				)
+				.build(),
+			StructuredComponent::Profile {
+				name,
+				unique_id,
+				properties,
+			} => Fields::default()
+				.optional("name", name.as_deref().map(HashValue::string))
+				.optional("id", unique_id.as_ref().map(HashValue::uuid))
+				.or_default(
+					"properties",
+					HashValue::List(properties.iter().map(|o| self.property(o)).collect()),
+					empty_list(),
+				)
+				.build(),
+			StructuredComponent::BannerPatterns(layers) => {
+				HashValue::List(layers.iter().map(|o| self.banner_layer(o)).collect())
+			}
+			StructuredComponent::BaseColor(color)
+			| StructuredComponent::WolfCollar(color)
+			| StructuredComponent::TropicalFishBaseColor(color)
+			| StructuredComponent::TropicalFishPatternColor(color)
+			| StructuredComponent::CatCollar(color)
+			| StructuredComponent::SheepColor(color)
+			| StructuredComponent::ShulkerColor(color) => HashValue::named(&DYE_COLORS, *color),
+			StructuredComponent::PotDecorations(items) => HashValue::List(
+				items
+					.iter()
+					.map(|o| self.name("minecraft:item", *o as u32))
+					.collect(),
+			),
+			// Пустые слоты не пишутся, у остальных указан номер
+			StructuredComponent::Container(items) => HashValue::List(
+				items
+					.iter()
+					.enumerate()
+					.filter_map(|(slot, item)| {
+						Some(
+							Fields::default()
+								.field("slot", HashValue::Int(slot as i32))
+								.field("item", self.item(item.as_ref()?))
+								.build(),
+						)
+					})
+					.collect(),
+			),
+			StructuredComponent::BlockState(properties) => self.strings(properties),
+			StructuredComponent::Bees(bees) => {
+				HashValue::List(bees.iter().map(|o| self.bee(o)).collect())
+			}
+			StructuredComponent::BreakSound(sound) => self.sound(sound),
+			StructuredComponent::VillagerVariant(id) => self.name("minecraft:villager_type", *id as u32),
+			StructuredComponent::WolfVariant(id) => self.name("minecraft:wolf_variant", *id as u32),
+			StructuredComponent::WolfSoundVariant(id) => {
+				self.name("minecraft:wolf_sound_variant", *id as u32)
+			}
+			StructuredComponent::FoxVariant(id) => HashValue::named(&FOX_VARIANTS, *id),
+			StructuredComponent::SalmonSize(id) => HashValue::named(&SALMON_SIZES, *id),
+			StructuredComponent::ParrotVariant(id) => HashValue::named(&PARROT_VARIANTS, *id as u8),
+			StructuredComponent::TropicalFishPattern(id) => {
+				HashValue::named(&TROPICAL_FISH_PATTERNS, *id)
+			}
+			StructuredComponent::MooshroomVariant(id) => HashValue::named(&MOOSHROOM_VARIANTS, *id),
+			// У злого кролика айди 99
+			StructuredComponent::RabbitVariant(99) => HashValue::string("evil"),
+			StructuredComponent::RabbitVariant(id) => HashValue::named(&RABBIT_VARIANTS, *id),
+			StructuredComponent::PigVariant(id) => self.name("minecraft:pig_variant", *id as u32),
+			StructuredComponent::CowVariant(id) => self.name("minecraft:cow_variant", *id as u32),
+			StructuredComponent::ChickenVariant(variant) => match variant {
+				ChickenVariant::ById(id) => self.name("minecraft:chicken_variant", *id),
+				ChickenVariant::ByName(name) => HashValue::string(name),
+			},
+			StructuredComponent::FrogVariant(id) => self.name("minecraft:frog_variant", *id as u32),
+			StructuredComponent::HorseVariant(id) => HashValue::named(&HORSE_VARIANTS, *id),
+			StructuredComponent::PaintingVariant(variant) => self.holder(
+				"minecraft:painting_variant",
+				variant,
+				Self::painting_variant,
+			),
+			StructuredComponent::LlamaVariant(id) => HashValue::named(&LLAMA_VARIANTS, *id),
+			StructuredComponent::AxolotlVariant(id) => HashValue::named(&AXOLOTL_VARIANTS, *id),
+			StructuredComponent::CatVariant(id) => self.name("minecraft:cat_variant", *id as u32),
+		}
+	}
+}
+
+// Типы частичных предикатов компонентов, см. BlockPredicatePartialDataMatcher
+fn partial_predicate_name(id: u8) -> Option<&'static str> {
+	[
+		"minecraft:damage",
+		"minecraft:enchantments",
+		"minecraft:stored_enchantments",
+		"minecraft:potion_contents",
+		"minecraft:custom_data",
+		"minecraft:container",
+		"minecraft:bundle_contents",
+		"minecraft:firework_explosion",
+		"minecraft:fireworks",
+		"minecraft:writable_book_content",
+		"minecraft:written_book_content",
+		"minecraft:attribute_modifiers",
+		"minecraft:trim",
+		"minecraft:jukebox_playable",
+	]
+	.get(id as usize)
+	.copied()
+}
+
+impl StructuredComponent {
+	/// Имя типа компонента, например minecraft:custom_data
+	pub fn type_name(&self) -> &'static str {
+		COMPONENT_NAMES[self.enum_index()]
+	}
+}
@@ -0,0 +1,550 @@
+// Предметы в NBT, в том виде как их хранит ванилла (кодек ItemStack)
+//
+// Запись идет через ComponentHasher: дерево HashValue переводится в NBT один в один.
+// Чтение разбирает кодеки обратно, но не для всех компонентов - незнакомые пропускаются
+// с предупреждением и при следующем сохранении теряются
+
+use std::collections::HashMap;
+
+use craftflow_nbt::DynNBT;
+use log::warn;
+use uuid::Uuid;
+
+use crate::play::registry::Registries;
+
+use super::{
+	IdOr, IdSet, Position, Property,
+	component::TextComponent,
+	hash::{COMPONENT_NAMES, ComponentHasher, DYE_COLORS, MAP_POST_PROCESSING, RARITIES},
+	slot::{
+		InstrumentComponent, JukeboxPlayable, PotionEffect, PotionEffectDetail, ProvidesTrimMaterial,
+		Slot, StructuredComponent,
+	},
+};
+
+/// Предмет в NBT: `{id: "minecraft:stone", count: 1, components: {...}}`
+pub fn item_to_nbt(item: &Slot, registries: &Registries) -> DynNBT {
+	ComponentHasher::new(registries).item(item).to_nbt()
+}
+
+/// Предмет из NBT, None если такого предмета нет в реестре
+pub fn item_from_nbt(nbt: &DynNBT, registries: &Registries) -> Option<Slot> {
+	ComponentReader::new(registries).item(nbt)
+}
+
+// Имя без неймспейса в ванилле значит minecraft:
+fn full_name(name: &str) -> String {
+	if name.contains(':') {
+		name.to_string()
+	} else {
+		format!("minecraft:{name}")
+	}
+}
+
+// NbtOps читает числа из любого числового тега
+fn number(nbt: &DynNBT) -> Option<f64> {
+	match nbt {
+		DynNBT::Byte(value) => Some(*value as f64),
+		DynNBT::Short(value) => Some(*value as f64),
+		DynNBT::Int(value) => Some(*value as f64),
+		DynNBT::Long(value) => Some(*value as f64),
+		DynNBT::Float(value) => Some(*value as f64),
+		DynNBT::Double(value) => Some(*value),
+		_ => None,
+	}
+}
+
+fn int(nbt: &DynNBT) -> Option<i32> {
+	number(nbt).map(|o| o as i32)
+}
+
+fn float(nbt: &DynNBT) -> Option<f32> {
+	number(nbt).map(|o| o as f32)
+}
+
+fn boolean(nbt: &DynNBT) -> Option<bool> {
+	number(nbt).map(|o| o != 0.0)
+}
+
+fn string(nbt: &DynNBT) -> Option<String> {
+	nbt.as_string().cloned()
+}
+
+// Массивы тоже списки, NbtOps пишет в них списки чисел
+fn list(nbt: &DynNBT) -> Option<Vec<DynNBT>> {
+	match nbt {
+		DynNBT::List(values) => Some(values.clone()),
+		DynNBT::ByteArray(values) => Some(values.iter().map(|o| DynNBT::Byte(*o as i8)).collect()),
+		DynNBT::IntArray(values) => Some(values.iter().map(|o| DynNBT::Int(*o)).collect()),
+		DynNBT::LongArray(values) => Some(values.iter().map(|o| DynNBT::Long(*o)).collect()),
+		_ => None,
+	}
+}
+
+fn named(names: &[&str], nbt: &DynNBT) -> Option<u8> {
+	match nbt {
+		DynNBT::String(name) => names.iter().position(|o| o == name).map(|o| o as u8),
+		other => int(other).map(|o| o as u8),
+	}
+}
+
+fn uuid(nbt: &DynNBT) -> Option<Uuid> {
+	match nbt.as_int_array()?.as_slice() {
+		[a, b, c, d] => Some(Uuid::from_u64_pair(
+			((*a as u32 as u64) << 32) | *b as u32 as u64,
+			((*c as u32 as u64) << 32) | *d as u32 as u64,
+		)),
+		_ => None,
+	}
+}
+
+// Поля компаунда, отсутствующее поле берется по умолчанию
+struct Fields<'a>(&'a HashMap<String, DynNBT>);
+
+impl<'a> Fields<'a> {
+	fn new(nbt: &'a DynNBT) -> Option<Fields<'a>> {
+		nbt.as_compound().map(Fields)
+	}
+
+	fn get(&self, name: &str) -> Option<&'a DynNBT> {
+		self.0.get(name)
+	}
+
+	fn int_or(&self, name: &str, default: i32) -> i32 {
+		self.get(name).and_then(int).unwrap_or(default)
+	}
+
+	fn bool_or(&self, name: &str, default: bool) -> bool {
+		self.get(name).and_then(boolean).unwrap_or(default)
+	}
+
+	fn string(&self, name: &str) -> Option<String> {
+		self.get(name).and_then(string)
+	}
+
+	fn list(&self, name: &str) -> Vec<DynNBT> {
+		self.get(name).and_then(list).unwrap_or_default()
+	}
+}
+
+/// Обратное ComponentHasher: читает компоненты из их кодеков
+pub struct ComponentReader<'a> {
+	registries: &'a Registries,
+}
+
+impl<'a> ComponentReader<'a> {
+	pub fn new(registries: &'a Registries) -> ComponentReader<'a> {
+		ComponentReader { registries }
+	}
+
+	fn id(&self, registry: &str, nbt: &DynNBT) -> Option<u32> {
+		match nbt {
+			DynNBT::String(name) => self
+				.registries
+				.entry_id(registry, &full_name(name))
+				.map(|o| o as u32),
+			other => int(other).map(|o| o as u32),
+		}
+	}
+
+	fn holder<T>(&self, registry: &str, nbt: &DynNBT) -> Option<IdOr<T>> {
+		self.id(registry, nbt).map(|o| IdOr::Id(o as i32))
+	}
+
+	fn id_set(&self, registry: &str, nbt: &DynNBT) -> Option<IdSet> {
+		if let Some(tag) = nbt.as_string().and_then(|o| o.strip_prefix('#')) {
+			return Some(IdSet::Tag(full_name(tag)));
+		}
+
+		match list(nbt) {
+			Some(values) => Some(IdSet::Ids(
+				values
+					.iter()
+					.map(|o| self.id(registry, o))
+					.collect::<Option<Vec<u32>>>()?,
+			)),
+			None => Some(IdSet::Ids(vec![self.id(registry, nbt)?])),
+		}
+	}
+
+	fn text(&self, nbt: &DynNBT) -> Option<TextComponent> {
+		match nbt {
+			DynNBT::String(text) => Some(TextComponent::new(text.clone())),
+			// Список - это первый компонент, а остальные его дети
+			DynNBT::List(values) => {
+				let mut values = values.iter().map(|o| self.text(o));
+				let mut text = values.next()??;
+				let extra = values.collect::<Option<Vec<TextComponent>>>()?;
+				if !extra.is_empty() {
+					text.extra.get_or_insert_default().extend(extra);
+				}
+				Some(text)
+			}
+			nbt => {
+				let fields = Fields::new(nbt)?;
+				let mut text = TextComponent::new(fields.string("text").unwrap_or_default());
+				text.color = fields.string("color");
+				text.bold = fields.get("bold").and_then(boolean);
+				text.italic = fields.get("italic").and_then(boolean);
+				text.underlined = fields.get("underlined").and_then(boolean);
+				text.strikethrough = fields.get("strikethrough").and_then(boolean);
+				text.obfuscated = fields.get("obfuscated").and_then(boolean);
+				text.extra = match fields.get("extra").and_then(list) {
+					Some(extra) => Some(
+						extra
+							.iter()
+							.map(|o| self.text(o))
+							.collect::<Option<Vec<TextComponent>>>()?,
+					),
+					None => None,
+				};
+				Some(text)
+			}
+		}
+	}
+
+	// Строка или {raw, filtered}
+	fn filterable<T>(
+		&self,
+		nbt: &DynNBT,
+		value: impl Fn(&DynNBT) -> Option<T>,
+	) -> Option<(T, Option<T>)> {
+		match Fields::new(nbt) {
+			Some(fields) if fields.get("raw").is_some() => Some((
+				value(fields.get("raw")?)?,
+				fields.get("filtered").and_then(&value),
+			)),
+			_ => Some((value(nbt)?, None)),
+		}
+	}
+
+	fn enchantments(&self, nbt: &DynNBT) -> Option<Vec<(u64, i32)>> {
+		nbt
+			.as_compound()?
+			.iter()
+			.map(|(name, level)| {
+				Some((
+					self
+						.registries
+						.entry_id("minecraft:enchantment", &full_name(name))? as u64,
+					int(level)?,
+				))
+			})
+			.collect()
+	}
+
+	fn effect_detail(&self, fields: &Fields) -> PotionEffectDetail {
+		let show_particles = fields.bool_or("show_particles", true);
+
+		PotionEffectDetail {
+			amplifier: fields.int_or("amplifier", 0),
+			duration: fields.int_or("duration", 0),
+			ambient: fields.bool_or("ambient", false),
+			show_particles,
+			show_icon: fields.bool_or("show_icon", show_particles),
+			hidden_effect: fields
+				.get("hidden_effect")
+				.and_then(Fields::new)
+				.map(|o| Box::new(self.effect_detail(&o))),
+		}
+	}
+
+	fn effect(&self, nbt: &DynNBT) -> Option<PotionEffect> {
+		let fields = Fields::new(nbt)?;
+
+		Some(PotionEffect {
+			type_id: self.id("minecraft:mob_effect", fields.get("id")?)?,
+			detail: self.effect_detail(&fields),
+		})
+	}
+
+	fn items(&self, nbt: &DynNBT) -> Option<Vec<Slot>> {
+		list(nbt)?.iter().map(|o| self.item(o)).collect()
+	}
+
+	/// Предмет кодеком ItemStack, компоненты которые не удалось прочитать пропускаются
+	pub fn item(&self, nbt: &DynNBT) -> Option<Slot> {
+		let fields = Fields::new(nbt)?;
+		let id = self.id("minecraft:item", fields.get("id")?)?;
+		let mut item = Slot::new(id as i32, fields.int_or("count", 1));
+
+		let Some(components) = fields.get("components").and_then(|o| o.as_compound()) else {
+			return Some(item);
+		};
+
+		for (name, value) in components {
+			// Удаленный компонент пишется с восклицательным знаком
+			if let Some(name) = name.strip_prefix('!') {
+				match COMPONENT_NAMES.iter().position(|o| *o == full_name(name)) {
+					Some(id) => item.removed_components.push(id as u16),
+					None => warn!("Неизвестный компонент {name} у предмета {id}"),
+				}
+				continue;
+			}
+
+			match self.component(&full_name(name), value) {
+				Some(component) => item.components.push(component),
+				None => warn!("Не удалось прочитать компонент {name} у предмета {id}, он будет потерян"),
+			}
+		}
+
+		Some(item)
+	}
+
+	/// Компонент по имени его типа, None если кодек не поддерживается или значение сломано
+	pub fn component(&self, name: &str, nbt: &DynNBT) -> Option<StructuredComponent> {
+		Some(match name {
+			"minecraft:custom_data" => StructuredComponent::CustomData(nbt.clone()),
+			"minecraft:map_decorations" => StructuredComponent::MapDecorations(nbt.clone()),
+			"minecraft:debug_stick_state" => StructuredComponent::DebugStrickState(nbt.clone()),
+			"minecraft:entity_data" => StructuredComponent::EntityData(nbt.clone()),
+			"minecraft:bucket_entity_data" => StructuredComponent::BucketEntityData(nbt.clone()),
+			"minecraft:block_entity_data" => StructuredComponent::BlockEntityData(nbt.clone()),
+			"minecraft:recipes" => StructuredComponent::Recipes(nbt.clone()),
+			"minecraft:lock" => StructuredComponent::Lock(nbt.clone()),
+			"minecraft:container_loot" => StructuredComponent::ContainerLoot(nbt.clone()),
+			"minecraft:max_stack_size" => StructuredComponent::MaxStackSize(int(nbt)?),
+			"minecraft:max_damage" => StructuredComponent::MaxDamage(int(nbt)?),
+			"minecraft:damage" => StructuredComponent::Damage(int(nbt)?),
+			"minecraft:repair_cost" => StructuredComponent::RepairCost(int(nbt)?),
+			"minecraft:map_id" => StructuredComponent::MapId(int(nbt)?),
+			"minecraft:dyed_color" => StructuredComponent::DyedColor(int(nbt)?),
+			"minecraft:map_color" => StructuredComponent::MapColor(int(nbt)?),
+			"minecraft:unbreakable" => StructuredComponent::Unbreakable,
+			"minecraft:creative_slot_lock" => StructuredComponent::CreativeSlotLock,
+			"minecraft:intangible_projectile" => StructuredComponent::IntangibleProjectile,
+			"minecraft:glider" => StructuredComponent::Glider,
+			"minecraft:custom_name" => StructuredComponent::CustomName(self.text(nbt)?),
+			"minecraft:item_name" => StructuredComponent::ItemName(self.text(nbt)?),
+			"minecraft:item_model" => StructuredComponent::ItemModel(string(nbt)?),
+			"minecraft:tooltip_style" => StructuredComponent::TooltipStyle(string(nbt)?),
+			"minecraft:note_block_sound" => StructuredComponent::NoteBlockSound(string(nbt)?),
+			"minecraft:lore" => StructuredComponent::Lore(
+				list(nbt)?
+					.iter()
+					.map(|o| self.text(o))
+					.collect::<Option<Vec<TextComponent>>>()?,
+			),
+			"minecraft:rarity" => StructuredComponent::Rarity(named(&RARITIES, nbt)?),
+			"minecraft:enchantments" => StructuredComponent::Enchantments(self.enchantments(nbt)?),
+			"minecraft:stored_enchantments" => {
+				StructuredComponent::StoredEnchantments(self.enchantments(nbt)?)
+			}
+			"minecraft:custom_model_data" => {
+				let fields = Fields::new(nbt)?;
+				StructuredComponent::CustomModelData(
+					fields.list("floats").iter().filter_map(float).collect(),
+					fields.list("flags").iter().filter_map(boolean).collect(),
+					fields.list("strings").iter().filter_map(string).collect(),
+					fields.list("colors").iter().filter_map(int).collect(),
+				)
+			}
+			"minecraft:tooltip_display" => {
+				let fields = Fields::new(nbt)?;
+				StructuredComponent::TooltipDisplay(
+					fields.bool_or("hide_tooltip", false),
+					fields
+						.list("hidden_components")
+						.iter()
+						.filter_map(|o| {
+							let name = full_name(o.as_string()?);
+							COMPONENT_NAMES
+								.iter()
+								.position(|o| *o == name)
+								.map(|o| o as u64)
+						})
+						.collect(),
+				)
+			}
+			"minecraft:enchantment_glint_override" => {
+				StructuredComponent::EnchantmentGlintOverride(boolean(nbt)?)
+			}
+			"minecraft:use_remainder" => {
+				StructuredComponent::UseRemainder(self.item(Fields::new(nbt)?.get("convert_into")?)?)
+			}
+			"minecraft:use_cooldown" => {
+				let fields = Fields::new(nbt)?;
+				StructuredComponent::UseCooldown {
+					seconds: float(fields.get("seconds")?)?,
+					group: fields.string("cooldown_group"),
+				}
+			}
+			"minecraft:damage_resistant" => StructuredComponent::DamageResistant(full_name(
+				Fields::new(nbt)?.string("types")?.trim_start_matches('#'),
+			)),
+			"minecraft:enchantable" => {
+				StructuredComponent::Enchantable(int(Fields::new(nbt)?.get("value")?)?)
+			}
+			"minecraft:repairable" => StructuredComponent::Repairable(
+				self.id_set("minecraft:item", Fields::new(nbt)?.get("items")?)?,
+			),
+			"minecraft:map_post_processing" => {
+				StructuredComponent::MapPostProcessing(named(&MAP_POST_PROCESSING, nbt)?)
+			}
+			"minecraft:charged_projectiles" => StructuredComponent::ChargedProjectiles(self.items(nbt)?),
+			"minecraft:bundle_contents" => StructuredComponent::BundleContents(self.items(nbt)?),
+			"minecraft:potion_contents" => match nbt {
+				// Просто зелье можно записать одним именем
+				DynNBT::String(_) => StructuredComponent::PotionContents {
+					potion_id: Some(self.id("minecraft:potion", nbt)? as u64),
+					custom_color: None,
+					custom_effects: Vec::new(),
+					custom_name: None,
+				},
+				nbt => {
+					let fields = Fields::new(nbt)?;
+					StructuredComponent::PotionContents {
+						potion_id: match fields.get("potion") {
+							Some(potion) => Some(self.id("minecraft:potion", potion)? as u64),
+							None => None,
+						},
+						custom_color: fields.get("custom_color").and_then(int),
+						custom_effects: fields
+							.list("custom_effects")
+							.iter()
+							.map(|o| self.effect(o))
+							.collect::<Option<Vec<PotionEffect>>>()?,
+						custom_name: fields.string("custom_name"),
+					}
+				}
+			},
+			"minecraft:potion_duration_scale" => StructuredComponent::PotionDurationScale(float(nbt)?),
+			"minecraft:suspicious_stew_effects" => StructuredComponent::SuspiciousStewEffects(
+				list(nbt)?
+					.iter()
+					.map(|o| {
+						let fields = Fields::new(o)?;
+						Some((
+							self.id("minecraft:mob_effect", fields.get("id")?)? as u64,
+							fields.int_or("duration", 160),
+						))
+					})
+					.collect::<Option<Vec<(u64, i32)>>>()?,
+			),
+			"minecraft:writable_book_content" => StructuredComponent::WritableBookContent(
+				Fields::new(nbt)?
+					.list("pages")
+					.iter()
+					.map(|o| self.filterable(o, string))
+					.collect::<Option<Vec<(String, Option<String>)>>>()?,
+			),
+			"minecraft:written_book_content" => {
+				let fields = Fields::new(nbt)?;
+				let (raw_title, filtered_title) = self.filterable(fields.get("title")?, string)?;
+				StructuredComponent::WrittenBookContent {
+					raw_title,
+					filtered_title,
+					author: fields.string("author")?,
+					generation: fields.int_or("generation", 0),
+					pages: fields
+						.list("pages")
+						.iter()
+						.map(|o| self.filterable(o, |o| self.text(o)))
+						.collect::<Option<Vec<(TextComponent, Option<TextComponent>)>>>()?,
+					resolved: fields.bool_or("resolved", false),
+				}
+			}
+			"minecraft:trim" => {
+				let fields = Fields::new(nbt)?;
+				StructuredComponent::Trim(
+					self.holder("minecraft:trim_material", fields.get("material")?)?,
+					self.holder("minecraft:trim_pattern", fields.get("pattern")?)?,
+				)
+			}
+			"minecraft:instrument" => {
+				StructuredComponent::Instrument(match self.holder("minecraft:instrument", nbt) {
+					Some(id) => InstrumentComponent::ById(id),
+					None => InstrumentComponent::ByName(string(nbt)?),
+				})
+			}
+			"minecraft:provides_trim_material" => StructuredComponent::ProvidesTrimMaterial(
+				match self.holder("minecraft:trim_material", nbt) {
+					Some(id) => ProvidesTrimMaterial::ById(id),
+					None => ProvidesTrimMaterial::ByName(string(nbt)?),
+				},
+			),
+			"minecraft:jukebox_playable" => {
+				StructuredComponent::JukeboxPlayable(match self.holder("minecraft:jukebox_song", nbt) {
+					Some(id) => JukeboxPlayable::ById(id),
+					None => JukeboxPlayable::ByName(string(nbt)?),
+				})
+			}
+			"minecraft:ominous_bottle_amplifier" => {
+				StructuredComponent::OminousBottleAmplifier(int(nbt)? as u8)
+			}
+			"minecraft:provides_banner_patterns" => {
+				StructuredComponent::ProvidesBannerPatterns(full_name(string(nbt)?.trim_start_matches('#')))
+			}
+			"minecraft:lodestone_tracker" => {
+				let fields = Fields::new(nbt)?;
+				StructuredComponent::LodestoneTracker {
+					global_position: match fields.get("target").and_then(Fields::new) {
+						Some(target) => {
+							let pos = target.get("pos")?.as_int_array()?;
+							let [x, y, z] = pos.as_slice() else {
+								return None;
+							};
+							Some((target.string("dimension")?, Position::new(*x, *y, *z)))
+						}
+						None => None,
+					},
+					tracked: fields.bool_or("tracked", true),
+				}
+			}
+			"minecraft:profile" => match nbt {
+				// Профиль можно записать одним ником
+				DynNBT::String(name) => StructuredComponent::Profile {
+					name: Some(name.clone()),
+					unique_id: None,
+					properties: Vec::new(),
+				},
+				nbt => {
+					let fields = Fields::new(nbt)?;
+					StructuredComponent::Profile {
+						name: fields.string("name"),
+						unique_id: fields.get("id").and_then(uuid),
+						properties: fields
+							.list("properties")
+							.iter()
+							.map(|o| {
+								let fields = Fields::new(o)?;
+								Some(Property {
+									name: fields.string("name")?,
+									value: fields.string("value")?,
+									signature: fields.string("signature"),
+								})
+							})
+							.collect::<Option<Vec<Property>>>()?,
+					}
+				}
+			},
+			"minecraft:base_color" => StructuredComponent::BaseColor(named(&DYE_COLORS, nbt)?),
+			"minecraft:pot_decorations" => StructuredComponent::PotDecorations(
+				list(nbt)?
+					.iter()
+					.map(|o| self.id("minecraft:item", o).map(|o| o as u64))
+					.collect::<Option<Vec<u64>>>()?,
+			),
+			"minecraft:container" => {
+				let mut items = Vec::new();
+				for entry in list(nbt)? {
+					let fields = Fields::new(&entry)?;
+					let slot = int(fields.get("slot")?)?.clamp(0, 255) as usize;
+					if items.len() <= slot {
+						items.resize(slot + 1, None);
+					}
+					items[slot] = Some(self.item(fields.get("item")?)?);
+				}
+				StructuredComponent::Container(items)
+			}
+			"minecraft:block_state" => StructuredComponent::BlockState(
+				nbt
+					.as_compound()?
+					.iter()
+					.map(|(k, v)| Some((k.clone(), string(v)?)))
+					.collect::<Option<Vec<(String, String)>>>()?,
+			),
+			_ => return None,
+		})
+	}
+}
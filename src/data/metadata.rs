@@ -0,0 +1,180 @@
+// Метаданные сущностей для Set Entity Metadata
+//
+// Каждое значение - индекс, тип и само значение, список заканчивается индексом 0xFF.
+// Индексы зависят от класса сущности, см. https://minecraft.wiki/w/Java_Edition_protocol/Entity_metadata
+
+use std::collections::BTreeMap;
+
+use rust_mc_proto::{DataReader, DataWriter, Packet};
+
+use crate::{
+	ServerError,
+	protocol::codec::{Decode, Encode, VarInt},
+};
+
+use super::{Position, component::TextComponent, slot::Slot};
+
+// Конец списка метаданных
+const END_INDEX: u8 = 0xFF;
+
+// Индексы базового класса Entity
+pub const FLAGS_INDEX: u8 = 0;
+pub const CUSTOM_NAME_INDEX: u8 = 2;
+pub const CUSTOM_NAME_VISIBLE_INDEX: u8 = 3;
+pub const SILENT_INDEX: u8 = 4;
+pub const NO_GRAVITY_INDEX: u8 = 5;
+
+/// Предмет у сущности minecraft:item
+pub const ITEM_INDEX: u8 = 8;
+
+// Флаги в FLAGS_INDEX
+pub const ON_FIRE_FLAG: u8 = 0x01;
+pub const INVISIBLE_FLAG: u8 = 0x20;
+pub const GLOWING_FLAG: u8 = 0x40;
+
+#[derive(Debug, Clone)]
+pub enum MetadataValue {
+	Byte(u8),
+	VarInt(i32),
+	Float(f32),
+	String(String),
+	TextComponent(TextComponent),
+	OptionalTextComponent(Option<TextComponent>),
+	Slot(Option<Slot>),
+	Boolean(bool),
+	/// Поворот по x, y, z в градусах, например у частей стойки для брони
+	Rotations(f32, f32, f32),
+	Position(Position),
+	/// Айди состояния блока
+	BlockState(i32),
+}
+
+impl MetadataValue {
+	/// Айди типа значения в протоколе
+	pub fn type_id(&self) -> i32 {
+		match self {
+			MetadataValue::Byte(_) => 0,
+			MetadataValue::VarInt(_) => 1,
+			MetadataValue::Float(_) => 3,
+			MetadataValue::String(_) => 4,
+			MetadataValue::TextComponent(_) => 5,
+			MetadataValue::OptionalTextComponent(_) => 6,
+			MetadataValue::Slot(_) => 7,
+			MetadataValue::Boolean(_) => 8,
+			MetadataValue::Rotations(..) => 9,
+			MetadataValue::Position(_) => 10,
+			MetadataValue::BlockState(_) => 14,
+		}
+	}
+}
+
+impl Encode for MetadataValue {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_varint(self.type_id())?;
+
+		match self {
+			MetadataValue::Byte(value) => packet.write_byte(*value)?,
+			MetadataValue::VarInt(value) | MetadataValue::BlockState(value) => {
+				packet.write_varint(*value)?
+			}
+			MetadataValue::Float(value) => packet.write_float(*value)?,
+			MetadataValue::String(value) => packet.write_string(value)?,
+			MetadataValue::TextComponent(value) => value.encode(packet)?,
+			MetadataValue::OptionalTextComponent(value) => value.encode(packet)?,
+			MetadataValue::Slot(value) => value.encode(packet)?,
+			MetadataValue::Boolean(value) => packet.write_boolean(*value)?,
+			MetadataValue::Rotations(x, y, z) => {
+				packet.write_float(*x)?;
+				packet.write_float(*y)?;
+				packet.write_float(*z)?;
+			}
+			MetadataValue::Position(value) => value.encode(packet)?,
+		}
+
+		Ok(())
+	}
+}
+
+impl Decode for MetadataValue {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(match VarInt::decode(packet)?.0 {
+			0 => MetadataValue::Byte(packet.read_byte()?),
+			1 => MetadataValue::VarInt(packet.read_varint()?),
+			3 => MetadataValue::Float(packet.read_float()?),
+			4 => MetadataValue::String(packet.read_string()?),
+			5 => MetadataValue::TextComponent(TextComponent::decode(packet)?),
+			6 => MetadataValue::OptionalTextComponent(Option::decode(packet)?),
+			7 => MetadataValue::Slot(Option::<Slot>::decode(packet)?),
+			8 => MetadataValue::Boolean(packet.read_boolean()?),
+			9 => MetadataValue::Rotations(
+				packet.read_float()?,
+				packet.read_float()?,
+				packet.read_float()?,
+			),
+			10 => MetadataValue::Position(Position::decode(packet)?),
+			14 => MetadataValue::BlockState(packet.read_varint()?),
+			_ => return Err(ServerError::WrongPacket),
+		})
+	}
+}
+
+/// Метаданные сущности по индексам
+#[derive(Debug, Clone, Default)]
+pub struct EntityMetadata(pub BTreeMap<u8, MetadataValue>);
+
+impl EntityMetadata {
+	pub fn new() -> EntityMetadata {
+		EntityMetadata::default()
+	}
+
+	pub fn get(&self, index: u8) -> Option<&MetadataValue> {
+		self.0.get(&index)
+	}
+
+	pub fn set(&mut self, index: u8, value: MetadataValue) {
+		self.0.insert(index, value);
+	}
+
+	pub fn remove(&mut self, index: u8) -> Option<MetadataValue> {
+		self.0.remove(&index)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Только эти индексы, для рассылки изменений
+	pub fn only(&self, indexes: &[u8]) -> EntityMetadata {
+		EntityMetadata(
+			self
+				.0
+				.iter()
+				.filter(|(index, _)| indexes.contains(index))
+				.map(|(index, value)| (*index, value.clone()))
+				.collect(),
+		)
+	}
+}
+
+impl Encode for EntityMetadata {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		for (index, value) in self.0.iter() {
+			packet.write_byte(*index)?;
+			value.encode(packet)?;
+		}
+		Ok(packet.write_byte(END_INDEX)?)
+	}
+}
+
+impl Decode for EntityMetadata {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let mut metadata = EntityMetadata::new();
+		loop {
+			let index = packet.read_byte()?;
+			if index == END_INDEX {
+				return Ok(metadata);
+			}
+			metadata.set(index, MetadataValue::decode(packet)?);
+		}
+	}
+}
@@ -2,13 +2,133 @@ use std::io::Read;
 
 use craftflow_nbt::DynNBT;
 use rust_mc_proto::{DataReader, DataWriter, Packet};
+use serde::{Deserialize, Serialize};
 
-use super::ServerError;
+use super::{
+	ServerError,
+	protocol::codec::{Decode, Encode},
+};
 
 pub mod component;
+pub mod hash;
+pub mod item_nbt;
+pub mod metadata;
 pub mod slot;
 pub mod sound;
 
+/// Глубже NBT не бывает, как NbtAccounter.MAX_DEPTH у ванили
+pub const MAX_NBT_DEPTH: usize = 512;
+
+enum NbtFrame {
+	Compound,
+	// Тип элементов и сколько еще осталось
+	List(u8, usize),
+}
+
+fn take_bytes<'a>(data: &mut &'a [u8], length: usize) -> Result<&'a [u8], ServerError> {
+	if data.len() < length {
+		return Err(ServerError::DeNbt);
+	}
+	let (head, tail) = data.split_at(length);
+	*data = tail;
+	Ok(head)
+}
+
+fn take_length(data: &mut &[u8], size: usize) -> Result<usize, ServerError> {
+	let bytes = take_bytes(data, size)?;
+	let length = if size == 2 {
+		u16::from_be_bytes([bytes[0], bytes[1]]) as i32
+	} else {
+		i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+	};
+	usize::try_from(length).map_err(|_| ServerError::DeNbt)
+}
+
+/// Проходит безымянный NBT без рекурсии и проверяет вложенность
+///
+/// craftflow_nbt читает рекурсивно, и NBT из пакета с тысячами вложенных списков
+/// переполнил бы стек
+pub fn check_nbt_depth(mut data: &[u8]) -> Result<(), ServerError> {
+	let mut stack = Vec::new();
+	let mut tag = take_bytes(&mut data, 1)?[0];
+
+	loop {
+		match tag {
+			0 => {}
+			1 => {
+				take_bytes(&mut data, 1)?;
+			}
+			2 => {
+				take_bytes(&mut data, 2)?;
+			}
+			3 | 5 => {
+				take_bytes(&mut data, 4)?;
+			}
+			4 | 6 => {
+				take_bytes(&mut data, 8)?;
+			}
+			7 => {
+				let length = take_length(&mut data, 4)?;
+				take_bytes(&mut data, length)?;
+			}
+			8 => {
+				let length = take_length(&mut data, 2)?;
+				take_bytes(&mut data, length)?;
+			}
+			9 => {
+				let element = take_bytes(&mut data, 1)?[0];
+				let length = take_length(&mut data, 4)?;
+				// Пустые элементы ничего не занимают, и такой список крутился бы вечно
+				if element == 0 && length > 0 {
+					return Err(ServerError::DeNbt);
+				}
+				stack.push(NbtFrame::List(element, length));
+			}
+			10 => stack.push(NbtFrame::Compound),
+			11 => {
+				let length = take_length(&mut data, 4)?;
+				take_bytes(&mut data, length.checked_mul(4).ok_or(ServerError::DeNbt)?)?;
+			}
+			12 => {
+				let length = take_length(&mut data, 4)?;
+				take_bytes(&mut data, length.checked_mul(8).ok_or(ServerError::DeNbt)?)?;
+			}
+			_ => return Err(ServerError::DeNbt),
+		}
+
+		if stack.len() > MAX_NBT_DEPTH {
+			return Err(ServerError::DeNbt);
+		}
+
+		// Следующий тег: поле компаунда или элемент списка
+		loop {
+			match stack.last_mut() {
+				None => return Ok(()),
+				Some(NbtFrame::Compound) => {
+					let next = take_bytes(&mut data, 1)?[0];
+					if next == 0 {
+						stack.pop();
+						continue;
+					}
+					let name_length = take_length(&mut data, 2)?;
+					take_bytes(&mut data, name_length)?;
+					tag = next;
+					break;
+				}
+				Some(NbtFrame::List(element, remaining)) => {
+					if *remaining == 0 {
+						stack.pop();
+						continue;
+					}
+					*remaining -= 1;
+					tag = *element;
+					break;
+				}
+			}
+		}
+	}
+}
+
 // Трейт для чтения NBT-совместимых приколов
 pub trait ReadWriteNBT<T>: DataReader + DataWriter {
 	fn read_nbt(&mut self) -> Result<T, ServerError>;
@@ -23,6 +143,7 @@ impl ReadWriteNBT<DynNBT> for Packet {
 			.get_mut()
 			.read_to_end(&mut data)
 			.map_err(|_| ServerError::DeNbt)?;
+		check_nbt_depth(&data)?;
 		let (remaining, value) = craftflow_nbt::from_slice(&data).map_err(|_| ServerError::DeNbt)?;
 		self
 			.get_mut()
@@ -52,21 +173,135 @@ impl ReadWritePosition for Packet {
 	}
 }
 
-#[derive(Clone)]
+// Позиция блока, в протоколе упакована в один long
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Position {
+	pub x: i32,
+	pub y: i32,
+	pub z: i32,
+}
+
+impl Position {
+	pub fn new(x: i32, y: i32, z: i32) -> Position {
+		Position { x, y, z }
+	}
+
+	/// Соседний блок со стороны face: 0 - низ, 1 - верх, 2 - север, 3 - юг, 4 - запад, 5 - восток
+	pub fn relative(&self, face: i32) -> Position {
+		match face {
+			0 => Position::new(self.x, self.y - 1, self.z),
+			1 => Position::new(self.x, self.y + 1, self.z),
+			2 => Position::new(self.x, self.y, self.z - 1),
+			3 => Position::new(self.x, self.y, self.z + 1),
+			4 => Position::new(self.x - 1, self.y, self.z),
+			5 => Position::new(self.x + 1, self.y, self.z),
+			_ => *self,
+		}
+	}
+}
+
+impl Encode for Position {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_position(self.x as i64, self.y as i64, self.z as i64)
+	}
+}
+
+impl Decode for Position {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let (x, y, z) = packet.read_position()?;
+		Ok(Position::new(x as i32, y as i32, z as i32))
+	}
+}
+
+#[derive(Debug, Clone)]
 pub enum IdOr<T> {
 	Id(i32),
 	Or(T),
 }
 
-#[derive(Clone)]
+// Айди в реестре пишется как id + 1, ноль означает что дальше идет само значение
+impl<T: Encode> Encode for IdOr<T> {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			IdOr::Id(id) => Ok(packet.write_varint(id + 1)?),
+			IdOr::Or(value) => {
+				packet.write_varint(0)?;
+				value.encode(packet)
+			}
+		}
+	}
+}
+
+impl<T: Decode> Decode for IdOr<T> {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		match packet.read_varint()? {
+			0 => Ok(IdOr::Or(T::decode(packet)?)),
+			id => Ok(IdOr::Id(id - 1)),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
 pub enum IdSet {
 	Tag(String),
 	Ids(Vec<u32>),
 }
 
-#[derive(Clone)]
+// Ноль - дальше имя тега, иначе список айди длиной на единицу меньше
+impl Encode for IdSet {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			IdSet::Tag(tag) => {
+				packet.write_varint(0)?;
+				tag.encode(packet)
+			}
+			IdSet::Ids(ids) => {
+				packet.write_usize_varint(ids.len() + 1)?;
+				for id in ids {
+					packet.write_u32_varint(*id)?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+impl Decode for IdSet {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		match packet.read_usize_varint()? {
+			0 => Ok(IdSet::Tag(String::decode(packet)?)),
+			length => {
+				let mut ids = Vec::with_capacity((length - 1).min(4096));
+				for _ in 1..length {
+					ids.push(packet.read_u32_varint()?);
+				}
+				Ok(IdSet::Ids(ids))
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Property {
 	pub name: String,
 	pub value: String,
 	pub signature: Option<String>,
 }
+
+impl Encode for Property {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.name.encode(packet)?;
+		self.value.encode(packet)?;
+		self.signature.encode(packet)
+	}
+}
+
+impl Decode for Property {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(Property {
+			name: String::decode(packet)?,
+			value: String::decode(packet)?,
+			signature: Option::decode(packet)?,
+		})
+	}
+}
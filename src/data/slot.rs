@@ -4,13 +4,29 @@ use enum_index_derive::EnumIndex;
 use rust_mc_proto::{DataReader, DataWriter, Packet};
 use uuid::Uuid;
 
-use crate::ServerError;
+use crate::{
+	ServerError, codec,
+	play::registry::Registries,
+	protocol::codec::{Decode, Encode},
+	world::item::ItemRegistry,
+};
 
-use super::{IdOr, IdSet, Property, component::TextComponent, sound::SoundEvent};
+use super::{
+	IdOr, IdSet, Position, Property, component::TextComponent, hash::ComponentHasher,
+	sound::SoundEvent,
+};
 
 pub const SLOT_COMPONENT_LENGTH: u16 = 96;
 
-#[derive(Clone)]
+/// Сколько предметов можно вложить друг в друга (бандл в шалкере в бандле...)
+///
+/// Без ограничения клиент положит сервер переполнением стека одним пакетом
+pub const MAX_SLOT_DEPTH: usize = 16;
+
+/// Больше предметов в контейнере или бандле не бывает, как ItemContainerContents.MAX_SIZE
+pub const MAX_NESTED_ITEMS: usize = 256;
+
+#[derive(Debug, Clone)]
 pub struct JukeboxSong {
 	pub sound: IdOr<SoundEvent>,
 	pub description: TextComponent,
@@ -20,19 +36,25 @@ pub struct JukeboxSong {
 	pub output: i32,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum JukeboxPlayable {
 	ByName(String),
 	ById(IdOr<JukeboxSong>),
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum ChickenVariant {
 	ByName(String),
 	ById(u32),
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
+pub enum InstrumentComponent {
+	ByName(String),
+	ById(IdOr<Instrument>),
+}
+
+#[derive(Debug, Clone)]
 pub struct PaintingVariant {
 	pub width: i32,
 	pub height: i32,
@@ -42,26 +64,20 @@ pub struct PaintingVariant {
 	pub author: Option<TextComponent>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum ProvidesTrimMaterial {
 	ByName(String),
 	ById(IdOr<TrimMaterial>),
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum BlockPredicatePropertyMatch {
 	Exact(String),
-	Range(String, String),
+	/// Min, Max
+	Range(Option<String>, Option<String>),
 }
 
-#[derive(Clone)]
-pub struct BlockPredicateExactDataMatcher {
-	/// ID of the data component as listed in the table of data component types above.
-	pub component_index: usize,
-	pub value: StructuredComponent,
-}
-
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct BlockPredicatePartialDataMatcher {
 	/// 0: damage, 1: enchantments, 2: stored_enchantments, 3: potion_contents, 4: custom_data, 5: container, 6: bundle_contents, 7: firework_explosion, 8: fireworks, 9: writable_book_content, 10: written_book_content, 11: attribute_modifiers, 12: trim, 13: jukebox_playable.
 	pub type_id: u8,
@@ -69,30 +85,33 @@ pub struct BlockPredicatePartialDataMatcher {
 	pub predicate: DynNBT,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct BlockPredicateProperty {
 	pub name: String,
 	pub matches: BlockPredicatePropertyMatch,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct BlockPredicate {
 	pub blocks: Option<IdSet>,
 	pub properties: Option<Vec<BlockPredicateProperty>>,
 	pub nbt: Option<DynNBT>,
-	pub exact_matchers: Vec<BlockPredicateExactDataMatcher>,
+	/// Components the item must have with exactly these values.
+	pub exact_matchers: Vec<StructuredComponent>,
 	pub partial_matchers: Vec<BlockPredicatePartialDataMatcher>,
 }
 
-#[derive(Clone)]
-pub struct Instrument {
-	pub sound: IdOr<SoundEvent>,
-	pub sound_range: f32,
-	pub range: f32,
-	pub description: TextComponent,
+codec! {
+	pub struct Instrument {
+		pub sound: IdOr<SoundEvent>,
+		/// Seconds
+		pub use_duration: f32,
+		pub range: f32,
+		pub description: TextComponent,
+	}
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum ConsumeEffect {
 	/// Effects, Probability
 	ApplyEffects(Vec<PotionEffect>, f32),
@@ -100,27 +119,28 @@ pub enum ConsumeEffect {
 	ClearAllEffects,
 	/// Diameter
 	TeleportRandomly(f32),
-	PlaySound(SoundEvent),
+	PlaySound(IdOr<SoundEvent>),
 }
 
-#[derive(Clone)]
-pub struct TrimMaterial {
-	pub suffix: String,
-	/// Key - Armor Material Type Identifier
-	/// Value - Overriden Asset Name
-	pub overrides: Vec<(String, String)>,
-	pub description: TextComponent,
+codec! {
+	pub struct TrimMaterial {
+		pub suffix: String,
+		/// Key - Armor Material Type Identifier
+		/// Value - Overriden Asset Name
+		pub overrides: Vec<(String, String)>,
+		pub description: TextComponent,
+	}
 }
 
-#[derive(Clone)]
-pub struct TrimPattern {
-	pub asset_name: String,
-	pub template_item: i32,
-	pub description: TextComponent,
-	pub decal: bool,
+codec! {
+	pub struct TrimPattern {
+		pub asset_name: String,
+		pub description: TextComponent,
+		pub decal: bool,
+	}
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct PotionEffectDetail {
 	pub amplifier: i32,
 	/// -1 for infinite.
@@ -131,13 +151,13 @@ pub struct PotionEffectDetail {
 	pub hidden_effect: Option<Box<PotionEffectDetail>>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct PotionEffect {
 	pub type_id: u32,
 	pub detail: PotionEffectDetail,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct FireworkExplosion {
 	/// Can be one of the following:
 	/// - 0 - Small ball
@@ -152,18 +172,23 @@ pub struct FireworkExplosion {
 	pub has_twinkle: bool,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct HiveBee {
 	pub entity_data: DynNBT,
 	pub ticks_in_hive: i32,
 	pub min_ticks_in_hive: i32,
 }
 
-#[derive(Clone)]
+codec! {
+	pub struct BannerPattern {
+		pub asset_id: String,
+		pub translation_key: String,
+	}
+}
+
+#[derive(Debug, Clone)]
 pub struct BannerLayer {
-	pub pattern_type: i32,
-	pub asset_id: Option<String>,
-	pub translation_key: Option<String>,
+	pub pattern: IdOr<BannerPattern>,
 	/// Can be one of the following:
 	/// - 0 - White
 	/// - 1 - Orange
@@ -184,7 +209,7 @@ pub struct BannerLayer {
 	pub color: u8,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct AttributeModifier {
 	pub attribute_id: u64,
 	pub modifier_id: String,
@@ -209,16 +234,14 @@ pub struct AttributeModifier {
 	pub slot: u8,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct ToolRule {
 	pub blocks: IdSet,
-	pub has_speed: bool,
 	pub speed: Option<f32>,
-	pub has_correct_drop_for_blocks: bool,
 	pub correct_drop_for_blocks: Option<bool>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct DamageReduction {
 	pub horizontal_blocking_angle: f32,
 	pub damage_kind: Option<IdSet>,
@@ -227,7 +250,7 @@ pub struct DamageReduction {
 }
 
 /// https://minecraft.wiki/w/Java_Edition_protocol/Slot_data#Structured_components
-#[derive(Clone, EnumIndex)]
+#[derive(Debug, Clone, EnumIndex)]
 pub enum StructuredComponent {
 	CustomData(DynNBT),
 	/// 1 - 99
@@ -238,7 +261,7 @@ pub enum StructuredComponent {
 	CustomName(TextComponent),
 	ItemName(TextComponent),
 	ItemModel(String),
-	Lore(TextComponent),
+	Lore(Vec<TextComponent>),
 	/// Can be one of the following:
 	/// - 0 - Common (white)
 	/// - 1 - Uncommon (yellow)
@@ -295,6 +318,7 @@ pub enum StructuredComponent {
 		rules: Vec<ToolRule>,
 		default_mining_speed: f32,
 		damage_per_block: i32,
+		can_destroy_blocks_in_creative: bool,
 	},
 	Weapon {
 		damage_per_attack: i32,
@@ -311,6 +335,7 @@ pub enum StructuredComponent {
 		dispensable: bool,
 		swappable: bool,
 		damage_on_hurt: bool,
+		equip_on_interact: bool,
 	},
 	/// Items that can be combined with this item in an anvil to repair it.
 	Repairable(IdSet),
@@ -349,7 +374,7 @@ pub enum StructuredComponent {
 		potion_id: Option<u64>,
 		custom_color: Option<i32>,
 		custom_effects: Vec<PotionEffect>,
-		custom_name: String,
+		custom_name: Option<String>,
 	},
 	/// Parameter - Effect Multiplier
 	PotionDurationScale(f32),
@@ -369,7 +394,7 @@ pub enum StructuredComponent {
 		/// Page:
 		/// - The raw text of the page
 		/// - The content after passing through chat filters
-		pages: Vec<(String, Option<String>)>,
+		pages: Vec<(TextComponent, Option<TextComponent>)>,
 		resolved: bool,
 	},
 	/// Armor's trim pattern and color
@@ -378,7 +403,7 @@ pub enum StructuredComponent {
 	EntityData(DynNBT),
 	BucketEntityData(DynNBT),
 	BlockEntityData(DynNBT),
-	Instrument(IdOr<Instrument>),
+	Instrument(InstrumentComponent),
 	ProvidesTrimMaterial(ProvidesTrimMaterial),
 	/// Between 0 and 4.
 	OminousBottleAmplifier(u8),
@@ -387,9 +412,8 @@ pub enum StructuredComponent {
 	ProvidesBannerPatterns(String),
 	Recipes(DynNBT),
 	LodestoneTracker {
-		has_global_position: bool,
-		dimension: String,
-		position: (f64, f64, f64),
+		/// Dimension and position, None if the lodestone is not set.
+		global_position: Option<(String, Position)>,
 		tracked: bool,
 	},
 	FireworkExplosion(FireworkExplosion),
@@ -423,12 +447,13 @@ pub enum StructuredComponent {
 	/// - 15 - Black
 	BaseColor(u8),
 	/// The ID of the items in the item registry.
-	PotDecorations([u64; 4]),
-	/// Items inside a container of any type.
-	Container(Vec<Slot>),
+	PotDecorations(Vec<u64>),
+	/// Items inside a container of any type, None for empty slots.
+	Container(Vec<Option<Slot>>),
 	BlockState(Vec<(String, String)>),
 	Bees(Vec<HiveBee>),
-	Lock(String),
+	/// Item predicate the key must match.
+	Lock(DynNBT),
 	ContainerLoot(DynNBT),
 	BreakSound(IdOr<SoundEvent>),
 	VillagerVariant(u64),
@@ -488,7 +513,7 @@ pub enum StructuredComponent {
 	FrogVariant(u64),
 	/// 0: white, 1: creamy, 2: chestnut, 3: brown, 4: black, 5: gray, 6: dark brown.
 	HorseVariant(u8),
-	PaintingVariant(PaintingVariant),
+	PaintingVariant(IdOr<PaintingVariant>),
 	/// 0: creamy, 1: white, 2: brown, 3: gray.
 	LlamaVariant(u8),
 	/// 0: lucy, 1: wild, 2: gold, 3: cyan, 4: blue.
@@ -550,6 +575,940 @@ pub enum StructuredComponent {
 	ShulkerColor(u8),
 }
 
+// Айди и перечисления в протоколе VarInt, а в модели у них свои типы
+fn read_var<T: TryFrom<i32>>(packet: &mut Packet) -> Result<T, ServerError> {
+	T::try_from(packet.read_varint()?).map_err(|_| ServerError::WrongPacket)
+}
+
+fn write_var<T: Copy + TryInto<i32>>(packet: &mut Packet, val: T) -> Result<(), ServerError> {
+	let val = val.try_into().map_err(|_| ServerError::WrongPacket)?;
+	Ok(packet.write_varint(val)?)
+}
+
+fn read_var_list<T: TryFrom<i32>>(packet: &mut Packet) -> Result<Vec<T>, ServerError> {
+	let length = packet.read_usize_varint()?;
+	let mut values = Vec::with_capacity(length.min(4096));
+	for _ in 0..length {
+		values.push(read_var(packet)?);
+	}
+	Ok(values)
+}
+
+fn write_var_list<T: Copy + TryInto<i32>>(
+	packet: &mut Packet,
+	values: &[T],
+) -> Result<(), ServerError> {
+	packet.write_usize_varint(values.len())?;
+	for value in values {
+		write_var(packet, *value)?;
+	}
+	Ok(())
+}
+
+// Пары айди и уровня у чар и эффектов стью
+fn read_var_pairs(packet: &mut Packet) -> Result<Vec<(u64, i32)>, ServerError> {
+	let length = packet.read_usize_varint()?;
+	let mut values = Vec::with_capacity(length.min(4096));
+	for _ in 0..length {
+		values.push((read_var(packet)?, packet.read_varint()?));
+	}
+	Ok(values)
+}
+
+fn write_var_pairs(packet: &mut Packet, values: &[(u64, i32)]) -> Result<(), ServerError> {
+	packet.write_usize_varint(values.len())?;
+	for (id, value) in values {
+		write_var(packet, *id)?;
+		packet.write_varint(*value)?;
+	}
+	Ok(())
+}
+
+// Слоты внутри компонентов не бывают пустыми, кроме содержимого контейнера
+fn read_item(packet: &mut Packet, depth: usize) -> Result<Slot, ServerError> {
+	read_slot(packet, depth)?.ok_or(ServerError::WrongPacket)
+}
+
+// depth - глубина вложенных предметов, см. MAX_SLOT_DEPTH
+fn read_items(packet: &mut Packet, depth: usize) -> Result<Vec<Slot>, ServerError> {
+	let length = read_nested_length(packet)?;
+	let mut items = Vec::with_capacity(length);
+	for _ in 0..length {
+		items.push(read_item(packet, depth)?);
+	}
+	Ok(items)
+}
+
+fn read_nested_length(packet: &mut Packet) -> Result<usize, ServerError> {
+	let length = packet.read_usize_varint()?;
+	if length > MAX_NESTED_ITEMS {
+		return Err(ServerError::WrongPacket);
+	}
+	Ok(length)
+}
+
+fn write_items(packet: &mut Packet, items: &[Slot]) -> Result<(), ServerError> {
+	packet.write_usize_varint(items.len())?;
+	for item in items {
+		packet.write_slot(Some(item.clone()))?;
+	}
+	Ok(())
+}
+
+impl Encode for JukeboxSong {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.sound.encode(packet)?;
+		self.description.encode(packet)?;
+		self.duration.encode(packet)?;
+		Ok(packet.write_varint(self.output)?)
+	}
+}
+
+impl Decode for JukeboxSong {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(JukeboxSong {
+			sound: IdOr::decode(packet)?,
+			description: TextComponent::decode(packet)?,
+			duration: f32::decode(packet)?,
+			output: packet.read_varint()?,
+		})
+	}
+}
+
+impl Encode for PaintingVariant {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_varint(self.width)?;
+		packet.write_varint(self.height)?;
+		self.asset_id.encode(packet)?;
+		self.title.encode(packet)?;
+		self.author.encode(packet)
+	}
+}
+
+impl Decode for PaintingVariant {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(PaintingVariant {
+			width: packet.read_varint()?,
+			height: packet.read_varint()?,
+			asset_id: String::decode(packet)?,
+			title: Option::decode(packet)?,
+			author: Option::decode(packet)?,
+		})
+	}
+}
+
+// Компоненты с EitherHolder: true - значение или айди в реестре, false - имя записи
+impl Encode for JukeboxPlayable {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			JukeboxPlayable::ById(song) => {
+				packet.write_boolean(true)?;
+				song.encode(packet)
+			}
+			JukeboxPlayable::ByName(name) => {
+				packet.write_boolean(false)?;
+				name.encode(packet)
+			}
+		}
+	}
+}
+
+impl Decode for JukeboxPlayable {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		if packet.read_boolean()? {
+			Ok(JukeboxPlayable::ById(IdOr::decode(packet)?))
+		} else {
+			Ok(JukeboxPlayable::ByName(String::decode(packet)?))
+		}
+	}
+}
+
+impl Encode for ProvidesTrimMaterial {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			ProvidesTrimMaterial::ById(material) => {
+				packet.write_boolean(true)?;
+				material.encode(packet)
+			}
+			ProvidesTrimMaterial::ByName(name) => {
+				packet.write_boolean(false)?;
+				name.encode(packet)
+			}
+		}
+	}
+}
+
+impl Decode for ProvidesTrimMaterial {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		if packet.read_boolean()? {
+			Ok(ProvidesTrimMaterial::ById(IdOr::decode(packet)?))
+		} else {
+			Ok(ProvidesTrimMaterial::ByName(String::decode(packet)?))
+		}
+	}
+}
+
+impl Encode for InstrumentComponent {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			InstrumentComponent::ById(instrument) => {
+				packet.write_boolean(true)?;
+				instrument.encode(packet)
+			}
+			InstrumentComponent::ByName(name) => {
+				packet.write_boolean(false)?;
+				name.encode(packet)
+			}
+		}
+	}
+}
+
+impl Decode for InstrumentComponent {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		if packet.read_boolean()? {
+			Ok(InstrumentComponent::ById(IdOr::decode(packet)?))
+		} else {
+			Ok(InstrumentComponent::ByName(String::decode(packet)?))
+		}
+	}
+}
+
+// Варианты куриц не передаются значением, только айди
+impl Encode for ChickenVariant {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			ChickenVariant::ById(id) => {
+				packet.write_boolean(true)?;
+				Ok(packet.write_u32_varint(*id)?)
+			}
+			ChickenVariant::ByName(name) => {
+				packet.write_boolean(false)?;
+				name.encode(packet)
+			}
+		}
+	}
+}
+
+impl Decode for ChickenVariant {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		if packet.read_boolean()? {
+			Ok(ChickenVariant::ById(packet.read_u32_varint()?))
+		} else {
+			Ok(ChickenVariant::ByName(String::decode(packet)?))
+		}
+	}
+}
+
+impl Encode for BlockPredicateProperty {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.name.encode(packet)?;
+		match &self.matches {
+			BlockPredicatePropertyMatch::Exact(value) => {
+				packet.write_boolean(true)?;
+				value.encode(packet)
+			}
+			BlockPredicatePropertyMatch::Range(min, max) => {
+				packet.write_boolean(false)?;
+				min.encode(packet)?;
+				max.encode(packet)
+			}
+		}
+	}
+}
+
+impl Decode for BlockPredicateProperty {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let name = String::decode(packet)?;
+		let matches = if packet.read_boolean()? {
+			BlockPredicatePropertyMatch::Exact(String::decode(packet)?)
+		} else {
+			BlockPredicatePropertyMatch::Range(Option::decode(packet)?, Option::decode(packet)?)
+		};
+		Ok(BlockPredicateProperty { name, matches })
+	}
+}
+
+impl Encode for BlockPredicatePartialDataMatcher {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		write_var(packet, self.type_id)?;
+		self.predicate.encode(packet)
+	}
+}
+
+impl Decode for BlockPredicatePartialDataMatcher {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(BlockPredicatePartialDataMatcher {
+			type_id: read_var(packet)?,
+			predicate: DynNBT::decode(packet)?,
+		})
+	}
+}
+
+impl Encode for BlockPredicate {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.blocks.encode(packet)?;
+		self.properties.encode(packet)?;
+		self.nbt.encode(packet)?;
+		self.exact_matchers.encode(packet)?;
+		self.partial_matchers.encode(packet)
+	}
+}
+
+impl Decode for BlockPredicate {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(BlockPredicate {
+			blocks: Option::decode(packet)?,
+			properties: Option::decode(packet)?,
+			nbt: Option::decode(packet)?,
+			exact_matchers: Vec::decode(packet)?,
+			partial_matchers: Vec::decode(packet)?,
+		})
+	}
+}
+
+impl Encode for ConsumeEffect {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			ConsumeEffect::ApplyEffects(effects, probability) => {
+				packet.write_varint(0)?;
+				effects.encode(packet)?;
+				probability.encode(packet)
+			}
+			ConsumeEffect::RemoveEffects(effects) => {
+				packet.write_varint(1)?;
+				effects.encode(packet)
+			}
+			ConsumeEffect::ClearAllEffects => Ok(packet.write_varint(2)?),
+			ConsumeEffect::TeleportRandomly(diameter) => {
+				packet.write_varint(3)?;
+				diameter.encode(packet)
+			}
+			ConsumeEffect::PlaySound(sound) => {
+				packet.write_varint(4)?;
+				sound.encode(packet)
+			}
+		}
+	}
+}
+
+impl Decode for ConsumeEffect {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(match packet.read_varint()? {
+			0 => ConsumeEffect::ApplyEffects(Vec::decode(packet)?, f32::decode(packet)?),
+			1 => ConsumeEffect::RemoveEffects(IdSet::decode(packet)?),
+			2 => ConsumeEffect::ClearAllEffects,
+			3 => ConsumeEffect::TeleportRandomly(f32::decode(packet)?),
+			4 => ConsumeEffect::PlaySound(IdOr::decode(packet)?),
+			_ => return Err(ServerError::WrongPacket),
+		})
+	}
+}
+
+impl Encode for PotionEffectDetail {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_varint(self.amplifier)?;
+		packet.write_varint(self.duration)?;
+		self.ambient.encode(packet)?;
+		self.show_particles.encode(packet)?;
+		self.show_icon.encode(packet)?;
+		self.hidden_effect.encode(packet)
+	}
+}
+
+impl Decode for PotionEffectDetail {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(PotionEffectDetail {
+			amplifier: packet.read_varint()?,
+			duration: packet.read_varint()?,
+			ambient: bool::decode(packet)?,
+			show_particles: bool::decode(packet)?,
+			show_icon: bool::decode(packet)?,
+			hidden_effect: Option::<PotionEffectDetail>::decode(packet)?.map(Box::new),
+		})
+	}
+}
+
+impl Encode for Box<PotionEffectDetail> {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.as_ref().encode(packet)
+	}
+}
+
+impl Encode for PotionEffect {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_u32_varint(self.type_id)?;
+		self.detail.encode(packet)
+	}
+}
+
+impl Decode for PotionEffect {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(PotionEffect {
+			type_id: packet.read_u32_varint()?,
+			detail: PotionEffectDetail::decode(packet)?,
+		})
+	}
+}
+
+impl Encode for FireworkExplosion {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		write_var(packet, self.shape)?;
+		self.colors.encode(packet)?;
+		self.fade_colors.encode(packet)?;
+		self.has_trail.encode(packet)?;
+		self.has_twinkle.encode(packet)
+	}
+}
+
+impl Decode for FireworkExplosion {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(FireworkExplosion {
+			shape: read_var(packet)?,
+			colors: Vec::decode(packet)?,
+			fade_colors: Vec::decode(packet)?,
+			has_trail: bool::decode(packet)?,
+			has_twinkle: bool::decode(packet)?,
+		})
+	}
+}
+
+impl Encode for HiveBee {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.entity_data.encode(packet)?;
+		packet.write_varint(self.ticks_in_hive)?;
+		Ok(packet.write_varint(self.min_ticks_in_hive)?)
+	}
+}
+
+impl Decode for HiveBee {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(HiveBee {
+			entity_data: DynNBT::decode(packet)?,
+			ticks_in_hive: packet.read_varint()?,
+			min_ticks_in_hive: packet.read_varint()?,
+		})
+	}
+}
+
+impl Encode for BannerLayer {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.pattern.encode(packet)?;
+		write_var(packet, self.color)
+	}
+}
+
+impl Decode for BannerLayer {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(BannerLayer {
+			pattern: IdOr::decode(packet)?,
+			color: read_var(packet)?,
+		})
+	}
+}
+
+impl Encode for AttributeModifier {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		write_var(packet, self.attribute_id)?;
+		self.modifier_id.encode(packet)?;
+		self.value.encode(packet)?;
+		write_var(packet, self.operation)?;
+		write_var(packet, self.slot)
+	}
+}
+
+impl Decode for AttributeModifier {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(AttributeModifier {
+			attribute_id: read_var(packet)?,
+			modifier_id: String::decode(packet)?,
+			value: f64::decode(packet)?,
+			operation: read_var(packet)?,
+			slot: read_var(packet)?,
+		})
+	}
+}
+
+impl Encode for ToolRule {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.blocks.encode(packet)?;
+		self.speed.encode(packet)?;
+		self.correct_drop_for_blocks.encode(packet)
+	}
+}
+
+impl Decode for ToolRule {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(ToolRule {
+			blocks: IdSet::decode(packet)?,
+			speed: Option::decode(packet)?,
+			correct_drop_for_blocks: Option::decode(packet)?,
+		})
+	}
+}
+
+impl Encode for DamageReduction {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.horizontal_blocking_angle.encode(packet)?;
+		self.damage_kind.encode(packet)?;
+		self.base.encode(packet)?;
+		self.factor.encode(packet)
+	}
+}
+
+impl Decode for DamageReduction {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(DamageReduction {
+			horizontal_blocking_angle: f32::decode(packet)?,
+			damage_kind: Option::decode(packet)?,
+			base: f32::decode(packet)?,
+			factor: f32::decode(packet)?,
+		})
+	}
+}
+
+// Компонент пишется вместе со своим айди, айди - индекс варианта в StructuredComponent
+impl Encode for StructuredComponent {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_usize_varint(self.enum_index())?;
+
+		match self {
+			StructuredComponent::CustomData(nbt)
+			| StructuredComponent::MapDecorations(nbt)
+			| StructuredComponent::DebugStrickState(nbt)
+			| StructuredComponent::EntityData(nbt)
+			| StructuredComponent::BucketEntityData(nbt)
+			| StructuredComponent::BlockEntityData(nbt)
+			| StructuredComponent::Recipes(nbt)
+			| StructuredComponent::Lock(nbt)
+			| StructuredComponent::ContainerLoot(nbt) => nbt.encode(packet),
+			StructuredComponent::MaxStackSize(value)
+			| StructuredComponent::MaxDamage(value)
+			| StructuredComponent::Damage(value)
+			| StructuredComponent::RepairCost(value)
+			| StructuredComponent::Enchantable(value)
+			| StructuredComponent::MapId(value) => Ok(packet.write_varint(*value)?),
+			StructuredComponent::Rarity(value)
+			| StructuredComponent::MapPostProcessing(value)
+			| StructuredComponent::OminousBottleAmplifier(value)
+			| StructuredComponent::BaseColor(value)
+			| StructuredComponent::WolfCollar(value)
+			| StructuredComponent::FoxVariant(value)
+			| StructuredComponent::SalmonSize(value)
+			| StructuredComponent::TropicalFishPattern(value)
+			| StructuredComponent::TropicalFishBaseColor(value)
+			| StructuredComponent::TropicalFishPatternColor(value)
+			| StructuredComponent::MooshroomVariant(value)
+			| StructuredComponent::RabbitVariant(value)
+			| StructuredComponent::HorseVariant(value)
+			| StructuredComponent::LlamaVariant(value)
+			| StructuredComponent::AxolotlVariant(value)
+			| StructuredComponent::CatCollar(value)
+			| StructuredComponent::SheepColor(value)
+			| StructuredComponent::ShulkerColor(value) => write_var(packet, *value),
+			StructuredComponent::VillagerVariant(id)
+			| StructuredComponent::WolfVariant(id)
+			| StructuredComponent::WolfSoundVariant(id)
+			| StructuredComponent::ParrotVariant(id)
+			| StructuredComponent::PigVariant(id)
+			| StructuredComponent::CowVariant(id)
+			| StructuredComponent::FrogVariant(id)
+			| StructuredComponent::CatVariant(id) => write_var(packet, *id),
+			StructuredComponent::Unbreakable
+			| StructuredComponent::CreativeSlotLock
+			| StructuredComponent::Glider => Ok(()),
+			// Не синхронизируется, поэтому передается пустым NBT
+			StructuredComponent::IntangibleProjectile => {
+				DynNBT::Compound(Default::default()).encode(packet)
+			}
+			StructuredComponent::CustomName(text) | StructuredComponent::ItemName(text) => {
+				text.encode(packet)
+			}
+			StructuredComponent::ItemModel(name)
+			| StructuredComponent::DamageResistant(name)
+			| StructuredComponent::TooltipStyle(name)
+			| StructuredComponent::ProvidesBannerPatterns(name)
+			| StructuredComponent::NoteBlockSound(name) => name.encode(packet),
+			StructuredComponent::Lore(lines) => lines.encode(packet),
+			StructuredComponent::Enchantments(enchantments)
+			| StructuredComponent::StoredEnchantments(enchantments)
+			| StructuredComponent::SuspiciousStewEffects(enchantments) => {
+				write_var_pairs(packet, enchantments)
+			}
+			StructuredComponent::CanPlaceOn(predicates) | StructuredComponent::CanBreak(predicates) => {
+				predicates.encode(packet)
+			}
+			StructuredComponent::AttributeModifiers(modifiers) => modifiers.encode(packet),
+			StructuredComponent::CustomModelData(floats, flags, strings, colors) => {
+				floats.encode(packet)?;
+				flags.encode(packet)?;
+				strings.encode(packet)?;
+				colors.encode(packet)
+			}
+			StructuredComponent::TooltipDisplay(hide_tooltip, hidden) => {
+				hide_tooltip.encode(packet)?;
+				write_var_list(packet, hidden)
+			}
+			StructuredComponent::EnchantmentGlintOverride(value) => value.encode(packet),
+			StructuredComponent::Food(nutrition, saturation, can_always_eat) => {
+				write_var(packet, *nutrition)?;
+				saturation.encode(packet)?;
+				can_always_eat.encode(packet)
+			}
+			StructuredComponent::Consumable {
+				consume_seconds,
+				animation,
+				sound,
+				has_particles,
+				effects,
+			} => {
+				consume_seconds.encode(packet)?;
+				write_var(packet, *animation)?;
+				sound.encode(packet)?;
+				has_particles.encode(packet)?;
+				effects.encode(packet)
+			}
+			StructuredComponent::UseRemainder(item) => packet.write_slot(Some(item.clone())),
+			StructuredComponent::UseCooldown { seconds, group } => {
+				seconds.encode(packet)?;
+				group.encode(packet)
+			}
+			StructuredComponent::Tool {
+				rules,
+				default_mining_speed,
+				damage_per_block,
+				can_destroy_blocks_in_creative,
+			} => {
+				rules.encode(packet)?;
+				default_mining_speed.encode(packet)?;
+				packet.write_varint(*damage_per_block)?;
+				can_destroy_blocks_in_creative.encode(packet)
+			}
+			StructuredComponent::Weapon {
+				damage_per_attack,
+				disable_blocking_for_seconds,
+			} => {
+				packet.write_varint(*damage_per_attack)?;
+				disable_blocking_for_seconds.encode(packet)
+			}
+			StructuredComponent::Equippable {
+				slot,
+				equip_sound,
+				model,
+				camera_overlay,
+				allowed_entities,
+				dispensable,
+				swappable,
+				damage_on_hurt,
+				equip_on_interact,
+			} => {
+				write_var(packet, *slot)?;
+				equip_sound.encode(packet)?;
+				model.encode(packet)?;
+				camera_overlay.encode(packet)?;
+				allowed_entities.encode(packet)?;
+				dispensable.encode(packet)?;
+				swappable.encode(packet)?;
+				damage_on_hurt.encode(packet)?;
+				equip_on_interact.encode(packet)
+			}
+			StructuredComponent::Repairable(items) => items.encode(packet),
+			StructuredComponent::DeathProtection(effects) => effects.encode(packet),
+			StructuredComponent::BlockAttacks {
+				block_delay_seconds,
+				disable_cooldown_scale,
+				damage_reductions,
+				item_damage_threshold,
+				item_damage_base,
+				item_damage_factor,
+				bypassed_by,
+				block_sound,
+				disable_sound,
+			} => {
+				block_delay_seconds.encode(packet)?;
+				disable_cooldown_scale.encode(packet)?;
+				damage_reductions.encode(packet)?;
+				item_damage_threshold.encode(packet)?;
+				item_damage_base.encode(packet)?;
+				item_damage_factor.encode(packet)?;
+				bypassed_by.encode(packet)?;
+				block_sound.encode(packet)?;
+				disable_sound.encode(packet)
+			}
+			StructuredComponent::DyedColor(color) | StructuredComponent::MapColor(color) => {
+				color.encode(packet)
+			}
+			StructuredComponent::ChargedProjectiles(items)
+			| StructuredComponent::BundleContents(items) => write_items(packet, items),
+			StructuredComponent::PotionContents {
+				potion_id,
+				custom_color,
+				custom_effects,
+				custom_name,
+			} => {
+				packet.write_boolean(potion_id.is_some())?;
+				if let Some(potion_id) = potion_id {
+					write_var(packet, *potion_id)?;
+				}
+				custom_color.encode(packet)?;
+				custom_effects.encode(packet)?;
+				custom_name.encode(packet)
+			}
+			StructuredComponent::PotionDurationScale(scale) => scale.encode(packet),
+			StructuredComponent::WritableBookContent(pages) => pages.encode(packet),
+			StructuredComponent::WrittenBookContent {
+				raw_title,
+				filtered_title,
+				author,
+				generation,
+				pages,
+				resolved,
+			} => {
+				raw_title.encode(packet)?;
+				filtered_title.encode(packet)?;
+				author.encode(packet)?;
+				packet.write_varint(*generation)?;
+				pages.encode(packet)?;
+				resolved.encode(packet)
+			}
+			StructuredComponent::Trim(material, pattern) => {
+				material.encode(packet)?;
+				pattern.encode(packet)
+			}
+			StructuredComponent::Instrument(instrument) => instrument.encode(packet),
+			StructuredComponent::ProvidesTrimMaterial(material) => material.encode(packet),
+			StructuredComponent::JukeboxPlayable(song) => song.encode(packet),
+			StructuredComponent::LodestoneTracker {
+				global_position,
+				tracked,
+			} => {
+				global_position.encode(packet)?;
+				tracked.encode(packet)
+			}
+			StructuredComponent::FireworkExplosion(explosion) => explosion.encode(packet),
+			StructuredComponent::Fireworks {
+				flight_duration,
+				explosions,
+			} => {
+				packet.write_varint(*flight_duration)?;
+				explosions.encode(packet)
+			}
+			StructuredComponent::Profile {
+				name,
+				unique_id,
+				properties,
+			} => {
+				name.encode(packet)?;
+				unique_id.encode(packet)?;
+				properties.encode(packet)
+			}
+			StructuredComponent::BannerPatterns(layers) => layers.encode(packet),
+			StructuredComponent::PotDecorations(items) => write_var_list(packet, items),
+			StructuredComponent::Container(items) => {
+				packet.write_usize_varint(items.len())?;
+				for item in items {
+					packet.write_slot(item.clone())?;
+				}
+				Ok(())
+			}
+			StructuredComponent::BlockState(properties) => properties.encode(packet),
+			StructuredComponent::Bees(bees) => bees.encode(packet),
+			StructuredComponent::BreakSound(sound) => sound.encode(packet),
+			StructuredComponent::ChickenVariant(variant) => variant.encode(packet),
+			StructuredComponent::PaintingVariant(variant) => variant.encode(packet),
+		}
+	}
+}
+
+impl Decode for StructuredComponent {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		read_component(packet, 0)
+	}
+}
+
+// Компонент предмета на глубине depth, предметы внутри него читаются уже на depth + 1
+fn read_component(packet: &mut Packet, depth: usize) -> Result<StructuredComponent, ServerError> {
+	Ok(match packet.read_u16_varint()? {
+		0 => StructuredComponent::CustomData(DynNBT::decode(packet)?),
+		1 => StructuredComponent::MaxStackSize(packet.read_varint()?),
+		2 => StructuredComponent::MaxDamage(packet.read_varint()?),
+		3 => StructuredComponent::Damage(packet.read_varint()?),
+		4 => StructuredComponent::Unbreakable,
+		5 => StructuredComponent::CustomName(TextComponent::decode(packet)?),
+		6 => StructuredComponent::ItemName(TextComponent::decode(packet)?),
+		7 => StructuredComponent::ItemModel(String::decode(packet)?),
+		8 => StructuredComponent::Lore(Vec::decode(packet)?),
+		9 => StructuredComponent::Rarity(read_var(packet)?),
+		10 => StructuredComponent::Enchantments(read_var_pairs(packet)?),
+		11 => StructuredComponent::CanPlaceOn(Vec::decode(packet)?),
+		12 => StructuredComponent::CanBreak(Vec::decode(packet)?),
+		13 => StructuredComponent::AttributeModifiers(Vec::decode(packet)?),
+		14 => StructuredComponent::CustomModelData(
+			Vec::decode(packet)?,
+			Vec::decode(packet)?,
+			Vec::decode(packet)?,
+			Vec::decode(packet)?,
+		),
+		15 => StructuredComponent::TooltipDisplay(bool::decode(packet)?, read_var_list(packet)?),
+		16 => StructuredComponent::RepairCost(packet.read_varint()?),
+		17 => StructuredComponent::CreativeSlotLock,
+		18 => StructuredComponent::EnchantmentGlintOverride(bool::decode(packet)?),
+		19 => {
+			DynNBT::decode(packet)?;
+			StructuredComponent::IntangibleProjectile
+		}
+		20 => StructuredComponent::Food(
+			read_var(packet)?,
+			f32::decode(packet)?,
+			bool::decode(packet)?,
+		),
+		21 => StructuredComponent::Consumable {
+			consume_seconds: f32::decode(packet)?,
+			animation: read_var(packet)?,
+			sound: IdOr::decode(packet)?,
+			has_particles: bool::decode(packet)?,
+			effects: Vec::decode(packet)?,
+		},
+		22 => StructuredComponent::UseRemainder(read_item(packet, depth + 1)?),
+		23 => StructuredComponent::UseCooldown {
+			seconds: f32::decode(packet)?,
+			group: Option::decode(packet)?,
+		},
+		24 => StructuredComponent::DamageResistant(String::decode(packet)?),
+		25 => StructuredComponent::Tool {
+			rules: Vec::decode(packet)?,
+			default_mining_speed: f32::decode(packet)?,
+			damage_per_block: packet.read_varint()?,
+			can_destroy_blocks_in_creative: bool::decode(packet)?,
+		},
+		26 => StructuredComponent::Weapon {
+			damage_per_attack: packet.read_varint()?,
+			disable_blocking_for_seconds: f32::decode(packet)?,
+		},
+		27 => StructuredComponent::Enchantable(packet.read_varint()?),
+		28 => StructuredComponent::Equippable {
+			slot: read_var(packet)?,
+			equip_sound: IdOr::decode(packet)?,
+			model: Option::decode(packet)?,
+			camera_overlay: Option::decode(packet)?,
+			allowed_entities: Option::decode(packet)?,
+			dispensable: bool::decode(packet)?,
+			swappable: bool::decode(packet)?,
+			damage_on_hurt: bool::decode(packet)?,
+			equip_on_interact: bool::decode(packet)?,
+		},
+		29 => StructuredComponent::Repairable(IdSet::decode(packet)?),
+		30 => StructuredComponent::Glider,
+		31 => StructuredComponent::TooltipStyle(String::decode(packet)?),
+		32 => StructuredComponent::DeathProtection(Vec::decode(packet)?),
+		33 => StructuredComponent::BlockAttacks {
+			block_delay_seconds: f32::decode(packet)?,
+			disable_cooldown_scale: f32::decode(packet)?,
+			damage_reductions: Vec::decode(packet)?,
+			item_damage_threshold: f32::decode(packet)?,
+			item_damage_base: f32::decode(packet)?,
+			item_damage_factor: f32::decode(packet)?,
+			bypassed_by: Option::decode(packet)?,
+			block_sound: Option::decode(packet)?,
+			disable_sound: Option::decode(packet)?,
+		},
+		34 => StructuredComponent::StoredEnchantments(read_var_pairs(packet)?),
+		35 => StructuredComponent::DyedColor(i32::decode(packet)?),
+		36 => StructuredComponent::MapColor(i32::decode(packet)?),
+		37 => StructuredComponent::MapId(packet.read_varint()?),
+		38 => StructuredComponent::MapDecorations(DynNBT::decode(packet)?),
+		39 => StructuredComponent::MapPostProcessing(read_var(packet)?),
+		40 => StructuredComponent::ChargedProjectiles(read_items(packet, depth + 1)?),
+		41 => StructuredComponent::BundleContents(read_items(packet, depth + 1)?),
+		42 => StructuredComponent::PotionContents {
+			potion_id: match packet.read_boolean()? {
+				true => Some(read_var(packet)?),
+				false => None,
+			},
+			custom_color: Option::decode(packet)?,
+			custom_effects: Vec::decode(packet)?,
+			custom_name: Option::decode(packet)?,
+		},
+		43 => StructuredComponent::PotionDurationScale(f32::decode(packet)?),
+		44 => StructuredComponent::SuspiciousStewEffects(read_var_pairs(packet)?),
+		45 => StructuredComponent::WritableBookContent(Vec::decode(packet)?),
+		46 => StructuredComponent::WrittenBookContent {
+			raw_title: String::decode(packet)?,
+			filtered_title: Option::decode(packet)?,
+			author: String::decode(packet)?,
+			generation: packet.read_varint()?,
+			pages: Vec::decode(packet)?,
+			resolved: bool::decode(packet)?,
+		},
+		47 => StructuredComponent::Trim(IdOr::decode(packet)?, IdOr::decode(packet)?),
+		48 => StructuredComponent::DebugStrickState(DynNBT::decode(packet)?),
+		49 => StructuredComponent::EntityData(DynNBT::decode(packet)?),
+		50 => StructuredComponent::BucketEntityData(DynNBT::decode(packet)?),
+		51 => StructuredComponent::BlockEntityData(DynNBT::decode(packet)?),
+		52 => StructuredComponent::Instrument(InstrumentComponent::decode(packet)?),
+		53 => StructuredComponent::ProvidesTrimMaterial(ProvidesTrimMaterial::decode(packet)?),
+		54 => StructuredComponent::OminousBottleAmplifier(read_var(packet)?),
+		55 => StructuredComponent::JukeboxPlayable(JukeboxPlayable::decode(packet)?),
+		56 => StructuredComponent::ProvidesBannerPatterns(String::decode(packet)?),
+		57 => StructuredComponent::Recipes(DynNBT::decode(packet)?),
+		58 => StructuredComponent::LodestoneTracker {
+			global_position: Option::decode(packet)?,
+			tracked: bool::decode(packet)?,
+		},
+		59 => StructuredComponent::FireworkExplosion(FireworkExplosion::decode(packet)?),
+		60 => StructuredComponent::Fireworks {
+			flight_duration: packet.read_varint()?,
+			explosions: Vec::decode(packet)?,
+		},
+		61 => StructuredComponent::Profile {
+			name: Option::decode(packet)?,
+			unique_id: Option::decode(packet)?,
+			properties: Vec::decode(packet)?,
+		},
+		62 => StructuredComponent::NoteBlockSound(String::decode(packet)?),
+		63 => StructuredComponent::BannerPatterns(Vec::decode(packet)?),
+		64 => StructuredComponent::BaseColor(read_var(packet)?),
+		65 => StructuredComponent::PotDecorations(read_var_list(packet)?),
+		66 => StructuredComponent::Container({
+			let length = read_nested_length(packet)?;
+			let mut items = Vec::with_capacity(length);
+			for _ in 0..length {
+				items.push(read_slot(packet, depth + 1)?);
+			}
+			items
+		}),
+		67 => StructuredComponent::BlockState(Vec::decode(packet)?),
+		68 => StructuredComponent::Bees(Vec::decode(packet)?),
+		69 => StructuredComponent::Lock(DynNBT::decode(packet)?),
+		70 => StructuredComponent::ContainerLoot(DynNBT::decode(packet)?),
+		71 => StructuredComponent::BreakSound(IdOr::decode(packet)?),
+		72 => StructuredComponent::VillagerVariant(read_var(packet)?),
+		73 => StructuredComponent::WolfVariant(read_var(packet)?),
+		74 => StructuredComponent::WolfSoundVariant(read_var(packet)?),
+		75 => StructuredComponent::WolfCollar(read_var(packet)?),
+		76 => StructuredComponent::FoxVariant(read_var(packet)?),
+		77 => StructuredComponent::SalmonSize(read_var(packet)?),
+		78 => StructuredComponent::ParrotVariant(read_var(packet)?),
+		79 => StructuredComponent::TropicalFishPattern(read_var(packet)?),
+		80 => StructuredComponent::TropicalFishBaseColor(read_var(packet)?),
+		81 => StructuredComponent::TropicalFishPatternColor(read_var(packet)?),
+		82 => StructuredComponent::MooshroomVariant(read_var(packet)?),
+		83 => StructuredComponent::RabbitVariant(read_var(packet)?),
+		84 => StructuredComponent::PigVariant(read_var(packet)?),
+		85 => StructuredComponent::CowVariant(read_var(packet)?),
+		86 => StructuredComponent::ChickenVariant(ChickenVariant::decode(packet)?),
+		87 => StructuredComponent::FrogVariant(read_var(packet)?),
+		88 => StructuredComponent::HorseVariant(read_var(packet)?),
+		89 => StructuredComponent::PaintingVariant(IdOr::decode(packet)?),
+		90 => StructuredComponent::LlamaVariant(read_var(packet)?),
+		91 => StructuredComponent::AxolotlVariant(read_var(packet)?),
+		92 => StructuredComponent::CatVariant(read_var(packet)?),
+		93 => StructuredComponent::CatCollar(read_var(packet)?),
+		94 => StructuredComponent::SheepColor(read_var(packet)?),
+		95 => StructuredComponent::ShulkerColor(read_var(packet)?),
+		_ => return Err(ServerError::WrongPacket),
+	})
+}
+
 pub trait ReadWriteSlotComponent: DataReader + DataWriter {
 	fn read_slot_component(&mut self) -> Result<StructuredComponent, ServerError>;
 	fn write_slot_component(&mut self, val: &StructuredComponent) -> Result<(), ServerError>;
@@ -557,22 +1516,134 @@ pub trait ReadWriteSlotComponent: DataReader + DataWriter {
 
 impl ReadWriteSlotComponent for Packet {
 	fn read_slot_component(&mut self) -> Result<StructuredComponent, ServerError> {
-		let _ = self.read_u16_varint()?; // id
-
-		todo!()
+		read_component(self, 0)
 	}
 	fn write_slot_component(&mut self, val: &StructuredComponent) -> Result<(), ServerError> {
-		self.write_usize_varint(val.enum_index())?;
-
-		todo!()
+		val.encode(self)
 	}
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Slot {
 	pub id: i32,
 	pub amount: i32,
+	/// Components added or changed relative to the item's defaults.
 	pub components: Vec<StructuredComponent>,
+	/// IDs of default components removed from the item.
+	pub removed_components: Vec<u16>,
+}
+
+impl Slot {
+	pub fn new(id: i32, amount: i32) -> Slot {
+		Slot {
+			id,
+			amount,
+			components: Vec::new(),
+			removed_components: Vec::new(),
+		}
+	}
+
+	/// Тот же предмет с теми же компонентами, количество не важно
+	///
+	/// Такие предметы можно сложить в один стак
+	pub fn is_similar(&self, other: &Slot) -> bool {
+		self.id == other.id && self.components_bytes() == other.components_bytes()
+	}
+
+	// Компоненты в том виде как они идут по сети, так их проще всего сравнить
+	fn components_bytes(&self) -> Option<Vec<u8>> {
+		let mut packet = Packet::empty(0);
+		for component in &self.components {
+			packet.write_slot_component(component).ok()?;
+		}
+		for id in &self.removed_components {
+			packet.write_u16_varint(*id).ok()?;
+		}
+		Some(packet.get_bytes().to_vec())
+	}
+
+	/// Копия с другим количеством
+	pub fn with_amount(&self, amount: i32) -> Slot {
+		Slot {
+			amount,
+			..self.clone()
+		}
+	}
+
+	/// Размер стака с учетом компонента max_stack_size
+	pub fn max_stack_size(&self, items: &ItemRegistry) -> i32 {
+		self
+			.components
+			.iter()
+			.find_map(|o| match o {
+				StructuredComponent::MaxStackSize(size) => Some(*size),
+				_ => None,
+			})
+			.unwrap_or_else(|| items.max_stack_size(self.id as u32))
+	}
+
+	/// Слот экипировки с учетом компонента equippable, см. ItemRegistry::equipment_slot
+	pub fn equipment_slot(&self, items: &ItemRegistry) -> Option<u8> {
+		self
+			.components
+			.iter()
+			.find_map(|o| match o {
+				StructuredComponent::Equippable { slot, .. } => Some(*slot),
+				_ => None,
+			})
+			.or_else(|| items.equipment_slot(self.id as u32))
+	}
+}
+
+// Пустой слот в сети - это количество 0, а не префикс bool как у Option
+impl Encode for Option<Slot> {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_slot(self.clone())
+	}
+}
+
+impl Decode for Option<Slot> {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		packet.read_slot()
+	}
+}
+
+// Слот на глубине depth, у предмета верхнего уровня она 0
+fn read_slot(packet: &mut Packet, depth: usize) -> Result<Option<Slot>, ServerError> {
+	if depth > MAX_SLOT_DEPTH {
+		return Err(ServerError::WrongPacket);
+	}
+
+	let amount = packet.read_varint()?;
+
+	if amount <= 0 {
+		return Ok(None);
+	}
+
+	let id = packet.read_varint()?;
+	let components_len = packet.read_usize_varint()?;
+	let removed_len = packet.read_usize_varint()?;
+
+	// Каждый компонент может быть в слоте только один раз
+	if components_len + removed_len > SLOT_COMPONENT_LENGTH as usize {
+		return Err(ServerError::WrongPacket);
+	}
+
+	let mut components = Vec::with_capacity(components_len);
+	for _ in 0..components_len {
+		components.push(read_component(packet, depth)?);
+	}
+	let mut removed_components = Vec::with_capacity(removed_len);
+	for _ in 0..removed_len {
+		removed_components.push(packet.read_u16_varint()?);
+	}
+
+	Ok(Some(Slot {
+		id,
+		amount,
+		components,
+		removed_components,
+	}))
 }
 
 pub trait ReadWriteSlot: DataReader + DataWriter {
@@ -582,41 +1653,18 @@ pub trait ReadWriteSlot: DataReader + DataWriter {
 
 impl ReadWriteSlot for Packet {
 	fn read_slot(&mut self) -> Result<Option<Slot>, ServerError> {
-		let amount = self.read_varint()?;
-
-		if amount > 0 {
-			let id = self.read_varint()?;
-			let components_len = self.read_varint()?;
-			self.read_varint()?; // components_to_remove_len
-			let mut components = Vec::new();
-			for _ in 0..components_len {
-				components.push(self.read_slot_component()?);
-			}
-
-			Ok(Some(Slot {
-				id,
-				amount,
-				components,
-			}))
-		} else {
-			Ok(None)
-		}
+		read_slot(self, 0)
 	}
 	fn write_slot(&mut self, val: Option<Slot>) -> Result<(), ServerError> {
 		if let Some(val) = val {
 			self.write_varint(val.amount)?;
 			self.write_varint(val.id)?;
 			self.write_usize_varint(val.components.len())?;
-			let mut components_to_remove: Vec<u16> = (0..SLOT_COMPONENT_LENGTH).collect();
-			self.write_usize_varint(SLOT_COMPONENT_LENGTH as usize - val.components.len())?;
-			for comp in val.components {
-				let id = comp.enum_index() as u16;
-				self.write_slot_component(&comp)?;
-				if let Some(index) = components_to_remove.iter().position(|v| *v == id) {
-					components_to_remove.swap_remove(index);
-				}
+			self.write_usize_varint(val.removed_components.len())?;
+			for comp in &val.components {
+				self.write_slot_component(comp)?;
 			}
-			for id in components_to_remove {
+			for id in val.removed_components {
 				self.write_u16_varint(id)?;
 			}
 		} else {
@@ -626,12 +1674,52 @@ impl ReadWriteSlot for Packet {
 	}
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct HashedSlot {
 	pub id: i32,
 	pub amount: i32,
 	/// id -> crc32 hash
 	pub components: Vec<(u16, i32)>,
+	/// IDs of default components removed from the item.
+	pub removed_components: Vec<u16>,
+}
+
+impl HashedSlot {
+	/// Хеши компонентов предмета, как их посчитает клиент
+	pub fn from_slot(slot: &Slot, registries: &Registries) -> HashedSlot {
+		let hasher = ComponentHasher::new(registries);
+
+		HashedSlot {
+			id: slot.id,
+			amount: slot.amount,
+			components: slot
+				.components
+				.iter()
+				.map(|o| (o.enum_index() as u16, hasher.hash(o)))
+				.collect(),
+			removed_components: slot.removed_components.clone(),
+		}
+	}
+
+	/// Сверяет с предметом на сервере, порядок компонентов не важен
+	pub fn matches(&self, slot: &Slot, registries: &Registries) -> bool {
+		let expected = HashedSlot::from_slot(slot, registries);
+
+		let mut components = self.components.clone();
+		let mut expected_components = expected.components;
+		components.sort();
+		expected_components.sort();
+
+		let mut removed = self.removed_components.clone();
+		let mut expected_removed = expected.removed_components;
+		removed.sort();
+		expected_removed.sort();
+
+		self.id == expected.id
+			&& self.amount == expected.amount
+			&& components == expected_components
+			&& removed == expected_removed
+	}
 }
 
 pub trait ReadWriteHashedSlot: DataReader + DataWriter {
@@ -641,46 +1729,52 @@ pub trait ReadWriteHashedSlot: DataReader + DataWriter {
 
 impl ReadWriteHashedSlot for Packet {
 	fn read_hashed_slot(&mut self) -> Result<Option<HashedSlot>, ServerError> {
-		let amount = self.read_varint()?;
-
-		if amount > 0 {
-			let id = self.read_varint()?;
-			let components_len = self.read_varint()?;
-			self.read_varint()?; // components_to_remove_len
-			let mut components = Vec::new();
-			for _ in 0..components_len {
-				components.push((self.read_u16_varint()?, self.read_int()?));
-			}
-
-			Ok(Some(HashedSlot {
-				id,
-				amount,
-				components,
-			}))
-		} else {
-			Ok(None)
-		}
+		Option::decode(self)
 	}
 	fn write_hashed_slot(&mut self, val: Option<HashedSlot>) -> Result<(), ServerError> {
-		if let Some(val) = val {
-			self.write_varint(val.amount)?;
-			self.write_varint(val.id)?;
-			self.write_usize_varint(val.components.len())?;
-			let mut components_to_remove: Vec<u16> = (0..SLOT_COMPONENT_LENGTH).collect();
-			self.write_usize_varint(SLOT_COMPONENT_LENGTH as usize - val.components.len())?;
-			for (id, hash) in val.components {
-				self.write_u16_varint(id)?;
-				self.write_int(hash)?;
-				if let Some(index) = components_to_remove.iter().position(|v| *v == id) {
-					components_to_remove.swap_remove(index);
-				}
-			}
-			for id in components_to_remove {
-				self.write_u16_varint(id)?;
-			}
-		} else {
-			self.write_varint(0)?;
+		val.encode(self)
+	}
+}
+
+// Пустой хешированный слот - это как раз префикс bool, так что Option<HashedSlot> работает сам
+impl Encode for HashedSlot {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_varint(self.id)?;
+		packet.write_varint(self.amount)?;
+		packet.write_usize_varint(self.components.len())?;
+		for (id, hash) in &self.components {
+			packet.write_u16_varint(*id)?;
+			packet.write_int(*hash)?;
+		}
+		packet.write_usize_varint(self.removed_components.len())?;
+		for id in &self.removed_components {
+			packet.write_u16_varint(*id)?;
 		}
 		Ok(())
 	}
 }
+
+impl Decode for HashedSlot {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let id = packet.read_varint()?;
+		let amount = packet.read_varint()?;
+		let components_len = packet.read_usize_varint()?;
+		let mut components = Vec::with_capacity(components_len.min(SLOT_COMPONENT_LENGTH as usize));
+		for _ in 0..components_len {
+			components.push((packet.read_u16_varint()?, packet.read_int()?));
+		}
+		let removed_len = packet.read_usize_varint()?;
+		let mut removed_components =
+			Vec::with_capacity(removed_len.min(SLOT_COMPONENT_LENGTH as usize));
+		for _ in 0..removed_len {
+			removed_components.push(packet.read_u16_varint()?);
+		}
+
+		Ok(HashedSlot {
+			id,
+			amount,
+			components,
+			removed_components,
+		})
+	}
+}
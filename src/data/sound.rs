@@ -1,2 +1,18 @@
-#[derive(Clone)]
-pub struct SoundEvent;
+use crate::codec;
+
+codec! {
+	pub struct SoundEvent {
+		pub sound_name: String,
+		/// The fixed range of the sound. If not present, the volume is used to compute the range.
+		pub fixed_range: Option<f32>,
+	}
+}
+
+impl SoundEvent {
+	pub fn new(sound_name: &str) -> SoundEvent {
+		SoundEvent {
+			sound_name: sound_name.to_string(),
+			fixed_range: None,
+		}
+	}
+}
@@ -1,8 +1,16 @@
 use rust_mc_proto::Packet;
 
 use super::{ServerError, player::context::ClientContext, protocol::ConnectionState};
+use player::{
+	PlayerBlockBreakEvent, PlayerBlockPlaceEvent, PlayerChatEvent, PlayerClickContainerEvent,
+	PlayerCloseContainerEvent, PlayerCommandEvent, PlayerDamageEvent, PlayerDeathEvent,
+	PlayerDropItemEvent, PlayerGameModeChangeEvent, PlayerJoinEvent, PlayerMoveEvent,
+	PlayerPickupItemEvent, PlayerQuitEvent, PlayerRenameItemEvent, PlayerSwingArmEvent,
+};
 use std::sync::Arc;
 
+pub mod player;
+
 #[macro_export]
 macro_rules! generate_handlers {
     ($name:ident $(, $arg_ty:ty)* $(,)?) => {
@@ -20,7 +28,9 @@ macro_rules! generate_handlers {
 
 /// Пример использования:
 ///
-///     trigger_event!(client, status, &mut response, state);
+/// ```ignore
+/// trigger_event!(client, status, &mut response, state);
+/// ```
 #[macro_export]
 macro_rules! trigger_event {
     ($client:ident, $event:ident $(, $arg_ty:expr)* $(,)?) => {{
@@ -58,6 +68,24 @@ pub trait Listener: Sync + Send {
 	generate_handlers!(status, &mut String);
 	generate_handlers!(plugin_message, &str, &[u8]);
 	generate_handlers!(disconnect);
+
+	// События игрока в режиме Play, листенер может менять поля и отменять их
+	generate_handlers!(player_join, &mut PlayerJoinEvent);
+	generate_handlers!(player_quit, &mut PlayerQuitEvent);
+	generate_handlers!(player_chat, &mut PlayerChatEvent);
+	generate_handlers!(player_command, &mut PlayerCommandEvent);
+	generate_handlers!(player_move, &mut PlayerMoveEvent);
+	generate_handlers!(player_swing_arm, &mut PlayerSwingArmEvent);
+	generate_handlers!(player_block_break, &mut PlayerBlockBreakEvent);
+	generate_handlers!(player_block_place, &mut PlayerBlockPlaceEvent);
+	generate_handlers!(player_click_container, &mut PlayerClickContainerEvent);
+	generate_handlers!(player_close_container, &mut PlayerCloseContainerEvent);
+	generate_handlers!(player_drop_item, &mut PlayerDropItemEvent);
+	generate_handlers!(player_pickup_item, &mut PlayerPickupItemEvent);
+	generate_handlers!(player_rename_item, &mut PlayerRenameItemEvent);
+	generate_handlers!(player_game_mode_change, &mut PlayerGameModeChangeEvent);
+	generate_handlers!(player_damage, &mut PlayerDamageEvent);
+	generate_handlers!(player_death, &mut PlayerDeathEvent);
 }
 
 pub trait PacketHandler: Sync + Send {
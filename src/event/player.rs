@@ -0,0 +1,213 @@
+// Типизированные события игрока
+// Передаются в листенеры по &mut, так что листенер может менять поля и отменять событие
+
+use crate::{
+	data::{Position, component::TextComponent, slot::Slot},
+	player::{health::DamageSource, inventory::Container},
+};
+
+/// Событие которое можно отменить
+pub trait Cancellable {
+	fn is_cancelled(&self) -> bool;
+	fn set_cancelled(&mut self, cancelled: bool);
+
+	fn cancel(&mut self) {
+		self.set_cancelled(true);
+	}
+}
+
+macro_rules! cancellable {
+	($($name:ident),* $(,)?) => {
+		$(
+			impl Cancellable for $name {
+				fn is_cancelled(&self) -> bool {
+					self.cancelled
+				}
+
+				fn set_cancelled(&mut self, cancelled: bool) {
+					self.cancelled = cancelled;
+				}
+			}
+		)*
+	};
+}
+
+/// Игрок зашел в режим Play
+///
+/// При отмене игрок кикается с kick_reason
+#[derive(Debug, Clone)]
+pub struct PlayerJoinEvent {
+	/// Сообщение остальным игрокам, None - не отправлять
+	pub message: Option<TextComponent>,
+	pub kick_reason: TextComponent,
+	pub cancelled: bool,
+}
+
+/// Игрок вышел, отменить нельзя
+#[derive(Debug, Clone)]
+pub struct PlayerQuitEvent {
+	/// Сообщение остальным игрокам, None - не отправлять
+	pub message: Option<TextComponent>,
+}
+
+/// Игрок написал в чат
+#[derive(Debug, Clone)]
+pub struct PlayerChatEvent {
+	pub message: String,
+	pub cancelled: bool,
+}
+
+/// Игрок ввел команду
+///
+/// Чтобы сделать свою команду, обрабатываем ее и отменяем событие
+#[derive(Debug, Clone)]
+pub struct PlayerCommandEvent {
+	/// Без слеша в начале
+	pub command: String,
+	pub cancelled: bool,
+}
+
+/// Игрок сдвинулся или повернулся
+///
+/// При отмене игрок возвращается в from, а если поменять to - телепортируется туда
+#[derive(Debug, Clone)]
+pub struct PlayerMoveEvent {
+	pub from: Location,
+	pub to: Location,
+	pub on_ground: bool,
+	pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+	pub x: f64,
+	pub y: f64,
+	pub z: f64,
+	pub yaw: f32,
+	pub pitch: f32,
+}
+
+/// Игрок махнул рукой
+#[derive(Debug, Clone)]
+pub struct PlayerSwingArmEvent {
+	/// 0 - основная рука, 1 - вторая
+	pub hand: i32,
+	pub cancelled: bool,
+}
+
+/// Игрок сломал блок
+#[derive(Debug, Clone)]
+pub struct PlayerBlockBreakEvent {
+	pub position: Position,
+	/// Айди состояния ломаемого блока
+	pub block: u32,
+	pub cancelled: bool,
+}
+
+/// Игрок ставит блок
+#[derive(Debug, Clone)]
+pub struct PlayerBlockPlaceEvent {
+	pub position: Position,
+	/// Айди состояния блока, можно подменить
+	pub block: u32,
+	/// 0 - основная рука, 1 - вторая
+	pub hand: i32,
+	pub cancelled: bool,
+}
+
+/// Игрок кликнул в окне, событие приходит до того как сервер применит клик
+///
+/// Меню плагинов обычно отменяют все клики и смотрят на slot.
+/// При отмене клиенту отправляется настоящее содержимое окна
+#[derive(Debug, Clone)]
+pub struct PlayerClickContainerEvent {
+	/// 0 - инвентарь, иначе айди из open_container
+	pub window_id: u8,
+	/// Слот окна, -999 - клик за пределами окна
+	pub slot: i16,
+	pub button: i8,
+	/// Режим клика, см. player::inventory::PICKUP и остальные
+	pub mode: i32,
+	pub cancelled: bool,
+}
+
+/// Окно закрыл игрок или сервер, отменить нельзя
+#[derive(Debug, Clone)]
+pub struct PlayerCloseContainerEvent {
+	pub window_id: u8,
+	/// Что лежало в закрытом контейнере, у инвентаря None
+	pub container: Option<Container>,
+}
+
+/// Игрок выкидывает предмет
+///
+/// Предмет можно подменить, он появится на земле перед игроком.
+/// При отмене предмет возвращается в инвентарь
+#[derive(Debug, Clone)]
+pub struct PlayerDropItemEvent {
+	pub item: Slot,
+	pub cancelled: bool,
+}
+
+/// Игрок подбирает предмет с земли
+///
+/// При отмене предмет остается лежать
+#[derive(Debug, Clone)]
+pub struct PlayerPickupItemEvent {
+	/// Айди сущности предмета
+	pub entity_id: i32,
+	pub item: Slot,
+	pub cancelled: bool,
+}
+
+/// Игрок поменял текст в поле наковальни
+#[derive(Debug, Clone)]
+pub struct PlayerRenameItemEvent {
+	pub window_id: u8,
+	pub name: String,
+}
+
+/// Игрок получает урон, от другого игрока или от мира
+///
+/// Урон можно поменять, при отмене он не проходит и отбрасывания нет.
+/// Кто ударил - в source.attacker
+#[derive(Debug, Clone)]
+pub struct PlayerDamageEvent {
+	pub damage: f32,
+	pub source: DamageSource,
+	pub cancelled: bool,
+}
+
+/// Игрок умер, отменить нельзя
+#[derive(Debug, Clone)]
+pub struct PlayerDeathEvent {
+	/// Сообщение всем игрокам и на экран смерти, None - не отправлять
+	pub message: Option<TextComponent>,
+	/// Оставить инвентарь при игроке, как gamerule keepInventory, иначе он выпадает на землю
+	pub keep_inventory: bool,
+}
+
+/// Игроку меняют режим игры через play::gamemode::set_game_mode
+///
+/// Режим можно подменить, при отмене он остается прежним
+#[derive(Debug, Clone)]
+pub struct PlayerGameModeChangeEvent {
+	/// 0 - выживание, 1 - креатив, 2 - приключение, 3 - наблюдатель
+	pub game_mode: u8,
+	pub cancelled: bool,
+}
+
+cancellable!(
+	PlayerJoinEvent,
+	PlayerChatEvent,
+	PlayerCommandEvent,
+	PlayerMoveEvent,
+	PlayerSwingArmEvent,
+	PlayerBlockBreakEvent,
+	PlayerBlockPlaceEvent,
+	PlayerClickContainerEvent,
+	PlayerDropItemEvent,
+	PlayerPickupItemEvent,
+	PlayerGameModeChangeEvent,
+	PlayerDamageEvent,
+);
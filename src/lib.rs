@@ -1,12 +1,29 @@
-use std::{error::Error, fmt::Display, net::TcpListener, sync::Arc, thread, time::Duration};
+use std::{
+	error::Error,
+	fmt::Display,
+	net::SocketAddr,
+	sync::{Arc, mpsc},
+	thread,
+	time::Duration,
+};
 
 use context::ServerContext;
-use ignore_result::Ignore;
-use log::{error, info};
+use log::{error, info, warn};
+use play::tick::{run_tick_loop, schedule_autosave};
 use player::context::ClientContext;
-use protocol::handler::handle_connection;
-use rust_mc_proto::{MinecraftConnection, ProtocolError};
-
+use protocol::{
+	connection::Connection,
+	handler::handle_connection,
+	proxy_protocol::{is_trusted, read_proxy_header},
+};
+use rust_mc_proto::ProtocolError;
+use tokio::{
+	net::{TcpListener, TcpStream},
+	runtime::Builder,
+	task, time,
+};
+
+pub mod command;
 pub mod config;
 pub mod context;
 pub mod data;
@@ -14,6 +31,7 @@ pub mod event;
 pub mod play;
 pub mod player;
 pub mod protocol;
+pub mod world;
 
 // Ошибки сервера
 #[derive(Debug)]
@@ -27,6 +45,17 @@ pub enum ServerError {
 	SerNbt,           // Ошибка при сериализации nbt
 	DeNbt,            // Ошибка при десериализации nbt
 	UnexpectedState, // Указывает на то что этот пакет не может быть отправлен в данном режиме (в основном через ProtocolHelper)
+	Encryption,      // Ошибка при работе с шифрованием (RSA ключи, AES)
+	SessionServer,   // Сессионный сервер недоступен или ответил херню
+	Auth,            // Игрок не прошел проверку через сессионный сервер
+	Io(std::io::Error), // Ошибка ввода-вывода при работе с файлами
+	Region,          // Файл региона или чанк в нем поломан
+	Generator(String), // Неверные настройки генератора мира
+	Command(String), // Команда введена неверно или не выполнилась, сообщение показывается игроку
+	Forwarding,      // Прокси не передал данные игрока или подпись не сошлась
+	ProxyProtocol,   // Заголовок PROXY протокола поломан или не пришел
+	UnsupportedVersion(i32), // Клиент на версии протокола которую мы не поддерживаем
+	Config(String),  // С такой конфигурацией сервер запускать нельзя
 	Other(String), // Другая ошибка, либо очень специфичная, либо хз, лучше не использовать и создавать новое поле ошибки
 }
 
@@ -38,6 +67,12 @@ impl Display for ServerError {
 
 impl Error for ServerError {}
 
+impl From<std::io::Error> for ServerError {
+	fn from(error: std::io::Error) -> ServerError {
+		ServerError::Io(error)
+	}
+}
+
 // Делаем чтобы ProtocolError мог переделываться в наш ServerError
 impl From<ProtocolError> for ServerError {
 	fn from(error: ProtocolError) -> ServerError {
@@ -51,8 +86,21 @@ impl From<ProtocolError> for ServerError {
 }
 
 pub fn start_server(server: Arc<ServerContext>) {
+	// Сеть крутится в рантайме tokio, игровая логика остается в своих потоках
+	let runtime = match Builder::new_multi_thread().enable_all().build() {
+		Ok(runtime) => runtime,
+		Err(error) => {
+			error!("Не удалось запустить рантайм: {error:?}");
+			return;
+		}
+	};
+
+	runtime.block_on(run_listener(server));
+}
+
+async fn run_listener(server: Arc<ServerContext>) {
 	// Биндим сервер где надо
-	let Ok(listener) = TcpListener::bind(&server.config.bind.host) else {
+	let Ok(listener) = TcpListener::bind(&server.config.bind.host).await else {
 		error!(
 			"Не удалось забиндить сервер на {}",
 			&server.config.bind.host
@@ -62,48 +110,118 @@ pub fn start_server(server: Arc<ServerContext>) {
 
 	info!("Сервер запущен на {}", &server.config.bind.host);
 
-	while let Ok((stream, addr)) = listener.accept() {
+	// Главный тик, 20 раз в секунду
+	thread::spawn({
 		let server = server.clone();
+		move || run_tick_loop(server)
+	});
+	schedule_autosave(&server);
+
+	loop {
+		match listener.accept().await {
+			Ok((stream, addr)) => {
+				tokio::spawn(handle_client(server.clone(), stream, addr));
+			}
+			Err(error) => {
+				// Например кончились файловые дескрипторы, это не повод падать
+				error!("Ошибка принятия подключения: {error:?}");
+			}
+		}
+	}
+}
+
+async fn handle_client(server: Arc<ServerContext>, mut stream: TcpStream, peer: SocketAddr) {
+	// За балансировщиком настоящий адрес клиента приходит в заголовке PROXY протокола
+	let addr = if server.config.bind.proxy_protocol {
+		if !is_trusted(&server.config.bind.trusted_proxies, peer.ip()) {
+			warn!("Отклонено подключение от недоверенного прокси {peer}");
+			return;
+		}
+
+		let timeout = Duration::from_secs(server.config.bind.timeout);
+
+		match time::timeout(timeout, read_proxy_header(&mut stream, peer)).await {
+			Ok(Ok(addr)) => addr,
+			Ok(Err(error)) => {
+				error!("Ошибка PROXY протокола от {peer}: {error:?}");
+				return;
+			}
+			Err(_) => {
+				error!("Прокси {peer} не прислал заголовок PROXY протокола");
+				return;
+			}
+		}
+	} else {
+		peer
+	};
+
+	info!("Подключение: {}", addr);
+
+	// Разбиваем сокет на задачи чтения и записи
+	// Таймаут и размер очереди отправки берутся из конфига
+	let (conn, reader) = match Connection::new(stream, addr, &server.config.bind) {
+		Ok(v) => v,
+		Err(error) => {
+			error!("Ошибка подключения: {error:?}");
+			return;
+		}
+	};
 
-		thread::spawn(move || {
-			info!("Подключение: {}", addr);
-
-			// Установка таймаутов на чтение и запись
-			// По умолчанию пусть будет 5 секунд, надо будет сделать настройку через конфиг
-			stream
-				.set_read_timeout(Some(Duration::from_secs(server.config.bind.timeout)))
-				.ignore();
-			stream
-				.set_write_timeout(Some(Duration::from_secs(server.config.bind.timeout)))
-				.ignore();
-
-			// Оборачиваем стрим в майнкрафт конекшн лично для нашего удовольствия
-			let conn = MinecraftConnection::new(stream);
-
-			// Создаем контекст клиента
-			// Передавется во все листенеры и хандлеры чтобы определять именно этот клиент
-			let client = Arc::new(ClientContext::new(server.clone(), conn));
-
-			// Добавляем клиента в список клиентов сервера
-			// Используем адрес как ключ, врятли ipv4 будет нам врать
-			server.clients.insert(client.addr, client.clone());
-
-			// Обработка подключения
-			// Если ошибка -> выводим
-			match handle_connection(client.clone()) {
-				Ok(_) => {}
-				Err(ServerError::ConnectionClosed) => {}
-				Err(error) => {
-					error!("Ошибка подключения: {error:?}");
-				}
-			};
-
-			trigger_event_ignore!(client, disconnect);
-
-			// Удаляем клиента из списка клиентов
-			server.clients.remove(&client.addr);
-
-			info!("Отключение: {}", addr);
-		});
+	// Пакеты из задачи чтения идут в канал, оттуда их забирает read_packet или главный тик
+	let (inbound, receiver) = mpsc::channel();
+
+	// Создаем контекст клиента
+	// Передавется во все листенеры и хандлеры чтобы определять именно этот клиент
+	let client = Arc::new(ClientContext::new(server.clone(), conn, receiver));
+
+	// Добавляем клиента в список клиентов сервера
+	// Используем адрес как ключ, врятли ipv4 будет нам врать
+	server.clients.insert(client.addr, client.clone());
+
+	let read_loop = tokio::spawn(client.clone().run_read_loop(reader, inbound));
+
+	// Обработка подключения
+	// Логин и конфигурация написаны блокирующими, поэтому идут в отдельный пул потоков
+	// Если ошибка -> выводим и закрываем соединение
+	let result = task::spawn_blocking({
+		let client = client.clone();
+		move || handle_connection(client)
+	})
+	.await;
+
+	match result {
+		Ok(Ok(())) => {}
+		Ok(Err(ServerError::ConnectionClosed)) => client.close(),
+		Ok(Err(error)) => {
+			error!("Ошибка подключения: {error:?}");
+			client.close();
+		}
+		Err(error) => {
+			error!("Обработчик подключения упал: {error:?}");
+			client.close();
+		}
 	}
+
+	// Игрок живет пока живо чтение, своего потока у него нет
+	match read_loop.await {
+		Ok(Ok(())) | Ok(Err(ServerError::ConnectionClosed)) => {}
+		Ok(Err(error)) => error!("Ошибка подключения: {error:?}"),
+		Err(error) => error!("Задача чтения упала: {error:?}"),
+	}
+
+	let _ = task::spawn_blocking(move || {
+		trigger_event_ignore!(client, disconnect);
+
+		// Задачи клиента больше не нужны
+		server.planner().cancel_client_tasks(&client);
+
+		// Удаляем клиента из списка клиентов
+		server.clients.remove(&client.addr);
+
+		// Дописываем то что осталось в очереди и закрываем сокет
+		client.close();
+
+		info!("Отключение: {}", addr);
+	})
+	.await;
 }
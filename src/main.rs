@@ -7,10 +7,16 @@ use rust_mc_serv::{
 	config::Config,
 	context::ServerContext,
 	data::component::TextComponent,
-	event::{Listener, PacketHandler},
-	play::{PlayHandler, PlayListener},
+	event::{
+		Listener, PacketHandler,
+		player::{Cancellable, PlayerCommandEvent},
+	},
+	play::{
+		PlayHandler, PlayListener, command::register_default_commands, playerdata::save_all_players,
+		send_rainbow_message,
+	},
 	player::context::ClientContext,
-	protocol::ConnectionState,
+	protocol::{ConnectionState, version::ProtocolVersion},
 	start_server,
 };
 
@@ -22,10 +28,14 @@ impl Listener for ExampleListener {
 		client: Arc<ClientContext>,
 		response: &mut String,
 	) -> Result<(), ServerError> {
+		// Чужую версию клиент покажет в списке серверов красным
+		let version = ProtocolVersion::from_protocol(client.handshake().unwrap().protocol_version)
+			.unwrap_or(ProtocolVersion::LATEST);
+
 		*response = format!(
 			"{{
 				\"version\": {{
-					\"name\": \"idk\",
+					\"name\": \"{}\",
 					\"protocol\": {}
 				}},
 				\"players\": {{
@@ -42,7 +52,8 @@ impl Listener for ExampleListener {
 				\"favicon\": \"data:image/png;base64,<data>\",
 				\"enforcesSecureChat\": false
 			}}",
-			client.handshake().unwrap().protocol_version,
+			version.name(),
+			version.protocol(),
 			TextComponent::builder()
 				.text("Hello World! ")
 				.extra(vec![
@@ -77,6 +88,20 @@ impl Listener for ExampleListener {
 
 		Ok(())
 	}
+
+	fn on_player_command(
+		&self,
+		client: Arc<ClientContext>,
+		event: &mut PlayerCommandEvent,
+	) -> Result<(), ServerError> {
+		// Своя команда, отменяем событие чтобы сервер ее не обрабатывал дальше
+		if event.command == "hello" {
+			send_rainbow_message(&client, "Hello World!".to_string())?;
+			event.cancel();
+		}
+
+		Ok(())
+	}
 }
 
 struct ExamplePacketHandler;
@@ -134,7 +159,7 @@ fn main() {
 	}
 
 	// Берем путь из аргумента либо по дефолту берем "./server.toml"
-	let config_path = PathBuf::from(args.get(0).unwrap_or(&"server.toml".to_string()));
+	let config_path = PathBuf::from(args.first().unwrap_or(&"server.toml".to_string()));
 
 	// Чтение конфига, если ошибка - выводим
 	let config = match Config::load_from_file(config_path) {
@@ -152,11 +177,18 @@ fn main() {
 
 	// Создаем контекст сервера
 	// Передается во все подключения
-	let mut server = ServerContext::new(config);
+	let mut server = match ServerContext::new(config) {
+		Ok(server) => server,
+		Err(error) => {
+			error!("Не удалось запустить сервер: {error:?}");
+			return;
+		}
+	};
 
 	// Добавляем дефолтную обработку режима Play
 	server.add_packet_handler(Box::new(PlayHandler));
 	server.add_listener(Box::new(PlayListener));
+	register_default_commands(&mut server);
 
 	server.add_listener(Box::new(ExampleListener)); // Добавляем пример листенера
 	server.add_packet_handler(Box::new(ExamplePacketHandler)); // Добавляем пример пакет хандлера
@@ -164,6 +196,23 @@ fn main() {
 	// Бетонируем сервер контекст от изменений
 	let server = Arc::new(server);
 
+	// Сохраняем мир при выключении через Ctrl+C, SIGTERM или SIGHUP
+	{
+		let server = server.clone();
+		let result = ctrlc::set_handler(move || {
+			info!("Сохранение игроков...");
+			save_all_players(&server);
+			info!("Сохранение мира...");
+			if let Err(error) = server.world.save() {
+				error!("Ошибка сохранения мира: {error:?}");
+			}
+			std::process::exit(0);
+		});
+		if let Err(error) = result {
+			error!("Не удалось установить обработчик выключения: {error:?}");
+		}
+	}
+
 	// Запускаем сервер из специально отведенной под это дело функцией
 	start_server(server);
 }
@@ -0,0 +1,172 @@
+// Ломание и установка блоков игроками
+// Клиент сам предсказывает результат, а мы подтверждаем его через Acknowledge Block Change,
+// если же мы не согласны - отправляем ему настоящий блок через Block Update
+
+use std::sync::Arc;
+
+use crate::{
+	ServerError,
+	data::Position,
+	event::player::{PlayerBlockBreakEvent, PlayerBlockPlaceEvent},
+	player::{abilities::SPECTATOR, combat::EYE_HEIGHT, context::ClientContext},
+	world::chunk::AIR,
+};
+
+use crate::trigger_event;
+
+use super::{
+	combat::reset_attack_strength,
+	health::exhaust_block_break,
+	helper::{acknowledge_block_change, send_block_update, set_block},
+	inventory::{drop_held_item, swap_hands, take_hand_item},
+};
+
+pub const STARTED_DIGGING: i32 = 0;
+pub const CANCELLED_DIGGING: i32 = 1;
+pub const FINISHED_DIGGING: i32 = 2;
+pub const DROP_ITEM_STACK: i32 = 3;
+pub const DROP_ITEM: i32 = 4;
+pub const SWAP_ITEM_IN_HAND: i32 = 6;
+
+// С запасом, ванилла разрешает 4.5 блока в выживании и 5 в креативе плюс погрешность
+const MAX_REACH: f64 = 8.0;
+
+fn in_reach(client: &Arc<ClientContext>, position: Position) -> bool {
+	let (x, y, z) = client.entity_info().unwrap().position();
+	let dx = position.x as f64 + 0.5 - x;
+	let dy = position.y as f64 + 0.5 - (y + EYE_HEIGHT);
+	let dz = position.z as f64 + 0.5 - z;
+	dx * dx + dy * dy + dz * dz <= MAX_REACH * MAX_REACH
+}
+
+// Отправляет клиенту настоящий блок, чтобы откатить его предсказание
+fn resync_block(client: &Arc<ClientContext>, position: Position) -> Result<(), ServerError> {
+	let state = client
+		.server
+		.world
+		.get_block(position.x, position.y, position.z);
+	send_block_update(client.clone(), position, state)
+}
+
+fn break_block(client: &Arc<ClientContext>, position: Position) -> Result<(), ServerError> {
+	// В приключении и наблюдателе may_build выключен
+	if !client.entity_info().unwrap().abilities().may_build || !in_reach(client, position) {
+		return resync_block(client, position);
+	}
+
+	let mut event = PlayerBlockBreakEvent {
+		position,
+		block: client
+			.server
+			.world
+			.get_block(position.x, position.y, position.z),
+		cancelled: false,
+	};
+
+	trigger_event!(client, player_block_break, &mut event);
+
+	if event.cancelled {
+		return resync_block(client, position);
+	}
+
+	set_block(&client.server, position, AIR)?;
+	exhaust_block_break(client);
+
+	Ok(())
+}
+
+/// Пакет Player Action
+pub fn handle_player_action(
+	client: Arc<ClientContext>,
+	status: i32,
+	location: Position,
+	sequence: i32,
+) -> Result<(), ServerError> {
+	let instabuild = client.entity_info().unwrap().abilities().instabuild;
+
+	match status {
+		// В креативе блоки ломаются сразу, FINISHED_DIGGING не приходит
+		STARTED_DIGGING if instabuild => break_block(&client, location)?,
+		STARTED_DIGGING | CANCELLED_DIGGING => {}
+		FINISHED_DIGGING => break_block(&client, location)?,
+		// Дальше не про блоки, подтверждать нечего
+		DROP_ITEM_STACK | DROP_ITEM => return drop_held_item(client, status == DROP_ITEM_STACK),
+		SWAP_ITEM_IN_HAND => return swap_hands(client),
+		// Еда, натягивание лука и тд
+		_ => return Ok(()),
+	}
+
+	acknowledge_block_change(client, sequence)
+}
+
+/// Пакет Use Item On
+pub fn handle_use_item_on(
+	client: Arc<ClientContext>,
+	hand: i32,
+	location: Position,
+	face: i32,
+	sequence: i32,
+) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+	let world = &client.server.world;
+
+	if entity_info.game_mode() == SPECTATOR {
+		return acknowledge_block_change(client, sequence);
+	}
+
+	// Если кликнули по воздуху (например по траве которую мы не знаем), ставим прямо туда
+	let target = if world.get_block(location.x, location.y, location.z) == AIR {
+		location
+	} else {
+		location.relative(face)
+	};
+
+	// Блочный предмет называется так же как и блок
+	let block = entity_info
+		.hand_item(hand)
+		.and_then(|o| world.items().get_name(o.id as u32))
+		.and_then(|o| world.blocks().get_default_id(o))
+		.filter(|o| *o != AIR);
+
+	let placeable = entity_info.abilities().may_build
+		&& in_reach(&client, target)
+		&& world.get_block(target.x, target.y, target.z) == AIR;
+
+	match block {
+		Some(block) if placeable => {
+			let mut event = PlayerBlockPlaceEvent {
+				position: target,
+				block,
+				hand,
+				cancelled: false,
+			};
+
+			trigger_event!(client, player_block_place, &mut event);
+
+			if event.cancelled {
+				resync_block(&client, location)?;
+				resync_block(&client, target)?;
+			} else if set_block(&client.server, target, event.block)?.is_none() {
+				resync_block(&client, target)?; // Вне мира по высоте
+			} else if !entity_info.abilities().instabuild {
+				// Клиент сам убрал предмет из руки, повторяем за ним
+				take_hand_item(&client, hand, 1);
+			}
+		}
+		_ => {
+			resync_block(&client, location)?;
+			resync_block(&client, target)?;
+		}
+	}
+
+	acknowledge_block_change(client, sequence)
+}
+
+/// Пакет Set Held Item
+pub fn handle_set_held_item(client: Arc<ClientContext>, slot: i16) -> Result<(), ServerError> {
+	if (0..9).contains(&slot) {
+		client.entity_info().unwrap().set_held_slot(slot as u8);
+		reset_attack_strength(&client);
+	}
+	Ok(())
+}
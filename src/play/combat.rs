@@ -0,0 +1,200 @@
+// Ближний бой между игроками: пакет Interact, заряд удара, криты и отбрасывание
+// Расчеты в player::combat, сам урон и смерть в play::health
+
+use std::sync::Arc;
+
+use crate::{
+	ServerError,
+	player::{
+		abilities::SPECTATOR,
+		combat::{
+			ATTACK_EXHAUSTION, ATTACK_REACH, BASE_KNOCKBACK, CRITICAL_MULTIPLIER, EYE_HEIGHT,
+			SPRINT_KNOCKBACK, attack_strength, distance_to_player, knockback, scaled_damage,
+			weapon_stats,
+		},
+		context::ClientContext,
+		health::DamageSource,
+	},
+	protocol::{
+		codec::VarInt,
+		packets::play::{InteractAction, clientbound},
+	},
+	world::entity::WorldEntity,
+};
+
+use super::{
+	health::damage,
+	helper::{log_send_error, play_entity_sound, send_entity_animation},
+};
+
+// Entity Animation
+const CRITICAL_EFFECT: u8 = 4;
+
+// Категория звуков игроков
+const PLAYERS_CATEGORY: i32 = 7;
+
+// Заряд после которого удар считается полным, только такой может быть критом
+const FULL_STRENGTH: f32 = 0.9;
+
+/// Пакет Interact, правый клик по игроку нам пока не нужен
+pub fn handle_interact(
+	client: Arc<ClientContext>,
+	entity_id: i32,
+	action: InteractAction,
+) -> Result<(), ServerError> {
+	match action {
+		InteractAction::Attack => attack(client, entity_id),
+		_ => Ok(()),
+	}
+}
+
+/// Сбрасывает заряд удара, ванилла делает так при ударе и смене предмета в руке
+pub fn reset_attack_strength(client: &Arc<ClientContext>) {
+	client
+		.entity_info()
+		.unwrap()
+		.set_last_attack_tick(client.server.planner().current_tick());
+}
+
+fn attack(client: Arc<ClientContext>, target_id: i32) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
+	// Наблюдатель левым кликом вселяется в сущность, а не бьет
+	if entity_info.is_dead() || entity_info.game_mode() == SPECTATOR {
+		return Ok(());
+	}
+
+	// Остальные сущности урон пока не получают
+	let Some(WorldEntity::Player(target)) = client.server.get_entity(target_id) else {
+		return Ok(());
+	};
+	if target.addr == client.addr {
+		return Ok(());
+	}
+	let target_info = target.entity_info().unwrap();
+
+	let (x, y, z) = entity_info.position();
+	let (target_x, _, target_z) = target_info.position();
+
+	if target_info.is_dead()
+		|| distance_to_player((x, y + EYE_HEIGHT, z), target_info.position()) > ATTACK_REACH
+	{
+		return Ok(());
+	}
+
+	let items = client.server.world.items();
+	let (weapon_damage, attack_speed) = weapon_stats(
+		entity_info
+			.hand_item(0)
+			.and_then(|o| items.get_name(o.id as u32)),
+	);
+
+	let ticks = client
+		.server
+		.planner()
+		.current_tick()
+		.saturating_sub(entity_info.last_attack_tick());
+	let strength = attack_strength(ticks, attack_speed);
+	reset_attack_strength(&client);
+
+	let full = strength > FULL_STRENGTH;
+	let sprinting = entity_info.sprinting();
+	let sprint_knockback = full && sprinting;
+	let critical =
+		full && !sprinting && !entity_info.on_ground() && entity_info.health_data().fall_distance > 0.0;
+
+	let mut amount = scaled_damage(weapon_damage, strength);
+	if critical {
+		amount *= CRITICAL_MULTIPLIER;
+	}
+
+	if !damage(
+		target.clone(),
+		amount,
+		&DamageSource::player_attack(entity_info.entity_id),
+	)? {
+		return broadcast_sound(&client, "minecraft:entity.player.attack.nodamage");
+	}
+
+	// Отбрасывание от ударившего, а на бегу еще и по направлению взгляда
+	let mut velocity = knockback(
+		(0.0, 0.0, 0.0),
+		BASE_KNOCKBACK,
+		x - target_x,
+		z - target_z,
+		target_info.on_ground(),
+	);
+
+	if sprint_knockback {
+		let yaw = (entity_info.rotation().0 as f64).to_radians();
+		velocity = knockback(
+			velocity,
+			SPRINT_KNOCKBACK,
+			yaw.sin(),
+			-yaw.cos(),
+			target_info.on_ground(),
+		);
+		entity_info.set_sprinting(false);
+	}
+
+	target_info.set_velocity(velocity);
+
+	let (velocity_x, velocity_y, velocity_z) = velocity;
+	let packet = clientbound::SetEntityVelocity {
+		entity_id: VarInt(target_info.entity_id),
+		velocity_x: (velocity_x * 8000.0) as i16,
+		velocity_y: (velocity_y * 8000.0) as i16,
+		velocity_z: (velocity_z * 8000.0) as i16,
+	};
+
+	for player in client.server.players() {
+		let result = player.send_packet(&packet).and_then(|_| {
+			if critical {
+				send_entity_animation(player.clone(), target_info.entity_id, CRITICAL_EFFECT)
+			} else {
+				Ok(())
+			}
+		});
+		log_send_error(&player, result);
+	}
+
+	// Камера жертвы наклоняется в сторону удара
+	let hurt_yaw = (z - target_z).atan2(x - target_x).to_degrees() as f32 - target_info.rotation().0;
+	target.send_packet(&clientbound::HurtAnimation {
+		entity_id: VarInt(target_info.entity_id),
+		yaw: hurt_yaw,
+	})?;
+
+	if sprint_knockback {
+		broadcast_sound(&client, "minecraft:entity.player.attack.knockback")?;
+	}
+
+	broadcast_sound(
+		&client,
+		if critical {
+			"minecraft:entity.player.attack.crit"
+		} else if full {
+			"minecraft:entity.player.attack.strong"
+		} else {
+			"minecraft:entity.player.attack.weak"
+		},
+	)?;
+
+	if !entity_info.abilities().invulnerable {
+		entity_info.health_data().add_exhaustion(ATTACK_EXHAUSTION);
+	}
+
+	Ok(())
+}
+
+// Звук удара от атакующего, слышат все
+fn broadcast_sound(client: &Arc<ClientContext>, sound: &str) -> Result<(), ServerError> {
+	let entity_id = client.entity_info().unwrap().entity_id;
+
+	for player in client.server.players() {
+		let result = play_entity_sound(player.clone(), entity_id, sound, 1.0, 1.0, PLAYERS_CATEGORY);
+		log_send_error(&player, result);
+	}
+
+	Ok(())
+}
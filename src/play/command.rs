@@ -0,0 +1,208 @@
+// Стандартные команды сервера
+
+use std::sync::Arc;
+
+use crate::{
+	ServerError,
+	command::{
+		CommandContext, CommandNode,
+		argument::{ArgumentParser, GAME_MODES, StringKind},
+	},
+	context::ServerContext,
+	player::{
+		context::ClientContext,
+		health::{DamageSource, GENERIC_KILL},
+	},
+	world::entity::{BUILTIN_ENTITY_TYPES, Entity, PLAYER},
+};
+
+use super::{
+	entity::{entity_type_id, spawn_entity},
+	gamemode::set_game_mode,
+	health,
+	helper::send_entity_event,
+	send_rainbow_message,
+};
+
+/// Меняет уровень прав игрока от 0 до 4
+///
+/// Клиенту уходит новый уровень и заново дерево команд, в нем теперь другие команды
+pub fn set_permission_level(client: Arc<ClientContext>, level: u8) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+	let level = level.min(4);
+
+	entity_info.set_permission_level(level);
+
+	send_entity_event(client.clone(), entity_info.entity_id, 24 + level)?; // 24-28 - op level 0-4
+	client.send_packet(&client.server.commands().to_packet(&client))
+}
+
+// Цели из аргумента, либо сам игрок если аргумента нет
+fn targets(context: &CommandContext, name: &str) -> Vec<Arc<ClientContext>> {
+	context
+		.get_entities(name)
+		.map(|o| o.to_vec())
+		.unwrap_or_else(|| vec![context.client.clone()])
+}
+
+fn gamemode(context: &CommandContext) -> Result<(), ServerError> {
+	let game_mode = context.get_game_mode("gamemode").unwrap_or_default();
+
+	for target in targets(context, "target") {
+		if !set_game_mode(target.clone(), game_mode)? {
+			continue;
+		}
+
+		send_rainbow_message(
+			&target,
+			format!("gamemode {} installed", GAME_MODES[game_mode as usize]),
+		)?;
+	}
+
+	Ok(())
+}
+
+fn kill(context: &CommandContext) -> Result<(), ServerError> {
+	for target in targets(context, "targets") {
+		health::kill(target.clone(), &DamageSource::new(GENERIC_KILL))?;
+	}
+
+	Ok(())
+}
+
+fn summon(context: &CommandContext) -> Result<(), ServerError> {
+	let client = &context.client;
+	let name = context.get_string("entity").unwrap_or_default().trim();
+	let entity_type = if name.contains(':') {
+		name.to_string()
+	} else {
+		format!("minecraft:{name}")
+	};
+
+	// Игроков спавнит только вход на сервер
+	if entity_type == PLAYER || entity_type_id(client, &entity_type).is_none() {
+		return Err(ServerError::Command(format!("Unknown entity: {name}")));
+	}
+
+	let entity_info = client.entity_info().unwrap();
+	let entity = spawn_entity(
+		&client.server,
+		Entity::new(
+			client.server.world.next_entity_id(),
+			&entity_type,
+			entity_info.position(),
+		)
+		.with_rotation((entity_info.rotation().0, 0.0)),
+	);
+
+	send_rainbow_message(client, format!("Summoned {entity_type} #{}", entity.id))
+}
+
+// Права выданные командой живут до выхода, постоянные операторы задаются в конфиге
+fn op(context: &CommandContext) -> Result<(), ServerError> {
+	let level = context.client.server.config.server.op_permission_level;
+
+	for target in targets(context, "targets") {
+		set_permission_level(target.clone(), level)?;
+		send_rainbow_message(&target, format!("permission level {level} installed"))?;
+	}
+
+	Ok(())
+}
+
+fn deop(context: &CommandContext) -> Result<(), ServerError> {
+	let level = context.client.server.config.server.default_permission_level;
+
+	for target in targets(context, "targets") {
+		set_permission_level(target.clone(), level)?;
+		send_rainbow_message(&target, format!("permission level {level} installed"))?;
+	}
+
+	Ok(())
+}
+
+fn tps(context: &CommandContext) -> Result<(), ServerError> {
+	let server = &context.client.server;
+	send_rainbow_message(
+		&context.client,
+		format!("TPS: {:.1}, MSPT: {:.2}", server.tps(), server.mspt()),
+	)
+}
+
+/// Регистрирует стандартные команды, вызывать до того как контекст обернут в Arc
+pub fn register_default_commands(server: &mut ServerContext) {
+	server.register_command(
+		CommandNode::literal("gamemode").requires(2).then(
+			CommandNode::argument("gamemode", ArgumentParser::GameMode)
+				.executes(gamemode)
+				.then(
+					CommandNode::argument(
+						"target",
+						ArgumentParser::Entity {
+							single: false,
+							players_only: true,
+						},
+					)
+					.executes(gamemode),
+				),
+		),
+	);
+
+	server.register_command(
+		CommandNode::literal("kill")
+			.requires(2)
+			.executes(kill)
+			.then(
+				CommandNode::argument(
+					"targets",
+					ArgumentParser::Entity {
+						single: false,
+						players_only: false,
+					},
+				)
+				.executes(kill),
+			),
+	);
+
+	server.register_command(
+		CommandNode::literal("summon").requires(2).then(
+			CommandNode::argument("entity", ArgumentParser::String(StringKind::Greedy))
+				.suggests(|_, _| {
+					BUILTIN_ENTITY_TYPES
+						.iter()
+						.filter(|(name, _)| *name != PLAYER)
+						.map(|(name, _)| name.to_string())
+						.collect()
+				})
+				.executes(summon),
+		),
+	);
+
+	server.register_command(
+		CommandNode::literal("op").requires(3).then(
+			CommandNode::argument(
+				"targets",
+				ArgumentParser::Entity {
+					single: false,
+					players_only: true,
+				},
+			)
+			.executes(op),
+		),
+	);
+
+	server.register_command(
+		CommandNode::literal("deop").requires(3).then(
+			CommandNode::argument(
+				"targets",
+				ArgumentParser::Entity {
+					single: false,
+					players_only: true,
+				},
+			)
+			.executes(deop),
+		),
+	);
+
+	server.register_command(CommandNode::literal("tps").requires(2).executes(tps));
+}
@@ -1,28 +1,27 @@
-use std::io::Cursor;
 use std::sync::Arc;
 
-use rust_mc_proto::{DataWriter, Packet, read_packet};
+use log::warn;
 
-use crate::protocol::packet_id::*;
-use crate::{ServerError, player::context::ClientContext};
+use crate::protocol::packets::configuration::{KnownPack, clientbound, serverbound};
+use crate::{ServerError, data::component::TextComponent, player::context::ClientContext};
 
 pub fn send_update_tags(client: Arc<ClientContext>) -> Result<(), ServerError> {
-	// TODO: rewrite this hardcode bullshit
+	let registries = client.server.registries(client.protocol_version());
 
-	client.write_packet(&Packet::from_bytes(
-		clientbound::configuration::UPDATE_TAGS,
-		include_bytes!("update-tags.bin"),
-	))
+	client.send_packet(&clientbound::UpdateTags {
+		registries: registries.update_tags(),
+	})
 }
 
-pub fn send_registry_data(client: Arc<ClientContext>) -> Result<(), ServerError> {
-	// TODO: rewrite this hardcode bullshit
-
-	let mut registry_data = Cursor::new(include_bytes!("registry-data.bin"));
+/// known_packs - паки которые клиент знает сам, из его ответа на Known Packs
+pub fn send_registry_data(
+	client: Arc<ClientContext>,
+	known_packs: &[KnownPack],
+) -> Result<(), ServerError> {
+	let registries = client.server.registries(client.protocol_version());
 
-	while let Ok(mut packet) = read_packet(&mut registry_data, None) {
-		packet.set_id(clientbound::configuration::REGISTRY_DATA);
-		client.write_packet(&packet)?;
+	for packet in registries.registry_data(known_packs) {
+		client.send_packet(&packet)?;
 	}
 
 	Ok(())
@@ -32,20 +31,39 @@ pub fn send_registry_data(client: Arc<ClientContext>) -> Result<(), ServerError>
 pub fn handle_configuration_state(
 	client: Arc<ClientContext>, // Контекст клиента
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::configuration::FEATURE_FLAGS);
-	packet.write_varint(1)?;
-	packet.write_string("minecraft:vanilla")?;
-	client.write_packet(&packet)?;
+	client.send_packet(&clientbound::FeatureFlags {
+		feature_flags: vec!["minecraft:vanilla".to_string()],
+	})?;
+
+	let registries = client.server.registries(client.protocol_version());
 
-	let mut packet = Packet::empty(clientbound::configuration::KNOWN_PACKS);
-	packet.write_varint(1)?;
-	packet.write_string("minecraft")?;
-	packet.write_string("core")?;
-	packet.write_string("1.21.5")?;
-	client.write_packet(&packet)?;
+	// Объявляем ванильный core и все датапаки, клиент ответит какие из них знает сам
+	client.send_packet(&clientbound::KnownPacks {
+		known_packs: registries.packs().to_vec(),
+	})?;
 
-	client.read_packet(&[serverbound::configuration::KNOWN_PACKS])?;
+	let known_packs = client
+		.receive_packet::<serverbound::KnownPacks>()?
+		.known_packs;
+
+	// Ванильных данных у нас нет, без core клиент не соберет реестры и упадет сам
+	if let Some(core) = registries.packs().first()
+		&& !known_packs.contains(core)
+	{
+		warn!(
+			"Клиент {} не знает пак {}:{} {}, отключаем",
+			client.addr, core.namespace, core.id, core.version
+		);
+		client
+			.protocol_helper()
+			.disconnect(TextComponent::new(format!(
+				"Your client does not have the {}:{} data pack for {}",
+				core.namespace, core.id, core.version
+			)))?;
+		client.close();
+		return Err(ServerError::ConnectionClosed);
+	}
 
-	send_registry_data(client.clone())?;
+	send_registry_data(client.clone(), &known_packs)?;
 	send_update_tags(client.clone())
 }
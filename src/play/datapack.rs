@@ -0,0 +1,199 @@
+// Загрузка датапаков в ванильном формате
+//
+// datapacks/<пак>/pack.mcmeta
+// datapacks/<пак>/data/<неймспейс>/<реестр>/<запись>.json
+// datapacks/<пак>/data/<неймспейс>/tags/<реестр>/<тег>.json
+//
+// Берутся только синхронизируемые реестры и теги, остальное (лут, рецепты и тд) пока игнорируется
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+	ServerError,
+	protocol::{packets::configuration::KnownPack, version::ProtocolVersion},
+};
+
+use super::registry::{Registries, TagValue};
+
+#[derive(Deserialize)]
+struct PackMeta {
+	pack: PackInfo,
+}
+
+#[derive(Deserialize)]
+struct PackInfo {
+	pack_format: i32,
+}
+
+#[derive(Deserialize)]
+struct TagFile {
+	#[serde(default)]
+	replace: bool,
+	values: Vec<Value>,
+}
+
+/// Загружает все датапаки из папки, паки применяются по алфавиту
+///
+/// Поломанный пак пропускается целиком, остальные все равно загрузятся
+pub fn load_datapacks(path: &Path, version: ProtocolVersion, registries: &mut Registries) {
+	let Ok(dir) = fs::read_dir(path) else {
+		return;
+	};
+
+	let mut packs = dir
+		.filter_map(|o| o.ok())
+		.map(|o| o.path())
+		.collect::<Vec<PathBuf>>();
+	packs.sort();
+
+	for pack in packs {
+		let name = pack
+			.file_name()
+			.unwrap_or_default()
+			.to_string_lossy()
+			.to_string();
+
+		if !pack.is_dir() {
+			warn!("Датапак {name} пропущен: поддерживаются только папки");
+			continue;
+		}
+
+		// Пак применяем к копии, чтобы поломанный не оставил половину своих записей
+		let mut loaded = registries.clone();
+
+		match load_datapack(&pack, &name, version, &mut loaded) {
+			Ok(()) => {
+				*registries = loaded;
+				info!("Загружен датапак {name}");
+			}
+			Err(error) => warn!("Не удалось загрузить датапак {name}: {error:?}"),
+		}
+	}
+}
+
+fn load_datapack(
+	path: &Path,
+	name: &str,
+	version: ProtocolVersion,
+	registries: &mut Registries,
+) -> Result<(), ServerError> {
+	let meta: PackMeta = read_json(&path.join("pack.mcmeta"))?;
+
+	if meta.pack.pack_format != version.data_pack_format() {
+		warn!(
+			"Датапак {name} сделан для другой версии (pack_format {}, нужен {})",
+			meta.pack.pack_format,
+			version.data_pack_format()
+		);
+	}
+
+	// Как у ванильного сервера, паки из папки идут под неймспейсом file
+	let pack = KnownPack {
+		namespace: "file".to_string(),
+		id: name.to_string(),
+		version: meta.pack.pack_format.to_string(),
+	};
+
+	let Ok(namespaces) = fs::read_dir(path.join("data")) else {
+		registries.add_pack(pack);
+		return Ok(());
+	};
+
+	let mut namespaces = namespaces
+		.filter_map(|o| o.ok())
+		.filter(|o| o.path().is_dir())
+		.map(|o| o.file_name().to_string_lossy().to_string())
+		.collect::<Vec<String>>();
+	namespaces.sort();
+
+	for namespace in namespaces {
+		let data = path.join("data").join(&namespace);
+
+		for registry in registries.registry_names() {
+			let Some(registry_path) = registry.strip_prefix("minecraft:") else {
+				continue;
+			};
+
+			for (entry, file) in json_files(&data.join(registry_path))? {
+				let value: Value = read_json(&file)?;
+				registries.add_entry(&pack, &registry, &format!("{namespace}:{entry}"), value);
+			}
+		}
+
+		// Путь тега внутри tags/ начинается с пути реестра, берем самый длинный подходящий
+		let mut tag_registries = registries.registry_names();
+		tag_registries.extend(registries.tag_registry_names());
+		tag_registries.sort_by_key(|o| std::cmp::Reverse(o.len()));
+
+		for (tag_path, file) in json_files(&data.join("tags"))? {
+			let Some((registry, tag)) = tag_registries.iter().find_map(|registry| {
+				let registry_path = registry.strip_prefix("minecraft:")?;
+				let tag = tag_path.strip_prefix(registry_path)?.strip_prefix('/')?;
+				Some((registry, tag))
+			}) else {
+				warn!("Датапак {name}: неизвестный реестр у тега {namespace}:{tag_path}");
+				continue;
+			};
+
+			let tag_file: TagFile = read_json(&file)?;
+
+			registries.add_tag(
+				registry,
+				&format!("{namespace}:{tag}"),
+				tag_file
+					.values
+					.iter()
+					.filter_map(TagValue::from_json)
+					.collect(),
+				tag_file.replace,
+			);
+		}
+	}
+
+	registries.add_pack(pack);
+
+	Ok(())
+}
+
+fn read_json<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<T, ServerError> {
+	let content = fs::read_to_string(path)?;
+	serde_json::from_str(&content).map_err(|e| ServerError::Io(e.into()))
+}
+
+// Все .json в папке и ее подпапках, путь относительно папки без расширения
+// Отсортированы, чтобы айди новых записей не менялись от запуска к запуску
+fn json_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, ServerError> {
+	let mut files = Vec::new();
+
+	if !dir.is_dir() {
+		return Ok(files);
+	}
+
+	for entry in fs::read_dir(dir)? {
+		let path = entry?.path();
+		let name = path
+			.file_name()
+			.unwrap_or_default()
+			.to_string_lossy()
+			.to_string();
+
+		if path.is_dir() {
+			for (inner, file) in json_files(&path)? {
+				files.push((format!("{name}/{inner}"), file));
+			}
+		} else if let Some(name) = name.strip_suffix(".json") {
+			files.push((name.to_string(), path));
+		}
+	}
+
+	files.sort();
+
+	Ok(files)
+}
@@ -0,0 +1,398 @@
+// Сущности мира кроме игроков: спавн, отслеживание, метаданные и тик
+// Сама модель сущности в world::entity
+//
+// Игрок видит сущности в загруженных у него чанках. Кого он видит - в tracked_entities,
+// главный тик сверяет этот список с чанками и спавнит или убирает сущности у клиента
+
+use std::{collections::HashSet, f64::consts::PI, sync::Arc};
+
+use log::warn;
+
+use crate::{
+	ServerError,
+	context::ServerContext,
+	data::{
+		metadata::{ITEM_INDEX, MetadataValue},
+		slot::Slot,
+	},
+	event::player::PlayerPickupItemEvent,
+	player::{abilities::SPECTATOR, combat::EYE_HEIGHT, context::ClientContext},
+	protocol::{
+		codec::{Angle, VarInt},
+		packets::play::clientbound,
+	},
+	trigger_event,
+	world::{
+		block_to_chunk,
+		chunk::AIR,
+		entity::{
+			DRAG, DROP_PICKUP_DELAY, Entity, GROUND_FRICTION, ITEM_LIFETIME, builtin_entity_type_id,
+			can_pick_up,
+		},
+	},
+};
+
+use super::{health::VOID_Y, helper::log_send_error, inventory::give_item};
+
+// Скорость меньше этой считается нулем, как в ванилле
+const MIN_VELOCITY: f64 = 0.003;
+
+// Скорость выкинутого предмета по направлению взгляда
+const DROP_VELOCITY: f64 = 0.3;
+
+/// Айди типа сущности для версии клиента, без отчета registries.json знаем только основные типы
+pub fn entity_type_id(client: &Arc<ClientContext>, entity_type: &str) -> Option<i32> {
+	client
+		.server
+		.registries(client.protocol_version())
+		.entry_id("minecraft:entity_type", entity_type)
+		.map(|o| o as i32)
+		.or_else(|| builtin_entity_type_id(entity_type))
+}
+
+/// Добавляет сущность в мир, игроки рядом увидят ее в конце тика
+///
+/// Айди для сущности берется из WorldContext::next_entity_id
+pub fn spawn_entity(server: &Arc<ServerContext>, entity: Entity) -> Arc<Entity> {
+	let entity = Arc::new(entity);
+	server.world.entities().insert(entity.clone());
+	entity
+}
+
+/// Кладет предмет на землю, подобрать его можно через pickup_delay тиков
+pub fn spawn_item(
+	server: &Arc<ServerContext>,
+	item: Slot,
+	position: (f64, f64, f64),
+	velocity: (f64, f64, f64),
+	pickup_delay: u32,
+) -> Arc<Entity> {
+	let entity = Entity::item(server.world.next_entity_id(), item, position)
+		.with_velocity(velocity)
+		.with_pickup_delay(pickup_delay);
+	spawn_entity(server, entity)
+}
+
+/// Выкидывает предмет перед игроком по направлению взгляда, как клавиша Q
+pub fn drop_item(client: Arc<ClientContext>, item: Slot) -> Arc<Entity> {
+	let entity_info = client.entity_info().unwrap();
+	let (x, y, z) = entity_info.position();
+	let (yaw, pitch) = entity_info.rotation();
+	let (yaw, pitch) = ((yaw as f64).to_radians(), (pitch as f64).to_radians());
+
+	// Чуть-чуть разброса, как у ванильного Player.drop
+	let angle = rand::random::<f64>() * PI * 2.0;
+	let spread = rand::random::<f64>() * 0.02;
+
+	let velocity = (
+		-yaw.sin() * pitch.cos() * DROP_VELOCITY + angle.cos() * spread,
+		-pitch.sin() * DROP_VELOCITY + 0.1 + (rand::random::<f64>() - rand::random::<f64>()) * 0.1,
+		yaw.cos() * pitch.cos() * DROP_VELOCITY + angle.sin() * spread,
+	);
+
+	spawn_item(
+		&client.server,
+		item,
+		(x, y + EYE_HEIGHT - 0.3, z),
+		velocity,
+		DROP_PICKUP_DELAY,
+	)
+}
+
+/// Разбрасывает предмет вокруг игрока, как при смерти
+pub fn scatter_item(client: Arc<ClientContext>, item: Slot) -> Arc<Entity> {
+	let (x, y, z) = client.entity_info().unwrap().position();
+
+	let strength = rand::random::<f64>() * 0.5;
+	let angle = rand::random::<f64>() * PI * 2.0;
+
+	spawn_item(
+		&client.server,
+		item,
+		(x, y + EYE_HEIGHT - 0.3, z),
+		(-angle.sin() * strength, 0.2, angle.cos() * strength),
+		DROP_PICKUP_DELAY,
+	)
+}
+
+/// Убирает сущность из мира и у всех кто ее видел
+pub fn remove_entity(
+	server: &Arc<ServerContext>,
+	entity_id: i32,
+) -> Result<Option<Arc<Entity>>, ServerError> {
+	let Some(entity) = server.world.entities().remove(entity_id) else {
+		return Ok(None);
+	};
+
+	entity.set_removed();
+
+	for player in server.players() {
+		if player.entity_info().unwrap().untrack(entity_id) {
+			let result = player.send_packet(&clientbound::RemoveEntities {
+				entity_ids: vec![VarInt(entity_id)],
+			});
+			log_send_error(&player, result);
+		}
+	}
+
+	Ok(Some(entity))
+}
+
+/// Меняет метаданные сущности и рассылает изменение тем кто ее видит
+pub fn set_entity_metadata(
+	server: &Arc<ServerContext>,
+	entity: &Arc<Entity>,
+	index: u8,
+	value: MetadataValue,
+) -> Result<(), ServerError> {
+	entity.set_metadata(index, value);
+
+	let packet = clientbound::SetEntityMetadata {
+		entity_id: VarInt(entity.id),
+		metadata: entity.metadata().only(&[index]),
+	};
+
+	for player in trackers(server, entity.id) {
+		log_send_error(&player, player.send_packet(&packet));
+	}
+
+	Ok(())
+}
+
+/// Игроки у которых заспавнена сущность
+pub fn trackers(server: &Arc<ServerContext>, entity_id: i32) -> Vec<Arc<ClientContext>> {
+	server
+		.players()
+		.into_iter()
+		.filter(|o| o.entity_info().unwrap().is_tracking(entity_id))
+		.collect()
+}
+
+/// Спавнит сущность у receiver вместе с метаданными
+pub fn send_entity(receiver: Arc<ClientContext>, entity: &Arc<Entity>) -> Result<(), ServerError> {
+	let Some(entity_type) = entity_type_id(&receiver, &entity.entity_type) else {
+		warn!(
+			"Неизвестный тип сущности {}, клиент ее не увидит",
+			entity.entity_type
+		);
+		return Ok(());
+	};
+
+	let (x, y, z) = entity.position();
+	let (yaw, pitch) = entity.rotation();
+	let (vel_x, vel_y, vel_z) = entity.velocity();
+
+	receiver.send_packet(&clientbound::SpawnEntity {
+		entity_id: VarInt(entity.id),
+		entity_uuid: entity.uuid,
+		entity_type: VarInt(entity_type),
+		x,
+		y,
+		z,
+		pitch: Angle(pitch),
+		yaw: Angle(yaw),
+		head_yaw: Angle(yaw),
+		data: VarInt(entity.data),
+		velocity_x: (vel_x * 8000.0) as i16,
+		velocity_y: (vel_y * 8000.0) as i16,
+		velocity_z: (vel_z * 8000.0) as i16,
+	})?;
+
+	let metadata = entity.metadata();
+	if metadata.is_empty() {
+		return Ok(());
+	}
+
+	receiver.send_packet(&clientbound::SetEntityMetadata {
+		entity_id: VarInt(entity.id),
+		metadata,
+	})
+}
+
+/// Спавнит игроку сущности в его загруженных чанках и убирает те что из них ушли
+pub fn update_tracking(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+	let chunks = entity_info.loaded_chunks();
+	let tracked = entity_info.tracked_entities();
+	let mut visible = HashSet::new();
+
+	for entity in client.server.world.entities().all() {
+		let (x, _, z) = entity.position();
+		let (chunk, _) = block_to_chunk(x.floor() as i32, z.floor() as i32);
+
+		if !chunks.contains(&chunk) {
+			continue;
+		}
+
+		// Неизвестные клиенту тоже запоминаем, чтобы не пытаться каждый тик
+		if !tracked.contains(&entity.id) {
+			send_entity(client.clone(), &entity)?;
+		}
+		visible.insert(entity.id);
+	}
+
+	let removed = tracked
+		.difference(&visible)
+		.map(|o| VarInt(*o))
+		.collect::<Vec<VarInt>>();
+
+	if !removed.is_empty() {
+		client.send_packet(&clientbound::RemoveEntities {
+			entity_ids: removed,
+		})?;
+	}
+
+	entity_info.set_tracked_entities(visible);
+
+	Ok(())
+}
+
+/// Тик сущностей: падение, исчезновение предметов и их подбор, вызывается главным тиком
+pub fn tick_entities(server: &Arc<ServerContext>) -> Result<(), ServerError> {
+	for entity in server.world.entities().all() {
+		entity.set_age(entity.age() + 1);
+		if entity.pickup_delay() > 0 {
+			entity.set_pickup_delay(entity.pickup_delay() - 1);
+		}
+
+		move_entity(server, &entity);
+
+		if entity.position().1 < VOID_Y || entity.is_item() && entity.age() >= ITEM_LIFETIME {
+			remove_entity(server, entity.id)?;
+			continue;
+		}
+
+		if entity.is_item() && entity.pickup_delay() == 0 {
+			pick_up(server, &entity)?;
+		}
+	}
+
+	Ok(())
+}
+
+// Простая физика: гравитация, сопротивление воздуха и столкновение с непустыми блоками
+//
+// Сущность считается точкой, хитбоксы блоков не учитываются
+fn move_entity(server: &Arc<ServerContext>, entity: &Arc<Entity>) {
+	let world = &server.world;
+	let (x, y, z) = entity.position();
+
+	// В выгруженных чанках сущности замирают
+	let ((chunk_x, chunk_z), _) = block_to_chunk(x.floor() as i32, z.floor() as i32);
+	if world.get_loaded_chunk(chunk_x, chunk_z).is_none() {
+		return;
+	}
+
+	let (mut vel_x, mut vel_y, mut vel_z) = entity.velocity();
+	if entity.has_gravity() {
+		vel_y -= entity.gravity();
+	}
+
+	if (vel_x, vel_y, vel_z) == (0.0, 0.0, 0.0) {
+		return;
+	}
+
+	let solid = |x: f64, y: f64, z: f64| {
+		world.get_block(x.floor() as i32, y.floor() as i32, z.floor() as i32) != AIR
+	};
+
+	let mut on_ground = false;
+	let mut new_y = y + vel_y;
+	if vel_y < 0.0 && solid(x, new_y, z) {
+		new_y = new_y.floor() + 1.0;
+		vel_y = 0.0;
+		on_ground = true;
+	} else if vel_y > 0.0 && solid(x, new_y, z) {
+		new_y = y;
+		vel_y = 0.0;
+	}
+
+	let mut new_x = x + vel_x;
+	if solid(new_x, new_y, z) {
+		new_x = x;
+		vel_x = 0.0;
+	}
+
+	let mut new_z = z + vel_z;
+	if solid(new_x, new_y, new_z) {
+		new_z = z;
+		vel_z = 0.0;
+	}
+
+	let friction = if on_ground {
+		DRAG * GROUND_FRICTION
+	} else {
+		DRAG
+	};
+	let damp = |velocity: f64, friction: f64| {
+		let velocity = velocity * friction;
+		if velocity.abs() < MIN_VELOCITY {
+			0.0
+		} else {
+			velocity
+		}
+	};
+
+	entity.set_position((new_x, new_y, new_z));
+	entity.set_velocity((
+		damp(vel_x, friction),
+		damp(vel_y, DRAG),
+		damp(vel_z, friction),
+	));
+	entity.set_on_ground(on_ground);
+}
+
+// Отдает предмет ближайшему живому игроку рядом, что не влезло - остается лежать
+fn pick_up(server: &Arc<ServerContext>, entity: &Arc<Entity>) -> Result<(), ServerError> {
+	let Some(item) = entity.item_stack() else {
+		remove_entity(server, entity.id)?;
+		return Ok(());
+	};
+
+	let position = entity.position();
+
+	let Some(client) = server.players().into_iter().find(|o| {
+		let entity_info = o.entity_info().unwrap();
+		!entity_info.is_dead()
+			&& entity_info.game_mode() != SPECTATOR
+			&& can_pick_up(position, entity_info.position())
+	}) else {
+		return Ok(());
+	};
+
+	let mut event = PlayerPickupItemEvent {
+		entity_id: entity.id,
+		item,
+		cancelled: false,
+	};
+
+	trigger_event!(client, player_pickup_item, &mut event);
+
+	if event.cancelled {
+		return Ok(());
+	}
+
+	let amount = event.item.amount;
+	let rest = give_item(client.clone(), event.item)?;
+	let taken = amount - rest.as_ref().map_or(0, |o| o.amount);
+
+	if taken <= 0 {
+		return Ok(());
+	}
+
+	// Клиент сам рисует как предмет летит в игрока
+	let packet = clientbound::PickupItem {
+		collected_entity_id: VarInt(entity.id),
+		collector_entity_id: VarInt(client.entity_info().unwrap().entity_id),
+		pickup_item_count: VarInt(taken),
+	};
+
+	for player in trackers(server, entity.id) {
+		log_send_error(&player, player.send_packet(&packet));
+	}
+
+	match rest {
+		Some(rest) => set_entity_metadata(server, entity, ITEM_INDEX, MetadataValue::Slot(Some(rest))),
+		None => remove_entity(server, entity.id).map(|_| ()),
+	}
+}
@@ -0,0 +1,92 @@
+// Смена режима игры и синхронизация способностей с клиентом
+// Сами способности и режимы в player::abilities
+
+use std::sync::Arc;
+
+use crate::{
+	ServerError,
+	event::player::PlayerGameModeChangeEvent,
+	player::{
+		abilities::{FLYING_FLAG, SPECTATOR},
+		context::ClientContext,
+	},
+	protocol::{
+		codec::VarInt,
+		packets::play::{PlayerInfoAction, PlayerInfoEntries, PlayerInfoEntry, clientbound},
+	},
+	trigger_event,
+};
+
+use super::helper::{log_send_error, send_game_event};
+
+/// Отправляет игроку его способности
+pub fn send_abilities(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let abilities = client.entity_info().unwrap().abilities();
+
+	client.send_packet(&clientbound::PlayerAbilities {
+		flags: abilities.flags(),
+		flying_speed: abilities.fly_speed,
+		fov_modifier: abilities.walk_speed,
+	})
+}
+
+/// Меняет режим игры, 0 - выживание, 1 - креатив, 2 - приключение, 3 - наблюдатель
+///
+/// Самому игроку уходят Game Event и Player Abilities, всем игрокам - новый режим в табе.
+/// Возвращает false если режим не поменялся: он уже такой или листенер отменил событие
+pub fn set_game_mode(client: Arc<ClientContext>, game_mode: u8) -> Result<bool, ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
+	if entity_info.game_mode() == game_mode {
+		return Ok(false);
+	}
+
+	let mut event = PlayerGameModeChangeEvent {
+		game_mode,
+		cancelled: false,
+	};
+
+	trigger_event!(client, player_game_mode_change, &mut event);
+
+	let game_mode = event.game_mode;
+
+	if event.cancelled || game_mode > SPECTATOR || entity_info.game_mode() == game_mode {
+		return Ok(false);
+	}
+
+	entity_info.set_game_mode(game_mode);
+
+	send_game_event(client.clone(), 3, game_mode as f32)?; // 3 - Change game mode
+	send_abilities(client.clone())?;
+
+	let packet = clientbound::PlayerInfoUpdate {
+		entries: PlayerInfoEntries(vec![PlayerInfoEntry {
+			uuid: entity_info.uuid,
+			actions: vec![PlayerInfoAction::UpdateGameMode(VarInt(game_mode as i32))],
+		}]),
+	};
+
+	for player in client.server.players() {
+		log_send_error(&player, player.send_packet(&packet));
+	}
+
+	Ok(true)
+}
+
+/// Пакет Player Abilities, игрок начал или перестал летать
+///
+/// Если летать ему нельзя, отправляем настоящие способности и клиент падает
+pub fn handle_player_abilities(client: Arc<ClientContext>, flags: u8) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+	let mut abilities = entity_info.abilities();
+	let flying = flags & FLYING_FLAG != 0;
+
+	if flying && !abilities.may_fly {
+		return send_abilities(client);
+	}
+
+	abilities.flying = flying;
+	entity_info.set_abilities(abilities);
+
+	Ok(())
+}
@@ -0,0 +1,424 @@
+// Урон, смерть и возрождение игроков, голод и падения
+// Сама модель здоровья в player::health
+//
+// Пока держим блокировку health_data() пакеты не отправляем и события не вызываем
+
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+	ServerError,
+	data::{Position, component::TextComponent},
+	event::player::{Location, PlayerDamageEvent, PlayerDeathEvent},
+	player::{
+		context::ClientContext,
+		data::DEFAULT_DIMENSION,
+		health::{
+			BREAK_BLOCK_EXHAUSTION, DamageSource, FALL, FoodTick, HealthData, JUMP_EXHAUSTION,
+			OUT_OF_WORLD, SPRINT_EXHAUSTION, SPRINT_JUMP_EXHAUSTION, STARVE,
+		},
+	},
+	protocol::{
+		codec::VarInt,
+		packets::play::{DeathLocation, clientbound},
+	},
+	trigger_event,
+	world::{WORLD_MIN_Y, block_to_chunk},
+};
+
+use super::{
+	HASHED_SEED, SEA_LEVEL,
+	entity::scatter_item,
+	gamemode::send_abilities,
+	helper::{
+		log_send_error, send_entity_event, send_game_event, send_system_message, set_center_chunk,
+		sync_player_pos,
+	},
+	inventory::{close_container, send_window_content},
+	send_chunks_in_distance, spawn_player, spawn_position,
+	tick::send_time,
+};
+
+// Ниже этой высоты игрок получает урон от пустоты
+pub const VOID_Y: f64 = WORLD_MIN_Y as f64 - 64.0;
+const VOID_DAMAGE: f32 = 4.0;
+
+// Через сколько тиков тело исчезает у остальных игроков
+const DEATH_DURATION: u64 = 20;
+
+// Entity Event
+const DEATH_ANIMATION: u8 = 3;
+
+/// Client Status: игрок нажал "Возродиться"
+pub const PERFORM_RESPAWN: i32 = 0;
+
+/// Отправляет игроку здоровье, еду и насыщение
+pub fn send_health(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let health = client.entity_info().unwrap().health_data().clone();
+
+	client.send_packet(&clientbound::SetHealth {
+		health: health.health,
+		food: VarInt(health.food_level),
+		food_saturation: health.saturation,
+	})
+}
+
+/// Наносит игроку урон, при смерти отправляет экран смерти и сообщение всем
+///
+/// Возвращает false если урон не прошел: игрок неуязвим, уже мертв
+/// или недавно получил урон не меньше этого
+pub fn damage(
+	client: Arc<ClientContext>,
+	amount: f32,
+	source: &DamageSource,
+) -> Result<bool, ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
+	if entity_info.is_dead()
+		|| entity_info.abilities().invulnerable && !source.bypasses_invulnerability()
+	{
+		return Ok(false);
+	}
+
+	let mut event = PlayerDamageEvent {
+		damage: amount,
+		source: source.clone(),
+		cancelled: false,
+	};
+
+	trigger_event!(client, player_damage, &mut event);
+
+	if event.cancelled {
+		return Ok(false);
+	}
+
+	let source = &event.source;
+
+	let (dealt, dead) = {
+		let mut health = entity_info.health_data();
+		(health.hurt(event.damage), health.is_dead())
+	};
+
+	if dealt <= 0.0 {
+		return Ok(false);
+	}
+
+	for player in client.server.players() {
+		let registries = player.server.registries(player.protocol_version());
+		let Some(source_type_id) = registries.entry_id("minecraft:damage_type", &source.damage_type)
+		else {
+			continue;
+		};
+
+		let result = player.send_packet(&clientbound::DamageEvent {
+			entity_id: VarInt(entity_info.entity_id),
+			source_type_id: VarInt(source_type_id as i32),
+			source_cause_id: VarInt(source.attacker.map_or(0, |o| o + 1)),
+			source_direct_id: VarInt(source.attacker.map_or(0, |o| o + 1)),
+			source_position: None,
+		});
+		log_send_error(&player, result);
+	}
+
+	send_health(client.clone())?;
+
+	if dead {
+		die(client, source)?;
+	}
+
+	Ok(true)
+}
+
+/// Убивает игрока даже в креативе, как /kill
+pub fn kill(client: Arc<ClientContext>, source: &DamageSource) -> Result<bool, ServerError> {
+	damage(client, f32::MAX, source)
+}
+
+fn die(client: Arc<ClientContext>, source: &DamageSource) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+	let name = client.player_info().unwrap().name;
+
+	let (x, y, z) = entity_info.position();
+	entity_info.set_last_death_location(Some(Position {
+		x: x.floor() as i32,
+		y: y.floor() as i32,
+		z: z.floor() as i32,
+	}));
+	entity_info.set_sprinting(false);
+	entity_info.health_data().fall_distance = 0.0;
+
+	let attacker = source.attacker.and_then(|id| {
+		client
+			.server
+			.players()
+			.into_iter()
+			.find(|o| o.entity_info().is_some_and(|o| o.entity_id == id))
+			.and_then(|o| o.player_info())
+			.map(|o| o.name)
+	});
+
+	let mut event = PlayerDeathEvent {
+		message: Some(TextComponent::new(
+			source.death_message(&name, attacker.as_deref()),
+		)),
+		keep_inventory: false,
+	};
+
+	trigger_event!(client, player_death, &mut event);
+
+	if !event.keep_inventory {
+		close_container(client.clone())?;
+		let items = entity_info.windows().take_all();
+		for item in items {
+			scatter_item(client.clone(), item);
+		}
+		send_window_content(client.clone())?;
+	}
+
+	client.send_packet(&clientbound::CombatDeath {
+		player_id: VarInt(entity_info.entity_id),
+		message: event.message.clone().unwrap_or_default(),
+	})?;
+
+	for player in client.server.players() {
+		let result = event
+			.message
+			.as_ref()
+			.map_or(Ok(()), |o| {
+				send_system_message(player.clone(), o.clone(), false)
+			})
+			.and_then(|_| {
+				if client.addr != player.addr {
+					send_entity_event(player.clone(), entity_info.entity_id, DEATH_ANIMATION)
+				} else {
+					Ok(())
+				}
+			});
+		log_send_error(&player, result);
+	}
+
+	// Тело лежит пока идет анимация смерти, потом пропадает
+	client
+		.server
+		.planner()
+		.task({
+			let client = client.clone();
+			move |_| {
+				let entity_info = client.entity_info().unwrap();
+				if !entity_info.is_dead() {
+					return Ok(());
+				}
+				for player in client.server.players() {
+					if client.addr != player.addr {
+						let result = player.send_packet(&clientbound::RemoveEntities {
+							entity_ids: vec![VarInt(entity_info.entity_id)],
+						});
+						log_send_error(&player, result);
+					}
+				}
+				Ok(())
+			}
+		})
+		.delay(DEATH_DURATION)
+		.owner(&client)
+		.schedule();
+
+	Ok(())
+}
+
+/// Возрождает мертвого игрока на спавне, живых не трогает
+pub fn respawn(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
+	if !entity_info.is_dead() {
+		return Ok(());
+	}
+
+	*entity_info.health_data() = HealthData::default();
+
+	let (x, y, z) = spawn_position(&client.server);
+	entity_info.set_position((x, y, z));
+	entity_info.set_rotation((0.0, 0.0));
+	entity_info.set_velocity((0.0, 0.0, 0.0));
+	entity_info.set_on_ground(false);
+
+	// Respawn стирает у клиента все сущности, дальше их заново заспавнит главный тик
+	entity_info.set_tracked_entities(HashSet::new());
+
+	client.send_packet(&clientbound::Respawn {
+		dimension_type: VarInt(0),
+		dimension_name: DEFAULT_DIMENSION.to_string(),
+		hashed_seed: HASHED_SEED,
+		game_mode: entity_info.game_mode(),
+		previous_game_mode: -1,
+		is_debug: false,
+		is_flat: true,
+		death_location: entity_info
+			.last_death_location()
+			.map(|location| DeathLocation {
+				dimension_name: DEFAULT_DIMENSION.to_string(),
+				location,
+			}),
+		portal_cooldown: VarInt(0),
+		sea_level: VarInt(SEA_LEVEL),
+		data_kept: 0,
+	})?;
+
+	// Новая сущность на клиенте ничего не помнит, отправляем все заново
+	send_abilities(client.clone())?;
+	sync_player_pos(client.clone(), x, y, z, 0.0, 0.0, 0.0, 0.0, 0.0, 0)?;
+	client.send_packet(&clientbound::SetHeldItem {
+		slot: VarInt(entity_info.held_slot() as i32),
+	})?;
+	send_game_event(client.clone(), 13, 0.0)?; // 13 - Start waiting for level chunks
+	send_entity_event(
+		client.clone(),
+		entity_info.entity_id,
+		24 + entity_info.permission_level(),
+	)?; // 24-28 - op level 0-4
+	send_time(client.clone())?;
+	send_window_content(client.clone())?;
+	send_health(client.clone())?;
+
+	let ((chunk_x, chunk_z), _) = block_to_chunk(x.floor() as i32, z.floor() as i32);
+	set_center_chunk(client.clone(), chunk_x, chunk_z)?;
+	let view_distance = client.client_info().unwrap().view_distance as i32 / 2;
+	send_chunks_in_distance(client.clone(), view_distance, (chunk_x, chunk_z))?;
+
+	for player in client.server.players() {
+		if client.addr == player.addr {
+			continue;
+		}
+		let result = player
+			.send_packet(&clientbound::RemoveEntities {
+				entity_ids: vec![VarInt(entity_info.entity_id)],
+			})
+			.and_then(|_| spawn_player(player.clone(), client.clone()));
+		log_send_error(&player, result);
+	}
+
+	Ok(())
+}
+
+/// Пакет Client Status
+pub fn handle_client_status(client: Arc<ClientContext>, action_id: i32) -> Result<(), ServerError> {
+	match action_id {
+		PERFORM_RESPAWN => respawn(client),
+		// Статистики у нас нет
+		_ => Ok(()),
+	}
+}
+
+/// Падение, прыжки и бег после движения игрока
+pub fn handle_movement(
+	client: Arc<ClientContext>,
+	from: Location,
+	to: Location,
+	was_on_ground: bool,
+	on_ground: bool,
+) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+	let abilities = entity_info.abilities();
+	let sprinting = entity_info.sprinting();
+
+	if entity_info.is_dead() {
+		return Ok(());
+	}
+
+	let dy = to.y - from.y;
+	let in_water = {
+		let world = &client.server.world;
+		let block = world.get_block(
+			to.x.floor() as i32,
+			to.y.floor() as i32,
+			to.z.floor() as i32,
+		);
+		world
+			.blocks()
+			.get_state(block)
+			.is_some_and(|o| o.name == "minecraft:water")
+	};
+
+	let fall_damage = {
+		let mut health = entity_info.health_data();
+
+		// Creative и наблюдатель не получают урон от падения, у них may_fly
+		let fall_damage = if abilities.flying || in_water {
+			health.fall_distance = 0.0;
+			0.0
+		} else if on_ground {
+			health.land()
+		} else {
+			if dy < 0.0 {
+				health.fall_distance -= dy;
+			}
+			0.0
+		};
+
+		// Истощение копится только у тех, кто может получить урон
+		if !abilities.invulnerable {
+			if was_on_ground && !on_ground && dy > 0.0 {
+				health.add_exhaustion(if sprinting {
+					SPRINT_JUMP_EXHAUSTION
+				} else {
+					JUMP_EXHAUSTION
+				});
+			}
+
+			if sprinting && !abilities.flying {
+				let distance = (to.x - from.x).hypot(to.z - from.z);
+				health.add_exhaustion(SPRINT_EXHAUSTION * distance as f32);
+			}
+		}
+
+		if abilities.may_fly { 0.0 } else { fall_damage }
+	};
+
+	if fall_damage > 0.0 {
+		damage(client, fall_damage, &DamageSource::new(FALL))?;
+	}
+
+	Ok(())
+}
+
+/// Игрок сломал блок
+pub fn exhaust_block_break(client: &Arc<ClientContext>) {
+	let entity_info = client.entity_info().unwrap();
+	if !entity_info.abilities().invulnerable {
+		entity_info
+			.health_data()
+			.add_exhaustion(BREAK_BLOCK_EXHAUSTION);
+	}
+}
+
+/// Тик здоровья игрока: голод, лечение и пустота, вызывается главным тиком
+pub fn tick_health(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
+	let (before, food_tick, after) = {
+		let mut health = entity_info.health_data();
+		let before = health.clone();
+		let food_tick = health.tick_food(true);
+		if let FoodTick::Heal(amount) = food_tick {
+			health.heal(amount);
+		}
+		(before, food_tick, health.clone())
+	};
+
+	if let FoodTick::Starve(amount) = food_tick {
+		damage(client.clone(), amount, &DamageSource::new(STARVE))?;
+	}
+
+	// Клиенту не нужно истощение, только то что он рисует
+	if before.health != after.health
+		|| before.food_level != after.food_level
+		|| (before.saturation == 0.0) != (after.saturation == 0.0)
+	{
+		send_health(client.clone())?;
+	}
+
+	if entity_info.position().1 < VOID_Y {
+		damage(client, VOID_DAMAGE, &DamageSource::new(OUT_OF_WORLD))?;
+	}
+
+	Ok(())
+}
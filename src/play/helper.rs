@@ -1,28 +1,39 @@
 use std::{
 	sync::Arc,
-	time::{SystemTime, UNIX_EPOCH},
+	time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use rust_mc_proto::{DataReader, DataWriter, Packet};
+use log::warn;
 
 use crate::{
 	ServerError,
-	data::{ReadWriteNBT, component::TextComponent},
+	context::ServerContext,
+	data::{IdOr, Position, component::TextComponent, sound::SoundEvent},
 	player::context::ClientContext,
-	protocol::packet_id::{clientbound, serverbound},
+	protocol::{
+		codec::{Angle, VarInt},
+		packets::play::clientbound,
+	},
+	world::block_to_chunk,
 };
 
+/// Ошибку отправки одному из получателей рассылки только пишем в лог
+///
+/// Получатель может как раз отключаться или не успевать читать, из-за него не должна
+/// прерываться рассылка и тем более вылетать игрок который ее начал
+pub fn log_send_error(receiver: &Arc<ClientContext>, result: Result<(), ServerError>) {
+	match result {
+		Ok(()) | Err(ServerError::ConnectionClosed) => {}
+		Err(error) => warn!("Не удалось отправить пакет {}: {error:?}", receiver.addr),
+	}
+}
+
 pub fn send_game_event(
 	client: Arc<ClientContext>,
 	event: u8,
 	value: f32,
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::GAME_EVENT);
-
-	packet.write_byte(event)?;
-	packet.write_float(value)?;
-
-	client.write_packet(&packet)
+	client.send_packet(&clientbound::GameEvent { event, value })
 }
 
 pub fn send_entity_event(
@@ -30,12 +41,10 @@ pub fn send_entity_event(
 	entity_id: i32,
 	status: u8,
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::ENTITY_EVENT);
-
-	packet.write_int(entity_id)?;
-	packet.write_byte(status)?;
-
-	client.write_packet(&packet)
+	client.send_packet(&clientbound::EntityEvent {
+		entity_id,
+		entity_status: status,
+	})
 }
 
 pub fn send_entity_animation(
@@ -43,14 +52,10 @@ pub fn send_entity_animation(
 	entity_id: i32,
 	animation: u8,
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::ENTITY_ANIMATION);
-
-	packet.write_varint(entity_id)?;
-	packet.write_byte(animation)?;
-
-	receiver.write_packet(&packet)?;
-
-	Ok(())
+	receiver.send_packet(&clientbound::EntityAnimation {
+		entity_id: VarInt(entity_id),
+		animation,
+	})
 }
 
 pub fn play_global_sound(
@@ -60,28 +65,35 @@ pub fn play_global_sound(
 	pitch: f32,
 	category: i32,
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::ENTITY_SOUND_EFFECT);
+	let entity_id = receiver.entity_info().unwrap().entity_id;
+	play_entity_sound(receiver, entity_id, &sound, volume, pitch, category)
+}
 
+/// Звук который играет от сущности и двигается вместе с ней
+pub fn play_entity_sound(
+	receiver: Arc<ClientContext>,
+	entity_id: i32,
+	sound: &str,
+	volume: f32,
+	pitch: f32,
+	category: i32,
+) -> Result<(), ServerError> {
 	let timestamp = SystemTime::now()
 		.duration_since(UNIX_EPOCH)
 		.unwrap()
 		.as_micros() as i64;
 
-	packet.write_varint(0)?;
-	packet.write_string(&sound)?;
-	packet.write_boolean(false)?; // is fixed range
-	// packet.write_float(0.0)?; // fixed range
-	packet.write_varint(receiver.entity_info().unwrap().entity_id)?;
-	packet.write_varint(category)?; // sound category (0 - master)
-	packet.write_float(volume)?; // volume
-	packet.write_float(pitch)?; // pitch
-	packet.write_long(timestamp)?; // seed
-
-	receiver.write_packet(&packet)?;
-
-	Ok(())
+	receiver.send_packet(&clientbound::EntitySoundEffect {
+		sound: IdOr::Or(SoundEvent::new(sound)),
+		category: VarInt(category), // sound category (0 - master)
+		entity_id: VarInt(entity_id),
+		volume,
+		pitch,
+		seed: timestamp,
+	})
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn sync_player_pos(
 	client: Arc<ClientContext>,
 	x: f64,
@@ -100,31 +112,32 @@ pub fn sync_player_pos(
 		.as_millis()
 		& 0xFFFFFFFF) as i32;
 
-	let mut packet = Packet::empty(clientbound::play::SYNCHRONIZE_PLAYER_POSITION);
-
-	packet.write_varint(timestamp)?;
-	packet.write_double(x)?;
-	packet.write_double(y)?;
-	packet.write_double(z)?;
-	packet.write_double(vel_x)?;
-	packet.write_double(vel_y)?;
-	packet.write_double(vel_z)?;
-	packet.write_float(yaw)?;
-	packet.write_float(pitch)?;
-	packet.write_int(flags)?;
-
-	client.write_packet(&packet)?;
+	// Абсолютный телепорт, падение до него не считается
+	if flags == 0 {
+		let entity_info = client.entity_info().unwrap();
+		entity_info.set_position((x, y, z));
+		entity_info.health_data().fall_distance = 0.0;
+	}
 
-	Ok(())
+	client.send_packet(&clientbound::SynchronizePlayerPosition {
+		teleport_id: VarInt(timestamp),
+		x,
+		y,
+		z,
+		velocity_x: vel_x,
+		velocity_y: vel_y,
+		velocity_z: vel_z,
+		yaw,
+		pitch,
+		flags,
+	})
 }
 
 pub fn set_center_chunk(client: Arc<ClientContext>, x: i32, z: i32) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::SET_CENTER_CHUNK);
-
-	packet.write_varint(x)?;
-	packet.write_varint(z)?;
-
-	client.write_packet(&packet)
+	client.send_packet(&clientbound::SetCenterChunk {
+		chunk_x: VarInt(x),
+		chunk_z: VarInt(z),
+	})
 }
 
 pub fn send_keep_alive(client: Arc<ClientContext>) -> Result<(), ServerError> {
@@ -133,18 +146,22 @@ pub fn send_keep_alive(client: Arc<ClientContext>) -> Result<(), ServerError> {
 		.unwrap()
 		.as_secs() as i64;
 
-	let mut packet = Packet::empty(clientbound::play::KEEP_ALIVE);
-	packet.write_long(timestamp)?;
-	client.write_packet(&packet)?;
-
-	let mut packet = client.read_packet(&[serverbound::play::KEEP_ALIVE])?;
-	let timestamp2 = packet.read_long()?;
-	if timestamp2 != timestamp {
-		// Послать клиента нахуй
-		Err(ServerError::WrongPacket)
-	} else {
-		Ok(())
+	// Ответ проверяет главный тик, см. handle_play_packet
+	// Пока клиент не ответил на прошлый, новый не шлем, а за таймаут отключаем
+	if let Some((_, sent)) = client.pending_keep_alive() {
+		if sent.elapsed() > Duration::from_secs(client.server.config.bind.timeout) {
+			client
+				.protocol_helper()
+				.disconnect(TextComponent::new("Timed out".to_string()))?;
+			client.close();
+		}
+		return Ok(());
 	}
+
+	client.set_pending_keep_alive(timestamp);
+	client.send_packet(&clientbound::KeepAlive {
+		keep_alive_id: timestamp,
+	})
 }
 
 pub fn send_system_message(
@@ -152,15 +169,83 @@ pub fn send_system_message(
 	message: TextComponent,
 	is_action_bar: bool,
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::SYSTEM_CHAT_MESSAGE);
-	packet.write_nbt(&message)?;
-	packet.write_boolean(is_action_bar)?;
-	client.write_packet(&packet)
+	client.send_packet(&clientbound::SystemChatMessage {
+		content: message,
+		overlay: is_action_bar,
+	})
 }
 
 pub fn unload_chunk(client: Arc<ClientContext>, x: i32, z: i32) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::UNLOAD_CHUNK);
-	packet.write_int(z)?;
-	packet.write_int(x)?;
-	client.write_packet(&packet)
+	client.send_packet(&clientbound::UnloadChunk {
+		chunk_z: z,
+		chunk_x: x,
+	})
+}
+
+pub fn send_head_rotation(
+	receiver: Arc<ClientContext>,
+	entity_id: i32,
+	head_yaw: f32,
+) -> Result<(), ServerError> {
+	receiver.send_packet(&clientbound::SetHeadRotation {
+		entity_id: VarInt(entity_id),
+		head_yaw: Angle(head_yaw),
+	})
+}
+
+pub fn send_block_update(
+	receiver: Arc<ClientContext>,
+	position: Position,
+	state: u32,
+) -> Result<(), ServerError> {
+	receiver.send_packet(&clientbound::BlockUpdate {
+		location: position,
+		block_id: VarInt(state as i32),
+	})
+}
+
+/// Отправляет обновление блока всем у кого загружен его чанк
+pub fn broadcast_block_update(
+	server: &Arc<ServerContext>,
+	position: Position,
+	state: u32,
+) -> Result<(), ServerError> {
+	let ((chunk_x, chunk_z), _) = block_to_chunk(position.x, position.z);
+
+	for player in server.players() {
+		let Some(entity_info) = player.entity_info() else {
+			continue;
+		};
+		if entity_info.is_chunk_loaded(chunk_x, chunk_z) {
+			log_send_error(&player, send_block_update(player.clone(), position, state));
+		}
+	}
+
+	Ok(())
+}
+
+/// Ставит блок в мире и рассылает обновление, возвращает предыдущий блок
+pub fn set_block(
+	server: &Arc<ServerContext>,
+	position: Position,
+	state: u32,
+) -> Result<Option<u32>, ServerError> {
+	let previous = server
+		.world
+		.set_block(position.x, position.y, position.z, state);
+
+	if previous.is_some_and(|o| o != state) {
+		broadcast_block_update(server, position, state)?;
+	}
+
+	Ok(previous)
+}
+
+pub fn acknowledge_block_change(
+	client: Arc<ClientContext>,
+	sequence: i32,
+) -> Result<(), ServerError> {
+	client.send_packet(&clientbound::AcknowledgeBlockChange {
+		sequence: VarInt(sequence),
+	})
 }
@@ -0,0 +1,442 @@
+// Окна и клики по инвентарю
+// Сама модель инвентаря в player::inventory, тут пакеты, события и синхронизация с клиентом
+//
+// Пока держим блокировку windows() пакеты не отправляем и события не вызываем,
+// листенеры и обработчики пакетов сами могут полезть в инвентарь
+
+use std::sync::Arc;
+
+use crate::{
+	ServerError,
+	data::slot::{HashedSlot, Slot},
+	event::player::{
+		PlayerClickContainerEvent, PlayerCloseContainerEvent, PlayerDropItemEvent,
+		PlayerRenameItemEvent,
+	},
+	player::{
+		abilities::SPECTATOR,
+		context::ClientContext,
+		inventory::{
+			CRAFTING_RESULT_SLOT, Container, HOTBAR_START, OFFHAND_SLOT, PLAYER_INVENTORY_SIZE,
+			PLAYER_WINDOW, PlayerInventory, Windows,
+		},
+	},
+	protocol::{
+		codec::VarInt,
+		packets::play::{ChangedSlot, clientbound},
+	},
+	trigger_event,
+};
+
+use super::entity::drop_item;
+
+/// Отправляет клиенту все слоты открытого окна и курсор
+pub fn send_window_content(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let packet = {
+		let entity_info = client.entity_info().unwrap();
+		let mut windows = entity_info.windows();
+		clientbound::SetContainerContent {
+			window_id: VarInt(windows.window_id() as i32),
+			state_id: VarInt(windows.next_state_id()),
+			slots: windows.window_slots(),
+			carried_item: windows.cursor.clone(),
+		}
+	};
+
+	client.send_packet(&packet)
+}
+
+/// Отправляет клиенту слот инвентаря, slot - индекс в окне инвентаря
+///
+/// Если открыт контейнер, слот отправляется в нем, а броня и вторая рука - отдельным пакетом
+pub fn send_inventory_slot(client: Arc<ClientContext>, slot: usize) -> Result<(), ServerError> {
+	let (window_slot, window_id, state_id, item) = {
+		let entity_info = client.entity_info().unwrap();
+		let mut windows = entity_info.windows();
+		(
+			windows.window_slot(slot),
+			windows.window_id(),
+			windows.next_state_id(),
+			windows.inventory.get(slot).cloned(),
+		)
+	};
+
+	match (window_slot, PlayerInventory::inventory_index(slot)) {
+		(Some(window_slot), _) => client.send_packet(&clientbound::SetContainerSlot {
+			window_id: VarInt(window_id as i32),
+			state_id: VarInt(state_id),
+			slot: window_slot as i16,
+			item,
+		}),
+		(None, Some(index)) => client.send_packet(&clientbound::SetPlayerInventorySlot {
+			slot: VarInt(index),
+			item,
+		}),
+		// Сетку крафта инвентаря за открытым контейнером не видно
+		(None, None) => Ok(()),
+	}
+}
+
+/// Кладет предмет в слот инвентаря, slot - индекс в окне инвентаря
+pub fn set_inventory_slot(
+	client: Arc<ClientContext>,
+	slot: usize,
+	item: Option<Slot>,
+) -> Result<(), ServerError> {
+	if slot >= PLAYER_INVENTORY_SIZE {
+		return Ok(());
+	}
+
+	client
+		.entity_info()
+		.unwrap()
+		.windows()
+		.inventory
+		.set(slot, item);
+
+	send_inventory_slot(client, slot)
+}
+
+/// Выдает предмет игроку, возвращает то что не влезло в инвентарь
+pub fn give_item(client: Arc<ClientContext>, item: Slot) -> Result<Option<Slot>, ServerError> {
+	let (changed, rest) = {
+		let entity_info = client.entity_info().unwrap();
+		let mut windows = entity_info.windows();
+		let amounts = |windows: &Windows| {
+			windows
+				.inventory
+				.slots()
+				.iter()
+				.map(|o| o.as_ref().map(|o| o.amount).unwrap_or_default())
+				.collect::<Vec<i32>>()
+		};
+
+		let before = amounts(&windows);
+		let rest = windows
+			.inventory
+			.add_item(item, client.server.world.items());
+		let after = amounts(&windows);
+
+		let changed = (0..PLAYER_INVENTORY_SIZE)
+			.filter(|o| before[*o] != after[*o])
+			.collect::<Vec<usize>>();
+		(changed, rest)
+	};
+
+	for slot in changed {
+		send_inventory_slot(client.clone(), slot)?;
+	}
+
+	Ok(rest)
+}
+
+/// Забирает предметы из руки без отправки клиенту, он сам это предсказывает (например при установке блока)
+pub fn take_hand_item(client: &Arc<ClientContext>, hand: i32, amount: i32) -> Option<Slot> {
+	let entity_info = client.entity_info().unwrap();
+	let slot = hand_slot(client, hand);
+	let mut windows = entity_info.windows();
+	let item = windows.inventory.get(slot)?.clone();
+	let taken = amount.min(item.amount);
+	windows
+		.inventory
+		.set(slot, Some(item.with_amount(item.amount - taken)));
+	Some(item.with_amount(taken))
+}
+
+// Слот окна инвентаря для руки, 0 - основная, 1 - вторая
+fn hand_slot(client: &Arc<ClientContext>, hand: i32) -> usize {
+	if hand == 1 {
+		OFFHAND_SLOT
+	} else {
+		HOTBAR_START + client.entity_info().unwrap().held_slot() as usize
+	}
+}
+
+/// Выкидывает предмет из основной руки, Q или Ctrl+Q вне инвентаря
+pub fn drop_held_item(client: Arc<ClientContext>, whole_stack: bool) -> Result<(), ServerError> {
+	let amount = if whole_stack { i32::MAX } else { 1 };
+	let Some(item) = take_hand_item(&client, 0, amount) else {
+		return Ok(());
+	};
+
+	drop_items(client.clone(), vec![item])?;
+	send_inventory_slot(client.clone(), hand_slot(&client, 0))
+}
+
+/// Меняет местами предметы в руках, клавиша F
+pub fn swap_hands(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let main_slot = hand_slot(&client, 0);
+
+	{
+		let entity_info = client.entity_info().unwrap();
+		let mut windows = entity_info.windows();
+		let main = windows.inventory.take(main_slot);
+		let offhand = windows.inventory.take(OFFHAND_SLOT);
+		windows.inventory.set(main_slot, offhand);
+		windows.inventory.set(OFFHAND_SLOT, main);
+	}
+
+	send_inventory_slot(client.clone(), main_slot)?;
+	send_inventory_slot(client, OFFHAND_SLOT)
+}
+
+// Выкинутые предметы, при отмене события они возвращаются в инвентарь
+fn drop_items(client: Arc<ClientContext>, items: Vec<Slot>) -> Result<(), ServerError> {
+	for item in items {
+		let mut event = PlayerDropItemEvent {
+			item,
+			cancelled: false,
+		};
+
+		trigger_event!(client, player_drop_item, &mut event);
+
+		if event.cancelled {
+			give_item(client.clone(), event.item)?;
+		} else {
+			drop_item(client.clone(), event.item);
+		}
+	}
+
+	Ok(())
+}
+
+/// Открывает игроку контейнер, например меню плагина, и возвращает айди окна
+///
+/// Контейнер у каждого игрока свой, его содержимое потом придет в PlayerCloseContainerEvent
+pub fn open_container(client: Arc<ClientContext>, container: Container) -> Result<u8, ServerError> {
+	close_container(client.clone())?;
+
+	let packet = {
+		let entity_info = client.entity_info().unwrap();
+		let mut windows = entity_info.windows();
+		let window_id = windows.open(container);
+		let container = windows.container().unwrap();
+		clientbound::OpenScreen {
+			window_id: VarInt(window_id as i32),
+			window_type: VarInt(container.kind.id()),
+			title: container.title.clone(),
+		}
+	};
+
+	client.send_packet(&packet)?;
+	send_window_content(client)?;
+
+	Ok(packet.window_id.0 as u8)
+}
+
+/// Закрывает открытый контейнер, у инвентаря ничего не делает
+pub fn close_container(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let window_id = client.entity_info().unwrap().windows().window_id();
+
+	if window_id == PLAYER_WINDOW {
+		return Ok(());
+	}
+
+	client.send_packet(&clientbound::CloseContainer {
+		window_id: VarInt(window_id as i32),
+	})?;
+
+	close_window(client)
+}
+
+// Закрывает окно на сервере, курсор возвращается в инвентарь
+fn close_window(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let (window_id, container, dropped) = {
+		let entity_info = client.entity_info().unwrap();
+		let mut windows = entity_info.windows();
+		let window_id = windows.window_id();
+		let (container, dropped) = windows.close(client.server.world.items());
+		(window_id, container, dropped)
+	};
+
+	let mut event = PlayerCloseContainerEvent {
+		window_id,
+		container,
+	};
+
+	trigger_event!(client, player_close_container, &mut event);
+
+	drop_items(client.clone(), dropped)?;
+	send_window_content(client)
+}
+
+/// Меняет слот открытого контейнера, slot - индекс в контейнере
+pub fn set_container_slot(
+	client: Arc<ClientContext>,
+	slot: usize,
+	item: Option<Slot>,
+) -> Result<(), ServerError> {
+	let packet = {
+		let entity_info = client.entity_info().unwrap();
+		let mut windows = entity_info.windows();
+		let window_id = windows.window_id();
+		let Some(container) = windows.container_mut().filter(|o| slot < o.size()) else {
+			return Ok(());
+		};
+		container.set(slot, item);
+		let item = container.get(slot).cloned();
+
+		clientbound::SetContainerSlot {
+			window_id: VarInt(window_id as i32),
+			state_id: VarInt(windows.next_state_id()),
+			slot: slot as i16,
+			item,
+		}
+	};
+
+	client.send_packet(&packet)
+}
+
+/// Свойство открытого окна, например цена ремонта у наковальни или прогресс печки
+pub fn set_container_property(
+	client: Arc<ClientContext>,
+	property: i16,
+	value: i16,
+) -> Result<(), ServerError> {
+	let window_id = client.entity_info().unwrap().windows().window_id();
+
+	client.send_packet(&clientbound::SetContainerProperty {
+		window_id: VarInt(window_id as i32),
+		property,
+		value,
+	})
+}
+
+/// Меняет предмет на курсоре
+pub fn set_cursor_item(client: Arc<ClientContext>, item: Option<Slot>) -> Result<(), ServerError> {
+	client.entity_info().unwrap().windows().cursor = item.clone();
+	client.send_packet(&clientbound::SetCursorItem { item })
+}
+
+/// Пакет Click Container
+///
+/// Клик считаем сами, а если клиент предсказал другое - отправляем ему все окно заново
+#[allow(clippy::too_many_arguments)]
+pub fn handle_click_container(
+	client: Arc<ClientContext>,
+	window_id: i32,
+	state_id: i32,
+	slot: i16,
+	button: i8,
+	mode: i32,
+	changed_slots: &[ChangedSlot],
+	carried_item: &Option<HashedSlot>,
+) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+	let current_window = entity_info.windows().window_id();
+
+	// Клик по уже закрытому окну, как и ванилла просто игнорируем
+	if window_id != current_window as i32 {
+		return Ok(());
+	}
+
+	if entity_info.game_mode() == SPECTATOR {
+		return send_window_content(client);
+	}
+
+	let mut event = PlayerClickContainerEvent {
+		window_id: current_window,
+		slot,
+		button,
+		mode,
+		cancelled: false,
+	};
+
+	trigger_event!(client, player_click_container, &mut event);
+
+	if event.cancelled {
+		return send_window_content(client);
+	}
+
+	let creative = entity_info.abilities().instabuild;
+	let registries = client.server.registries(client.protocol_version());
+
+	let (dropped, in_sync) = {
+		let mut windows = entity_info.windows();
+
+		// Листенер мог закрыть окно или открыть другое
+		if windows.window_id() != current_window {
+			return Ok(());
+		}
+
+		let stale = state_id != windows.state_id();
+		let dropped = windows.click(slot, button, mode, creative, client.server.world.items());
+
+		let in_sync = !stale
+			&& changed_slots.iter().all(|o| {
+				let item = usize::try_from(o.slot)
+					.ok()
+					.filter(|o| *o < windows.window_size())
+					.map(|o| windows.get_slot(o));
+				match (&o.item, item) {
+					(None, Some(None)) => true,
+					(Some(hashed), Some(Some(item))) => hashed.matches(&item, registries),
+					_ => false,
+				}
+			}) && match (carried_item, &windows.cursor) {
+			(None, None) => true,
+			(Some(hashed), Some(item)) => hashed.matches(item, registries),
+			_ => false,
+		};
+
+		(dropped, in_sync)
+	};
+
+	if !in_sync {
+		send_window_content(client.clone())?;
+	}
+
+	drop_items(client, dropped)
+}
+
+/// Пакет Close Container, ванилла не смотрит на айди и закрывает то что открыто
+pub fn handle_close_container(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	close_window(client)
+}
+
+/// Пакет Set Creative Mode Slot, креативный инвентарь меняет слоты окна инвентаря напрямую
+pub fn handle_set_creative_mode_slot(
+	client: Arc<ClientContext>,
+	slot: i16,
+	item: Option<Slot>,
+) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
+	// Креативное меню есть только в креативе, вне его откатываем то что клиент успел показать
+	if !entity_info.abilities().instabuild {
+		return send_window_content(client);
+	}
+
+	let items = client.server.world.items();
+	if item
+		.as_ref()
+		.is_some_and(|o| o.amount > o.max_stack_size(items))
+	{
+		return send_window_content(client);
+	}
+
+	match (slot, item) {
+		// Выкинул предмет из креативного меню
+		(-1, Some(item)) => drop_items(client, vec![item]),
+		(slot, item) if slot > CRAFTING_RESULT_SLOT as i16 && slot < PLAYER_INVENTORY_SIZE as i16 => {
+			entity_info.windows().inventory.set(slot as usize, item);
+			Ok(())
+		}
+		_ => Ok(()),
+	}
+}
+
+/// Пакет Rename Item, текст в поле наковальни
+pub fn handle_rename_item(client: Arc<ClientContext>, name: String) -> Result<(), ServerError> {
+	let window_id = client.entity_info().unwrap().windows().window_id();
+
+	if window_id == PLAYER_WINDOW {
+		return Ok(());
+	}
+
+	let mut event = PlayerRenameItemEvent { window_id, name };
+
+	trigger_event!(client, player_rename_item, &mut event);
+
+	Ok(())
+}
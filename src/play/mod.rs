@@ -1,25 +1,65 @@
-use std::sync::atomic::Ordering;
-use std::{sync::Arc, thread, time::Duration};
+use std::{collections::HashSet, sync::Arc};
 
+use block::{handle_player_action, handle_set_held_item, handle_use_item_on};
+use combat::handle_interact;
 use config::handle_configuration_state;
+use entity::entity_type_id;
+use gamemode::{handle_player_abilities, send_abilities};
+use health::{handle_client_status, handle_movement, send_health};
 use helper::{
-	send_entity_animation, send_entity_event, send_game_event, send_keep_alive, send_system_message,
-	set_center_chunk, sync_player_pos, unload_chunk,
+	log_send_error, send_entity_animation, send_entity_event, send_game_event, send_keep_alive,
+	send_system_message, set_center_chunk, sync_player_pos, unload_chunk,
 };
-use rust_mc_proto::{DataReader, DataWriter, Packet};
+use inventory::{
+	handle_click_container, handle_close_container, handle_rename_item,
+	handle_set_creative_mode_slot, send_window_content,
+};
+use log::{error, warn};
+use playerdata::{load_player_data, save_player_data};
+use rust_mc_proto::Packet;
+use tick::send_time;
 use uuid::Uuid;
 
+use crate::command::suggestions_response;
+use crate::context::ServerContext;
 use crate::event::Listener;
+use crate::event::player::{
+	Location, PlayerChatEvent, PlayerCommandEvent, PlayerJoinEvent, PlayerMoveEvent, PlayerQuitEvent,
+	PlayerSwingArmEvent,
+};
 use crate::player::context::PlayerEntityInfo;
+use crate::player::data::DEFAULT_DIMENSION;
+use crate::world::block_to_chunk;
+use crate::world::entity::PLAYER;
 use crate::{
-	ServerError, data::component::TextComponent, event::PacketHandler, player::context::ClientContext,
+	ServerError, data::component::TextComponent, event::PacketHandler,
+	player::context::ClientContext, trigger_event,
 };
 
-use crate::protocol::{ConnectionState, packet_id::*};
+use crate::protocol::{
+	ConnectionState,
+	codec::{Angle, PacketData, VarInt},
+	packet_id,
+	packets::play::{
+		DeathLocation, PlayerInfoAction, PlayerInfoEntries, PlayerInfoEntry, ServerboundPlayPacket,
+		clientbound, serverbound,
+	},
+};
 
+pub mod block;
+pub mod combat;
+pub mod command;
 pub mod config;
+pub mod datapack;
+pub mod entity;
+pub mod gamemode;
+pub mod health;
 pub mod helper;
+pub mod inventory;
 pub mod planner;
+pub mod playerdata;
+pub mod registry;
+pub mod tick;
 
 pub struct PlayHandler;
 
@@ -33,7 +73,7 @@ impl PacketHandler for PlayHandler {
 	) -> Result<(), ServerError> {
 		if !*cancel	// проверяем что пакет не отмененный, облегчаем себе задачу, ведь проверять айди наверняка сложней
 			&& state == ConnectionState::Configuration // проверяем стейт, т.к айди могут быть одинаковыми между стейтами
-			&& packet.id() == clientbound::configuration::FINISH
+			&& packet.id() == packet_id::clientbound::configuration::FINISH
 		{
 			handle_configuration_state(client)?; // делаем наши грязные дела
 		}
@@ -64,123 +104,72 @@ impl Listener for PlayListener {
 	}
 }
 
+// Первые 8 байт SHA-256 от сида мира
+const HASHED_SEED: i64 = 0x0f38f26ad09c3e20;
+const SEA_LEVEL: i32 = 60;
+
 pub fn send_login(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
 	// Отправка пакета Login
-	let mut packet = Packet::empty(clientbound::play::LOGIN);
-
-	packet.write_int(client.entity_info().unwrap().entity_id)?; // Entity ID
-	packet.write_boolean(false)?; // Is hardcore
-	packet.write_varint(4)?; // Dimension Names
-	packet.write_string("minecraft:overworld")?;
-	packet.write_string("minecraft:nether")?;
-	packet.write_string("minecraft:the_end")?;
-	packet.write_string("minecraft:overworld_caves")?;
-	packet.write_varint(0)?; // Max Players
-	packet.write_varint(8)?; // View Distance
-	packet.write_varint(5)?; // Simulation Distance
-	packet.write_boolean(false)?; // Reduced Debug Info
-	packet.write_boolean(true)?; // Enable respawn screen
-	packet.write_boolean(false)?; // Do limited crafting
-
-	packet.write_varint(0)?; // Dimension Type
-	packet.write_string("minecraft:overworld")?; // Dimension Name
-	packet.write_long(0x0f38f26ad09c3e20)?; // Hashed seed
-	packet.write_byte(0)?; // Game mode
-	packet.write_signed_byte(-1)?; // Previous Game mode
-	packet.write_boolean(false)?; // Is Debug
-	packet.write_boolean(true)?; // Is Flat
-	packet.write_boolean(false)?; // Has death location
-	packet.write_varint(20)?; // Portal cooldown
-	packet.write_varint(60)?; // Sea level
-
-	packet.write_boolean(false)?; // Enforces Secure Chat
-
-	client.write_packet(&packet)
+	client.send_packet(&clientbound::Login {
+		entity_id: entity_info.entity_id,
+		is_hardcore: false,
+		dimension_names: vec![
+			"minecraft:overworld".to_string(),
+			"minecraft:nether".to_string(),
+			"minecraft:the_end".to_string(),
+			"minecraft:overworld_caves".to_string(),
+		],
+		max_players: VarInt(0),
+		view_distance: VarInt(8),
+		simulation_distance: VarInt(5),
+		reduced_debug_info: false,
+		enable_respawn_screen: true,
+		do_limited_crafting: false,
+		dimension_type: VarInt(0),
+		dimension_name: "minecraft:overworld".to_string(),
+		hashed_seed: HASHED_SEED,
+		game_mode: entity_info.game_mode(),
+		previous_game_mode: -1,
+		is_debug: false,
+		is_flat: true,
+		death_location: entity_info
+			.last_death_location()
+			.map(|location| DeathLocation {
+				dimension_name: DEFAULT_DIMENSION.to_string(),
+				location,
+			}),
+		portal_cooldown: VarInt(20),
+		sea_level: VarInt(SEA_LEVEL),
+		enforces_secure_chat: false,
+	})
 }
 
-pub fn send_example_chunk(client: Arc<ClientContext>, x: i32, z: i32) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::CHUNK_DATA_AND_UPDATE_LIGHT);
-
-	packet.write_int(x)?;
-	packet.write_int(z)?;
-
-	// heightmap
-
-	packet.write_varint(1)?; // heightmaps count
-	packet.write_varint(0)?; // MOTION_BLOCKING - 0
-	// bits per entry is ceil(log2(385)) = 9 where 385 is the world height
-	// so, the length of the following array is (9 * 16 * 16) / 8 = 37
-	// ... idk how it came to that
-	packet.write_varint(37)?; // Length of the following long array 
-	for _ in 0..37 {
-		packet.write_long(0)?; // THIS WORKS ONLY BECAUSE OUR HEIGHT IS 0
-	}
-
-	// sending chunk data
-
-	let mut chunk_data = Vec::new();
-
-	// we want to fill the area from -64 to 0, so it will be 4 chunk sections
-
-	for _ in 0..4 {
-		chunk_data.write_short(4096)?; // non-air blocks count, 16 * 16 * 16 = 4096 stone blocks
-
-		// blocks paletted container
-		chunk_data.write_byte(0)?; // Bits Per Entry, use Single valued palette format
-		chunk_data.write_varint(1)?; // block state id in the registry 
-
-		// biomes palleted container
-		chunk_data.write_byte(0)?; // Bits Per Entry, use Single valued palette format
-		chunk_data.write_varint(1)?; // biome id in the registry
-	}
-
-	// air chunk sections
-
-	for _ in 0..20 {
-		chunk_data.write_short(0)?; // non-air blocks count, 0
-
-		// blocks paletted container
-		chunk_data.write_byte(0)?; // Bits Per Entry, use Single valued palette format
-		chunk_data.write_varint(0)?; // block state id in the registry (0 for air)
-
-		// biomes palleted container
-		chunk_data.write_byte(0)?; // Bits Per Entry, use Single valued palette format
-		chunk_data.write_varint(27)?; // biome id in the registry
-	}
-
-	packet.write_usize_varint(chunk_data.len())?;
-	packet.write_bytes(&chunk_data)?;
-
-	packet.write_byte(0)?;
-
-	// light data
-
-	packet.write_byte(0)?;
-	packet.write_byte(0)?;
-	packet.write_byte(0)?;
-	packet.write_byte(0)?;
-	packet.write_byte(0)?;
-	packet.write_byte(0)?;
-
-	client.write_packet(&packet)?;
-
-	Ok(())
+pub fn send_chunk(client: Arc<ClientContext>, x: i32, z: i32) -> Result<(), ServerError> {
+	let chunk = client.server.world.get_chunk(x, z);
+	let packet = chunk
+		.read()
+		.unwrap()
+		.to_packet(client.server.world.biome_bits())?;
+	client.send_packet(&packet)
 }
 
-pub fn send_example_chunks_in_distance(
+pub fn send_chunks_in_distance(
 	client: Arc<ClientContext>,
-	chunks: &mut Vec<(i32, i32)>,
 	distance: i32,
 	center: (i32, i32),
 ) -> Result<(), ServerError> {
-	let mut new_chunks = Vec::new();
+	let entity_info = client.entity_info().unwrap();
+	let chunks = entity_info.loaded_chunks();
+	let mut new_chunks = HashSet::new();
 
 	for x in -distance + center.0..=distance + center.0 {
 		for z in -distance + center.1..=distance + center.1 {
 			if !chunks.contains(&(x, z)) {
-				send_example_chunk(client.clone(), x as i32, z as i32)?;
+				send_chunk(client.clone(), x, z)?;
 			}
-			new_chunks.push((x, z));
+			new_chunks.insert((x, z));
 		}
 	}
 
@@ -190,7 +179,7 @@ pub fn send_example_chunks_in_distance(
 		}
 	}
 
-	*chunks = new_chunks;
+	entity_info.set_loaded_chunks(new_chunks);
 
 	Ok(())
 }
@@ -199,60 +188,68 @@ pub fn remove_player(
 	receiver: Arc<ClientContext>,
 	player: Arc<ClientContext>,
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::PLAYER_INFO_REMOVE);
-
-	packet.write_varint(1)?;
-	packet.write_uuid(&player.entity_info().unwrap().uuid)?;
-
-	receiver.write_packet(&packet)?;
+	receiver.send_packet(&clientbound::PlayerInfoRemove {
+		uuids: vec![player.entity_info().unwrap().uuid],
+	})?;
 
-	let mut packet = Packet::empty(clientbound::play::REMOVE_ENTITIES);
-
-	packet.write_varint(1)?;
-	packet.write_varint(player.entity_info().unwrap().entity_id)?; // Entity ID
-
-	receiver.write_packet(&packet)?;
-
-	Ok(())
+	receiver.send_packet(&clientbound::RemoveEntities {
+		entity_ids: vec![VarInt(player.entity_info().unwrap().entity_id)],
+	})
 }
 
 pub fn send_player(
 	receiver: Arc<ClientContext>,
 	player: Arc<ClientContext>,
 ) -> Result<(), ServerError> {
-	let mut packet = Packet::empty(clientbound::play::PLAYER_INFO_UPDATE);
-
-	packet.write_byte(0x01)?; // only Add Player 
-	packet.write_varint(1)?; // players list
-	packet.write_uuid(&player.entity_info().unwrap().uuid)?; // player uuid
-	packet.write_string(&player.player_info().unwrap().name)?; // player name
-	packet.write_varint(0)?; // no properties
-
-	receiver.write_packet(&packet)?;
-
-	let mut packet = Packet::empty(clientbound::play::SPAWN_ENTITY);
-
-	let (x, y, z) = player.entity_info().unwrap().position();
-	let (yaw, pitch) = player.entity_info().unwrap().rotation();
-	let (vel_x, vel_y, vel_z) = player.entity_info().unwrap().velocity();
-
-	packet.write_varint(player.entity_info().unwrap().entity_id)?; // Entity ID
-	packet.write_uuid(&player.entity_info().unwrap().uuid)?; // Entity UUID
-	packet.write_varint(148)?; // Entity type TODO: move to const
-	packet.write_double(x)?;
-	packet.write_double(y)?;
-	packet.write_double(z)?;
-	packet.write_signed_byte((pitch / 360.0 * 256.0) as i8)?;
-	packet.write_signed_byte((yaw / 360.0 * 256.0) as i8)?;
-	packet.write_signed_byte((yaw / 360.0 * 256.0) as i8)?; // head yaw TODO: make player head yaw field
-	packet.write_varint(0)?;
-	packet.write_short(vel_x as i16)?;
-	packet.write_short(vel_y as i16)?;
-	packet.write_short(vel_z as i16)?;
-
-	receiver.write_packet(&packet)?;
+	let player_info = player.player_info().unwrap();
+	let entity_info = player.entity_info().unwrap();
+
+	receiver.send_packet(&clientbound::PlayerInfoUpdate {
+		entries: PlayerInfoEntries(vec![PlayerInfoEntry {
+			uuid: entity_info.uuid,
+			actions: vec![
+				PlayerInfoAction::AddPlayer {
+					name: player_info.name,
+					properties: player_info.properties, // skin
+				},
+				PlayerInfoAction::UpdateGameMode(VarInt(entity_info.game_mode() as i32)),
+			],
+		}]),
+	})?;
+
+	spawn_player(receiver, player)
+}
 
-	Ok(())
+/// Спавнит сущность игрока у receiver, в табе он уже должен быть
+pub fn spawn_player(
+	receiver: Arc<ClientContext>,
+	player: Arc<ClientContext>,
+) -> Result<(), ServerError> {
+	let entity_info = player.entity_info().unwrap();
+
+	let (x, y, z) = entity_info.position();
+	let (yaw, pitch) = entity_info.rotation();
+	let (vel_x, vel_y, vel_z) = entity_info.velocity();
+
+	let Some(entity_type) = entity_type_id(&receiver, PLAYER) else {
+		return Ok(());
+	};
+
+	receiver.send_packet(&clientbound::SpawnEntity {
+		entity_id: VarInt(entity_info.entity_id),
+		entity_uuid: entity_info.uuid,
+		entity_type: VarInt(entity_type),
+		x,
+		y,
+		z,
+		pitch: Angle(pitch),
+		yaw: Angle(yaw),
+		head_yaw: Angle(yaw), // TODO: make player head yaw field
+		data: VarInt(0),
+		velocity_x: vel_x as i16,
+		velocity_y: vel_y as i16,
+		velocity_z: vel_z as i16,
+	})
 }
 
 pub fn get_offline_uuid(name: &str) -> Uuid {
@@ -265,7 +262,7 @@ pub fn get_offline_uuid(name: &str) -> Uuid {
 		namespaces_bytes[i] = *byte;
 	}
 	let namespace = Uuid::from_bytes(namespaces_bytes);
-	Uuid::new_v3(&namespace, (&name[2..]).as_bytes())
+	Uuid::new_v3(&namespace, &name.as_bytes()[2..])
 }
 
 pub fn send_rainbow_message(
@@ -275,43 +272,84 @@ pub fn send_rainbow_message(
 	send_system_message(client.clone(), TextComponent::rainbow(message), false)
 }
 
+/// Точка спавна, на поверхности над 8 8
+pub fn spawn_position(server: &Arc<ServerContext>) -> (f64, f64, f64) {
+	let y = server
+		.world
+		.get_highest_block(8, 8)
+		.map(|o| o + 1)
+		.unwrap_or(0) as f64;
+	(8.0, y, 8.0)
+}
+
+/// Выполняет команду, ошибки в самой команде показываем игроку
+pub fn handle_command(client: Arc<ClientContext>, command: &str) -> Result<(), ServerError> {
+	match client.server.commands().execute(client.clone(), command) {
+		Err(ServerError::Command(message)) => send_system_message(
+			client,
+			TextComponent::builder().text(&message).color("red").build(),
+			false,
+		),
+		result => result,
+	}
+}
+
 // Отдельная функция для работы с самой игрой
 pub fn handle_play_state(
 	client: Arc<ClientContext>, // Контекст клиента
 ) -> Result<(), ServerError> {
 	let player_name = client.player_info().unwrap().name;
-	let player_uuid = get_offline_uuid(&client.player_info().unwrap().name); // TODO: authenticated uuid
-	let entity_id = client
-		.server
-		.world
-		.entity_id_counter
-		.fetch_add(1, Ordering::SeqCst);
+	let player_uuid = client.player_info().unwrap().uuid; // в онлайне - с сессионного сервера, иначе оффлайн uuid
+	let entity_id = client.server.world.next_entity_id();
 
 	client.set_entity_info(PlayerEntityInfo::new(entity_id, player_uuid));
 
-	client.entity_info().unwrap().set_position((8.0, 0.0, 8.0)); // set 8 0 8 as position
-
-	thread::spawn({
-		let client = client.clone();
-
-		move || {
-			let _ = client.run_read_loop();
-			client.close();
+	let permission_level = client
+		.server
+		.config
+		.server
+		.permission_level(&player_name, &player_uuid);
+
+	let entity_info = client.entity_info().unwrap();
+	entity_info.set_position(spawn_position(&client.server));
+	entity_info.set_permission_level(permission_level);
+
+	// Вернувшийся игрок появляется там же где вышел, с тем же инвентарем
+	if let Some(data) = load_player_data(&client, player_uuid) {
+		data.apply(&entity_info);
+
+		// Других дименшенов у нас нет, из них игрок попадает на спавн
+		if data.dimension != DEFAULT_DIMENSION {
+			warn!(
+				"{player_name} вышел в {}, такого дименшена нет, он появится на спавне",
+				data.dimension
+			);
+			entity_info.set_position(spawn_position(&client.server));
 		}
-	});
+	}
+
+	let (x, y, z) = entity_info.position();
+	let (yaw, pitch) = entity_info.rotation();
 
 	send_login(client.clone())?;
-	sync_player_pos(client.clone(), 8.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0)?;
+	send_abilities(client.clone())?;
+	send_health(client.clone())?;
+	sync_player_pos(client.clone(), x, y, z, 0.0, 0.0, 0.0, yaw, pitch, 0)?;
+	client.send_packet(&clientbound::SetHeldItem {
+		slot: VarInt(entity_info.held_slot() as i32),
+	})?;
 	send_game_event(client.clone(), 13, 0.0)?; // 13 - Start waiting for level chunks
-	// send_game_event(client.clone(), 3, 1.0)?; // 3 - Set gamemode, 1.0 - creative
-	send_entity_event(client.clone(), entity_id, 28)?; // 28 - give op level 4
-	set_center_chunk(client.clone(), 0, 0)?;
+	send_entity_event(client.clone(), entity_id, 24 + permission_level)?; // 24-28 - op level 0-4
+	client.send_packet(&client.server.commands().to_packet(&client))?;
+	send_time(client.clone())?;
+	send_window_content(client.clone())?;
 
-	let mut chunks = Vec::new();
+	let ((chunk_x, chunk_z), _) = block_to_chunk(x.floor() as i32, z.floor() as i32);
+	set_center_chunk(client.clone(), chunk_x, chunk_z)?;
 
 	let view_distance = client.client_info().unwrap().view_distance as i32 / 2;
 
-	send_example_chunks_in_distance(client.clone(), &mut chunks, view_distance, (0, 0))?;
+	send_chunks_in_distance(client.clone(), view_distance, (chunk_x, chunk_z))?;
 
 	// sync_player_pos(client.clone(), 8.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0)?;
 
@@ -328,242 +366,375 @@ pub fn handle_play_state(
 	// send_rainbow_message(&client, format!("Your Name: {}", &player_name))?;
 	// send_rainbow_message(&client, format!("Your Entity ID: {}", entity_id))?;
 
+	let mut join_event = PlayerJoinEvent {
+		message: Some(TextComponent::rainbow(format!(
+			"{player_name} joined the game"
+		))),
+		kick_reason: TextComponent::new("You are not allowed to join".to_string()),
+		cancelled: false,
+	};
+
+	trigger_event!(client, player_join, &mut join_event);
+
+	if join_event.cancelled {
+		client
+			.protocol_helper()
+			.disconnect(join_event.kick_reason)?;
+		client.close();
+		return Ok(());
+	}
+
 	for player in client.server.players() {
 		if client.addr == player.addr {
 			continue;
 		}
-		send_player(client.clone(), player.clone())?;
-		send_player(player.clone(), client.clone())?;
-		send_rainbow_message(&player, format!("{} joined the game", player_name))?;
+		// Проблема у одного из игроков не должна выкинуть входящего
+		log_send_error(&client, send_player(client.clone(), player.clone()));
+
+		let result = send_player(player.clone(), client.clone()).and_then(|_| {
+			if let Some(message) = &join_event.message {
+				send_system_message(player.clone(), message.clone(), false)?;
+			}
+			Ok(())
+		});
+		log_send_error(&player, result);
 	}
 
-	thread::spawn({
-		let client = client.clone();
-
-		move || -> Result<(), ServerError> {
-			while client.is_alive() {
-				let mut packet = client.read_packet(&[
-					serverbound::play::SET_PLAYER_POSITION,
-					serverbound::play::SET_PLAYER_POSITION_AND_ROTATION,
-					serverbound::play::SET_PLAYER_ROTATION,
-					serverbound::play::CHAT_MESSAGE,
-					serverbound::play::CLICK_CONTAINER,
-					serverbound::play::CHAT_COMMAND,
-					serverbound::play::SIGNED_CHAT_COMMAND,
-					serverbound::play::PLAYER_COMMAND,
-					serverbound::play::SWING_ARM,
-				])?;
-
-				match packet.id() {
-					serverbound::play::CLICK_CONTAINER => {
-						let _ = packet.read_varint()?; // window id
-						let _ = packet.read_varint()?; // state id
-						let slot = packet.read_short()?; // slot
-						let _ = packet.read_byte()?; // button
-						let _ = packet.read_varint()?; // mode
-						// i cannot read item slots now
-
-						send_rainbow_message(&client, format!("index clicked: {slot}"))?;
-					}
-					serverbound::play::SWING_ARM => {
-						let hand = packet.read_varint()?; // hand (0 - main, 1 - off)
-
-						send_rainbow_message(&client, format!("hand swinged: {hand}"))?;
-
-						let animation = match hand {
-							0 => 0, // 0 - mainhand swing animatiom
-							1 => 3, // 3 - offhand swing animatiom
-							_ => continue,
-						};
-
-						for player in client.server.players() {
-							if client.addr == player.addr {
-								continue;
-							}
-
-							send_entity_animation(player, client.entity_info().unwrap().entity_id, animation)?;
-						}
-					}
-					serverbound::play::PLAYER_COMMAND => {
-						let _ = packet.read_varint()?; // entity id
-						let action = packet.read_varint()?; // action id
-						let _ = packet.read_varint()?; // jump boost on horse
-
-						if action == 0 {
-							// press sneak key
-						} else if action == 1 {
-							// release sneak key
-						}
-					}
-					serverbound::play::CHAT_COMMAND | serverbound::play::SIGNED_CHAT_COMMAND => {
-						let command = packet.read_string()?;
-
-						if command == "gamemode creative" {
-							send_game_event(client.clone(), 3, 1.0)?; // 3 - Set gamemode
-							send_rainbow_message(&client, format!("gamemode creative installed"))?;
-						} else if command == "gamemode survival" {
-							send_game_event(client.clone(), 3, 0.0)?; // 3 - Set gamemode
-							send_rainbow_message(&client, format!("gamemode survival installed"))?;
-						} else if command == "kill" {
-							sync_player_pos(client.clone(), 8.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0)?;
-							send_rainbow_message(&client, format!("killed"))?;
-						}
-					}
-					serverbound::play::CHAT_MESSAGE => {
-						let message_text = packet.read_string()?;
-						// skip remaining data coz they suck
-
-						let mut message =
-							TextComponent::rainbow(format!("{} said: ", client.player_info().unwrap().name));
-
-						message.italic = Some(true);
-
-						let text_message = TextComponent::builder()
-							.color("white")
-							.text(&message_text)
-							.italic(false)
-							.build();
-
-						if let Some(extra) = &mut message.extra {
-							extra.push(text_message);
-						}
-
-						for player in client.server.players() {
-							send_system_message(player.clone(), message.clone(), false)?;
-						}
-					}
-					serverbound::play::SET_PLAYER_POSITION => {
-						let x = packet.read_double()?;
-						let y = packet.read_double()?;
-						let z = packet.read_double()?;
-						let flags = packet.read_byte()?; // flags
-
-						let prev = client.entity_info().unwrap().position();
-
-						for player in client.server.players() {
-							if client.addr == player.addr {
-								continue;
-							}
-
-							let mut packet = Packet::empty(clientbound::play::UPDATE_ENTITY_POSITION);
-							packet.write_varint(client.entity_info().unwrap().entity_id)?;
-							packet.write_short((x * 4096.0 - prev.0 * 4096.0) as i16)?; // formula: currentX * 4096 - prevX * 4096
-							packet.write_short((y * 4096.0 - prev.1 * 4096.0) as i16)?;
-							packet.write_short((z * 4096.0 - prev.2 * 4096.0) as i16)?;
-							packet.write_boolean(flags & 0x01 != 0)?;
-							player.write_packet(&packet)?;
-						}
-
-						client.entity_info().unwrap().set_position((x, y, z));
-					}
-					serverbound::play::SET_PLAYER_POSITION_AND_ROTATION => {
-						let x = packet.read_double()?;
-						let y = packet.read_double()?;
-						let z = packet.read_double()?;
-						let yaw = packet.read_float()?;
-						let pitch = packet.read_float()?;
-						let flags = packet.read_byte()?; // flags
-
-						let prev = client.entity_info().unwrap().position();
-
-						for player in client.server.players() {
-							if client.addr == player.addr {
-								continue;
-							}
-
-							let mut packet =
-								Packet::empty(clientbound::play::UPDATE_ENTITY_POSITION_AND_ROTATION);
-							packet.write_varint(client.entity_info().unwrap().entity_id)?;
-							packet.write_short((x * 4096.0 - prev.0 * 4096.0) as i16)?; // formula: currentX * 4096 - prevX * 4096
-							packet.write_short((y * 4096.0 - prev.1 * 4096.0) as i16)?;
-							packet.write_short((z * 4096.0 - prev.2 * 4096.0) as i16)?;
-							packet.write_signed_byte((yaw / 360.0 * 256.0) as i8)?;
-							packet.write_signed_byte((pitch / 360.0 * 256.0) as i8)?;
-							packet.write_boolean(flags & 0x01 != 0)?;
-							player.write_packet(&packet)?;
-
-							let mut packet = Packet::empty(clientbound::play::SET_HEAD_ROTATION);
-							packet.write_varint(client.entity_info().unwrap().entity_id)?;
-							packet.write_signed_byte((yaw / 360.0 * 256.0) as i8)?;
-							player.write_packet(&packet)?;
-						}
-
-						client.entity_info().unwrap().set_position((x, y, z));
-						client.entity_info().unwrap().set_rotation((yaw, pitch));
-					}
-					serverbound::play::SET_PLAYER_ROTATION => {
-						let yaw = packet.read_float()?;
-						let pitch = packet.read_float()?;
-						let flags = packet.read_byte()?; // flags
-
-						for player in client.server.players() {
-							if client.addr == player.addr {
-								continue;
-							}
-
-							let mut packet = Packet::empty(clientbound::play::UPDATE_ENTITY_ROTATION);
-							packet.write_varint(client.entity_info().unwrap().entity_id)?;
-							packet.write_signed_byte((yaw / 360.0 * 256.0) as i8)?;
-							packet.write_signed_byte((pitch / 360.0 * 256.0) as i8)?;
-							packet.write_boolean(flags & 0x01 != 0)?;
-							player.write_packet(&packet)?;
-
-							let mut packet = Packet::empty(clientbound::play::SET_HEAD_ROTATION);
-							packet.write_varint(client.entity_info().unwrap().entity_id)?;
-							packet.write_signed_byte((yaw / 360.0 * 256.0) as i8)?;
-							player.write_packet(&packet)?;
-						}
-
-						client.entity_info().unwrap().set_rotation((yaw, pitch));
-					}
-					_ => {}
-				}
+	let planner = client.server.planner();
+
+	// 10 secs timer
+	planner
+		.task({
+			let client = client.clone();
+			move |_| send_keep_alive(client.clone())
+		})
+		.repeat(200)
+		.asynchronous()
+		.owner(&client)
+		.schedule();
+
+	// 1 sec timer, догружаем чанки вокруг игрока
+	planner
+		.task({
+			let client = client.clone();
+			move |_| {
+				let (x, _, z) = client.entity_info().unwrap().position();
+
+				let ((chunk_x, chunk_z), _) = block_to_chunk(x.floor() as i32, z.floor() as i32);
+
+				set_center_chunk(client.clone(), chunk_x, chunk_z)?;
+				send_chunks_in_distance(client.clone(), view_distance, (chunk_x, chunk_z))
 			}
+		})
+		.delay(20)
+		.repeat(20)
+		.asynchronous()
+		.owner(&client)
+		.schedule();
+
+	// Дальше игроком занимаются главный тик и задачи, поток подключения освобождается
+	Ok(())
+}
 
-			Ok(())
+/// Пакеты режима Play которые обрабатывает главный тик
+pub const PLAY_PACKETS: [u8; 24] = [
+	serverbound::SetPlayerPosition::ID,
+	serverbound::SetPlayerPositionAndRotation::ID,
+	serverbound::SetPlayerRotation::ID,
+	serverbound::ChatMessage::ID,
+	serverbound::ClickContainer::ID,
+	serverbound::CloseContainer::ID,
+	serverbound::RenameItem::ID,
+	serverbound::ChatCommand::ID,
+	serverbound::SignedChatCommand::ID,
+	serverbound::PlayerCommand::ID,
+	serverbound::SwingArm::ID,
+	serverbound::Interact::ID,
+	serverbound::PlayerAbilities::ID,
+	serverbound::PlayerAction::ID,
+	serverbound::UseItemOn::ID,
+	serverbound::SetHeldItem::ID,
+	serverbound::SetCreativeModeSlot::ID,
+	serverbound::CommandSuggestionsRequest::ID,
+	serverbound::ClientStatus::ID,
+	serverbound::KeepAlive::ID,
+	// Эти просто выкидываем, чтобы не копились в буфере
+	serverbound::ClientTickEnd::ID,
+	serverbound::ConfirmTeleportation::ID,
+	serverbound::PlayerLoaded::ID,
+	serverbound::SetPlayerMovementFlags::ID,
+];
+
+/// Обрабатывает один пакет игрока, вызывается из главного тика
+pub fn handle_play_packet(
+	client: Arc<ClientContext>,
+	mut packet: Packet,
+) -> Result<(), ServerError> {
+	match ServerboundPlayPacket::decode(&mut packet)? {
+		ServerboundPlayPacket::ClickContainer {
+			window_id,
+			state_id,
+			slot,
+			button,
+			mode,
+			changed_slots,
+			carried_item,
+		} => {
+			handle_click_container(
+				client.clone(),
+				window_id.0,
+				state_id.0,
+				slot,
+				button,
+				mode.0,
+				&changed_slots,
+				&carried_item,
+			)?;
+		}
+		ServerboundPlayPacket::CloseContainer { .. } => {
+			handle_close_container(client.clone())?;
 		}
-	});
+		ServerboundPlayPacket::RenameItem { item_name } => {
+			handle_rename_item(client.clone(), item_name)?;
+		}
+		ServerboundPlayPacket::SwingArm { hand } => {
+			let hand = hand.0; // hand (0 - main, 1 - off)
+
+			let mut event = PlayerSwingArmEvent {
+				hand,
+				cancelled: false,
+			};
 
-	let mut ticks_alive = 0u64;
+			trigger_event!(client, player_swing_arm, &mut event);
+
+			if event.cancelled {
+				return Ok(());
+			}
+
+			let animation = match hand {
+				0 => 0, // 0 - mainhand swing animatiom
+				1 => 3, // 3 - offhand swing animatiom
+				_ => return Ok(()),
+			};
+
+			for player in client.server.players() {
+				if client.addr == player.addr {
+					continue;
+				}
 
-	while client.is_alive() {
-		if ticks_alive % 200 == 0 {
-			// 10 secs timer
-			send_keep_alive(client.clone())?;
+				let result = send_entity_animation(
+					player.clone(),
+					client.entity_info().unwrap().entity_id,
+					animation,
+				);
+				log_send_error(&player, result);
+			}
+		}
+		ServerboundPlayPacket::Interact {
+			entity_id, action, ..
+		} => {
+			handle_interact(client.clone(), entity_id.0, action)?;
+		}
+		ServerboundPlayPacket::PlayerAbilities { flags } => {
+			handle_player_abilities(client.clone(), flags)?;
+		}
+		ServerboundPlayPacket::PlayerAction {
+			status,
+			location,
+			sequence,
+			..
+		} => {
+			handle_player_action(client.clone(), status.0, location, sequence.0)?;
+		}
+		ServerboundPlayPacket::UseItemOn {
+			hand,
+			location,
+			face,
+			sequence,
+			..
+		} => {
+			handle_use_item_on(client.clone(), hand.0, location, face.0, sequence.0)?;
+		}
+		ServerboundPlayPacket::SetHeldItem { slot } => {
+			handle_set_held_item(client.clone(), slot)?;
+		}
+		ServerboundPlayPacket::SetCreativeModeSlot { slot, item } => {
+			handle_set_creative_mode_slot(client.clone(), slot, item)?;
 		}
+		ServerboundPlayPacket::PlayerCommand { action_id, .. } => {
+			if action_id.0 == 0 {
+				// press sneak key
+			} else if action_id.0 == 1 {
+				// release sneak key
+			} else if action_id.0 == 3 || action_id.0 == 4 {
+				// 3 - начал бежать, 4 - перестал
+				client
+					.entity_info()
+					.unwrap()
+					.set_sprinting(action_id.0 == 3);
+			}
+		}
+		ServerboundPlayPacket::ClientStatus { action_id } => {
+			handle_client_status(client.clone(), action_id.0)?;
+		}
+		// Чужой айди - клиент врет, отключаем
+		ServerboundPlayPacket::KeepAlive { keep_alive_id }
+			if !client.confirm_keep_alive(keep_alive_id) =>
+		{
+			return Err(ServerError::WrongPacket);
+		}
+		ServerboundPlayPacket::ChatCommand { command }
+		| ServerboundPlayPacket::SignedChatCommand { command, .. } => {
+			let mut event = PlayerCommandEvent {
+				command,
+				cancelled: false,
+			};
 
-		if ticks_alive % 20 == 0 {
-			// 1 sec timer
-			let (x, _, z) = client.entity_info().unwrap().position();
+			trigger_event!(client, player_command, &mut event);
 
-			let (chunk_x, chunk_z) = ((x / 16.0) as i64, (z / 16.0) as i64);
-			let (chunk_x, chunk_z) = (chunk_x as i32, chunk_z as i32);
+			if event.cancelled {
+				return Ok(());
+			}
 
-			set_center_chunk(client.clone(), chunk_x, chunk_z)?;
-			send_example_chunks_in_distance(
+			handle_command(client.clone(), &event.command)?;
+		}
+		ServerboundPlayPacket::CommandSuggestionsRequest {
+			transaction_id,
+			text,
+		} => {
+			client.send_packet(&suggestions_response(
 				client.clone(),
-				&mut chunks,
-				view_distance,
-				(chunk_x, chunk_z),
-			)?;
+				transaction_id.0,
+				&text,
+			))?;
 		}
+		ServerboundPlayPacket::ChatMessage {
+			message: message_text,
+			..
+		} => {
+			let mut event = PlayerChatEvent {
+				message: message_text,
+				cancelled: false,
+			};
+
+			trigger_event!(client, player_chat, &mut event);
+
+			if event.cancelled {
+				return Ok(());
+			}
 
-		// text animation
-		{
-			let animation_text = format!("Ticks alive: {}         жёпа", ticks_alive);
-			let animation_index = ((ticks_alive + 40) % 300) as usize;
-			let animation_end = animation_text.len() + 20;
+			let message_text = event.message;
+
+			let mut message =
+				TextComponent::rainbow(format!("{} said: ", client.player_info().unwrap().name));
 
-			if animation_index < animation_end {
-				let now_length = (animation_index + 1).min(animation_text.chars().count());
-				let now_text = animation_text.chars().take(now_length).collect();
+			message.italic = Some(true);
 
-				send_system_message(client.clone(), TextComponent::rainbow(now_text), true)?;
+			let text_message = TextComponent::builder()
+				.color("white")
+				.text(&message_text)
+				.italic(false)
+				.build();
+
+			if let Some(extra) = &mut message.extra {
+				extra.push(text_message);
+			}
+
+			for player in client.server.players() {
+				log_send_error(
+					&player,
+					send_system_message(player.clone(), message.clone(), false),
+				);
 			}
 		}
+		ServerboundPlayPacket::SetPlayerPosition { x, y, z, flags } => {
+			let (yaw, pitch) = client.entity_info().unwrap().rotation();
+			handle_player_move(client.clone(), (x, y, z), (yaw, pitch), flags)?;
+		}
+		ServerboundPlayPacket::SetPlayerPositionAndRotation {
+			x,
+			y,
+			z,
+			yaw,
+			pitch,
+			flags,
+		} => {
+			handle_player_move(client.clone(), (x, y, z), (yaw, pitch), flags)?;
+		}
+		ServerboundPlayPacket::SetPlayerRotation { yaw, pitch, flags } => {
+			let position = client.entity_info().unwrap().position();
+			handle_player_move(client.clone(), position, (yaw, pitch), flags)?;
+		}
+		_ => {}
+	}
 
-		thread::sleep(Duration::from_millis(50)); // 1 tick
-		ticks_alive += 1;
+	Ok(())
+}
+
+// Пакеты движения
+pub fn handle_player_move(
+	client: Arc<ClientContext>,
+	position: (f64, f64, f64),
+	rotation: (f32, f32),
+	flags: u8,
+) -> Result<(), ServerError> {
+	let entity_info = client.entity_info().unwrap();
+
+	let (x, y, z) = entity_info.position();
+	let (yaw, pitch) = entity_info.rotation();
+	let from = Location {
+		x,
+		y,
+		z,
+		yaw,
+		pitch,
+	};
+
+	let to = Location {
+		x: position.0,
+		y: position.1,
+		z: position.2,
+		yaw: rotation.0,
+		pitch: rotation.1,
+	};
+
+	let mut event = PlayerMoveEvent {
+		from,
+		to,
+		on_ground: flags & 0x01 != 0,
+		cancelled: false,
+	};
+
+	trigger_event!(client, player_move, &mut event);
+
+	// При отмене возвращаем игрока назад, а если листенер поменял точку - телепортируем туда
+	let target = if event.cancelled { from } else { event.to };
+
+	if target != to {
+		sync_player_pos(
+			client.clone(),
+			target.x,
+			target.y,
+			target.z,
+			0.0,
+			0.0,
+			0.0,
+			target.yaw,
+			target.pitch,
+			0,
+		)?;
+	}
+
+	let was_on_ground = entity_info.on_ground();
+
+	// Остальным игрокам движение рассылается в конце тика
+	entity_info.set_position((target.x, target.y, target.z));
+	entity_info.set_rotation((target.yaw, target.pitch));
+	entity_info.set_on_ground(event.on_ground);
+
+	// После телепорта падение считаем с новой точки
+	if target == to {
+		handle_movement(client, from, to, was_on_ground, event.on_ground)?;
 	}
 
 	Ok(())
@@ -572,16 +743,50 @@ pub fn handle_play_state(
 pub fn handle_disconnect(
 	client: Arc<ClientContext>, // Контекст клиента
 ) -> Result<(), ServerError> {
+	// Игрок мог отключиться еще до режима Play
+	let (Some(player_info), Some(entity_info)) = (client.player_info(), client.entity_info()) else {
+		return Ok(());
+	};
+
+	// Курсор и сетка крафта возвращаются в инвентарь, что не влезло - пропадает
+	entity_info.windows().close(client.server.world.items());
+
+	if let Err(error) = save_player_data(client.clone()) {
+		error!(
+			"Не удалось сохранить игрока {}: {error:?}",
+			player_info.name
+		);
+	}
+
+	let mut event = PlayerQuitEvent {
+		message: Some(TextComponent::rainbow(format!(
+			"{} left the game",
+			player_info.name
+		))),
+	};
+
+	trigger_event!(client, player_quit, &mut event);
+
 	for player in client.server.players() {
 		if client.addr == player.addr {
 			continue;
 		}
 
-		remove_player(player.clone(), client.clone())?;
-		send_rainbow_message(
-			&player,
-			format!("{} left the game", client.player_info().unwrap().name),
-		)?;
+		// Ошибка у одного игрока не должна оставить призрака у остальных
+		let result = remove_player(player.clone(), client.clone()).and_then(|_| {
+			if let Some(message) = &event.message {
+				send_system_message(player.clone(), message.clone(), false)?;
+			}
+			Ok(())
+		});
+
+		if let Err(error) = result {
+			warn!(
+				"Не удалось убрать {} у игрока {}: {error:?}",
+				player_info.name,
+				player.player_info().unwrap().name
+			);
+		}
 	}
 
 	Ok(())
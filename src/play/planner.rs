@@ -1 +1,314 @@
-// TODO: thread-safe planner like BukkitScheduler
+// Потокобезопасный планировщик задач как BukkitScheduler
+// Время считается в тиках, синхронные задачи выполняются прямо в главном тике,
+// асинхронные отдаются пулу воркеров
+
+use std::{
+	sync::{
+		Arc, Mutex, Weak,
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		mpsc::{Sender, channel},
+	},
+	thread,
+};
+
+use log::error;
+
+use crate::{ServerError, player::context::ClientContext};
+
+/// Сама задача, получает свой хендл чтобы отменить себя изнутри
+pub type TaskFn = Box<dyn FnMut(&TaskHandle) -> Result<(), ServerError> + Send>;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// Сколько потоков выполняют асинхронные задачи
+const ASYNC_WORKERS: usize = 4;
+
+/// Хендл задачи, через него задачу можно отменить
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+	id: u64,
+	cancelled: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+	pub fn id(&self) -> u64 {
+		self.id
+	}
+
+	/// Отменяет задачу, если она сейчас выполняется - выполнение не прерывается
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::SeqCst);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::SeqCst)
+	}
+}
+
+struct ScheduledTask {
+	handle: TaskHandle,
+	next_run: u64,
+	period: Option<u64>,
+	asynchronous: bool,
+	owner: Option<Weak<ClientContext>>,
+	task: Arc<Mutex<TaskFn>>,
+}
+
+impl ScheduledTask {
+	// Задача клиента живет пока жив клиент
+	fn is_alive(&self) -> bool {
+		!self.handle.is_cancelled()
+			&& self
+				.owner
+				.as_ref()
+				.is_none_or(|o| o.upgrade().is_some_and(|o| o.is_alive()))
+	}
+}
+
+// Выполняет задачу, ошибка отменяет ее
+fn run_task(handle: &TaskHandle, task: &Mutex<TaskFn>) {
+	// Асинхронная задача может еще выполняться с прошлого раза, тогда пропускаем
+	let Ok(mut task) = task.try_lock() else {
+		return;
+	};
+
+	if handle.is_cancelled() {
+		return;
+	}
+
+	match task(handle) {
+		Ok(()) => {}
+		Err(ServerError::ConnectionClosed) => handle.cancel(),
+		Err(error) => {
+			error!("Ошибка в задаче {}: {error:?}", handle.id);
+			handle.cancel();
+		}
+	}
+}
+
+pub struct Planner {
+	current_tick: AtomicU64,
+	next_id: AtomicU64,
+	tasks: Mutex<Vec<ScheduledTask>>,
+	workers: Sender<Job>,
+}
+
+impl Default for Planner {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Planner {
+	pub fn new() -> Planner {
+		let (sender, receiver) = channel::<Job>();
+		let receiver = Arc::new(Mutex::new(receiver));
+
+		for _ in 0..ASYNC_WORKERS {
+			let receiver = receiver.clone();
+			thread::spawn(move || {
+				loop {
+					let job = receiver.lock().unwrap().recv();
+					match job {
+						Ok(job) => job(),
+						Err(_) => break, // Планировщик удален
+					}
+				}
+			});
+		}
+
+		Planner {
+			current_tick: AtomicU64::new(0),
+			next_id: AtomicU64::new(0),
+			tasks: Mutex::new(Vec::new()),
+			workers: sender,
+		}
+	}
+
+	/// Сколько тиков прошло с запуска
+	pub fn current_tick(&self) -> u64 {
+		self.current_tick.load(Ordering::SeqCst)
+	}
+
+	/// Начинает описание задачи, например
+	///
+	/// ```ignore
+	/// planner.task(|_| Ok(())).delay(20).repeat(20).asynchronous().owner(&client).schedule();
+	/// ```
+	pub fn task<F>(&self, task: F) -> TaskBuilder<'_>
+	where
+		F: FnMut(&TaskHandle) -> Result<(), ServerError> + Send + 'static,
+	{
+		TaskBuilder {
+			planner: self,
+			task: Box::new(task),
+			delay: 0,
+			period: None,
+			asynchronous: false,
+			owner: None,
+		}
+	}
+
+	/// Выполнить в следующем тике
+	pub fn run_next_tick<F>(&self, task: F) -> TaskHandle
+	where
+		F: FnMut(&TaskHandle) -> Result<(), ServerError> + Send + 'static,
+	{
+		self.task(task).schedule()
+	}
+
+	/// Выполнить через delay тиков
+	pub fn run_later<F>(&self, delay: u64, task: F) -> TaskHandle
+	where
+		F: FnMut(&TaskHandle) -> Result<(), ServerError> + Send + 'static,
+	{
+		self.task(task).delay(delay).schedule()
+	}
+
+	/// Выполнять каждые period тиков начиная через delay тиков
+	pub fn run_timer<F>(&self, delay: u64, period: u64, task: F) -> TaskHandle
+	where
+		F: FnMut(&TaskHandle) -> Result<(), ServerError> + Send + 'static,
+	{
+		self.task(task).delay(delay).repeat(period).schedule()
+	}
+
+	/// Выполнить в пуле воркеров прямо сейчас
+	pub fn run_async<F>(&self, task: F) -> TaskHandle
+	where
+		F: FnMut(&TaskHandle) -> Result<(), ServerError> + Send + 'static,
+	{
+		self.task(task).asynchronous().schedule()
+	}
+
+	/// Отменяет все задачи клиента, вызывается при отключении
+	pub fn cancel_client_tasks(&self, client: &Arc<ClientContext>) {
+		self.tasks.lock().unwrap().retain(|o| {
+			let owned = o
+				.owner
+				.as_ref()
+				.is_some_and(|o| o.as_ptr() == Arc::as_ptr(client));
+			if owned {
+				o.handle.cancel();
+			}
+			!owned
+		});
+	}
+
+	/// Отменяет вообще все задачи
+	pub fn cancel_all(&self) {
+		for task in self.tasks.lock().unwrap().drain(..) {
+			task.handle.cancel();
+		}
+	}
+
+	fn dispatch(&self, handle: TaskHandle, task: Arc<Mutex<TaskFn>>, asynchronous: bool) {
+		if asynchronous {
+			let _ = self
+				.workers
+				.send(Box::new(move || run_task(&handle, &task)));
+		} else {
+			run_task(&handle, &task);
+		}
+	}
+
+	/// Один тик планировщика, вызывается из главного тика сервера
+	pub fn tick(&self) {
+		let now = self.current_tick.fetch_add(1, Ordering::SeqCst) + 1;
+
+		// Выполняем уже без блокировки, чтобы задачи могли планировать новые задачи
+		let due = {
+			let mut tasks = self.tasks.lock().unwrap();
+
+			let (due, rest): (Vec<_>, Vec<_>) = tasks
+				.drain(..)
+				.filter(|o| o.is_alive())
+				.partition(|o| o.next_run <= now);
+			*tasks = rest;
+
+			let mut jobs = Vec::new();
+			for mut task in due {
+				jobs.push((task.handle.clone(), task.task.clone(), task.asynchronous));
+				if let Some(period) = task.period {
+					task.next_run = now + period;
+					tasks.push(task);
+				}
+			}
+			jobs
+		};
+
+		for (handle, task, asynchronous) in due {
+			self.dispatch(handle, task, asynchronous);
+		}
+	}
+}
+
+/// Настройки задачи перед планированием
+pub struct TaskBuilder<'a> {
+	planner: &'a Planner,
+	task: TaskFn,
+	delay: u64,
+	period: Option<u64>,
+	asynchronous: bool,
+	owner: Option<Weak<ClientContext>>,
+}
+
+impl TaskBuilder<'_> {
+	/// Задержка в тиках, 0 - в следующем тике (асинхронная задача запускается сразу)
+	pub fn delay(mut self, ticks: u64) -> Self {
+		self.delay = ticks;
+		self
+	}
+
+	/// Повторять каждые period тиков пока не отменят
+	pub fn repeat(mut self, period: u64) -> Self {
+		self.period = Some(period.max(1));
+		self
+	}
+
+	/// Выполнять в пуле воркеров, а не в главном тике
+	pub fn asynchronous(mut self) -> Self {
+		self.asynchronous = true;
+		self
+	}
+
+	/// Привязывает задачу к клиенту, при его отключении задача отменяется
+	pub fn owner(mut self, client: &Arc<ClientContext>) -> Self {
+		self.owner = Some(Arc::downgrade(client));
+		self
+	}
+
+	pub fn schedule(self) -> TaskHandle {
+		let planner = self.planner;
+
+		let handle = TaskHandle {
+			id: planner.next_id.fetch_add(1, Ordering::SeqCst),
+			cancelled: Arc::new(AtomicBool::new(false)),
+		};
+
+		let task = ScheduledTask {
+			handle: handle.clone(),
+			next_run: planner.current_tick() + self.delay.max(1),
+			period: self.period,
+			asynchronous: self.asynchronous,
+			owner: self.owner,
+			task: Arc::new(Mutex::new(self.task)),
+		};
+
+		if task.asynchronous && self.delay == 0 {
+			planner.dispatch(handle.clone(), task.task.clone(), true);
+
+			// Повторяющаяся задача дальше идет по тикам
+			if let Some(period) = task.period {
+				planner.tasks.lock().unwrap().push(ScheduledTask {
+					next_run: planner.current_tick() + period,
+					..task
+				});
+			}
+		} else {
+			planner.tasks.lock().unwrap().push(task);
+		}
+
+		handle
+	}
+}
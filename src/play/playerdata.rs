@@ -0,0 +1,71 @@
+// Загрузка и сохранение игроков в playerdata/<uuid>.dat, формат см. в player::data
+//
+// Загружается игрок при входе до пакета Login, сохраняется при выходе и при остановке сервера
+
+use std::sync::Arc;
+
+use log::{error, warn};
+use uuid::Uuid;
+
+use crate::{
+	ServerError,
+	context::ServerContext,
+	player::{context::ClientContext, data::PlayerData},
+};
+
+/// Данные игрока из его файла, None если он заходит впервые
+///
+/// Сломанный файл не мешает войти, игрок просто появится на спавне
+pub fn load_player_data(client: &Arc<ClientContext>, uuid: Uuid) -> Option<PlayerData> {
+	let registries = client.server.registries(client.protocol_version());
+
+	match client.server.world.player_data().read_nbt(uuid) {
+		Ok(Some(nbt)) => {
+			let data = PlayerData::from_nbt(&nbt, registries);
+			if data.is_none() {
+				warn!("В файле игрока {uuid} нет позиции, он появится на спавне");
+			}
+			data
+		}
+		Ok(None) => None,
+		Err(error) => {
+			error!("Не удалось загрузить данные игрока {uuid}: {error:?}");
+			None
+		}
+	}
+}
+
+/// Сохраняет игрока поверх его старого файла, до режима Play сохранять нечего
+pub fn save_player_data(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let Some(entity_info) = client.entity_info() else {
+		return Ok(());
+	};
+
+	let storage = client.server.world.player_data();
+	let registries = client.server.registries(client.protocol_version());
+
+	// Старый файл не читается - пишем с нуля, прошлая версия останется в .dat_old
+	let base = storage.read_nbt(entity_info.uuid).unwrap_or_else(|error| {
+		warn!(
+			"Не удалось прочитать старые данные игрока {}: {error:?}",
+			entity_info.uuid
+		);
+		None
+	});
+
+	let nbt = PlayerData::from_entity(&entity_info).to_nbt(entity_info.uuid, base, registries);
+
+	storage.write_nbt(entity_info.uuid, &nbt)
+}
+
+/// Сохраняет всех игроков, ошибки только пишутся в лог
+pub fn save_all_players(server: &Arc<ServerContext>) {
+	for player in server.players() {
+		if let Err(error) = save_player_data(player.clone()) {
+			error!(
+				"Не удалось сохранить игрока {}: {error:?}",
+				player.player_info().unwrap().name
+			);
+		}
+	}
+}
@@ -0,0 +1,387 @@
+// Синхронизируемые реестры и теги, которые клиент получает в Configuration
+// Ванильные лежат в data/<версия>/ в JSON и собираются в пакеты при отправке
+//
+// Каждая запись помнит из какого она пака. Если клиент знает этот пак (Known Packs),
+// запись отправляется без данных, ванильные так и вовсе хранятся без них.
+// У записей из датапаков данные в JSON, в NBT они переводятся при отправке
+//
+// Имена записей встроенных реестров (предметы, звуки, эффекты и тд) клиенту не отправляются,
+// они берутся из отчета ванильного генератора данных и нужны только серверу
+
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	fs,
+	path::Path,
+};
+
+use craftflow_nbt::DynNBT;
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+	ServerError,
+	protocol::{
+		codec::VarInt,
+		packets::configuration::{KnownPack, RegistryDataEntry, RegistryTags, Tag, clientbound},
+		version::ProtocolVersion,
+	},
+};
+
+#[derive(Debug, Clone)]
+pub struct RegistryEntry {
+	/// Например minecraft:plains
+	pub name: String,
+	/// Данные записи в JSON формате датапака, None если клиент знает ее сам
+	pub data: Option<Value>,
+	/// Пак из которого пришла запись
+	pub pack: KnownPack,
+}
+
+/// Значение в теге
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagValue {
+	/// Запись реестра по имени
+	Entry(String),
+	/// Другой тег того же реестра, в JSON пишется через #
+	Tag(String),
+	/// Айди в протоколе, не зависит от отчета, но ломается при смене версии
+	///
+	/// Встроенные теги хранят имена, числа в них остались только если tags.json
+	/// еще не прогнан через tags_to_names.py
+	Id(i32),
+}
+
+impl TagValue {
+	/// Разбирает значение из JSON тега: строку, число или объект `{"id": ..., "required": ...}`
+	pub fn from_json(value: &Value) -> Option<TagValue> {
+		match value {
+			Value::String(name) => Some(match name.strip_prefix('#') {
+				Some(tag) => TagValue::Tag(tag.to_string()),
+				None => TagValue::Entry(name.to_string()),
+			}),
+			Value::Number(id) => Some(TagValue::Id(id.as_i64()?.try_into().ok()?)),
+			Value::Object(object) => TagValue::from_json(object.get("id")?),
+			_ => None,
+		}
+	}
+}
+
+// Формат отчета registries.json из ванильного генератора данных
+#[derive(Deserialize)]
+struct ReportRegistry {
+	entries: HashMap<String, ReportEntry>,
+}
+
+#[derive(Deserialize)]
+struct ReportEntry {
+	protocol_id: u32,
+}
+
+/// Все синхронизируемые реестры и теги одной версии
+///
+/// Порядок записей в реестре задает их айди в протоколе
+#[derive(Debug, Clone, Default)]
+pub struct Registries {
+	packs: Vec<KnownPack>,
+	registries: BTreeMap<String, Vec<RegistryEntry>>,
+	tags: BTreeMap<String, BTreeMap<String, Vec<TagValue>>>,
+	// Имена записей встроенных реестров по айди, из отчета
+	builtin: BTreeMap<String, HashMap<u32, String>>,
+	// Обратно, айди по имени, чтобы теги не искали каждое имя перебором
+	builtin_ids: BTreeMap<String, HashMap<String, u32>>,
+}
+
+impl Registries {
+	/// Ванильные реестры и теги версии, все они из пака minecraft:core
+	pub fn vanilla(version: ProtocolVersion) -> Result<Registries, ServerError> {
+		let core = KnownPack {
+			namespace: "minecraft".to_string(),
+			id: "core".to_string(),
+			version: version.name().to_string(),
+		};
+
+		match version {
+			ProtocolVersion::V1_21_5 => Registries::from_json(
+				core,
+				include_str!("data/1.21.5/registries.json"),
+				include_str!("data/1.21.5/tags.json"),
+			),
+		}
+	}
+
+	/// registries - `{"реестр": ["запись", ...]}`, tags - `{"реестр": {"тег": [значения]}}`
+	///
+	/// Данных у записей нет, клиент должен знать pack
+	pub fn from_json(
+		pack: KnownPack,
+		registries: &str,
+		tags: &str,
+	) -> Result<Registries, ServerError> {
+		let registries: BTreeMap<String, Vec<String>> =
+			serde_json::from_str(registries).map_err(|e| ServerError::Io(e.into()))?;
+		let tags: BTreeMap<String, BTreeMap<String, Vec<Value>>> =
+			serde_json::from_str(tags).map_err(|e| ServerError::Io(e.into()))?;
+
+		let mut result = Registries::default();
+
+		for (registry, entries) in registries {
+			result.registries.insert(
+				registry,
+				entries
+					.into_iter()
+					.map(|name| RegistryEntry {
+						name,
+						data: None,
+						pack: pack.clone(),
+					})
+					.collect(),
+			);
+		}
+
+		result.add_pack(pack);
+
+		for (registry, tags) in tags {
+			for (tag, values) in tags {
+				result.add_tag(
+					&registry,
+					&tag,
+					values.iter().filter_map(TagValue::from_json).collect(),
+					true,
+				);
+			}
+		}
+
+		Ok(result)
+	}
+
+	/// Загружает имена встроенных реестров из ванильного отчета registries.json
+	///
+	/// Отчет должен быть от той же версии, иначе айди разъедутся
+	pub fn load_report(&mut self, path: &Path) -> Result<(), ServerError> {
+		let content = fs::read_to_string(path)?;
+		let report: HashMap<String, ReportRegistry> =
+			serde_json::from_str(&content).map_err(|e| ServerError::Io(e.into()))?;
+
+		for (registry, entries) in report {
+			self.builtin_ids.insert(
+				registry.clone(),
+				entries
+					.entries
+					.iter()
+					.map(|(name, entry)| (name.clone(), entry.protocol_id))
+					.collect(),
+			);
+			self.builtin.insert(
+				registry,
+				entries
+					.entries
+					.into_iter()
+					.map(|(name, entry)| (entry.protocol_id, name))
+					.collect(),
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Паки в порядке загрузки, их сервер объявляет в Known Packs
+	pub fn packs(&self) -> &[KnownPack] {
+		&self.packs
+	}
+
+	pub fn add_pack(&mut self, pack: KnownPack) {
+		if !self.packs.contains(&pack) {
+			self.packs.push(pack);
+		}
+	}
+
+	/// Имена всех синхронизируемых реестров, например minecraft:worldgen/biome
+	pub fn registry_names(&self) -> Vec<String> {
+		self.registries.keys().cloned().collect()
+	}
+
+	/// Реестры у которых есть теги, сюда входят и не синхронизируемые (блоки, предметы)
+	pub fn tag_registry_names(&self) -> Vec<String> {
+		self.tags.keys().cloned().collect()
+	}
+
+	pub fn registry(&self, registry: &str) -> Option<&[RegistryEntry]> {
+		self.registries.get(registry).map(|o| o.as_slice())
+	}
+
+	/// Имена записей реестра, индекс в списке и есть айди записи
+	pub fn entry_names(&self, registry: &str) -> Vec<String> {
+		self
+			.registry(registry)
+			.unwrap_or_default()
+			.iter()
+			.map(|o| o.name.clone())
+			.collect()
+	}
+
+	/// Айди записи по имени, ищется и в синхронизируемых, и во встроенных реестрах
+	pub fn entry_id(&self, registry: &str, entry: &str) -> Option<usize> {
+		match self.registry(registry) {
+			Some(entries) => entries.iter().position(|o| o.name == entry),
+			None => self
+				.builtin_ids
+				.get(registry)?
+				.get(entry)
+				.map(|id| *id as usize),
+		}
+	}
+
+	/// Имя записи по айди, ищется и в синхронизируемых, и во встроенных реестрах
+	pub fn entry_name(&self, registry: &str, id: u32) -> Option<&str> {
+		match self.registry(registry) {
+			Some(entries) => entries.get(id as usize).map(|o| o.name.as_str()),
+			None => self.builtin.get(registry)?.get(&id).map(|o| o.as_str()),
+		}
+	}
+
+	/// Добавляет запись в конец реестра, а если такая уже есть - заменяет ее данные
+	pub fn add_entry(&mut self, pack: &KnownPack, registry: &str, name: &str, data: Value) {
+		let entries = self.registries.entry(registry.to_string()).or_default();
+
+		match entries.iter_mut().find(|o| o.name == name) {
+			Some(entry) => {
+				entry.data = Some(data);
+				entry.pack = pack.clone();
+			}
+			None => entries.push(RegistryEntry {
+				name: name.to_string(),
+				data: Some(data),
+				pack: pack.clone(),
+			}),
+		}
+	}
+
+	/// Дописывает значения в тег, replace как в JSON тега - заменить старые значения
+	pub fn add_tag(&mut self, registry: &str, tag: &str, values: Vec<TagValue>, replace: bool) {
+		let old = self
+			.tags
+			.entry(registry.to_string())
+			.or_default()
+			.entry(tag.to_string())
+			.or_default();
+
+		if replace {
+			old.clear();
+		}
+
+		for value in values {
+			if !old.contains(&value) {
+				old.push(value);
+			}
+		}
+	}
+
+	/// Пакеты Registry Data, по одному на реестр
+	///
+	/// known - паки которые клиент знает сам, данные их записей не отправляются
+	pub fn registry_data(&self, known: &[KnownPack]) -> Vec<clientbound::RegistryData> {
+		self
+			.registries
+			.iter()
+			.map(|(registry, entries)| clientbound::RegistryData {
+				registry_id: registry.clone(),
+				entries: entries
+					.iter()
+					.map(|o| RegistryDataEntry {
+						entry_id: o.name.clone(),
+						data: o
+							.data
+							.as_ref()
+							.filter(|_| !known.contains(&o.pack))
+							.map(json_to_nbt),
+					})
+					.collect(),
+			})
+			.collect()
+	}
+
+	/// Теги для Update Tags, вложенные теги раскрываются, имена переводятся в айди
+	pub fn update_tags(&self) -> Vec<RegistryTags> {
+		self
+			.tags
+			.iter()
+			.map(|(registry, tags)| RegistryTags {
+				registry: registry.clone(),
+				tags: tags
+					.keys()
+					.map(|tag| Tag {
+						name: tag.clone(),
+						entries: self
+							.resolve_tag(registry, tag, &mut HashSet::new())
+							.into_iter()
+							.map(VarInt)
+							.collect(),
+					})
+					.collect(),
+			})
+			.collect()
+	}
+
+	// visited спасает от тегов которые ссылаются друг на друга
+	fn resolve_tag(&self, registry: &str, tag: &str, visited: &mut HashSet<String>) -> Vec<i32> {
+		if !visited.insert(tag.to_string()) {
+			return Vec::new();
+		}
+
+		let Some(values) = self.tags.get(registry).and_then(|o| o.get(tag)) else {
+			warn!("Тег #{tag} в реестре {registry} не найден");
+			return Vec::new();
+		};
+
+		let mut ids = Vec::new();
+
+		for value in values {
+			let resolved = match value {
+				TagValue::Id(id) => vec![*id],
+				TagValue::Entry(name) => match self.entry_id(registry, name) {
+					Some(id) => vec![id as i32],
+					None => {
+						warn!("Запись {name} из тега #{tag} не найдена в реестре {registry}");
+						Vec::new()
+					}
+				},
+				TagValue::Tag(other) => self.resolve_tag(registry, other, visited),
+			};
+
+			for id in resolved {
+				if !ids.contains(&id) {
+					ids.push(id);
+				}
+			}
+		}
+
+		ids
+	}
+}
+
+/// Переводит JSON из датапака в NBT, как это делает ванилла при загрузке
+///
+/// Целые числа становятся Int (или Long если не влезают), дробные - Double, bool - Byte
+pub fn json_to_nbt(value: &Value) -> DynNBT {
+	match value {
+		Value::Null => DynNBT::Compound(HashMap::new()),
+		Value::Bool(value) => DynNBT::Byte(*value as i8),
+		Value::Number(number) => match number.as_i64() {
+			Some(number) => match i32::try_from(number) {
+				Ok(number) => DynNBT::Int(number),
+				Err(_) => DynNBT::Long(number),
+			},
+			None => DynNBT::Double(number.as_f64().unwrap_or_default()),
+		},
+		Value::String(value) => DynNBT::String(value.clone()),
+		Value::Array(values) => DynNBT::List(values.iter().map(json_to_nbt).collect()),
+		Value::Object(object) => DynNBT::Compound(
+			object
+				.iter()
+				.filter(|(_, o)| !o.is_null())
+				.map(|(k, v)| (k.clone(), json_to_nbt(v)))
+				.collect(),
+		),
+	}
+}
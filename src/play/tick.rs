@@ -0,0 +1,328 @@
+// Главный тик сервера, 20 раз в секунду
+// Двигает время мира, обрабатывает пакеты игроков, выполняет задачи планировщика
+// и в конце рассылает накопившиеся изменения
+
+use std::{
+	collections::{HashMap, HashSet, VecDeque},
+	sync::{Arc, Mutex},
+	thread,
+	time::{Duration, Instant},
+};
+
+use log::{debug, error, warn};
+
+use crate::{
+	ServerError,
+	context::ServerContext,
+	event::player::Location,
+	player::context::ClientContext,
+	protocol::{
+		codec::{Angle, VarInt},
+		packets::play::clientbound,
+	},
+	world::entity::WorldEntity,
+};
+
+use super::{
+	PLAY_PACKETS,
+	entity::{tick_entities, update_tracking},
+	handle_play_packet,
+	health::tick_health,
+	helper::send_head_rotation,
+	playerdata::save_all_players,
+};
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+
+// За сколько последних тиков считаем TPS и MSPT
+const STATS_SAMPLES: usize = 100;
+
+// Если отстали сильнее, то не пытаемся догнать, а пропускаем тики
+const MAX_LAG: Duration = Duration::from_secs(2);
+
+// Как часто синхронизировать время с клиентами
+const TIME_UPDATE_INTERVAL: i64 = 20;
+
+/// Замеры тиков для TPS и MSPT
+#[derive(Default)]
+pub struct TickStats {
+	// Интервал от начала прошлого тика и время выполнения самого тика
+	samples: Mutex<VecDeque<(Duration, Duration)>>,
+}
+
+impl TickStats {
+	pub fn record(&self, interval: Duration, work: Duration) {
+		let mut samples = self.samples.lock().unwrap();
+		if samples.len() >= STATS_SAMPLES {
+			samples.pop_front();
+		}
+		samples.push_back((interval, work));
+	}
+
+	/// Тиков в секунду за последние 5 секунд, не больше 20
+	pub fn tps(&self) -> f64 {
+		let samples = self.samples.lock().unwrap();
+		let total = samples.iter().map(|(o, _)| *o).sum::<Duration>();
+		if samples.is_empty() || total.is_zero() {
+			return TICKS_PER_SECOND as f64;
+		}
+		(samples.len() as f64 / total.as_secs_f64()).min(TICKS_PER_SECOND as f64)
+	}
+
+	/// Сколько миллисекунд в среднем занимает один тик
+	pub fn mspt(&self) -> f64 {
+		let samples = self.samples.lock().unwrap();
+		if samples.is_empty() {
+			return 0.0;
+		}
+		let total = samples.iter().map(|(_, o)| *o).sum::<Duration>();
+		total.as_secs_f64() * 1000.0 / samples.len() as f64
+	}
+}
+
+/// Крутит главный тик пока жив сервер, запускать в отдельном потоке
+pub fn run_tick_loop(server: Arc<ServerContext>) {
+	let mut synced = HashMap::new();
+	let mut next_tick = Instant::now();
+	let mut last_start = Instant::now();
+
+	loop {
+		let start = Instant::now();
+
+		tick(&server, &mut synced);
+
+		server
+			.tick_stats()
+			.record(start.duration_since(last_start), start.elapsed());
+		last_start = start;
+
+		next_tick += TICK_DURATION;
+		let now = Instant::now();
+
+		if next_tick > now {
+			thread::sleep(next_tick - now);
+		} else if now - next_tick > MAX_LAG {
+			warn!(
+				"Сервер не успевает! Пропущено {} тиков",
+				(now - next_tick).as_millis() / TICK_DURATION.as_millis()
+			);
+			next_tick = now;
+		}
+	}
+}
+
+/// Запускает автосохранение раз в world.autosave_interval секунд
+///
+/// Сохраняются игроки и измененные чанки, затем выгружаются чанки, которые не загружены
+/// ни у одного игрока. Работает в пуле воркеров, чтобы запись на диск не тормозила тик
+pub fn schedule_autosave(server: &Arc<ServerContext>) {
+	let period = server.config.world.autosave_interval * TICKS_PER_SECOND as u64;
+	if period == 0 {
+		return;
+	}
+
+	let task_server = server.clone();
+	server
+		.planner()
+		.task(move |_| {
+			let server = &task_server;
+			save_all_players(server);
+			// Ошибки отдельных чанков save уже записал в лог
+			let _ = server.world.save();
+
+			let used: HashSet<_> = server
+				.players()
+				.iter()
+				.filter_map(|o| o.entity_info())
+				.flat_map(|o| o.loaded_chunks())
+				.collect();
+			let unloaded = server.world.unload_unused_chunks(&used);
+			debug!("Автосохранение, выгружено чанков: {unloaded}");
+			Ok(())
+		})
+		.delay(period)
+		.repeat(period)
+		.asynchronous()
+		.schedule();
+}
+
+fn tick(server: &Arc<ServerContext>, synced: &mut HashMap<i32, Location>) {
+	server.world.tick_time();
+
+	for client in server.players() {
+		// ConnectionClosed значит соединение и так закрывается, дальше им займется on_disconnect
+		if let Err(error) = handle_packets(client.clone()).and_then(|_| tick_health(client.clone()))
+			&& !matches!(error, ServerError::ConnectionClosed)
+		{
+			error!("Ошибка обработки пакетов {}: {error:?}", client.addr);
+			client.close();
+		}
+	}
+
+	server.planner().tick();
+
+	if let Err(error) = tick_entities(server) {
+		error!("Ошибка тика сущностей: {error:?}");
+	}
+
+	for client in server.players() {
+		if let Err(error) = update_tracking(client.clone())
+			&& !matches!(error, ServerError::ConnectionClosed)
+		{
+			error!("Ошибка отслеживания сущностей {}: {error:?}", client.addr);
+			client.close();
+		}
+	}
+
+	flush_movement(server, synced);
+
+	if server.world.world_age() % TIME_UPDATE_INTERVAL == 0 {
+		for player in server.players() {
+			let _ = send_time(player);
+		}
+	}
+}
+
+// Обрабатывает все пакеты которые пришли от игрока с прошлого тика
+fn handle_packets(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let packets = client.take_packets(&PLAY_PACKETS);
+
+	// Остальные пакеты игры (Use Item, Client Information, Plugin Message...) мы не
+	// обрабатываем, в буфере они копились бы пока игрок на сервере
+	client.clear_packets();
+
+	for packet in packets {
+		handle_play_packet(client.clone(), packet)?;
+	}
+	Ok(())
+}
+
+/// Отправляет игроку время мира
+pub fn send_time(client: Arc<ClientContext>) -> Result<(), ServerError> {
+	let world = &client.server.world;
+	client.send_packet(&clientbound::UpdateTime {
+		world_age: world.world_age(),
+		time_of_day: world.time_of_day(),
+		time_of_day_increasing: world.daylight_cycle(),
+	})
+}
+
+// Дельта координаты для Update Entity Position, None если не влезает в i16 (8 блоков)
+//
+// Как у ваниллы, обе точки по отдельности округляются до 1/4096 блока. Клиент складывает
+// дельты к округленной прошлой точке, сумма сходится к округленной текущей и ошибка
+// не копится, сколько бы тиков сущность ни двигалась
+fn encode_delta(to: f64, from: f64) -> Option<i16> {
+	i16::try_from((to * 4096.0).round() as i64 - (from * 4096.0).round() as i64).ok()
+}
+
+// Рассылает игрокам кто куда сдвинулся за тик: другим игрокам - игроков,
+// а сущности мира - тем у кого они заспавнены
+fn flush_movement(server: &Arc<ServerContext>, synced: &mut HashMap<i32, Location>) {
+	let players = server.players();
+	let entities = server.entities();
+
+	// Вышедших и убранных забываем
+	synced.retain(|id, _| entities.iter().any(|o| o.id() == *id));
+
+	for entity in entities.iter() {
+		let (x, y, z) = entity.position();
+		let (yaw, pitch) = entity.rotation();
+		let to = Location {
+			x,
+			y,
+			z,
+			yaw,
+			pitch,
+		};
+
+		// Новая сущность уже заспавнена у остальных с текущей позицией
+		let Some(from) = synced.insert(entity.id(), to) else {
+			continue;
+		};
+
+		let moved = (to.x, to.y, to.z) != (from.x, from.y, from.z);
+		let rotated = (to.yaw, to.pitch) != (from.yaw, from.pitch);
+
+		if !moved && !rotated {
+			continue;
+		}
+
+		let entity_id = VarInt(entity.id());
+		let on_ground = entity.on_ground();
+
+		let delta = (
+			encode_delta(to.x, from.x),
+			encode_delta(to.y, from.y),
+			encode_delta(to.z, from.z),
+		);
+		let (delta_x, delta_y, delta_z) = match delta {
+			(Some(x), Some(y), Some(z)) => (x, y, z),
+			_ => (0, 0, 0),
+		};
+		let teleported = !matches!(delta, (Some(_), Some(_), Some(_)));
+
+		for player in players.iter() {
+			let receives = match entity {
+				WorldEntity::Player(client) => client.addr != player.addr,
+				WorldEntity::Entity(entity) => player.entity_info().unwrap().is_tracking(entity.id),
+			};
+			if !receives {
+				continue;
+			}
+
+			let result = if teleported {
+				player.send_packet(&clientbound::TeleportEntity {
+					entity_id,
+					x: to.x,
+					y: to.y,
+					z: to.z,
+					velocity_x: 0.0,
+					velocity_y: 0.0,
+					velocity_z: 0.0,
+					yaw: to.yaw,
+					pitch: to.pitch,
+					on_ground,
+				})
+			} else if moved && rotated {
+				player.send_packet(&clientbound::UpdateEntityPositionAndRotation {
+					entity_id,
+					delta_x,
+					delta_y,
+					delta_z,
+					yaw: Angle(to.yaw),
+					pitch: Angle(to.pitch),
+					on_ground,
+				})
+			} else if moved {
+				player.send_packet(&clientbound::UpdateEntityPosition {
+					entity_id,
+					delta_x,
+					delta_y,
+					delta_z,
+					on_ground,
+				})
+			} else {
+				player.send_packet(&clientbound::UpdateEntityRotation {
+					entity_id,
+					yaw: Angle(to.yaw),
+					pitch: Angle(to.pitch),
+					on_ground,
+				})
+			};
+
+			let result = result.and_then(|_| {
+				if rotated {
+					send_head_rotation(player.clone(), entity_id.0, to.yaw)
+				} else {
+					Ok(())
+				}
+			});
+
+			if result.is_err() {
+				player.close();
+			}
+		}
+	}
+}
@@ -0,0 +1,141 @@
+// Режимы игры и способности игрока из пакета Player Abilities
+//
+// Способности выводятся из режима игры так же как в ванилле (GameType.updatePlayerAbilities),
+// сервер проверяет по ним что игроку можно, а не по самому режиму
+
+use craftflow_nbt::DynNBT;
+
+pub const SURVIVAL: u8 = 0;
+pub const CREATIVE: u8 = 1;
+pub const ADVENTURE: u8 = 2;
+pub const SPECTATOR: u8 = 3;
+
+// Флаги пакета Player Abilities
+pub const INVULNERABLE_FLAG: u8 = 0x01;
+pub const FLYING_FLAG: u8 = 0x02;
+pub const MAY_FLY_FLAG: u8 = 0x04;
+pub const INSTABUILD_FLAG: u8 = 0x08;
+
+pub const DEFAULT_FLY_SPEED: f32 = 0.05;
+pub const DEFAULT_WALK_SPEED: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Abilities {
+	/// Не получает урон
+	pub invulnerable: bool,
+	pub flying: bool,
+	pub may_fly: bool,
+	/// Ломает блоки сразу и не тратит предметы
+	pub instabuild: bool,
+	/// Может ставить и ломать блоки
+	pub may_build: bool,
+	pub fly_speed: f32,
+	pub walk_speed: f32,
+}
+
+impl Default for Abilities {
+	fn default() -> Self {
+		Abilities::for_game_mode(SURVIVAL)
+	}
+}
+
+impl Abilities {
+	pub fn for_game_mode(game_mode: u8) -> Abilities {
+		let mut abilities = Abilities {
+			invulnerable: false,
+			flying: false,
+			may_fly: false,
+			instabuild: false,
+			may_build: true,
+			fly_speed: DEFAULT_FLY_SPEED,
+			walk_speed: DEFAULT_WALK_SPEED,
+		};
+		abilities.update_for_game_mode(game_mode);
+		abilities
+	}
+
+	/// Меняет флаги под режим игры, скорости остаются
+	///
+	/// В креативе полет не сбрасывается, наблюдатель летает всегда
+	pub fn update_for_game_mode(&mut self, game_mode: u8) {
+		match game_mode {
+			CREATIVE => {
+				self.may_fly = true;
+				self.instabuild = true;
+				self.invulnerable = true;
+			}
+			SPECTATOR => {
+				self.may_fly = true;
+				self.instabuild = false;
+				self.invulnerable = true;
+				self.flying = true;
+			}
+			_ => {
+				self.may_fly = false;
+				self.instabuild = false;
+				self.invulnerable = false;
+				self.flying = false;
+			}
+		}
+
+		self.may_build = game_mode != ADVENTURE && game_mode != SPECTATOR;
+	}
+
+	/// Флаги для пакета Player Abilities
+	pub fn flags(&self) -> u8 {
+		let mut flags = 0;
+		if self.invulnerable {
+			flags |= INVULNERABLE_FLAG;
+		}
+		if self.flying {
+			flags |= FLYING_FLAG;
+		}
+		if self.may_fly {
+			flags |= MAY_FLY_FLAG;
+		}
+		if self.instabuild {
+			flags |= INSTABUILD_FLAG;
+		}
+		flags
+	}
+
+	/// Компаунд abilities из файла игрока
+	pub fn to_nbt(&self) -> DynNBT {
+		DynNBT::Compound(
+			[
+				("invulnerable", DynNBT::Byte(self.invulnerable as i8)),
+				("flying", DynNBT::Byte(self.flying as i8)),
+				("mayfly", DynNBT::Byte(self.may_fly as i8)),
+				("instabuild", DynNBT::Byte(self.instabuild as i8)),
+				("mayBuild", DynNBT::Byte(self.may_build as i8)),
+				("flySpeed", DynNBT::Float(self.fly_speed)),
+				("walkSpeed", DynNBT::Float(self.walk_speed)),
+			]
+			.into_iter()
+			.map(|(name, value)| (name.to_string(), value))
+			.collect(),
+		)
+	}
+
+	/// Читает компаунд abilities, флаги все равно берутся из режима игры,
+	/// из файла только полет и скорости
+	pub fn from_nbt(nbt: Option<&DynNBT>, game_mode: u8) -> Abilities {
+		let mut abilities = Abilities::for_game_mode(game_mode);
+
+		let Some(nbt) = nbt.and_then(|o| o.as_compound()) else {
+			return abilities;
+		};
+
+		if let Some(flying) = nbt.get("flying").and_then(|o| o.as_byte()) {
+			abilities.flying = abilities.may_fly && (flying != 0 || game_mode == SPECTATOR);
+		}
+		if let Some(speed) = nbt.get("flySpeed").and_then(|o| o.as_float()) {
+			abilities.fly_speed = speed;
+		}
+		if let Some(speed) = nbt.get("walkSpeed").and_then(|o| o.as_float()) {
+			abilities.walk_speed = speed;
+		}
+
+		abilities
+	}
+}
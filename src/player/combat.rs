@@ -0,0 +1,113 @@
+// Расчеты ближнего боя как в ванилле (Player.attack)
+//
+// Атрибутов у предметов мы не читаем, урон и скорость атаки оружия взяты из ванильных значений
+// Пакеты и события в play::combat
+
+/// Урон кулаком
+pub const BASE_ATTACK_DAMAGE: f32 = 1.0;
+/// Ударов в секунду кулаком
+pub const BASE_ATTACK_SPEED: f32 = 4.0;
+
+/// Дальность удара от глаз до хитбокса, ванильные 3 блока плюс запас на задержку
+pub const ATTACK_REACH: f64 = 6.0;
+
+pub const CRITICAL_MULTIPLIER: f32 = 1.5;
+pub const ATTACK_EXHAUSTION: f32 = 0.1;
+
+// Отбрасывание от любого удара и дополнительное от удара на бегу
+pub const BASE_KNOCKBACK: f64 = 0.4;
+pub const SPRINT_KNOCKBACK: f64 = 0.5;
+
+// Хитбокс игрока
+pub const PLAYER_WIDTH: f64 = 0.6;
+pub const PLAYER_HEIGHT: f64 = 1.8;
+/// Высота глаз стоящего игрока
+pub const EYE_HEIGHT: f64 = 1.62;
+
+// Материалы инструментов, урон всегда с учетом базового
+const MATERIALS: [&str; 6] = ["wooden", "stone", "iron", "golden", "diamond", "netherite"];
+const SWORD_DAMAGE: [f32; 6] = [4.0, 5.0, 6.0, 4.0, 7.0, 8.0];
+const AXE_DAMAGE: [f32; 6] = [7.0, 9.0, 9.0, 7.0, 9.0, 10.0];
+const AXE_SPEED: [f32; 6] = [0.8, 0.8, 0.9, 1.0, 1.0, 1.0];
+const PICKAXE_DAMAGE: [f32; 6] = [2.0, 3.0, 4.0, 2.0, 5.0, 6.0];
+const SHOVEL_DAMAGE: [f32; 6] = [2.5, 3.5, 4.5, 2.5, 5.5, 6.5];
+const HOE_SPEED: [f32; 6] = [1.0, 2.0, 3.0, 1.0, 4.0, 4.0];
+
+/// Урон и скорость атаки предмета в руке, None - пустая рука
+pub fn weapon_stats(item: Option<&str>) -> (f32, f32) {
+	let Some(name) = item.map(|o| o.strip_prefix("minecraft:").unwrap_or(o)) else {
+		return (BASE_ATTACK_DAMAGE, BASE_ATTACK_SPEED);
+	};
+
+	match name {
+		"trident" => return (9.0, 1.1),
+		"mace" => return (6.0, 0.6),
+		_ => {}
+	}
+
+	let Some((material, tool)) = name.split_once('_') else {
+		return (BASE_ATTACK_DAMAGE, BASE_ATTACK_SPEED);
+	};
+	let Some(index) = MATERIALS.iter().position(|o| *o == material) else {
+		return (BASE_ATTACK_DAMAGE, BASE_ATTACK_SPEED);
+	};
+
+	match tool {
+		"sword" => (SWORD_DAMAGE[index], 1.6),
+		"axe" => (AXE_DAMAGE[index], AXE_SPEED[index]),
+		"pickaxe" => (PICKAXE_DAMAGE[index], 1.2),
+		"shovel" => (SHOVEL_DAMAGE[index], 1.0),
+		"hoe" => (BASE_ATTACK_DAMAGE, HOE_SPEED[index]),
+		_ => (BASE_ATTACK_DAMAGE, BASE_ATTACK_SPEED),
+	}
+}
+
+/// Насколько заряжен удар от 0 до 1, ticks - сколько тиков прошло с прошлого удара
+pub fn attack_strength(ticks: u64, attack_speed: f32) -> f32 {
+	let delay = 20.0 / attack_speed;
+	((ticks as f32 + 0.5) / delay).clamp(0.0, 1.0)
+}
+
+/// Урон удара с учетом заряда, незаряженный удар наносит 20% урона
+pub fn scaled_damage(damage: f32, strength: f32) -> f32 {
+	damage * (0.2 + strength * strength * 0.8)
+}
+
+/// Скорость после отбрасывания в сторону от (x, z), как LivingEntity.knockback
+pub fn knockback(
+	velocity: (f64, f64, f64),
+	strength: f64,
+	x: f64,
+	z: f64,
+	on_ground: bool,
+) -> (f64, f64, f64) {
+	let length = x.hypot(z);
+	if strength <= 0.0 || length < 1.0e-4 {
+		return velocity;
+	}
+
+	let (dx, dz) = (x / length * strength, z / length * strength);
+	let (vx, vy, vz) = velocity;
+
+	(
+		vx / 2.0 - dx,
+		if on_ground {
+			(vy / 2.0 + strength).min(0.4)
+		} else {
+			vy
+		},
+		vz / 2.0 - dz,
+	)
+}
+
+/// Расстояние от точки до хитбокса игрока который стоит в (x, y, z)
+pub fn distance_to_player(point: (f64, f64, f64), position: (f64, f64, f64)) -> f64 {
+	let (x, y, z) = position;
+	let half = PLAYER_WIDTH / 2.0;
+
+	let dx = point.0 - point.0.clamp(x - half, x + half);
+	let dy = point.1 - point.1.clamp(y, y + PLAYER_HEIGHT);
+	let dz = point.2 - point.2.clamp(z - half, z + half);
+
+	(dx * dx + dy * dy + dz * dz).sqrt()
+}
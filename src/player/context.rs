@@ -1,34 +1,58 @@
 use std::{
-	collections::VecDeque,
+	collections::{HashSet, VecDeque},
 	hash::Hash,
-	net::{SocketAddr, TcpStream},
+	io::ErrorKind,
+	net::{IpAddr, SocketAddr},
 	sync::{
-		Arc, Mutex, RwLock,
-		atomic::{AtomicBool, Ordering},
+		Arc, Mutex, RwLock, RwLockWriteGuard,
+		mpsc::{Receiver, RecvTimeoutError, Sender},
 	},
-	thread,
-	time::Duration,
+	time::{Duration, Instant},
 };
 
-use rust_mc_proto::{MinecraftConnection, Packet};
+use log::warn;
+use rust_mc_proto::Packet;
 use uuid::Uuid;
 
-use super::helper::ProtocolHelper;
-use crate::{ServerError, context::ServerContext, protocol::ConnectionState};
+use super::{
+	abilities::Abilities,
+	health::HealthData,
+	helper::ProtocolHelper,
+	inventory::{HOTBAR_START, OFFHAND_SLOT, Windows},
+};
+use crate::{
+	ServerError,
+	context::ServerContext,
+	data::{Position, Property, slot::Slot},
+	protocol::{
+		ConnectionState,
+		codec::PacketData,
+		connection::{Connection, NON_ESSENTIAL_PACKETS, PacketReader},
+		version::ProtocolVersion,
+	},
+};
+
+// Сколько пакетов может лежать в буфере, за один тик столько не присылает даже ванилла
+const MAX_BUFFERED_PACKETS: usize = 4096;
 
 // Клиент контекст
 // Должен быть обернут в Arc для передачи между потоками
 pub struct ClientContext {
 	pub server: Arc<ServerContext>,
 	pub addr: SocketAddr,
-	conn: RwLock<MinecraftConnection<TcpStream>>,
+	conn: Connection,
+	remote_ip: RwLock<Option<IpAddr>>,
 	handshake: RwLock<Option<Handshake>>,
+	protocol_version: RwLock<ProtocolVersion>,
 	client_info: RwLock<Option<ClientInfo>>,
 	player_info: RwLock<Option<PlayerInfo>>,
 	state: RwLock<ConnectionState>,
 	packet_buffer: Mutex<VecDeque<Packet>>,
-	read_loop: AtomicBool,
-	is_alive: AtomicBool,
+	inbound: Mutex<Receiver<Packet>>,
+	// Айди пакетов которые сейчас ждут в read_packet, по одному на каждого ждущего
+	waiting: Mutex<Vec<u8>>,
+	// Айди последнего Keep Alive без ответа и когда он отправлен
+	keep_alive: Mutex<Option<(i64, Instant)>>,
 	entity_info: RwLock<Option<Arc<PlayerEntityInfo>>>,
 }
 
@@ -49,26 +73,54 @@ impl Hash for ClientContext {
 impl Eq for ClientContext {}
 
 impl ClientContext {
-	pub fn new(server: Arc<ServerContext>, conn: MinecraftConnection<TcpStream>) -> ClientContext {
+	/// Пакеты из задачи чтения приходят в inbound, см. run_read_loop
+	pub fn new(
+		server: Arc<ServerContext>,
+		conn: Connection,
+		inbound: Receiver<Packet>,
+	) -> ClientContext {
 		ClientContext {
 			server,
-			addr: conn.get_ref().peer_addr().unwrap(),
-			conn: RwLock::new(conn),
+			addr: conn.addr(),
+			conn,
+			remote_ip: RwLock::new(None),
 			handshake: RwLock::new(None),
+			protocol_version: RwLock::new(ProtocolVersion::LATEST),
 			client_info: RwLock::new(None),
 			player_info: RwLock::new(None),
 			state: RwLock::new(ConnectionState::Handshake),
 			packet_buffer: Mutex::new(VecDeque::new()),
-			read_loop: AtomicBool::new(false),
-			is_alive: AtomicBool::new(true),
+			inbound: Mutex::new(inbound),
+			waiting: Mutex::new(Vec::new()),
+			keep_alive: Mutex::new(None),
 			entity_info: RwLock::new(None),
 		}
 	}
 
+	/// Настоящий айпи игрока, за прокси addr это адрес самого прокси
+	pub fn remote_ip(self: &Arc<Self>) -> IpAddr {
+		self.remote_ip.read().unwrap().unwrap_or(self.addr.ip())
+	}
+
+	pub fn set_remote_ip(self: &Arc<Self>, ip: IpAddr) {
+		*self.remote_ip.write().unwrap() = Some(ip);
+	}
+
 	pub fn set_handshake(self: &Arc<Self>, handshake: Handshake) {
 		*self.handshake.write().unwrap() = Some(handshake);
 	}
 
+	/// Версия протокола клиента, по ней переводятся айди пакетов
+	///
+	/// До рукопожатия и для неподдерживаемых версий это ProtocolVersion::LATEST
+	pub fn protocol_version(self: &Arc<Self>) -> ProtocolVersion {
+		*self.protocol_version.read().unwrap()
+	}
+
+	pub fn set_protocol_version(self: &Arc<Self>, version: ProtocolVersion) {
+		*self.protocol_version.write().unwrap() = version;
+	}
+
 	pub fn set_client_info(self: &Arc<Self>, client_info: ClientInfo) {
 		*self.client_info.write().unwrap() = Some(client_info);
 	}
@@ -128,32 +180,42 @@ impl ClientContext {
 			packet.get_mut().set_position(0);
 		}
 		if !cancelled {
-			match self.conn.write().unwrap().write_packet(&packet) {
-				Ok(_) => {}
-				Err(e) => {
-					self.is_alive.store(false, Ordering::SeqCst);
-					return Err(e.into());
-				}
-			};
+			let essential =
+				state != ConnectionState::Play || !NON_ESSENTIAL_PACKETS.contains(&packet.id());
+
+			self.conn.write_packet(&packet, essential)?;
 		}
 		Ok(())
 	}
 
-	pub fn run_read_loop(self: &Arc<Self>) -> Result<(), ServerError> {
-		self.read_loop.store(true, Ordering::SeqCst);
+	/// Отправляет типизированный пакет из protocol::packets
+	pub fn send_packet<P: PacketData>(self: &Arc<Self>, packet: &P) -> Result<(), ServerError> {
+		self.write_packet(&packet.to_packet()?)
+	}
 
-		let mut conn = self.conn.read().unwrap().try_clone()?; // так можно делать т.к сокет это просто поинтер
+	/// Читает типизированный пакет из protocol::packets
+	pub fn receive_packet<P: PacketData>(self: &Arc<Self>) -> Result<P, ServerError> {
+		P::decode(&mut self.read_packet(&[P::ID])?)
+	}
+
+	/// Задача чтения клиента, читает пакеты пока соединение живо и отдает их в канал
+	pub async fn run_read_loop(
+		self: Arc<Self>,
+		mut reader: PacketReader,
+		inbound: Sender<Packet>,
+	) -> Result<(), ServerError> {
+		let timeout = Duration::from_secs(self.server.config.bind.timeout);
 
 		while self.is_alive() {
-			let mut packet = match conn.read_packet() {
-				Ok(v) => v,
-				Err(e) => {
-					self.is_alive.store(false, Ordering::SeqCst);
-					return Err(e.into());
-				}
-			};
-			let mut cancelled = false;
 			let state = self.state();
+
+			// Ванилла в игре шлет пакеты каждый тик, молчит - значит отвалился
+			// В остальных режимах клиент может ждать сервер, там таймаут у read_packet
+			let read_timeout = (state == ConnectionState::Play).then_some(timeout);
+
+			let mut packet = self.conn.read_packet(&mut reader, read_timeout).await?;
+
+			let mut cancelled = false;
 			for handler in self
 				.server
 				.packet_handlers(|o| o.on_incoming_packet_priority())
@@ -162,78 +224,156 @@ impl ClientContext {
 				handler.on_incoming_packet(self.clone(), &mut packet, &mut cancelled, state.clone())?;
 				packet.get_mut().set_position(0);
 			}
-			if !cancelled {
-				self.packet_buffer.lock().unwrap().push_back(packet);
+			if !cancelled && inbound.send(packet).is_err() {
+				break;
 			}
 		}
 
 		Ok(())
 	}
 
-	/// Please avoid using of this bullshit
-	pub fn read_any_packet(self: &Arc<Self>) -> Result<Packet, ServerError> {
-		if self.read_loop.load(Ordering::SeqCst) {
-			loop {
-				if let Some(packet) = self.packet_buffer.lock().unwrap().pop_front() {
-					return Ok(packet);
-				}
-				thread::sleep(Duration::from_millis(4));
+	// Перекладывает все что пришло из канала в буфер
+	// Если канал сейчас слушает read_packet, он сам отложит чужие пакеты в буфер
+	fn drain_inbound(self: &Arc<Self>, buffer: &mut VecDeque<Packet>) {
+		let Ok(inbound) = self.inbound.try_lock() else {
+			return;
+		};
+		while let Ok(packet) = inbound.try_recv() {
+			if !self.buffer_packet(buffer, packet) {
+				break;
 			}
-		} else {
-			let state = self.state();
+		}
+	}
 
-			loop {
-				let mut packet = match self.conn.write().unwrap().read_packet() {
-					Ok(v) => v,
-					Err(e) => {
-						self.is_alive.store(false, Ordering::SeqCst);
-						return Err(e.into());
-					}
-				};
-				let mut cancelled = false;
-				for handler in self
-					.server
-					.packet_handlers(|o| o.on_incoming_packet_priority())
-					.iter()
-				{
-					handler.on_incoming_packet(self.clone(), &mut packet, &mut cancelled, state.clone())?;
-					packet.get_mut().set_position(0);
-				}
-				if !cancelled {
-					break Ok(packet);
-				}
+	// Откладывает пакет в буфер, если клиент завалил его пакетами - отключает
+	fn buffer_packet(self: &Arc<Self>, buffer: &mut VecDeque<Packet>, packet: Packet) -> bool {
+		if buffer.len() >= MAX_BUFFERED_PACKETS {
+			warn!(
+				"{} прислал больше {MAX_BUFFERED_PACKETS} необработанных пакетов, отключаем",
+				self.addr
+			);
+			buffer.clear();
+			self.close();
+			return false;
+		}
+		buffer.push_back(packet);
+		true
+	}
+
+	/// Ждет следующий пакет, блокирует поток, поэтому не вызывать из задач tokio
+	pub fn read_any_packet(self: &Arc<Self>) -> Result<Packet, ServerError> {
+		if let Some(packet) = self.packet_buffer.lock().unwrap().pop_front() {
+			return Ok(packet);
+		}
+
+		let timeout = Duration::from_secs(self.server.config.bind.timeout);
+
+		match self.inbound.lock().unwrap().recv_timeout(timeout) {
+			Ok(packet) => Ok(packet),
+			Err(RecvTimeoutError::Timeout) => {
+				self.close();
+				Err(ServerError::Io(ErrorKind::TimedOut.into()))
 			}
+			Err(RecvTimeoutError::Disconnected) => Err(ServerError::ConnectionClosed),
 		}
 	}
 
+	/// Ждет пакет с одним из айди
+	///
+	/// В игре остальные пакеты откладываются в буфер для главного тика,
+	/// в остальных режимах чужой пакет - ошибка
 	pub fn read_packet(self: &Arc<Self>, ids: &[u8]) -> Result<Packet, ServerError> {
-		if self.read_loop.load(Ordering::SeqCst) {
-			loop {
-				{
-					let mut locked = self.packet_buffer.lock().unwrap();
-					for (i, packet) in locked.clone().iter().enumerate() {
-						if ids.contains(&packet.id()) {
-							locked.remove(i);
-							return Ok(packet.clone());
-						}
-					}
+		// Пока ждем, главный тик не выкидывает эти пакеты из буфера
+		self.waiting.lock().unwrap().extend_from_slice(ids);
+		let result = self.wait_packet(ids);
+
+		let mut waiting = self.waiting.lock().unwrap();
+		for id in ids {
+			if let Some(index) = waiting.iter().position(|o| o == id) {
+				waiting.remove(index);
+			}
+		}
+
+		result
+	}
+
+	// Новые пакеты берутся только из канала, так что чужой пакет из буфера не крутится
+	// по кругу. Ждем не дольше таймаута, сколько бы чужих пакетов ни пришло
+	fn wait_packet(self: &Arc<Self>, ids: &[u8]) -> Result<Packet, ServerError> {
+		let deadline = Instant::now() + Duration::from_secs(self.server.config.bind.timeout);
+
+		loop {
+			let inbound = self.inbound.lock().unwrap();
+
+			// Пока мы ждали канал, другой ждущий мог отложить наш пакет в буфер
+			{
+				let mut buffer = self.packet_buffer.lock().unwrap();
+				if let Some(index) = buffer.iter().position(|o| ids.contains(&o.id())) {
+					return Ok(buffer.remove(index).unwrap());
 				}
-				thread::sleep(Duration::from_millis(4));
 			}
-		} else {
-			let packet = match self.read_any_packet() {
-				Ok(v) => v,
-				Err(e) => {
-					self.is_alive.store(false, Ordering::SeqCst);
-					return Err(e);
+
+			let packet = match inbound.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+				Ok(packet) => packet,
+				Err(RecvTimeoutError::Timeout) => {
+					self.close();
+					return Err(ServerError::Io(ErrorKind::TimedOut.into()));
 				}
+				Err(RecvTimeoutError::Disconnected) => return Err(ServerError::ConnectionClosed),
 			};
 
 			if ids.contains(&packet.id()) {
-				Ok(packet)
+				return Ok(packet);
+			} else if self.state() == ConnectionState::Play {
+				// Главный тик заберет его из буфера
+				if !self.buffer_packet(&mut self.packet_buffer.lock().unwrap(), packet) {
+					return Err(ServerError::ConnectionClosed);
+				}
 			} else {
-				Err(ServerError::UnexpectedPacket(packet.id()))
+				return Err(ServerError::UnexpectedPacket(packet.id()));
+			}
+		}
+	}
+
+	/// Забирает все пришедшие пакеты с такими айди, в порядке получения
+	pub fn take_packets(self: &Arc<Self>, ids: &[u8]) -> Vec<Packet> {
+		let mut buffer = self.packet_buffer.lock().unwrap();
+		self.drain_inbound(&mut buffer);
+		let (taken, rest): (VecDeque<Packet>, VecDeque<Packet>) =
+			buffer.drain(..).partition(|o| ids.contains(&o.id()));
+		*buffer = rest;
+		taken.into()
+	}
+
+	/// Выкидывает отложенные пакеты которые никто не ждет в read_packet,
+	/// главный тик так избавляется от необработанных
+	pub fn clear_packets(self: &Arc<Self>) {
+		let waiting = self.waiting.lock().unwrap();
+		self
+			.packet_buffer
+			.lock()
+			.unwrap()
+			.retain(|o| waiting.contains(&o.id()));
+	}
+
+	/// Keep Alive который клиент еще не вернул, айди и когда отправлен
+	pub fn pending_keep_alive(self: &Arc<Self>) -> Option<(i64, Instant)> {
+		*self.keep_alive.lock().unwrap()
+	}
+
+	pub fn set_pending_keep_alive(self: &Arc<Self>, id: i64) {
+		*self.keep_alive.lock().unwrap() = Some((id, Instant::now()));
+	}
+
+	/// Ответ на Keep Alive, false если такой мы не отправляли
+	pub fn confirm_keep_alive(self: &Arc<Self>, id: i64) -> bool {
+		let mut keep_alive = self.keep_alive.lock().unwrap();
+		match *keep_alive {
+			Some((pending, _)) if pending == id => {
+				*keep_alive = None;
+				true
 			}
+			_ => false,
 		}
 	}
 
@@ -241,16 +381,31 @@ impl ClientContext {
 		self.packet_buffer.lock().unwrap().push_back(packet)
 	}
 
+	/// Закрывает соединение, уже отправленные пакеты еще дойдут до клиента
 	pub fn close(self: &Arc<Self>) {
-		self.conn.write().unwrap().close();
+		self.conn.close();
+	}
+
+	/// Сколько пакетов ждет отправки клиенту
+	pub fn queued_packets(self: &Arc<Self>) -> usize {
+		self.conn.queued()
 	}
 
 	pub fn set_compression(self: &Arc<Self>, threshold: Option<usize>) {
-		self.conn.write().unwrap().set_compression(threshold);
+		self.conn.set_compression(threshold);
+	}
+
+	/// Включает шифрование AES/CFB8 на соединении
+	pub fn set_encryption(self: &Arc<Self>, shared_secret: &[u8]) -> Result<(), ServerError> {
+		self.conn.set_encryption(shared_secret)
+	}
+
+	pub fn is_encrypted(self: &Arc<Self>) -> bool {
+		self.conn.is_encrypted()
 	}
 
 	pub fn is_alive(self: &Arc<Self>) -> bool {
-		self.is_alive.load(Ordering::SeqCst)
+		self.conn.is_alive()
 	}
 
 	pub fn protocol_helper(self: &Arc<Self>) -> ProtocolHelper {
@@ -283,6 +438,7 @@ pub struct ClientInfo {
 pub struct PlayerInfo {
 	pub name: String,
 	pub uuid: Uuid,
+	pub properties: Vec<Property>, // Свойства профиля, например скин (textures)
 }
 
 pub struct PlayerEntityInfo {
@@ -291,6 +447,18 @@ pub struct PlayerEntityInfo {
 	position: RwLock<(f64, f64, f64)>,
 	velocity: RwLock<(f64, f64, f64)>,
 	rotation: RwLock<(f32, f32)>,
+	on_ground: RwLock<bool>,
+	game_mode: RwLock<u8>,
+	abilities: RwLock<Abilities>,
+	health: RwLock<HealthData>,
+	sprinting: RwLock<bool>,
+	last_attack_tick: RwLock<u64>,
+	last_death_location: RwLock<Option<Position>>,
+	permission_level: RwLock<u8>,
+	held_slot: RwLock<u8>,
+	windows: RwLock<Windows>,
+	loaded_chunks: RwLock<HashSet<(i32, i32)>>,
+	tracked_entities: RwLock<HashSet<i32>>,
 }
 
 impl PlayerEntityInfo {
@@ -301,9 +469,157 @@ impl PlayerEntityInfo {
 			position: RwLock::new((0.0, 0.0, 0.0)),
 			velocity: RwLock::new((0.0, 0.0, 0.0)),
 			rotation: RwLock::new((0.0, 0.0)),
+			on_ground: RwLock::new(false),
+			game_mode: RwLock::new(0),
+			abilities: RwLock::new(Abilities::default()),
+			health: RwLock::new(HealthData::default()),
+			sprinting: RwLock::new(false),
+			last_attack_tick: RwLock::new(0),
+			last_death_location: RwLock::new(None),
+			permission_level: RwLock::new(0),
+			held_slot: RwLock::new(0),
+			windows: RwLock::new(Windows::default()),
+			loaded_chunks: RwLock::new(HashSet::new()),
+			tracked_entities: RwLock::new(HashSet::new()),
+		}
+	}
+
+	/// 0 - выживание, 1 - креатив, 2 - приключение, 3 - наблюдатель
+	pub fn game_mode(self: &Arc<Self>) -> u8 {
+		*self.game_mode.read().unwrap()
+	}
+
+	/// Меняет режим и способности под него, клиенту ничего не отправляется, см. play::gamemode
+	pub fn set_game_mode(self: &Arc<Self>, game_mode: u8) {
+		*self.game_mode.write().unwrap() = game_mode;
+		self
+			.abilities
+			.write()
+			.unwrap()
+			.update_for_game_mode(game_mode);
+	}
+
+	pub fn abilities(self: &Arc<Self>) -> Abilities {
+		*self.abilities.read().unwrap()
+	}
+
+	pub fn set_abilities(self: &Arc<Self>, abilities: Abilities) {
+		*self.abilities.write().unwrap() = abilities;
+	}
+
+	/// Здоровье от 0 до 20
+	pub fn health(self: &Arc<Self>) -> f32 {
+		self.health.read().unwrap().health
+	}
+
+	/// Здоровье, голод и падение
+	///
+	/// После изменений клиенту надо отправить Set Health, см. play::health
+	pub fn health_data(self: &Arc<Self>) -> RwLockWriteGuard<'_, HealthData> {
+		self.health.write().unwrap()
+	}
+
+	pub fn is_dead(self: &Arc<Self>) -> bool {
+		self.health.read().unwrap().is_dead()
+	}
+
+	pub fn sprinting(self: &Arc<Self>) -> bool {
+		*self.sprinting.read().unwrap()
+	}
+
+	pub fn set_sprinting(self: &Arc<Self>, sprinting: bool) {
+		*self.sprinting.write().unwrap() = sprinting;
+	}
+
+	/// Тик планировщика когда игрок последний раз ударил или сменил предмет в руке,
+	/// от него считается заряд удара
+	pub fn last_attack_tick(self: &Arc<Self>) -> u64 {
+		*self.last_attack_tick.read().unwrap()
+	}
+
+	pub fn set_last_attack_tick(self: &Arc<Self>, tick: u64) {
+		*self.last_attack_tick.write().unwrap() = tick;
+	}
+
+	/// Где игрок умер в последний раз, для компаса восстановления
+	pub fn last_death_location(self: &Arc<Self>) -> Option<Position> {
+		*self.last_death_location.read().unwrap()
+	}
+
+	pub fn set_last_death_location(self: &Arc<Self>, location: Option<Position>) {
+		*self.last_death_location.write().unwrap() = location;
+	}
+
+	/// Уровень прав от 0 до 4, как у опки в ванилле
+	pub fn permission_level(self: &Arc<Self>) -> u8 {
+		*self.permission_level.read().unwrap()
+	}
+
+	pub fn set_permission_level(self: &Arc<Self>, level: u8) {
+		*self.permission_level.write().unwrap() = level;
+	}
+
+	/// Выбранный слот хотбара от 0 до 8
+	pub fn held_slot(self: &Arc<Self>) -> u8 {
+		*self.held_slot.read().unwrap()
+	}
+
+	pub fn set_held_slot(self: &Arc<Self>, slot: u8) {
+		*self.held_slot.write().unwrap() = slot;
+	}
+
+	/// Инвентарь, курсор и открытое окно
+	///
+	/// После изменений клиенту надо отправить слоты, см. play::inventory
+	pub fn windows(self: &Arc<Self>) -> RwLockWriteGuard<'_, Windows> {
+		self.windows.write().unwrap()
+	}
+
+	/// Предмет в слоте окна инвентаря
+	pub fn inventory_item(self: &Arc<Self>, slot: usize) -> Option<Slot> {
+		self.windows.read().unwrap().inventory.get(slot).cloned()
+	}
+
+	/// Предмет в руке, 0 - основная, 1 - вторая
+	pub fn hand_item(self: &Arc<Self>, hand: i32) -> Option<Slot> {
+		if hand == 1 {
+			self.inventory_item(OFFHAND_SLOT)
+		} else {
+			self.inventory_item(HOTBAR_START + self.held_slot() as usize)
 		}
 	}
 
+	/// Чанки которые сейчас загружены у клиента
+	pub fn loaded_chunks(self: &Arc<Self>) -> HashSet<(i32, i32)> {
+		self.loaded_chunks.read().unwrap().clone()
+	}
+
+	pub fn set_loaded_chunks(self: &Arc<Self>, chunks: HashSet<(i32, i32)>) {
+		*self.loaded_chunks.write().unwrap() = chunks;
+	}
+
+	pub fn is_chunk_loaded(self: &Arc<Self>, x: i32, z: i32) -> bool {
+		self.loaded_chunks.read().unwrap().contains(&(x, z))
+	}
+
+	/// Сущности мира которые заспавнены у клиента, игроки сюда не входят
+	pub fn tracked_entities(self: &Arc<Self>) -> HashSet<i32> {
+		self.tracked_entities.read().unwrap().clone()
+	}
+
+	pub fn set_tracked_entities(self: &Arc<Self>, entities: HashSet<i32>) {
+		*self.tracked_entities.write().unwrap() = entities;
+	}
+
+	pub fn is_tracking(self: &Arc<Self>, entity_id: i32) -> bool {
+		self.tracked_entities.read().unwrap().contains(&entity_id)
+	}
+
+	/// Забывает сущность, возвращает true если она была у клиента
+	pub fn untrack(self: &Arc<Self>, entity_id: i32) -> bool {
+		self.tracked_entities.write().unwrap().remove(&entity_id)
+	}
+
 	pub fn set_position(self: &Arc<Self>, position: (f64, f64, f64)) {
 		*self.position.write().unwrap() = position;
 	}
@@ -316,15 +632,23 @@ impl PlayerEntityInfo {
 		*self.rotation.write().unwrap() = rotation;
 	}
 
+	pub fn set_on_ground(self: &Arc<Self>, on_ground: bool) {
+		*self.on_ground.write().unwrap() = on_ground;
+	}
+
 	pub fn position(self: &Arc<Self>) -> (f64, f64, f64) {
-		self.position.read().unwrap().clone()
+		*self.position.read().unwrap()
 	}
 
 	pub fn velocity(self: &Arc<Self>) -> (f64, f64, f64) {
-		self.velocity.read().unwrap().clone()
+		*self.velocity.read().unwrap()
 	}
 
 	pub fn rotation(self: &Arc<Self>) -> (f32, f32) {
-		self.rotation.read().unwrap().clone()
+		*self.rotation.read().unwrap()
+	}
+
+	pub fn on_ground(self: &Arc<Self>) -> bool {
+		*self.on_ground.read().unwrap()
 	}
 }
@@ -0,0 +1,361 @@
+// Сохраняемое состояние игрока в формате ванильного playerdata
+// https://minecraft.wiki/w/Player.dat_format
+//
+// Пишется поверх старого файла, так что все что мы не понимаем (опыт, эффекты,
+// эндер сундук и тд) остается как было
+
+use std::{collections::HashMap, sync::Arc};
+
+use craftflow_nbt::DynNBT;
+use uuid::Uuid;
+
+use super::{
+	abilities::Abilities,
+	context::PlayerEntityInfo,
+	health::HealthData,
+	inventory::{ARMOR_START, OFFHAND_SLOT, PlayerInventory},
+};
+use crate::{
+	data::{
+		Position,
+		item_nbt::{item_from_nbt, item_to_nbt},
+	},
+	play::registry::Registries,
+	world::anvil::DATA_VERSION,
+};
+
+pub const DEFAULT_DIMENSION: &str = "minecraft:overworld";
+
+// С 1.21.5 броня и вторая рука лежат в equipment, а не в Inventory
+const EQUIPMENT: [(&str, usize); 5] = [
+	("head", ARMOR_START),
+	("chest", ARMOR_START + 1),
+	("legs", ARMOR_START + 2),
+	("feet", ARMOR_START + 3),
+	("offhand", OFFHAND_SLOT),
+];
+
+// Индекс брони в inventory_index, все что до него лежит в Inventory
+const ARMOR_INDEX_START: i32 = 36;
+
+// Так эти слоты хранились в Inventory раньше, от ботинок до шлема
+const LEGACY_ARMOR_START: i32 = 100;
+const LEGACY_OFFHAND: i32 = -106;
+
+#[derive(Debug, Clone)]
+pub struct PlayerData {
+	pub position: (f64, f64, f64),
+	pub velocity: (f64, f64, f64),
+	pub rotation: (f32, f32),
+	pub on_ground: bool,
+	/// 0 - выживание, 1 - креатив, 2 - приключение, 3 - наблюдатель
+	pub game_mode: u8,
+	/// Флаги все равно следуют за game_mode, из файла берутся полет и скорости
+	pub abilities: Abilities,
+	/// Выбранный слот хотбара от 0 до 8
+	pub held_slot: u8,
+	/// Тики неуязвимости не сохраняются
+	pub health: HealthData,
+	/// Где игрок умер в последний раз, всегда в DEFAULT_DIMENSION
+	pub last_death_location: Option<Position>,
+	/// Дименшен в котором игрок вышел, например minecraft:overworld
+	pub dimension: String,
+	/// Сетка крафта тут не хранится, как и в ванилле
+	pub inventory: PlayerInventory,
+}
+
+fn doubles(nbt: Option<&DynNBT>) -> Option<Vec<f64>> {
+	nbt?.as_list()?.iter().map(|o| o.as_double()).collect()
+}
+
+fn floats(nbt: Option<&DynNBT>) -> Option<Vec<f32>> {
+	nbt?.as_list()?.iter().map(|o| o.as_float()).collect()
+}
+
+fn uuid_to_nbt(uuid: Uuid) -> DynNBT {
+	let (most, least) = uuid.as_u64_pair();
+	DynNBT::IntArray(vec![
+		(most >> 32) as i32,
+		most as i32,
+		(least >> 32) as i32,
+		least as i32,
+	])
+}
+
+fn health_from_nbt(root: &HashMap<String, DynNBT>) -> HealthData {
+	let default = HealthData::default();
+	let float =
+		|name: &str, default: f32| root.get(name).and_then(|o| o.as_float()).unwrap_or(default);
+	let int = |name: &str, default: i32| root.get(name).and_then(|o| o.as_int()).unwrap_or(default);
+
+	HealthData {
+		health: float("Health", default.health).max(0.0),
+		food_level: int("foodLevel", default.food_level),
+		saturation: float("foodSaturationLevel", default.saturation),
+		exhaustion: float("foodExhaustionLevel", default.exhaustion),
+		food_tick_timer: int("foodTickTimer", default.food_tick_timer),
+		fall_distance: root
+			.get("fall_distance")
+			.and_then(|o| o.as_double())
+			.unwrap_or_default(),
+		..default
+	}
+}
+
+impl PlayerData {
+	/// Текущее состояние игрока
+	pub fn from_entity(entity_info: &Arc<PlayerEntityInfo>) -> PlayerData {
+		PlayerData {
+			position: entity_info.position(),
+			velocity: entity_info.velocity(),
+			rotation: entity_info.rotation(),
+			on_ground: entity_info.on_ground(),
+			game_mode: entity_info.game_mode(),
+			abilities: entity_info.abilities(),
+			held_slot: entity_info.held_slot(),
+			health: entity_info.health_data().clone(),
+			last_death_location: entity_info.last_death_location(),
+			dimension: DEFAULT_DIMENSION.to_string(),
+			inventory: entity_info.windows().inventory.clone(),
+		}
+	}
+
+	/// Переносит состояние на игрока, клиенту ничего не отправляется
+	pub fn apply(&self, entity_info: &Arc<PlayerEntityInfo>) {
+		entity_info.set_position(self.position);
+		entity_info.set_velocity(self.velocity);
+		entity_info.set_rotation(self.rotation);
+		entity_info.set_on_ground(self.on_ground);
+		entity_info.set_game_mode(self.game_mode);
+		entity_info.set_abilities(self.abilities);
+		entity_info.set_held_slot(self.held_slot);
+		*entity_info.health_data() = self.health.clone();
+		entity_info.set_last_death_location(self.last_death_location);
+		entity_info.windows().inventory = self.inventory.clone();
+	}
+
+	/// Читает данные из NBT файла игрока, None если в нем нет позиции
+	///
+	/// Предметы которых нет в реестре пропадают
+	pub fn from_nbt(nbt: &DynNBT, registries: &Registries) -> Option<PlayerData> {
+		let root = nbt.as_compound()?;
+
+		let position = match doubles(root.get("Pos"))?.as_slice() {
+			[x, y, z] => (*x, *y, *z),
+			_ => return None,
+		};
+		let velocity = match doubles(root.get("Motion")).as_deref() {
+			Some([x, y, z]) => (*x, *y, *z),
+			_ => (0.0, 0.0, 0.0),
+		};
+		let rotation = match floats(root.get("Rotation")).as_deref() {
+			Some([yaw, pitch]) => (*yaw, *pitch),
+			_ => (0.0, 0.0),
+		};
+
+		let mut inventory = PlayerInventory::new();
+
+		for entry in root
+			.get("Inventory")
+			.and_then(|o| o.as_list())
+			.into_iter()
+			.flatten()
+		{
+			let Some(index) = entry
+				.as_compound()
+				.and_then(|o| o.get("Slot"))
+				.and_then(|o| o.as_byte())
+				.map(|o| o as i32)
+			else {
+				continue;
+			};
+
+			let index = match index {
+				LEGACY_OFFHAND => 40,
+				index if index >= LEGACY_ARMOR_START => index - LEGACY_ARMOR_START + ARMOR_INDEX_START,
+				index => index,
+			};
+
+			if let Some(slot) = PlayerInventory::from_inventory_index(index) {
+				inventory.set(slot, item_from_nbt(entry, registries));
+			}
+		}
+
+		if let Some(equipment) = root.get("equipment").and_then(|o| o.as_compound()) {
+			for (name, slot) in EQUIPMENT {
+				if let Some(item) = equipment.get(name) {
+					inventory.set(slot, item_from_nbt(item, registries));
+				}
+			}
+		}
+
+		let game_mode = root
+			.get("playerGameType")
+			.and_then(|o| o.as_int())
+			.unwrap_or_default()
+			.clamp(0, 3) as u8;
+
+		Some(PlayerData {
+			position,
+			velocity,
+			rotation,
+			on_ground: root.get("OnGround").and_then(|o| o.as_byte()) == Some(1),
+			game_mode,
+			abilities: Abilities::from_nbt(root.get("abilities"), game_mode),
+			held_slot: root
+				.get("SelectedItemSlot")
+				.and_then(|o| o.as_int())
+				.unwrap_or_default()
+				.clamp(0, 8) as u8,
+			health: health_from_nbt(root),
+			last_death_location: root
+				.get("LastDeathLocation")
+				.and_then(|o| o.as_compound())
+				.filter(|o| {
+					o.get("dimension")
+						.and_then(|o| o.as_string())
+						.map(|o| o.as_str())
+						== Some(DEFAULT_DIMENSION)
+				})
+				.and_then(|o| o.get("pos"))
+				.and_then(|o| match o {
+					DynNBT::IntArray(pos) => match pos.as_slice() {
+						[x, y, z] => Some(Position {
+							x: *x,
+							y: *y,
+							z: *z,
+						}),
+						_ => None,
+					},
+					_ => None,
+				}),
+			dimension: root
+				.get("Dimension")
+				.and_then(|o| o.as_string())
+				.cloned()
+				.unwrap_or_else(|| DEFAULT_DIMENSION.to_string()),
+			inventory,
+		})
+	}
+
+	/// Переводит данные в NBT поверх base, старого файла игрока если он есть
+	pub fn to_nbt(&self, uuid: Uuid, base: Option<DynNBT>, registries: &Registries) -> DynNBT {
+		let mut root = base
+			.and_then(|o| o.as_compound().cloned())
+			.unwrap_or_default();
+
+		let (x, y, z) = self.position;
+		let (velocity_x, velocity_y, velocity_z) = self.velocity;
+		let (yaw, pitch) = self.rotation;
+
+		root.insert("DataVersion".to_string(), DynNBT::Int(DATA_VERSION));
+		root.insert("UUID".to_string(), uuid_to_nbt(uuid));
+		root.insert(
+			"Pos".to_string(),
+			DynNBT::List(vec![
+				DynNBT::Double(x),
+				DynNBT::Double(y),
+				DynNBT::Double(z),
+			]),
+		);
+		root.insert(
+			"Motion".to_string(),
+			DynNBT::List(vec![
+				DynNBT::Double(velocity_x),
+				DynNBT::Double(velocity_y),
+				DynNBT::Double(velocity_z),
+			]),
+		);
+		root.insert(
+			"Rotation".to_string(),
+			DynNBT::List(vec![DynNBT::Float(yaw), DynNBT::Float(pitch)]),
+		);
+		root.insert("OnGround".to_string(), DynNBT::Byte(self.on_ground as i8));
+		root.insert(
+			"playerGameType".to_string(),
+			DynNBT::Int(self.game_mode as i32),
+		);
+		root.insert("abilities".to_string(), self.abilities.to_nbt());
+		root.insert(
+			"SelectedItemSlot".to_string(),
+			DynNBT::Int(self.held_slot as i32),
+		);
+		root.insert("Health".to_string(), DynNBT::Float(self.health.health));
+		root.insert("foodLevel".to_string(), DynNBT::Int(self.health.food_level));
+		root.insert(
+			"foodSaturationLevel".to_string(),
+			DynNBT::Float(self.health.saturation),
+		);
+		root.insert(
+			"foodExhaustionLevel".to_string(),
+			DynNBT::Float(self.health.exhaustion),
+		);
+		root.insert(
+			"foodTickTimer".to_string(),
+			DynNBT::Int(self.health.food_tick_timer),
+		);
+		root.insert(
+			"fall_distance".to_string(),
+			DynNBT::Double(self.health.fall_distance),
+		);
+
+		match self.last_death_location {
+			Some(Position { x, y, z }) => root.insert(
+				"LastDeathLocation".to_string(),
+				DynNBT::Compound(
+					[
+						(
+							"dimension".to_string(),
+							DynNBT::String(DEFAULT_DIMENSION.to_string()),
+						),
+						("pos".to_string(), DynNBT::IntArray(vec![x, y, z])),
+					]
+					.into_iter()
+					.collect(),
+				),
+			),
+			None => root.remove("LastDeathLocation"),
+		};
+		root.insert(
+			"Dimension".to_string(),
+			DynNBT::String(self.dimension.clone()),
+		);
+
+		let mut items = Vec::new();
+
+		// В Inventory только хотбар и основной инвентарь, индексы 0-35
+		for index in 0..ARMOR_INDEX_START {
+			let Some(item) =
+				PlayerInventory::from_inventory_index(index).and_then(|o| self.inventory.get(o))
+			else {
+				continue;
+			};
+
+			if let DynNBT::Compound(mut item) = item_to_nbt(item, registries) {
+				item.insert("Slot".to_string(), DynNBT::Byte(index as i8));
+				items.push(DynNBT::Compound(item));
+			}
+		}
+
+		root.insert("Inventory".to_string(), DynNBT::List(items));
+
+		// Остальную экипировку (body, saddle) игрок не носит, но трогать ее незачем
+		let mut equipment = root
+			.remove("equipment")
+			.and_then(|o| o.as_compound().cloned())
+			.unwrap_or_default();
+
+		for (name, slot) in EQUIPMENT {
+			match self.inventory.get(slot) {
+				Some(item) => equipment.insert(name.to_string(), item_to_nbt(item, registries)),
+				None => equipment.remove(name),
+			};
+		}
+
+		if !equipment.is_empty() {
+			root.insert("equipment".to_string(), DynNBT::Compound(equipment));
+		}
+
+		DynNBT::Compound(root)
+	}
+}
@@ -0,0 +1,217 @@
+// Здоровье, голод и урон игрока
+//
+// Голод считается как в ванилле (FoodData): действия копят истощение, каждые 4 единицы
+// съедают сначала насыщение, потом еду. Сытый игрок лечится, голодный получает урон
+// Пакеты и события в play::health
+
+pub const MAX_HEALTH: f32 = 20.0;
+pub const MAX_FOOD: i32 = 20;
+pub const DEFAULT_SATURATION: f32 = 5.0;
+
+const MAX_EXHAUSTION: f32 = 40.0;
+const EXHAUSTION_PER_FOOD: f32 = 4.0;
+
+// Истощение от действий, как в ванилле
+pub const SPRINT_EXHAUSTION: f32 = 0.1; // за блок
+pub const JUMP_EXHAUSTION: f32 = 0.05;
+pub const SPRINT_JUMP_EXHAUSTION: f32 = 0.2;
+pub const BREAK_BLOCK_EXHAUSTION: f32 = 0.005;
+
+// Падение до 3 блоков проходит без урона
+const SAFE_FALL_DISTANCE: f64 = 3.0;
+
+// После удара игрок 20 тиков неуязвим, во второй половине проходит только урон сильнее прошлого
+const INVULNERABLE_TICKS: i32 = 20;
+
+// Ключи реестра minecraft:damage_type
+pub const FALL: &str = "minecraft:fall";
+pub const OUT_OF_WORLD: &str = "minecraft:out_of_world";
+pub const GENERIC_KILL: &str = "minecraft:generic_kill";
+pub const STARVE: &str = "minecraft:starve";
+pub const PLAYER_ATTACK: &str = "minecraft:player_attack";
+
+/// Откуда пришел урон
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamageSource {
+	/// Ключ в реестре minecraft:damage_type, например minecraft:fall
+	pub damage_type: String,
+	/// Айди сущности игрока который ударил
+	pub attacker: Option<i32>,
+}
+
+impl DamageSource {
+	pub fn new(damage_type: &str) -> DamageSource {
+		DamageSource {
+			damage_type: damage_type.to_string(),
+			attacker: None,
+		}
+	}
+
+	/// Удар игрока в ближнем бою
+	pub fn player_attack(attacker: i32) -> DamageSource {
+		DamageSource {
+			damage_type: PLAYER_ATTACK.to_string(),
+			attacker: Some(attacker),
+		}
+	}
+
+	/// Урон проходит даже по неуязвимым, как тег bypasses_invulnerability
+	pub fn bypasses_invulnerability(&self) -> bool {
+		self.damage_type == OUT_OF_WORLD || self.damage_type == GENERIC_KILL
+	}
+
+	/// Сообщение о смерти как у ваниллы в en_us, attacker - ник ударившего
+	pub fn death_message(&self, name: &str, attacker: Option<&str>) -> String {
+		if let Some(attacker) = attacker {
+			return format!("{name} was slain by {attacker}");
+		}
+
+		match self.damage_type.as_str() {
+			FALL => format!("{name} hit the ground too hard"),
+			OUT_OF_WORLD => format!("{name} fell out of the world"),
+			GENERIC_KILL => format!("{name} was killed"),
+			STARVE => format!("{name} starved to death"),
+			_ => format!("{name} died"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthData {
+	/// От 0 до 20, 0 - игрок мертв и ждет возрождения
+	pub health: f32,
+	/// От 0 до 20
+	pub food_level: i32,
+	/// Не больше food_level
+	pub saturation: f32,
+	pub exhaustion: f32,
+	pub food_tick_timer: i32,
+	/// Сколько блоков игрок пролетел вниз с последнего касания земли
+	pub fall_distance: f64,
+	/// Тики неуязвимости после удара
+	pub invulnerable_ticks: i32,
+	/// Последний прошедший урон, пока игрок неуязвим
+	pub last_damage: f32,
+}
+
+impl Default for HealthData {
+	fn default() -> Self {
+		HealthData {
+			health: MAX_HEALTH,
+			food_level: MAX_FOOD,
+			saturation: DEFAULT_SATURATION,
+			exhaustion: 0.0,
+			food_tick_timer: 0,
+			fall_distance: 0.0,
+			invulnerable_ticks: 0,
+			last_damage: 0.0,
+		}
+	}
+}
+
+/// Что надо сделать с игроком после тика голода
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FoodTick {
+	None,
+	Heal(f32),
+	Starve(f32),
+}
+
+impl HealthData {
+	pub fn is_dead(&self) -> bool {
+		self.health <= 0.0
+	}
+
+	pub fn add_exhaustion(&mut self, exhaustion: f32) {
+		self.exhaustion = (self.exhaustion + exhaustion).min(MAX_EXHAUSTION);
+	}
+
+	pub fn heal(&mut self, amount: f32) {
+		if !self.is_dead() {
+			self.health = (self.health + amount).min(MAX_HEALTH);
+		}
+	}
+
+	/// Урон с учетом неуязвимости после прошлого удара
+	///
+	/// Возвращает сколько урона реально прошло, 0 если удар поглотила неуязвимость
+	pub fn hurt(&mut self, amount: f32) -> f32 {
+		if self.is_dead() || amount <= 0.0 {
+			return 0.0;
+		}
+
+		let dealt = if self.invulnerable_ticks > INVULNERABLE_TICKS / 2 {
+			if amount <= self.last_damage {
+				return 0.0;
+			}
+			amount - self.last_damage
+		} else {
+			self.invulnerable_ticks = INVULNERABLE_TICKS;
+			amount
+		};
+
+		self.last_damage = amount;
+		self.health = (self.health - dealt).max(0.0);
+
+		dealt
+	}
+
+	/// Урон от падения при приземлении, сбрасывает пролетенное
+	pub fn land(&mut self) -> f32 {
+		let damage = (self.fall_distance - SAFE_FALL_DISTANCE).ceil().max(0.0);
+		self.fall_distance = 0.0;
+		damage as f32
+	}
+
+	/// Тик голода, natural_regeneration - лечиться от сытости
+	pub fn tick_food(&mut self, natural_regeneration: bool) -> FoodTick {
+		if self.invulnerable_ticks > 0 {
+			self.invulnerable_ticks -= 1;
+		}
+
+		if self.is_dead() {
+			return FoodTick::None;
+		}
+
+		if self.exhaustion > EXHAUSTION_PER_FOOD {
+			self.exhaustion -= EXHAUSTION_PER_FOOD;
+			if self.saturation > 0.0 {
+				self.saturation = (self.saturation - 1.0).max(0.0);
+			} else {
+				self.food_level = (self.food_level - 1).max(0);
+			}
+		}
+
+		let hurt = self.health < MAX_HEALTH;
+
+		if natural_regeneration && self.saturation > 0.0 && hurt && self.food_level >= MAX_FOOD {
+			self.food_tick_timer += 1;
+			if self.food_tick_timer >= 10 {
+				let amount = self.saturation.min(6.0);
+				self.food_tick_timer = 0;
+				self.add_exhaustion(amount);
+				return FoodTick::Heal(amount / 6.0);
+			}
+		} else if natural_regeneration && self.food_level >= 18 && hurt {
+			self.food_tick_timer += 1;
+			if self.food_tick_timer >= 80 {
+				self.food_tick_timer = 0;
+				self.add_exhaustion(6.0);
+				return FoodTick::Heal(1.0);
+			}
+		} else if self.food_level <= 0 {
+			self.food_tick_timer += 1;
+			if self.food_tick_timer >= 80 {
+				self.food_tick_timer = 0;
+				// Сложность у нас всегда нормальная, голод не убивает
+				if self.health > 1.0 {
+					return FoodTick::Starve(1.0);
+				}
+			}
+		} else {
+			self.food_tick_timer = 0;
+		}
+
+		FoodTick::None
+	}
+}
@@ -1,17 +1,15 @@
 use std::{
-	io::Read,
 	sync::Arc,
 	time::{Duration, SystemTime},
 };
 
-use rust_mc_proto::{DataReader, DataWriter, Packet};
-
 use crate::{
 	ServerError,
-	data::{ReadWriteNBT, component::TextComponent},
+	data::component::TextComponent,
 	protocol::{
-		packet_id::{clientbound, serverbound},
-		*,
+		ConnectionState,
+		codec::{RemainingBytes, VarInt},
+		packets::{configuration, login, play},
 	},
 };
 
@@ -42,7 +40,7 @@ impl ProtocolHelper {
 			ConnectionState::Configuration => {
 				self
 					.client
-					.write_packet(&Packet::empty(clientbound::configuration::RESET_CHAT))?;
+					.send_packet(&configuration::clientbound::ResetChat {})?;
 				Ok(())
 			}
 			_ => Err(ServerError::UnexpectedState),
@@ -50,18 +48,21 @@ impl ProtocolHelper {
 	}
 
 	pub fn store_cookie(&self, id: &str, data: &[u8]) -> Result<(), ServerError> {
-		self.client.write_packet(&Packet::build(
-			match self.state {
-				ConnectionState::Configuration => clientbound::configuration::STORE_COOKIE,
-				ConnectionState::Play => clientbound::play::STORE_COOKIE,
-				_ => return Err(ServerError::UnexpectedState),
-			},
-			|p| {
-				p.write_string(id)?;
-				p.write_bytes(data)
-			},
-		)?)?;
-		Ok(())
+		match self.state {
+			ConnectionState::Configuration => {
+				self
+					.client
+					.send_packet(&configuration::clientbound::StoreCookie {
+						key: id.to_string(),
+						payload: data.to_vec(),
+					})
+			}
+			ConnectionState::Play => self.client.send_packet(&play::clientbound::StoreCookie {
+				key: id.to_string(),
+				payload: data.to_vec(),
+			}),
+			_ => Err(ServerError::UnexpectedState),
+		}
 	}
 
 	/// Leave from Configuration to Play state
@@ -70,10 +71,10 @@ impl ProtocolHelper {
 			ConnectionState::Configuration => {
 				self
 					.client
-					.write_packet(&Packet::empty(clientbound::configuration::FINISH))?;
+					.send_packet(&configuration::clientbound::Finish {})?;
 				self
 					.client
-					.read_packet(&[serverbound::configuration::ACKNOWLEDGE_FINISH])?;
+					.receive_packet::<configuration::serverbound::AcknowledgeFinish>()?;
 				self.client.set_state(ConnectionState::Play)?;
 				Ok(())
 			}
@@ -87,10 +88,10 @@ impl ProtocolHelper {
 			ConnectionState::Play => {
 				self
 					.client
-					.write_packet(&Packet::empty(clientbound::play::START_CONFIGURATION))?;
+					.send_packet(&play::clientbound::StartConfiguration {})?;
 				self
 					.client
-					.read_packet(&[serverbound::play::ACKNOWLEDGE_CONFIGURATION])?;
+					.receive_packet::<play::serverbound::AcknowledgeConfiguration>()?;
 				self.client.set_state(ConnectionState::Configuration)?;
 				Ok(())
 			}
@@ -105,18 +106,18 @@ impl ProtocolHelper {
 				let time = SystemTime::now();
 				self
 					.client
-					.write_packet(&Packet::empty(clientbound::play::PING))?;
-				self.client.read_packet(&[serverbound::play::PONG])?;
+					.send_packet(&play::clientbound::Ping { id: 0 })?;
+				self.client.receive_packet::<play::serverbound::Pong>()?;
 				Ok(SystemTime::now().duration_since(time).unwrap())
 			}
 			ConnectionState::Configuration => {
 				let time = SystemTime::now();
 				self
 					.client
-					.write_packet(&Packet::empty(clientbound::configuration::PING))?;
+					.send_packet(&configuration::clientbound::Ping { id: 0 })?;
 				self
 					.client
-					.read_packet(&[serverbound::configuration::PONG])?;
+					.receive_packet::<configuration::serverbound::Pong>()?;
 				Ok(SystemTime::now().duration_since(time).unwrap())
 			}
 			_ => Err(ServerError::UnexpectedState),
@@ -124,68 +125,49 @@ impl ProtocolHelper {
 	}
 
 	pub fn disconnect(&self, reason: TextComponent) -> Result<(), ServerError> {
-		let packet = match self.state {
-			ConnectionState::Login => {
-				let text = reason.as_json()?;
-				Packet::build(0x00, |p| p.write_string(&text))?
-			}
-			ConnectionState::Configuration => {
-				let mut packet = Packet::empty(0x02);
-				packet.write_nbt(&reason)?;
-				packet
-			}
-			ConnectionState::Play => {
-				let mut packet = Packet::empty(0x1C);
-				packet.write_nbt(&reason)?;
-				packet
-			}
+		match self.state {
+			ConnectionState::Login => self.client.send_packet(&login::clientbound::Disconnect {
+				reason: reason.as_json()?,
+			}),
+			ConnectionState::Configuration => self
+				.client
+				.send_packet(&configuration::clientbound::Disconnect { reason }),
+			ConnectionState::Play => self
+				.client
+				.send_packet(&play::clientbound::Disconnect { reason }),
 			_ => {
 				self.client.close();
-				return Ok(());
+				Ok(())
 			}
-		};
-		self.client.write_packet(&packet)?;
-		Ok(())
+		}
 	}
 
 	/// Returns cookie content
 	pub fn request_cookie(&self, id: &str) -> Result<Option<Vec<u8>>, ServerError> {
 		match self.state {
 			ConnectionState::Configuration => {
-				let mut packet = Packet::empty(clientbound::configuration::COOKIE_REQUEST);
-				packet.write_string(id)?;
-				self.client.write_packet(&packet)?;
+				self
+					.client
+					.send_packet(&configuration::clientbound::CookieRequest {
+						key: id.to_string(),
+					})?;
 
-				let mut packet = self
+				let response = self
 					.client
-					.read_packet(&[serverbound::configuration::COOKIE_RESPONSE])?;
-				packet.read_string()?;
-				let data = if packet.read_boolean()? {
-					let n = packet.read_usize_varint()?;
-					Some(packet.read_bytes(n)?)
-				} else {
-					None
-				};
-
-				Ok(data)
+					.receive_packet::<configuration::serverbound::CookieResponse>()?;
+
+				Ok(response.payload)
 			}
 			ConnectionState::Play => {
-				let mut packet = Packet::empty(clientbound::play::COOKIE_REQUEST);
-				packet.write_string(id)?;
-				self.client.write_packet(&packet)?;
+				self.client.send_packet(&play::clientbound::CookieRequest {
+					key: id.to_string(),
+				})?;
 
-				let mut packet = self
+				let response = self
 					.client
-					.read_packet(&[serverbound::play::COOKIE_RESPONSE])?;
-				packet.read_string()?;
-				let data = if packet.read_boolean()? {
-					let n = packet.read_usize_varint()?;
-					Some(packet.read_bytes(n)?)
-				} else {
-					None
-				};
-
-				Ok(data)
+					.receive_packet::<play::serverbound::CookieResponse>()?;
+
+				Ok(response.payload)
 			}
 			_ => Err(ServerError::UnexpectedState),
 		}
@@ -200,39 +182,39 @@ impl ProtocolHelper {
 	) -> Result<(i32, Option<Vec<u8>>), ServerError> {
 		match self.state {
 			ConnectionState::Login => {
-				let mut packet = Packet::empty(clientbound::login::PLUGIN_REQUEST);
-				packet.write_varint(id)?;
-				packet.write_string(channel)?;
-				packet.write_bytes(data)?;
-				self.client.write_packet(&packet)?;
+				self
+					.client
+					.send_packet(&login::clientbound::PluginRequest {
+						message_id: VarInt(id),
+						channel: channel.to_string(),
+						data: RemainingBytes(data.to_vec()),
+					})?;
 
-				let mut packet = self
+				let response = self
 					.client
-					.read_packet(&[serverbound::login::PLUGIN_RESPONSE])?;
-				let identifier = packet.read_varint()?;
-				let data = if packet.read_boolean()? {
-					let mut data = Vec::new();
-					packet.get_mut().read_to_end(&mut data).unwrap();
-					Some(data)
-				} else {
-					None
-				};
-
-				Ok((identifier, data))
+					.receive_packet::<login::serverbound::PluginResponse>()?;
+
+				Ok((response.message_id.0, response.data.map(|o| o.0)))
 			}
 			_ => Err(ServerError::UnexpectedState),
 		}
 	}
 
 	pub fn send_plugin_message(&self, channel: &str, data: &[u8]) -> Result<(), ServerError> {
-		let mut packet = match self.state {
-			ConnectionState::Configuration => Packet::empty(clientbound::configuration::PLUGIN_MESSAGE),
-			ConnectionState::Play => Packet::empty(clientbound::play::PLUGIN_MESSAGE),
-			_ => return Err(ServerError::UnexpectedState),
-		};
-		packet.write_string(channel)?;
-		packet.write_bytes(data)?;
-		self.client.write_packet(&packet)?;
-		Ok(())
+		match self.state {
+			ConnectionState::Configuration => {
+				self
+					.client
+					.send_packet(&configuration::clientbound::PluginMessage {
+						channel: channel.to_string(),
+						data: RemainingBytes(data.to_vec()),
+					})
+			}
+			ConnectionState::Play => self.client.send_packet(&play::clientbound::PluginMessage {
+				channel: channel.to_string(),
+				data: RemainingBytes(data.to_vec()),
+			}),
+			_ => Err(ServerError::UnexpectedState),
+		}
 	}
 }
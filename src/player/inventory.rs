@@ -0,0 +1,840 @@
+// Инвентарь игрока и окна контейнеров
+//
+// Слоты окна инвентаря (айди окна 0): 0 - результат крафта, 1-4 крафт, 5-8 броня от шлема до ботинок,
+// 9-35 инвентарь, 36-44 хотбар, 45 - вторая рука
+// У остальных окон сначала идут слоты контейнера, за ними 27 слотов инвентаря и 9 хотбара
+//
+// Клики сервер считает сам так же как ванилла, присланные клиентом слоты только сверяются по хешам
+
+use crate::{
+	data::{component::TextComponent, slot::Slot},
+	world::item::ItemRegistry,
+};
+
+pub const PLAYER_INVENTORY_SIZE: usize = 46;
+pub const CRAFTING_RESULT_SLOT: usize = 0;
+pub const CRAFTING_GRID_START: usize = 1;
+pub const ARMOR_START: usize = 5;
+pub const MAIN_START: usize = 9;
+pub const HOTBAR_START: usize = 36;
+pub const OFFHAND_SLOT: usize = 45;
+
+/// Окно инвентаря игрока, оно открыто всегда
+pub const PLAYER_WINDOW: u8 = 0;
+
+/// Слот клика за пределами окна, так выкидывают предмет с курсора
+pub const OUTSIDE_SLOT: i16 = -999;
+
+// Режимы Click Container
+pub const PICKUP: i32 = 0;
+pub const QUICK_MOVE: i32 = 1;
+pub const SWAP: i32 = 2;
+pub const CLONE: i32 = 3;
+pub const THROW: i32 = 4;
+pub const QUICK_CRAFT: i32 = 5;
+pub const PICKUP_ALL: i32 = 6;
+
+// Кнопка 40 при SWAP - клавиша смены рук
+const OFFHAND_BUTTON: i8 = 40;
+
+// Слоты экипировки как в компоненте equippable
+const FEET: u8 = 1;
+const HEAD: u8 = 4;
+const OFFHAND: u8 = 5;
+
+/// Слот окна инвентаря для брони, equipment_slot: 1 - ботинки, 2 - штаны, 3 - нагрудник, 4 - шлем
+pub fn armor_slot(equipment_slot: u8) -> Option<usize> {
+	(FEET..=HEAD)
+		.contains(&equipment_slot)
+		.then(|| ARMOR_START + (HEAD - equipment_slot) as usize)
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerInventory {
+	slots: Vec<Option<Slot>>,
+}
+
+impl Default for PlayerInventory {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl PlayerInventory {
+	pub fn new() -> PlayerInventory {
+		PlayerInventory {
+			slots: vec![None; PLAYER_INVENTORY_SIZE],
+		}
+	}
+
+	/// Все слоты в порядке окна инвентаря
+	pub fn slots(&self) -> &[Option<Slot>] {
+		&self.slots
+	}
+
+	pub fn get(&self, slot: usize) -> Option<&Slot> {
+		self.slots.get(slot)?.as_ref()
+	}
+
+	/// Пустые стаки сразу превращаются в None
+	pub fn set(&mut self, slot: usize, item: Option<Slot>) {
+		if let Some(o) = self.slots.get_mut(slot) {
+			*o = item.filter(|o| o.amount > 0);
+		}
+	}
+
+	pub fn take(&mut self, slot: usize) -> Option<Slot> {
+		self.slots.get_mut(slot)?.take()
+	}
+
+	/// Слот хотбара от 0 до 8
+	pub fn hotbar(&self, index: usize) -> Option<&Slot> {
+		self.get(HOTBAR_START + index.min(8))
+	}
+
+	/// Броня, equipment_slot как в armor_slot
+	pub fn armor(&self, equipment_slot: u8) -> Option<&Slot> {
+		self.get(armor_slot(equipment_slot)?)
+	}
+
+	pub fn offhand(&self) -> Option<&Slot> {
+		self.get(OFFHAND_SLOT)
+	}
+
+	/// Сетка крафта 2x2, слева направо и сверху вниз
+	pub fn crafting_grid(&self) -> &[Option<Slot>] {
+		&self.slots[CRAFTING_GRID_START..ARMOR_START]
+	}
+
+	/// Кладет предмет как подобранный с земли: сначала в похожие стаки, потом в пустые слоты,
+	/// хотбар раньше инвентаря. Возвращает то что не влезло
+	pub fn add_item(&mut self, mut item: Slot, items: &ItemRegistry) -> Option<Slot> {
+		let max = item.max_stack_size(items);
+		let order = (HOTBAR_START..OFFHAND_SLOT).chain(MAIN_START..HOTBAR_START);
+
+		for index in order.clone() {
+			if let Some(stack) = self.slots[index].as_mut()
+				&& stack.is_similar(&item)
+			{
+				let moved = (max - stack.amount).clamp(0, item.amount);
+				stack.amount += moved;
+				item.amount -= moved;
+			}
+		}
+
+		for index in order {
+			if item.amount <= 0 {
+				break;
+			}
+			if self.slots[index].is_none() {
+				let moved = max.min(item.amount);
+				self.slots[index] = Some(item.with_amount(moved));
+				item.amount -= moved;
+			}
+		}
+
+		Some(item).filter(|o| o.amount > 0)
+	}
+
+	/// Индекс слота в Set Player Inventory Slot, в таком порядке слоты хранит ванилла
+	///
+	/// У сетки крафта и результата его нет
+	pub fn inventory_index(slot: usize) -> Option<i32> {
+		match slot {
+			ARMOR_START..MAIN_START => Some(39 - (slot - ARMOR_START) as i32),
+			MAIN_START..HOTBAR_START => Some(slot as i32),
+			HOTBAR_START..OFFHAND_SLOT => Some((slot - HOTBAR_START) as i32),
+			OFFHAND_SLOT => Some(40),
+			_ => None,
+		}
+	}
+
+	/// Обратное inventory_index: слот окна инвентаря по индексу
+	pub fn from_inventory_index(index: i32) -> Option<usize> {
+		match index {
+			0..9 => Some(HOTBAR_START + index as usize),
+			9..36 => Some(index as usize),
+			36..40 => Some(ARMOR_START + (39 - index) as usize),
+			40 => Some(OFFHAND_SLOT),
+			_ => None,
+		}
+	}
+}
+
+/// Тип окна, айди в реестре minecraft:menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+	Generic9x1,
+	Generic9x2,
+	Generic9x3,
+	Generic9x4,
+	Generic9x5,
+	Generic9x6,
+	Generic3x3,
+	/// Слот результата у крафтера идет после инвентаря, его мы не показываем
+	Crafter3x3,
+	Anvil,
+	Beacon,
+	BlastFurnace,
+	BrewingStand,
+	Crafting,
+	Enchantment,
+	Furnace,
+	Grindstone,
+	Hopper,
+	/// Без инвентаря игрока
+	Lectern,
+	Loom,
+	Merchant,
+	ShulkerBox,
+	Smithing,
+	Smoker,
+	CartographyTable,
+	Stonecutter,
+}
+
+impl ContainerKind {
+	pub fn id(self) -> i32 {
+		self as i32
+	}
+
+	/// Сундук на rows рядов, от 1 до 6
+	pub fn chest(rows: u8) -> ContainerKind {
+		match rows {
+			0 | 1 => ContainerKind::Generic9x1,
+			2 => ContainerKind::Generic9x2,
+			3 => ContainerKind::Generic9x3,
+			4 => ContainerKind::Generic9x4,
+			5 => ContainerKind::Generic9x5,
+			_ => ContainerKind::Generic9x6,
+		}
+	}
+
+	/// Слотов у самого контейнера, без инвентаря игрока
+	pub fn size(self) -> usize {
+		match self {
+			ContainerKind::Generic9x1 => 9,
+			ContainerKind::Generic9x2 => 18,
+			ContainerKind::Generic9x3 => 27,
+			ContainerKind::Generic9x4 => 36,
+			ContainerKind::Generic9x5 => 45,
+			ContainerKind::Generic9x6 => 54,
+			ContainerKind::Generic3x3 | ContainerKind::Crafter3x3 => 9,
+			ContainerKind::Beacon | ContainerKind::Lectern => 1,
+			ContainerKind::Enchantment | ContainerKind::Stonecutter => 2,
+			ContainerKind::Anvil
+			| ContainerKind::BlastFurnace
+			| ContainerKind::Furnace
+			| ContainerKind::Grindstone
+			| ContainerKind::Merchant
+			| ContainerKind::Smoker
+			| ContainerKind::CartographyTable => 3,
+			ContainerKind::Loom | ContainerKind::Smithing => 4,
+			ContainerKind::BrewingStand | ContainerKind::Hopper => 5,
+			ContainerKind::Crafting => 10,
+			ContainerKind::ShulkerBox => 27,
+		}
+	}
+
+	/// Слот результата, класть туда предметы нельзя
+	pub fn is_result_slot(self, slot: usize) -> bool {
+		match self {
+			ContainerKind::Crafting => slot == 0,
+			ContainerKind::Anvil
+			| ContainerKind::BlastFurnace
+			| ContainerKind::Furnace
+			| ContainerKind::Grindstone
+			| ContainerKind::Merchant
+			| ContainerKind::Smoker
+			| ContainerKind::CartographyTable => slot == 2,
+			ContainerKind::Loom | ContainerKind::Smithing => slot == 3,
+			ContainerKind::Stonecutter => slot == 1,
+			_ => false,
+		}
+	}
+
+	pub fn has_player_slots(self) -> bool {
+		self != ContainerKind::Lectern
+	}
+}
+
+/// Контейнер окна, например меню плагина
+#[derive(Debug, Clone)]
+pub struct Container {
+	pub kind: ContainerKind,
+	pub title: TextComponent,
+	slots: Vec<Option<Slot>>,
+}
+
+impl Container {
+	pub fn new(kind: ContainerKind, title: TextComponent) -> Container {
+		Container {
+			kind,
+			title,
+			slots: vec![None; kind.size()],
+		}
+	}
+
+	pub fn size(&self) -> usize {
+		self.slots.len()
+	}
+
+	pub fn slots(&self) -> &[Option<Slot>] {
+		&self.slots
+	}
+
+	pub fn get(&self, slot: usize) -> Option<&Slot> {
+		self.slots.get(slot)?.as_ref()
+	}
+
+	pub fn set(&mut self, slot: usize, item: Option<Slot>) {
+		if let Some(o) = self.slots.get_mut(slot) {
+			*o = item.filter(|o| o.amount > 0);
+		}
+	}
+}
+
+// Растягивание предметов по слотам (QUICK_CRAFT)
+#[derive(Debug, Clone)]
+struct Drag {
+	/// 0 - поровну, 1 - по одному, 2 - полными стаками (только креатив)
+	kind: i8,
+	slots: Vec<usize>,
+}
+
+/// Инвентарь, курсор и открытое окно игрока
+#[derive(Debug, Clone, Default)]
+pub struct Windows {
+	pub inventory: PlayerInventory,
+	/// Предмет который игрок держит мышкой
+	pub cursor: Option<Slot>,
+	window: Option<(u8, Container)>,
+	last_window_id: u8,
+	state_id: i32,
+	drag: Option<Drag>,
+}
+
+impl Windows {
+	/// Айди открытого окна, 0 - инвентарь
+	pub fn window_id(&self) -> u8 {
+		self.window.as_ref().map(|o| o.0).unwrap_or(PLAYER_WINDOW)
+	}
+
+	pub fn container(&self) -> Option<&Container> {
+		self.window.as_ref().map(|o| &o.1)
+	}
+
+	pub fn container_mut(&mut self) -> Option<&mut Container> {
+		self.window.as_mut().map(|o| &mut o.1)
+	}
+
+	/// Последний отправленный клиенту state id
+	pub fn state_id(&self) -> i32 {
+		self.state_id
+	}
+
+	/// Новый state id для пакета синхронизации, как в ванилле он 15-битный
+	pub fn next_state_id(&mut self) -> i32 {
+		self.state_id = (self.state_id + 1) & 0x7FFF;
+		self.state_id
+	}
+
+	/// Открывает контейнер и возвращает айди его окна, от 1 до 100 по кругу
+	///
+	/// Старое окно должно быть уже закрыто через close
+	pub fn open(&mut self, container: Container) -> u8 {
+		self.last_window_id = self.last_window_id % 100 + 1;
+		self.window = Some((self.last_window_id, container));
+		self.drag = None;
+		self.last_window_id
+	}
+
+	/// Закрывает окно и возвращает его контейнер, у инвентаря его нет
+	///
+	/// Курсор, а у инвентаря еще и сетка крафта, возвращаются в инвентарь.
+	/// Что туда не влезло - во втором значении, это надо выкинуть
+	pub fn close(&mut self, items: &ItemRegistry) -> (Option<Container>, Vec<Slot>) {
+		let container = self.window.take().map(|o| o.1);
+		self.drag = None;
+
+		let mut returned = Vec::new();
+		returned.extend(self.cursor.take());
+		if container.is_none() {
+			for slot in CRAFTING_GRID_START..ARMOR_START {
+				returned.extend(self.inventory.take(slot));
+			}
+		}
+
+		let dropped = returned
+			.into_iter()
+			.filter_map(|o| self.inventory.add_item(o, items))
+			.collect();
+
+		(container, dropped)
+	}
+
+	/// Забирает все предметы из инвентаря и курсора, как при смерти
+	///
+	/// Результат крафта не настоящий предмет, он просто пропадает
+	pub fn take_all(&mut self) -> Vec<Slot> {
+		self.inventory.take(CRAFTING_RESULT_SLOT);
+
+		let mut items = Vec::new();
+		items.extend(self.cursor.take());
+		for slot in CRAFTING_GRID_START..PLAYER_INVENTORY_SIZE {
+			items.extend(self.inventory.take(slot));
+		}
+
+		items
+	}
+
+	/// Сколько слотов в открытом окне
+	pub fn window_size(&self) -> usize {
+		match self.container() {
+			Some(container) if container.kind.has_player_slots() => container.size() + 36,
+			Some(container) => container.size(),
+			None => PLAYER_INVENTORY_SIZE,
+		}
+	}
+
+	/// Все слоты открытого окна, для Set Container Content
+	pub fn window_slots(&self) -> Vec<Option<Slot>> {
+		(0..self.window_size()).map(|o| self.get_slot(o)).collect()
+	}
+
+	// Слот окна в инвентаре игрока, None если это слот контейнера
+	fn inventory_slot(&self, index: usize) -> Option<usize> {
+		match self.container() {
+			Some(container) => index
+				.checked_sub(container.size())
+				.filter(|o| *o < 36 && container.kind.has_player_slots())
+				.map(|o| MAIN_START + o),
+			None => Some(index).filter(|o| *o < PLAYER_INVENTORY_SIZE),
+		}
+	}
+
+	/// Слот открытого окна в котором виден слот инвентаря игрока
+	pub fn window_slot(&self, inventory_slot: usize) -> Option<usize> {
+		match self.container() {
+			Some(container) => (MAIN_START..OFFHAND_SLOT)
+				.contains(&inventory_slot)
+				.then(|| container.size() + inventory_slot - MAIN_START)
+				.filter(|_| container.kind.has_player_slots()),
+			None => Some(inventory_slot).filter(|o| *o < PLAYER_INVENTORY_SIZE),
+		}
+	}
+
+	/// Предмет в слоте открытого окна
+	pub fn get_slot(&self, index: usize) -> Option<Slot> {
+		match self.inventory_slot(index) {
+			Some(slot) => self.inventory.get(slot).cloned(),
+			None => self.container()?.get(index).cloned(),
+		}
+	}
+
+	pub fn set_slot(&mut self, index: usize, item: Option<Slot>) {
+		match self.inventory_slot(index) {
+			Some(slot) => self.inventory.set(slot, item),
+			None => {
+				if let Some(container) = self.container_mut() {
+					container.set(index, item);
+				}
+			}
+		}
+	}
+
+	// Можно ли положить предмет в слот окна
+	fn may_place(&self, index: usize, item: &Slot, items: &ItemRegistry) -> bool {
+		match (self.container(), self.inventory_slot(index)) {
+			(_, Some(CRAFTING_RESULT_SLOT)) if self.container().is_none() => false,
+			(_, Some(slot @ ARMOR_START..MAIN_START)) => {
+				// Без отчета items.json не знаем что надевается, разрешаем все
+				!items.has_components()
+					|| item
+						.equipment_slot(items)
+						.and_then(armor_slot)
+						.is_some_and(|o| o == slot)
+			}
+			(Some(container), None) => index < container.size() && !container.kind.is_result_slot(index),
+			_ => index < self.window_size(),
+		}
+	}
+
+	// Сколько таких предметов влезет в слот
+	fn slot_limit(&self, index: usize, item: &Slot, items: &ItemRegistry) -> i32 {
+		let max = item.max_stack_size(items);
+		match self.inventory_slot(index) {
+			Some(ARMOR_START..MAIN_START) => max.min(1),
+			_ => max,
+		}
+	}
+
+	fn is_result_slot(&self, index: usize) -> bool {
+		match self.container() {
+			Some(container) => index < container.size() && container.kind.is_result_slot(index),
+			None => index == CRAFTING_RESULT_SLOT,
+		}
+	}
+
+	/// Применяет клик из Click Container к открытому окну
+	///
+	/// creative разрешает клонирование предметов. Возвращает выкинутые предметы
+	pub fn click(
+		&mut self,
+		slot: i16,
+		button: i8,
+		mode: i32,
+		creative: bool,
+		items: &ItemRegistry,
+	) -> Vec<Slot> {
+		// Любой другой клик прерывает растягивание
+		if mode != QUICK_CRAFT {
+			self.drag = None;
+		}
+
+		let index = usize::try_from(slot)
+			.ok()
+			.filter(|o| *o < self.window_size());
+
+		match (mode, index) {
+			(PICKUP, _) if slot == OUTSIDE_SLOT => self.drop_cursor(button),
+			(PICKUP, Some(index)) => {
+				self.pickup(index, button, items);
+				Vec::new()
+			}
+			(QUICK_MOVE, Some(index)) => {
+				self.quick_move(index, items);
+				Vec::new()
+			}
+			(SWAP, Some(index)) => {
+				self.swap(index, button, items);
+				Vec::new()
+			}
+			(CLONE, Some(index)) if creative && self.cursor.is_none() => {
+				self.cursor = self
+					.get_slot(index)
+					.map(|o| o.with_amount(o.max_stack_size(items)));
+				Vec::new()
+			}
+			(THROW, Some(index)) if self.cursor.is_none() => self.throw(index, button),
+			(QUICK_CRAFT, _) => {
+				self.quick_craft(index, button, creative, items);
+				Vec::new()
+			}
+			(PICKUP_ALL, Some(index)) => {
+				self.pickup_all(index, button, items);
+				Vec::new()
+			}
+			_ => Vec::new(),
+		}
+	}
+
+	fn drop_cursor(&mut self, button: i8) -> Vec<Slot> {
+		let Some(cursor) = self.cursor.take() else {
+			return Vec::new();
+		};
+
+		if button == 0 {
+			return vec![cursor];
+		}
+
+		self.cursor = Some(cursor.with_amount(cursor.amount - 1)).filter(|o| o.amount > 0);
+		vec![cursor.with_amount(1)]
+	}
+
+	fn pickup(&mut self, index: usize, button: i8, items: &ItemRegistry) {
+		let item = self.get_slot(index);
+		let cursor = self.cursor.take();
+
+		let (item, cursor) = match (item, cursor) {
+			(None, None) => (None, None),
+			// Левой кнопкой берем все, правой половину с округлением вверх
+			(Some(item), None) => {
+				let taken = if button == 0 {
+					item.amount
+				} else {
+					(item.amount + 1) / 2
+				};
+				(
+					Some(item.with_amount(item.amount - taken)),
+					Some(item.with_amount(taken)),
+				)
+			}
+			(None, Some(cursor)) if self.may_place(index, &cursor, items) => {
+				let wanted = if button == 0 { cursor.amount } else { 1 };
+				let placed = wanted.min(self.slot_limit(index, &cursor, items));
+				(
+					Some(cursor.with_amount(placed)),
+					Some(cursor.with_amount(cursor.amount - placed)),
+				)
+			}
+			(Some(item), Some(cursor)) if self.may_place(index, &cursor, items) => {
+				if item.is_similar(&cursor) {
+					let wanted = if button == 0 { cursor.amount } else { 1 };
+					let placed = wanted
+						.min(self.slot_limit(index, &cursor, items) - item.amount)
+						.max(0);
+					(
+						Some(item.with_amount(item.amount + placed)),
+						Some(cursor.with_amount(cursor.amount - placed)),
+					)
+				} else if cursor.amount <= self.slot_limit(index, &cursor, items) {
+					(Some(cursor), Some(item))
+				} else {
+					(Some(item), Some(cursor))
+				}
+			}
+			// Из слота результата можно только забрать, и только если все влезет на курсор
+			(Some(item), Some(cursor))
+				if item.is_similar(&cursor)
+					&& cursor.amount + item.amount <= cursor.max_stack_size(items) =>
+			{
+				let amount = cursor.amount + item.amount;
+				(None, Some(cursor.with_amount(amount)))
+			}
+			(item, cursor) => (item, cursor),
+		};
+
+		self.set_slot(index, item);
+		self.cursor = cursor.filter(|o| o.amount > 0);
+	}
+
+	// Слоты куда шифт-клик переносит предмет из слота окна, в порядке заполнения
+	fn quick_move_targets(&self, index: usize, item: &Slot, items: &ItemRegistry) -> Vec<usize> {
+		let Some(container) = self.container() else {
+			let equipment = item.equipment_slot(items);
+
+			// Броню и щит из инвентаря сначала пробуем надеть
+			let equip = match equipment {
+				Some(OFFHAND) => Some(OFFHAND_SLOT),
+				Some(slot) => armor_slot(slot),
+				None => None,
+			}
+			.filter(|o| self.inventory.get(*o).is_none())
+			.filter(|_| (MAIN_START..OFFHAND_SLOT).contains(&index));
+
+			return match (equip, index) {
+				(Some(slot), _) => vec![slot],
+				(None, MAIN_START..HOTBAR_START) => (HOTBAR_START..OFFHAND_SLOT).collect(),
+				(None, HOTBAR_START..OFFHAND_SLOT) => (MAIN_START..HOTBAR_START).collect(),
+				(None, CRAFTING_RESULT_SLOT) => (MAIN_START..OFFHAND_SLOT).rev().collect(),
+				_ => (MAIN_START..OFFHAND_SLOT).collect(),
+			};
+		};
+
+		let size = container.size();
+
+		if index < size {
+			// Из контейнера в инвентарь, начиная с конца хотбара
+			(size..self.window_size()).rev().collect()
+		} else {
+			(0..size).collect()
+		}
+	}
+
+	fn quick_move(&mut self, index: usize, items: &ItemRegistry) {
+		let Some(item) = self.get_slot(index) else {
+			return;
+		};
+
+		let targets = self.quick_move_targets(index, &item, items);
+		let rest = self.move_to(item, &targets, items);
+		self.set_slot(index, rest);
+	}
+
+	// Сначала доливает в похожие стаки, потом кладет в пустые слоты. Возвращает остаток
+	fn move_to(&mut self, mut item: Slot, targets: &[usize], items: &ItemRegistry) -> Option<Slot> {
+		for empty in [false, true] {
+			for index in targets {
+				if item.amount <= 0 {
+					return None;
+				}
+
+				if !self.may_place(*index, &item, items) {
+					continue;
+				}
+
+				let limit = self.slot_limit(*index, &item, items);
+
+				match self.get_slot(*index) {
+					Some(stack) if !empty && stack.is_similar(&item) => {
+						let moved = (limit - stack.amount).clamp(0, item.amount);
+						self.set_slot(*index, Some(stack.with_amount(stack.amount + moved)));
+						item.amount -= moved;
+					}
+					None if empty => {
+						let moved = limit.min(item.amount);
+						self.set_slot(*index, Some(item.with_amount(moved)));
+						item.amount -= moved;
+					}
+					_ => {}
+				}
+			}
+		}
+
+		Some(item).filter(|o| o.amount > 0)
+	}
+
+	// Цифры 1-9 меняют слот с хотбаром, F - со второй рукой
+	fn swap(&mut self, index: usize, button: i8, items: &ItemRegistry) {
+		let hotbar_slot = match button {
+			0..=8 => HOTBAR_START + button as usize,
+			OFFHAND_BUTTON => OFFHAND_SLOT,
+			_ => return,
+		};
+
+		if self.inventory_slot(index) == Some(hotbar_slot) {
+			return;
+		}
+
+		let item = self.get_slot(index);
+		let hotbar = self.inventory.get(hotbar_slot).cloned();
+
+		match (item, hotbar) {
+			(None, None) => {}
+			(Some(item), None) => {
+				self.inventory.set(hotbar_slot, Some(item));
+				self.set_slot(index, None);
+			}
+			(None, Some(hotbar)) if self.may_place(index, &hotbar, items) => {
+				let placed = hotbar.amount.min(self.slot_limit(index, &hotbar, items));
+				self.set_slot(index, Some(hotbar.with_amount(placed)));
+				self.inventory.set(
+					hotbar_slot,
+					Some(hotbar.with_amount(hotbar.amount - placed)),
+				);
+			}
+			(Some(item), Some(hotbar))
+				if self.may_place(index, &hotbar, items)
+					&& hotbar.amount <= self.slot_limit(index, &hotbar, items) =>
+			{
+				self.inventory.set(hotbar_slot, Some(item));
+				self.set_slot(index, Some(hotbar));
+			}
+			_ => {}
+		}
+	}
+
+	// Q выкидывает один предмет, Ctrl+Q весь стак
+	fn throw(&mut self, index: usize, button: i8) -> Vec<Slot> {
+		let Some(item) = self.get_slot(index) else {
+			return Vec::new();
+		};
+
+		let amount = if button == 0 { 1 } else { item.amount };
+		self.set_slot(index, Some(item.with_amount(item.amount - amount)));
+
+		vec![item.with_amount(amount)]
+	}
+
+	// Растягивание идет тремя стадиями: начало, по пакету на каждый слот, конец
+	fn quick_craft(
+		&mut self,
+		index: Option<usize>,
+		button: i8,
+		creative: bool,
+		items: &ItemRegistry,
+	) {
+		let stage = button & 3;
+		let kind = (button >> 2) & 3;
+
+		match (stage, self.drag.take()) {
+			(0, None) if kind < 2 || (kind == 2 && creative) => {
+				self.drag = Some(Drag {
+					kind,
+					slots: Vec::new(),
+				});
+			}
+			(1, Some(mut drag)) => {
+				if let (Some(index), Some(cursor)) = (index, &self.cursor)
+					&& drag.kind == kind
+					&& !drag.slots.contains(&index)
+					&& self.may_place(index, cursor, items)
+					&& (kind == 2 || cursor.amount > drag.slots.len() as i32)
+					&& self.get_slot(index).is_none_or(|o| {
+						o.is_similar(cursor) && o.amount < self.slot_limit(index, cursor, items)
+					}) {
+					drag.slots.push(index);
+				}
+				self.drag = Some(drag);
+			}
+			(2, Some(drag)) if drag.kind == kind => self.finish_drag(drag, items),
+			// Неправильный порядок стадий сбрасывает растягивание
+			_ => {}
+		}
+	}
+
+	fn finish_drag(&mut self, drag: Drag, items: &ItemRegistry) {
+		let Some(cursor) = self.cursor.clone() else {
+			return;
+		};
+
+		// Растянули на один слот - это обычный клик
+		if drag.slots.len() == 1 {
+			self.pickup(drag.slots[0], drag.kind.min(1), items);
+			return;
+		}
+
+		let mut remaining = cursor.amount;
+
+		for index in &drag.slots {
+			let existing = self.get_slot(*index).map(|o| o.amount).unwrap_or_default();
+			let add = match drag.kind {
+				0 => cursor.amount / drag.slots.len() as i32,
+				1 => 1,
+				_ => cursor.max_stack_size(items),
+			};
+			let amount = (existing + add).min(self.slot_limit(*index, &cursor, items));
+
+			remaining -= amount - existing;
+			self.set_slot(*index, Some(cursor.with_amount(amount)));
+		}
+
+		self.cursor = Some(cursor.with_amount(remaining)).filter(|o| o.amount > 0);
+	}
+
+	// Двойной клик собирает похожие предметы на курсор, сначала из неполных стаков
+	fn pickup_all(&mut self, index: usize, button: i8, items: &ItemRegistry) {
+		let Some(mut cursor) = self.cursor.take() else {
+			return;
+		};
+
+		// Двойной клик по предмету - это второй клик для взятия, а не сбор
+		if self.get_slot(index).is_some() {
+			self.cursor = Some(cursor);
+			return;
+		}
+
+		let max = cursor.max_stack_size(items);
+		let mut order = (0..self.window_size()).collect::<Vec<usize>>();
+		if button != 0 {
+			order.reverse();
+		}
+
+		for full in [false, true] {
+			for index in &order {
+				if cursor.amount >= max {
+					break;
+				}
+
+				if self.is_result_slot(*index) {
+					continue;
+				}
+
+				let Some(item) = self.get_slot(*index) else {
+					continue;
+				};
+
+				if !item.is_similar(&cursor) || (!full && item.amount >= item.max_stack_size(items)) {
+					continue;
+				}
+
+				let taken = item.amount.min(max - cursor.amount);
+				cursor.amount += taken;
+				self.set_slot(*index, Some(item.with_amount(item.amount - taken)));
+			}
+		}
+
+		self.cursor = Some(cursor);
+	}
+}
@@ -1,2 +1,7 @@
+pub mod abilities;
+pub mod combat;
 pub mod context;
+pub mod data;
+pub mod health;
 pub mod helper;
+pub mod inventory;
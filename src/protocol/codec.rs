@@ -0,0 +1,310 @@
+use std::io::Read;
+
+use craftflow_nbt::DynNBT;
+use rust_mc_proto::{DataReader, DataWriter, Packet};
+use uuid::Uuid;
+
+use crate::{
+	ServerError,
+	data::{ReadWriteNBT, component::TextComponent},
+};
+
+// Запись значения в пакет в формате протокола
+pub trait Encode {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError>;
+}
+
+// Чтение значения из пакета в формате протокола
+pub trait Decode: Sized {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError>;
+}
+
+// Типизированный пакет, привязан к айди из packet_id.rs
+pub trait PacketData: Encode + Decode {
+	const ID: u8;
+
+	fn to_packet(&self) -> Result<Packet, ServerError> {
+		let mut packet = Packet::empty(Self::ID);
+		self.encode(&mut packet)?;
+		Ok(packet)
+	}
+
+	fn from_packet(packet: &mut Packet) -> Result<Self, ServerError> {
+		if packet.id() != Self::ID {
+			return Err(ServerError::UnexpectedPacket(packet.id()));
+		}
+		Self::decode(packet)
+	}
+}
+
+/// Генерирует структуру и реализацию Encode/Decode для нее
+///
+/// Поля пишутся и читаются строго в порядке объявления
+///
+/// ```ignore
+/// codec! {
+///     pub struct KnownPack {
+///         pub namespace: String,
+///         pub id: String,
+///         pub version: String,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! codec {
+	(
+		$(#[$meta:meta])*
+		pub struct $name:ident {
+			$( $(#[$field_meta:meta])* pub $field:ident : $ty:ty ),* $(,)?
+		}
+	) => {
+		$(#[$meta])*
+		#[derive(Debug, Clone)]
+		pub struct $name {
+			$( $(#[$field_meta])* pub $field: $ty ),*
+		}
+
+		impl $crate::protocol::codec::Encode for $name {
+			#[allow(unused_variables)]
+			fn encode(
+				&self,
+				packet: &mut rust_mc_proto::Packet,
+			) -> Result<(), $crate::ServerError> {
+				$( $crate::protocol::codec::Encode::encode(&self.$field, packet)?; )*
+				Ok(())
+			}
+		}
+
+		impl $crate::protocol::codec::Decode for $name {
+			#[allow(unused_variables)]
+			fn decode(packet: &mut rust_mc_proto::Packet) -> Result<Self, $crate::ServerError> {
+				Ok(Self {
+					$( $field: $crate::protocol::codec::Decode::decode(packet)?, )*
+				})
+			}
+		}
+	};
+}
+
+// VarInt, в протоколе используется почти везде вместо int
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct VarInt(pub i32);
+
+// VarLong
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct VarLong(pub i64);
+
+// Угол в градусах, в протоколе передается одним байтом (1/256 оборота)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Angle(pub f32);
+
+// Все оставшиеся байты пакета, обычно это данные плагин-каналов
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RemainingBytes(pub Vec<u8>);
+
+impl Encode for VarInt {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		Ok(packet.write_varint(self.0)?)
+	}
+}
+
+impl Decode for VarInt {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(VarInt(packet.read_varint()?))
+	}
+}
+
+impl Encode for VarLong {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		Ok(packet.write_varlong(self.0)?)
+	}
+}
+
+impl Decode for VarLong {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(VarLong(packet.read_varlong()?))
+	}
+}
+
+impl Encode for Angle {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		// Угол больше 180 должен перейти в отрицательный байт, а не упереться в 127
+		let steps = (self.0 / 360.0 * 256.0).floor() as i32;
+		Ok(packet.write_signed_byte(steps as i8)?)
+	}
+}
+
+impl Decode for Angle {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(Angle(packet.read_signed_byte()? as f32 / 256.0 * 360.0))
+	}
+}
+
+impl Encode for RemainingBytes {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		Ok(packet.write_bytes(&self.0)?)
+	}
+}
+
+impl Decode for RemainingBytes {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let mut data = Vec::new();
+		packet
+			.get_mut()
+			.read_to_end(&mut data)
+			.map_err(|_| ServerError::WrongPacket)?;
+		Ok(RemainingBytes(data))
+	}
+}
+
+// Генерирует Encode/Decode для простых типов через методы DataReader/DataWriter
+macro_rules! primitive_codec {
+	($($ty:ty => $read:ident, $write:ident;)*) => {
+		$(
+			impl Encode for $ty {
+				fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+					Ok(packet.$write(*self)?)
+				}
+			}
+
+			impl Decode for $ty {
+				fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+					Ok(packet.$read()?)
+				}
+			}
+		)*
+	};
+}
+
+primitive_codec! {
+	bool => read_boolean, write_boolean;
+	u8 => read_byte, write_byte;
+	i8 => read_signed_byte, write_signed_byte;
+	u16 => read_unsigned_short, write_unsigned_short;
+	i16 => read_short, write_short;
+	i32 => read_int, write_int;
+	i64 => read_long, write_long;
+	f32 => read_float, write_float;
+	f64 => read_double, write_double;
+}
+
+impl Encode for String {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		Ok(packet.write_string(self)?)
+	}
+}
+
+impl Decode for String {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(packet.read_string()?)
+	}
+}
+
+impl Encode for Uuid {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		Ok(packet.write_uuid(self)?)
+	}
+}
+
+impl Decode for Uuid {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(packet.read_uuid()?)
+	}
+}
+
+impl Encode for TextComponent {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_nbt(self)
+	}
+}
+
+impl Decode for TextComponent {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		packet.read_nbt()
+	}
+}
+
+impl Encode for DynNBT {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_nbt(self)
+	}
+}
+
+impl Decode for DynNBT {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		packet.read_nbt()
+	}
+}
+
+// Массив с длиной в VarInt перед ним
+impl<T: Encode> Encode for Vec<T> {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_usize_varint(self.len())?;
+		for value in self {
+			value.encode(packet)?;
+		}
+		Ok(())
+	}
+}
+
+impl<T: Decode> Decode for Vec<T> {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let length = packet.read_usize_varint()?;
+		let mut values = Vec::with_capacity(length.min(4096));
+		for _ in 0..length {
+			values.push(T::decode(packet)?);
+		}
+		Ok(values)
+	}
+}
+
+// Опциональное значение с булевым флагом перед ним
+impl<T: Encode> Encode for Option<T> {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_boolean(self.is_some())?;
+		if let Some(value) = self {
+			value.encode(packet)?;
+		}
+		Ok(())
+	}
+}
+
+impl<T: Decode> Decode for Option<T> {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		if packet.read_boolean()? {
+			Ok(Some(T::decode(packet)?))
+		} else {
+			Ok(None)
+		}
+	}
+}
+
+// Массив байт фиксированной длины, например подписи сообщений
+impl<const N: usize> Encode for [u8; N] {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		Ok(packet.write_bytes(self)?)
+	}
+}
+
+impl<const N: usize> Decode for [u8; N] {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		packet
+			.read_bytes(N)?
+			.try_into()
+			.map_err(|_| ServerError::WrongPacket)
+	}
+}
+
+// Пара значений подряд, например ключ и значение в словаре
+impl<A: Encode, B: Encode> Encode for (A, B) {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		self.0.encode(packet)?;
+		self.1.encode(packet)
+	}
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok((A::decode(packet)?, B::decode(packet)?))
+	}
+}
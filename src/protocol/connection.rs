@@ -0,0 +1,389 @@
+// Неблокирующее соединение с клиентом поверх tokio
+// Запись идет через очередь в отдельную задачу, чтение делает задача чтения клиента
+// Сжатие и шифрование применяются сразу при отправке, чтобы не зависеть от очереди
+
+use std::{
+	io::{Cursor, ErrorKind},
+	net::SocketAddr,
+	sync::{
+		Arc, Mutex, RwLock,
+		atomic::{AtomicBool, AtomicUsize, Ordering},
+	},
+	time::Duration,
+};
+
+use aes::Aes128;
+use cfb8::{
+	Decryptor, Encryptor,
+	cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, generic_array::GenericArray},
+};
+use rust_mc_proto::{Packet, read_packet, write_packet};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::{
+		TcpStream,
+		tcp::{OwnedReadHalf, OwnedWriteHalf},
+	},
+	sync::{
+		Notify,
+		mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+	},
+	time::timeout,
+};
+
+use log::warn;
+
+use crate::{
+	ServerError,
+	config::{BindConfig, QueueOverflow},
+	protocol::packet_id::clientbound::play,
+};
+
+// Ванилла не принимает пакеты больше 2 мб
+const MAX_PACKET_SIZE: usize = 2097151;
+
+// Сколько читаем из сокета за раз
+const READ_CHUNK_SIZE: usize = 4096;
+
+// Сжатие как у ваниллы
+const COMPRESSION_LEVEL: u32 = 6;
+
+// Сколько байт склеиваем в одну запись в сокет
+const MAX_BATCH_SIZE: usize = 65536;
+
+/// Пакеты режима Play, которые можно выкинуть если клиент не успевает
+///
+/// Относительные перемещения сюда не входят, без них позиции сущностей разъедутся
+pub const NON_ESSENTIAL_PACKETS: [u8; 11] = [
+	play::ENTITY_ANIMATION,
+	play::HURT_ANIMATION,
+	play::UPDATE_ENTITY_ROTATION,
+	play::SET_HEAD_ROTATION,
+	play::SET_ENTITY_VELOCITY,
+	play::PARTICLE,
+	play::WORLD_EVENT,
+	play::SOUND_EFFECT,
+	play::ENTITY_SOUND_EFFECT,
+	play::DAMAGE_EVENT,
+	play::UPDATE_TIME,
+];
+
+/// Состояние соединения общее для него самого и задачи записи
+struct Shared {
+	alive: AtomicBool,
+	shutdown: Notify,
+	// Сколько пакетов ждет в очереди отправки
+	queued: AtomicUsize,
+	// Соединение оборвано без дописывания очереди
+	aborted: Notify,
+}
+
+impl Shared {
+	fn kill(&self) {
+		self.alive.store(false, Ordering::SeqCst);
+		self.shutdown.notify_one();
+	}
+}
+
+/// Читающая половина сокета, живет в задаче чтения клиента
+pub struct PacketReader {
+	stream: OwnedReadHalf,
+	buffer: Vec<u8>,
+}
+
+pub struct Connection {
+	addr: SocketAddr,
+	compression: RwLock<Option<usize>>,
+	// Лок шифратора держится и на время отправки в канал, так порядок байт не собьется
+	encryptor: Mutex<Option<Encryptor<Aes128>>>,
+	decryptor: Mutex<Option<Decryptor<Aes128>>>,
+	outbound: Mutex<Option<UnboundedSender<Vec<u8>>>>,
+	max_queue_size: usize,
+	queue_overflow: QueueOverflow,
+	shared: Arc<Shared>,
+}
+
+impl Connection {
+	/// Разбивает сокет на чтение и запись, запись сразу уходит в свою задачу
+	///
+	/// addr - адрес клиента, за балансировщиком он отличается от адреса сокета.
+	/// Вызывать только внутри рантайма tokio
+	pub fn new(
+		stream: TcpStream,
+		addr: SocketAddr,
+		config: &BindConfig,
+	) -> Result<(Connection, PacketReader), ServerError> {
+		stream.set_nodelay(true)?;
+
+		let (read, write) = stream.into_split();
+		let (sender, receiver) = unbounded_channel();
+
+		let shared = Arc::new(Shared {
+			alive: AtomicBool::new(true),
+			shutdown: Notify::new(),
+			queued: AtomicUsize::new(0),
+			aborted: Notify::new(),
+		});
+
+		tokio::spawn(run_write_loop(
+			write,
+			receiver,
+			Duration::from_secs(config.timeout),
+			shared.clone(),
+		));
+
+		Ok((
+			Connection {
+				addr,
+				compression: RwLock::new(None),
+				encryptor: Mutex::new(None),
+				decryptor: Mutex::new(None),
+				outbound: Mutex::new(Some(sender)),
+				max_queue_size: config.max_queue_size,
+				queue_overflow: config.queue_overflow,
+				shared,
+			},
+			PacketReader {
+				stream: read,
+				buffer: Vec::new(),
+			},
+		))
+	}
+
+	pub fn addr(&self) -> SocketAddr {
+		self.addr
+	}
+
+	pub fn is_alive(&self) -> bool {
+		self.shared.alive.load(Ordering::SeqCst)
+	}
+
+	/// Закрывает соединение, уже отправленные пакеты еще допишутся в сокет
+	pub fn close(&self) {
+		self.outbound.lock().unwrap().take();
+		self.shared.kill();
+	}
+
+	/// Обрывает соединение не дожидаясь отправки очереди
+	pub fn abort(&self) {
+		self.outbound.lock().unwrap().take();
+		self.shared.aborted.notify_one();
+		self.shared.kill();
+	}
+
+	/// Сколько пакетов ждет отправки
+	pub fn queued(&self) -> usize {
+		self.shared.queued.load(Ordering::SeqCst)
+	}
+
+	pub fn compression(&self) -> Option<usize> {
+		*self.compression.read().unwrap()
+	}
+
+	pub fn set_compression(&self, threshold: Option<usize>) {
+		*self.compression.write().unwrap() = threshold;
+	}
+
+	/// Включает шифрование, общий секрет используется и как ключ, и как IV
+	///
+	/// Шифрование включается после Encryption Response и уже не выключается
+	pub fn set_encryption(&self, shared_secret: &[u8]) -> Result<(), ServerError> {
+		*self.encryptor.lock().unwrap() = Some(
+			Encryptor::new_from_slices(shared_secret, shared_secret)
+				.map_err(|_| ServerError::Encryption)?,
+		);
+		*self.decryptor.lock().unwrap() = Some(
+			Decryptor::new_from_slices(shared_secret, shared_secret)
+				.map_err(|_| ServerError::Encryption)?,
+		);
+		Ok(())
+	}
+
+	pub fn is_encrypted(&self) -> bool {
+		self.encryptor.lock().unwrap().is_some()
+	}
+
+	/// Ставит пакет в очередь на отправку, не блокирует
+	///
+	/// Необязательный пакет может быть выкинут если клиент не успевает, см. QueueOverflow
+	pub fn write_packet(&self, packet: &Packet, essential: bool) -> Result<(), ServerError> {
+		let queued = self.queued();
+
+		if queued >= self.max_queue_size {
+			let kick = match self.queue_overflow {
+				QueueOverflow::Kick => true,
+				QueueOverflow::DropNonEssential if !essential => return Ok(()),
+				QueueOverflow::DropNonEssential => queued >= self.max_queue_size * 2,
+			};
+
+			if kick {
+				warn!(
+					"Клиент {} не успевает принимать пакеты ({queued} в очереди), отключаем",
+					self.addr
+				);
+				self.abort();
+				return Err(ServerError::ConnectionClosed);
+			}
+		}
+
+		let mut data = Vec::new();
+		write_packet(&mut data, self.compression(), COMPRESSION_LEVEL, packet)?;
+
+		let mut encryptor = self.encryptor.lock().unwrap();
+
+		if let Some(encryptor) = encryptor.as_mut() {
+			for byte in data.iter_mut() {
+				encryptor.encrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+			}
+		}
+
+		// Считаем до отправки, иначе задача записи может вычесть раньше чем мы прибавим
+		self.shared.queued.fetch_add(1, Ordering::SeqCst);
+
+		match self.outbound.lock().unwrap().as_ref() {
+			Some(sender) if sender.send(data).is_ok() => Ok(()),
+			_ => {
+				self.shared.queued.fetch_sub(1, Ordering::SeqCst);
+				Err(ServerError::ConnectionClosed)
+			}
+		}
+	}
+
+	/// Читает следующий пакет, прерывается при закрытии соединения
+	///
+	/// Таймаут считается на весь пакет, None - ждать сколько угодно
+	pub async fn read_packet(
+		&self,
+		reader: &mut PacketReader,
+		read_timeout: Option<Duration>,
+	) -> Result<Packet, ServerError> {
+		let result = tokio::select! {
+			result = self.read_frame(reader, read_timeout) => result,
+			_ = self.shared.shutdown.notified() => Err(ServerError::ConnectionClosed),
+		};
+
+		if result.is_err() {
+			self.shared.kill();
+		}
+
+		result
+	}
+
+	async fn read_frame(
+		&self,
+		reader: &mut PacketReader,
+		read_timeout: Option<Duration>,
+	) -> Result<Packet, ServerError> {
+		let read = async {
+			loop {
+				if let Some(packet) = self.decode_frame(&mut reader.buffer)? {
+					return Ok(packet);
+				}
+
+				let mut chunk = [0; READ_CHUNK_SIZE];
+				let size = match reader.stream.read(&mut chunk).await {
+					Ok(0) => return Err(ServerError::ConnectionClosed),
+					Ok(size) => size,
+					// Клиент просто закрыл игру, это не ошибка
+					Err(error)
+						if matches!(
+							error.kind(),
+							ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+						) =>
+					{
+						return Err(ServerError::ConnectionClosed);
+					}
+					Err(error) => return Err(error.into()),
+				};
+
+				// Расшифровываем сразу, шифр должен идти вместе с потоком байт
+				if let Some(decryptor) = self.decryptor.lock().unwrap().as_mut() {
+					for byte in chunk[..size].iter_mut() {
+						decryptor.decrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+					}
+				}
+
+				reader.buffer.extend_from_slice(&chunk[..size]);
+			}
+		};
+
+		match read_timeout {
+			Some(duration) => timeout(duration, read)
+				.await
+				.map_err(|_| ServerError::Io(ErrorKind::TimedOut.into()))?,
+			None => read.await,
+		}
+	}
+
+	// Достает из буфера целый пакет, если он уже весь пришел
+	fn decode_frame(&self, buffer: &mut Vec<u8>) -> Result<Option<Packet>, ServerError> {
+		let mut length = 0usize;
+		let mut prefix = 0;
+
+		loop {
+			let Some(byte) = buffer.get(prefix) else {
+				return Ok(None);
+			};
+			length |= ((byte & 0x7F) as usize) << (7 * prefix);
+			prefix += 1;
+			if byte & 0x80 == 0 {
+				break;
+			}
+			if prefix >= 3 {
+				return Err(ServerError::WrongPacket);
+			}
+		}
+
+		if length > MAX_PACKET_SIZE {
+			return Err(ServerError::WrongPacket);
+		}
+
+		if buffer.len() < prefix + length {
+			return Ok(None);
+		}
+
+		let frame = buffer.drain(..prefix + length).collect::<Vec<u8>>();
+
+		Ok(Some(read_packet(
+			&mut Cursor::new(frame),
+			self.compression(),
+		)?))
+	}
+}
+
+// Пишет в сокет все что приходит в канал, пока канал не закроют
+// Все что уже накопилось в очереди склеивается и пишется за один раз
+async fn run_write_loop(
+	mut stream: OwnedWriteHalf,
+	mut receiver: UnboundedReceiver<Vec<u8>>,
+	write_timeout: Duration,
+	shared: Arc<Shared>,
+) {
+	while let Some(mut batch) = receiver.recv().await {
+		let mut count = 1;
+
+		while batch.len() < MAX_BATCH_SIZE {
+			match receiver.try_recv() {
+				Ok(data) => {
+					batch.extend_from_slice(&data);
+					count += 1;
+				}
+				Err(_) => break,
+			}
+		}
+
+		let written = tokio::select! {
+			result = timeout(write_timeout, stream.write_all(&batch)) => matches!(result, Ok(Ok(()))),
+			_ = shared.aborted.notified() => false,
+		};
+
+		if !written {
+			shared.kill();
+			return;
+		}
+
+		shared.queued.fetch_sub(count, Ordering::SeqCst);
+	}
+
+	let _ = stream.shutdown().await;
+}
@@ -0,0 +1,90 @@
+use rand::{RngCore, rngs::OsRng};
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, pkcs8::EncodePublicKey};
+use sha1::{Digest, Sha1};
+
+use crate::ServerError;
+
+// Размер RSA ключа, ванилла использует 1024 бита
+pub const RSA_KEY_BITS: usize = 1024;
+
+// Ключи сервера для Encryption Request
+// Генерируются один раз при запуске сервера
+pub struct ServerKeys {
+	private_key: RsaPrivateKey,
+	public_key_der: Vec<u8>,
+}
+
+impl ServerKeys {
+	pub fn generate() -> Result<ServerKeys, ServerError> {
+		let private_key =
+			RsaPrivateKey::new(&mut OsRng, RSA_KEY_BITS).map_err(|_| ServerError::Encryption)?;
+		let public_key_der = private_key
+			.to_public_key()
+			.to_public_key_der()
+			.map_err(|_| ServerError::Encryption)?
+			.into_vec();
+
+		Ok(ServerKeys {
+			private_key,
+			public_key_der,
+		})
+	}
+
+	/// Публичный ключ в формате ASN.1 DER, именно его ждет клиент
+	pub fn public_key_der(&self) -> &[u8] {
+		&self.public_key_der
+	}
+
+	pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, ServerError> {
+		self
+			.private_key
+			.decrypt(Pkcs1v15Encrypt, data)
+			.map_err(|_| ServerError::Encryption)
+	}
+}
+
+/// Генерирует случайный verify token для Encryption Request
+pub fn generate_verify_token() -> [u8; 4] {
+	let mut token = [0; 4];
+	OsRng.fill_bytes(&mut token);
+	token
+}
+
+/// Хеш сервера для запроса к сессионному серверу
+///
+/// Это SHA1 от айди сервера, общего секрета и публичного ключа,
+/// записанный как знаковое число в hex (майнкрафт такой майнкрафт)
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+	let mut hasher = Sha1::new();
+	hasher.update(server_id.as_bytes());
+	hasher.update(shared_secret);
+	hasher.update(public_key);
+	let mut digest: [u8; 20] = hasher.finalize().into();
+
+	let negative = digest[0] & 0x80 != 0;
+
+	if negative {
+		// Дополнительный код: инвертируем и прибавляем единицу
+		let mut carry = true;
+		for byte in digest.iter_mut().rev() {
+			*byte = !*byte;
+			if carry {
+				let (value, overflow) = byte.overflowing_add(1);
+				*byte = value;
+				carry = overflow;
+			}
+		}
+	}
+
+	let hex = digest
+		.iter()
+		.map(|b| format!("{b:02x}"))
+		.collect::<String>();
+	let hex = hex.trim_start_matches('0');
+
+	if negative {
+		format!("-{hex}")
+	} else {
+		hex.to_string()
+	}
+}
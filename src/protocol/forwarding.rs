@@ -0,0 +1,140 @@
+// Форвардинг данных игрока от прокси
+// Прокси сам авторизует игрока, а нам передает его настоящий айпи, UUID и скин
+
+use std::{net::IpAddr, sync::Arc};
+
+use hmac::{Hmac, Mac};
+use rust_mc_proto::Packet;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+	ServerError, codec,
+	data::{Property, component::TextComponent},
+	player::context::{ClientContext, PlayerInfo},
+	protocol::codec::{Decode, VarInt},
+};
+
+/// Канал login plugin request для modern forwarding
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+
+// MODERN_DEFAULT, ключи чата с 1.19.3 не передаются, так что выше не нужно
+const VELOCITY_FORWARDING_VERSION: u8 = 1;
+
+// Длина подписи HMAC-SHA256 в начале ответа
+const VELOCITY_SIGNATURE_LENGTH: usize = 32;
+
+// Айди запроса, ответ на него ждем сразу, так что любое число подойдет
+const VELOCITY_MESSAGE_ID: i32 = 0x56;
+
+codec! {
+	/// Данные игрока от Velocity, идут после подписи
+	pub struct VelocityPlayerInfo {
+		pub version: VarInt,
+		pub address: String,
+		pub uuid: Uuid,
+		pub name: String,
+		pub properties: Vec<Property>,
+	}
+}
+
+/// Данные игрока от BungeeCord, приходят в адресе сервера из рукопожатия
+#[derive(Debug, Clone)]
+pub struct BungeeCordForwarding {
+	pub server_address: String,
+	pub ip: IpAddr,
+	pub uuid: Uuid,
+	pub properties: Vec<Property>,
+}
+
+/// Разбирает адрес вида `host\0ip\0uuid\0properties`, свойства могут отсутствовать
+///
+/// None если прокси не дописал данные (форвардинг выключен на стороне BungeeCord)
+pub fn parse_bungeecord(server_address: &str) -> Option<BungeeCordForwarding> {
+	let mut parts = server_address.split('\0');
+
+	let server_address = parts.next()?.to_string();
+	let ip = parts.next()?.parse().ok()?;
+	let uuid = Uuid::parse_str(parts.next()?).ok()?;
+	let properties = match parts.next() {
+		Some(properties) => serde_json::from_str(properties).ok()?,
+		None => Vec::new(),
+	};
+
+	Some(BungeeCordForwarding {
+		server_address,
+		ip,
+		uuid,
+		properties,
+	})
+}
+
+/// Запрашивает данные игрока у Velocity и проверяет подпись секретом из конфига
+///
+/// Возвращает настоящий айпи игрока и его профиль
+pub fn velocity_forward(client: Arc<ClientContext>) -> Result<(IpAddr, PlayerInfo), ServerError> {
+	let helper = client.protocol_helper();
+
+	let (_, data) = helper.send_login_plugin_request(
+		VELOCITY_MESSAGE_ID,
+		VELOCITY_CHANNEL,
+		&[VELOCITY_FORWARDING_VERSION],
+	)?;
+
+	// Клиент без прокси не знает этот канал и отвечает пустым ответом
+	let Some(data) = data else {
+		helper.disconnect(TextComponent::new(
+			"This server requires you to connect with Velocity.".to_string(),
+		))?;
+		return Err(ServerError::Forwarding);
+	};
+
+	if data.len() < VELOCITY_SIGNATURE_LENGTH
+		|| !verify_velocity_signature(&client.server.config.proxy.secret, &data)
+	{
+		helper.disconnect(TextComponent::new(
+			"Unable to verify player details.".to_string(),
+		))?;
+		return Err(ServerError::Forwarding);
+	}
+
+	let info = VelocityPlayerInfo::decode(&mut Packet::from_bytes(
+		0,
+		&data[VELOCITY_SIGNATURE_LENGTH..],
+	))?;
+
+	// Мы просили версию не выше нашей, другая значит другой формат данных
+	if info.version.0 != VELOCITY_FORWARDING_VERSION as i32 {
+		helper.disconnect(TextComponent::new(format!(
+			"Unsupported forwarding version {}, this server supports {VELOCITY_FORWARDING_VERSION}.",
+			info.version.0
+		)))?;
+		return Err(ServerError::Forwarding);
+	}
+
+	let ip = info.address.parse().map_err(|_| ServerError::Forwarding)?;
+
+	Ok((
+		ip,
+		PlayerInfo {
+			name: info.name,
+			uuid: info.uuid,
+			properties: info.properties,
+		},
+	))
+}
+
+// Первые 32 байта - HMAC-SHA256 от остальных данных
+//
+// С пустым секретом подпись может сделать кто угодно, такую не принимаем
+fn verify_velocity_signature(secret: &str, data: &[u8]) -> bool {
+	if secret.is_empty() {
+		return false;
+	}
+	let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+		return false;
+	};
+	let (signature, payload) = data.split_at(VELOCITY_SIGNATURE_LENGTH);
+	mac.update(payload);
+	mac.verify_slice(signature).is_ok()
+}
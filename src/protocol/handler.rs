@@ -1,17 +1,29 @@
-use std::{
-	io::{Cursor, Read},
-	sync::Arc,
-};
+use std::{io::Cursor, sync::Arc};
 
 use crate::{
 	ServerError,
+	config::ForwardingMode,
+	data::component::TextComponent,
+	play::get_offline_uuid,
 	player::context::{ClientContext, ClientInfo, Handshake, PlayerInfo},
 };
-use rust_mc_proto::{DataReader, DataWriter, Packet};
+use rust_mc_proto::{DataReader, DataWriter};
 
 use crate::trigger_event;
 
-use super::{ConnectionState, packet_id::*};
+use super::{
+	ConnectionState,
+	codec::{RemainingBytes, VarInt},
+	encryption::{generate_verify_token, server_hash},
+	forwarding::{parse_bungeecord, velocity_forward},
+	packets::{
+		configuration::{clientbound as config_clientbound, serverbound as config_serverbound},
+		handshake::serverbound as handshake_serverbound,
+		login::{clientbound as login_clientbound, serverbound as login_serverbound},
+		status::{ServerboundStatusPacket, clientbound as status_clientbound},
+	},
+	version::{ProtocolVersion, supported_version_names},
+};
 
 // TODO: move brand to the config
 pub const BRAND: &str = "rust_mc_serv";
@@ -23,20 +35,30 @@ pub fn handle_connection(
 	// Получение пакетов производится через client.conn(),
 	// ВАЖНО: не помещать сам client.conn() в переменные,
 	// он должен сразу убиваться иначе соединение гдето задедлочится
-	let mut packet = client.read_packet(&[serverbound::handshake::HANDSHAKE])?;
-
-	let protocol_version = packet.read_varint()?; // Получаем версия протокола, может быть отрицательным если наш клиент дэбил
-	let server_address = packet.read_string()?; // Получаем домен/адрес сервера к которому пытается подключиться клиент, например "play.example.com", а не айпи
-	let server_port = packet.read_unsigned_short()?; // Все тоже самое что и с адресом сервера и все потому же и за тем же
-	let next_state = packet.read_varint()?; // Тип подключения: 1 для получения статуса и пинга, 2 и 3 для обычного подключения
+	let handshake = client.receive_packet::<handshake_serverbound::Handshake>()?;
+
+	// BungeeCord дописывает данные игрока в адрес сервера, статус он проксирует без них
+	let bungeecord = if client.server.config.proxy.forwarding == ForwardingMode::Bungeecord
+		&& handshake.next_state.0 != 1
+	{
+		parse_bungeecord(&handshake.server_address)
+	} else {
+		None
+	};
+
+	let server_address = match &bungeecord {
+		Some(forwarding) => forwarding.server_address.clone(),
+		None => handshake.server_address,
+	};
 
 	client.set_handshake(Handshake {
-		protocol_version,
-		server_address,
-		server_port,
+		protocol_version: handshake.protocol_version.0, // Версия протокола, может быть отрицательной если наш клиент дэбил
+		server_address, // Домен/адрес сервера к которому пытается подключиться клиент, например "play.example.com", а не айпи
+		server_port: handshake.server_port, // Все тоже самое что и с адресом сервера и все потому же и за тем же
 	});
 
-	match next_state {
+	// Тип подключения: 1 для получения статуса и пинга, 2 и 3 для обычного подключения
+	match handshake.next_state.0 {
 		1 => {
 			// Тип подключения - статус
 			client.set_state(ConnectionState::Status)?; // Мы находимся в режиме Status
@@ -45,10 +67,9 @@ pub fn handle_connection(
 				// Чтение запроса
 				let mut packet = client.read_any_packet()?;
 
-				match packet.id() {
-					serverbound::status::REQUEST => {
+				match ServerboundStatusPacket::decode(&mut packet)? {
+					ServerboundStatusPacket::Request {} => {
 						// Запрос статуса
-						let mut packet = Packet::empty(clientbound::status::RESPONSE);
 
 						// Дефолтный статус
 						let mut status = "{
@@ -64,21 +85,15 @@ pub fn handle_connection(
 						trigger_event!(client, status, &mut status);
 
 						// Отправка статуса
-						packet.write_string(&status)?;
-
-						client.write_packet(&packet)?;
+						client.send_packet(&status_clientbound::Response {
+							json_response: status,
+						})?;
 					}
-					serverbound::status::PING_REQUEST => {
+					ServerboundStatusPacket::PingRequest { timestamp } => {
 						// Пинг
 						// Раньше мы просто отправляли ему его-же пакет, но сейчас,
-						// С приходом к власти констант айди-пакетов, нам приходится делать такое непотребство
-						let timestamp = packet.read_long()?;
-						let mut packet = Packet::empty(clientbound::status::PONG_RESPONSE);
-						packet.write_long(timestamp)?;
-						client.write_packet(&packet)?;
-					}
-					id => {
-						return Err(ServerError::UnexpectedPacket(id));
+						// С приходом к власти типизированных пакетов, нам приходится делать такое непотребство
+						client.send_packet(&status_clientbound::PongResponse { timestamp })?;
 					}
 				}
 			}
@@ -87,95 +102,126 @@ pub fn handle_connection(
 			// Тип подключения - игра
 			client.set_state(ConnectionState::Login)?; // Мы находимся в режиме Login
 
-			// Читаем пакет Login Start
-			let mut packet = client.read_packet(&[serverbound::login::START])?;
+			// Неподдерживаемую версию выкидываем сразу, как ванилла, а не где-то посреди конфигурации
+			let protocol_version = client.handshake().unwrap().protocol_version;
+			let Some(version) = ProtocolVersion::from_protocol(protocol_version) else {
+				let reason = if protocol_version < ProtocolVersion::LATEST.protocol() {
+					format!("Outdated client! Please use {}", supported_version_names())
+				} else {
+					format!(
+						"Outdated server! I'm still on {}",
+						ProtocolVersion::LATEST.name()
+					)
+				};
+
+				client
+					.protocol_helper()
+					.disconnect(TextComponent::new(reason))?;
+				return Err(ServerError::UnsupportedVersion(protocol_version));
+			};
 
-			let name = packet.read_string()?;
-			let uuid = packet.read_uuid()?;
+			client.set_protocol_version(version);
 
-			client.set_player_info(PlayerInfo {
-				name: name.clone(),
-				uuid: uuid.clone(),
-			});
+			// Читаем пакет Login Start
+			// Клиентскому UUID не доверяем, в онлайне он берется с сессионного сервера
+			let login_start = client.receive_packet::<login_serverbound::Start>()?;
+			let name = login_start.name;
+
+			// За прокси игрока уже авторизовал прокси, берем данные от него
+			let player_info = match client.server.config.proxy.forwarding {
+				ForwardingMode::None if client.server.config.server.online_mode => {
+					authenticate(client.clone(), &name)?
+				}
+				ForwardingMode::None => PlayerInfo {
+					uuid: get_offline_uuid(&name),
+					name,
+					properties: Vec::new(),
+				},
+				ForwardingMode::Bungeecord => {
+					let Some(forwarding) = bungeecord else {
+						client.protocol_helper().disconnect(TextComponent::new(
+							"If you wish to use IP forwarding, please enable it in your BungeeCord config as well!"
+								.to_string(),
+						))?;
+						return Err(ServerError::Forwarding);
+					};
+
+					client.set_remote_ip(forwarding.ip);
+
+					PlayerInfo {
+						uuid: forwarding.uuid,
+						name,
+						properties: forwarding.properties,
+					}
+				}
+				ForwardingMode::Velocity => {
+					let (ip, player_info) = velocity_forward(client.clone())?;
+					client.set_remote_ip(ip);
+					player_info
+				}
+			};
 
-			if client.server.config.server.online_mode {
-				// TODO: encryption packets
-			}
+			client.set_player_info(player_info.clone());
 
 			// Отправляем пакет Set Compression если сжатие указано
 			if let Some(threshold) = client.server.config.server.compression_threshold {
-				client.write_packet(&Packet::build(clientbound::login::SET_COMPRESSION, |p| {
-					p.write_usize_varint(threshold)
-				})?)?;
+				client.send_packet(&login_clientbound::SetCompression {
+					threshold: VarInt(threshold as i32),
+				})?;
 				client.set_compression(Some(threshold)); // Устанавливаем сжатие на соединении
 			}
 
 			// Отправка пакета Login Success
-			client.write_packet(&Packet::build(clientbound::login::SUCCESS, |p| {
-				p.write_uuid(&uuid)?;
-				p.write_string(&name)?;
-				p.write_varint(0)
-			})?)?;
+			client.send_packet(&login_clientbound::Success {
+				uuid: player_info.uuid,
+				name: player_info.name,
+				properties: player_info.properties,
+			})?;
 
-			client.read_packet(&[serverbound::login::ACKNOWLEDGED])?; // Пакет Login Acknowledged
+			client.receive_packet::<login_serverbound::Acknowledged>()?; // Пакет Login Acknowledged
 
 			client.set_state(ConnectionState::Configuration)?; // Мы перешли в режим Configuration
 
 			// Получение бренда клиента из Serverbound Plugin Message
 			// Identifier канала откуда берется бренд: minecraft:brand
 			let brand = loop {
-				let mut packet = client.read_packet(&[serverbound::configuration::PLUGIN_MESSAGE])?; // Пакет Serverbound Plugin Message
+				let message = client.receive_packet::<config_serverbound::PluginMessage>()?; // Пакет Serverbound Plugin Message
 
-				let identifier = packet.read_string()?;
-
-				let mut data = Vec::new();
-				packet.get_mut().read_to_end(&mut data).unwrap();
-
-				if identifier == "minecraft:brand" {
-					break Cursor::new(data).read_string()?;
+				if message.channel == "minecraft:brand" {
+					break Cursor::new(message.data.0).read_string()?;
 				} else {
-					trigger_event!(client, plugin_message, &identifier, &data);
+					trigger_event!(client, plugin_message, &message.channel, &message.data.0);
 				}
 			};
 
-			let mut packet = client.read_packet(&[serverbound::configuration::CLIENT_INFORMATION])?; // Пакет Client Information
-
-			let locale = packet.read_string()?; // for example: en_us
-			let view_distance = packet.read_signed_byte()?; // client-side render distance in chunks
-			let chat_mode = packet.read_varint()?; // 0: enabled, 1: commands only, 2: hidden. See Chat#Client chat mode for more information. 
-			let chat_colors = packet.read_boolean()?; // this settings does nothing on client but can be used on serverside
-			let displayed_skin_parts = packet.read_byte()?; // bit mask https://minecraft.wiki/w/Java_Edition_protocol#Client_Information_(configuration)
-			let main_hand = packet.read_varint()?; // 0 for left and 1 for right
-			let enable_text_filtering = packet.read_boolean()?; // filtering text for profanity, always false for offline mode
-			let allow_server_listings = packet.read_boolean()?; // allows showing player in server listings in status
-			let particle_status = packet.read_varint()?; // 0 for all, 1 for decreased, 2 for minimal
+			let info = client.receive_packet::<config_serverbound::ClientInformation>()?; // Пакет Client Information
 
 			client.set_client_info(ClientInfo {
 				brand,
-				locale,
-				view_distance,
-				chat_mode,
-				chat_colors,
-				displayed_skin_parts,
-				main_hand,
-				enable_text_filtering,
-				allow_server_listings,
-				particle_status,
+				locale: info.locale,
+				view_distance: info.view_distance,
+				chat_mode: info.chat_mode.0,
+				chat_colors: info.chat_colors,
+				displayed_skin_parts: info.displayed_skin_parts,
+				main_hand: info.main_hand.0,
+				enable_text_filtering: info.enable_text_filtering,
+				allow_server_listings: info.allow_server_listings,
+				particle_status: info.particle_status.0,
 			});
 
-			client.write_packet(&Packet::build(
-				clientbound::configuration::PLUGIN_MESSAGE,
-				|p| {
-					p.write_string("minecraft:brand")?;
-					p.write_string(BRAND)
-				},
-			)?)?;
+			let mut brand = Vec::new();
+			brand.write_string(BRAND)?;
 
-			client.write_packet(&Packet::empty(clientbound::configuration::FINISH))?;
+			client.send_packet(&config_clientbound::PluginMessage {
+				channel: "minecraft:brand".to_string(),
+				data: RemainingBytes(brand),
+			})?;
+
+			client.send_packet(&config_clientbound::Finish {})?;
 
 			// На этом моменте пакет хандер ловит пакет и перед ним делает свое мракобесие
 
-			client.read_packet(&[serverbound::configuration::ACKNOWLEDGE_FINISH])?;
+			client.receive_packet::<config_serverbound::AcknowledgeFinish>()?;
 
 			client.set_state(ConnectionState::Play)?; // Мы перешли в режим Play
 
@@ -189,3 +235,62 @@ pub fn handle_connection(
 
 	Ok(())
 }
+
+// Авторизация игрока в online-mode
+// Encryption Request -> Encryption Response -> включаем шифрование -> спрашиваем сессионный сервер
+fn authenticate(client: Arc<ClientContext>, name: &str) -> Result<PlayerInfo, ServerError> {
+	let Some(keys) = client.server.keys() else {
+		return Err(ServerError::Encryption);
+	};
+
+	let verify_token = generate_verify_token();
+
+	// Отправка пакета Encryption Request
+	client.send_packet(&login_clientbound::EncryptionRequest {
+		server_id: String::new(), // всегда пустой начиная с 1.7
+		public_key: keys.public_key_der().to_vec(),
+		verify_token: verify_token.to_vec(),
+		should_authenticate: true,
+	})?;
+
+	// Читаем пакет Encryption Response
+	let response = client.receive_packet::<login_serverbound::EncryptionResponse>()?;
+
+	let shared_secret = keys.decrypt(&response.shared_secret)?;
+	let client_verify_token = keys.decrypt(&response.verify_token)?;
+
+	if client_verify_token != verify_token || shared_secret.len() != 16 {
+		return Err(ServerError::Encryption);
+	}
+
+	// С этого момента все пакеты в обе стороны шифруются
+	client.set_encryption(&shared_secret)?;
+
+	let hash = server_hash("", &shared_secret, keys.public_key_der());
+
+	let ip = if client.server.config.server.prevent_proxy_connections {
+		Some(client.remote_ip())
+	} else {
+		None
+	};
+
+	match client
+		.server
+		.session_service()
+		.has_joined(name, &hash, ip)?
+	{
+		Some(profile) => Ok(PlayerInfo {
+			name: profile.name,
+			uuid: profile.uuid,
+			properties: profile.properties,
+		}),
+		None => {
+			client.protocol_helper().disconnect(
+				TextComponent::builder()
+					.text("Failed to verify username!")
+					.build(),
+			)?;
+			Err(ServerError::Auth)
+		}
+	}
+}
@@ -1,5 +1,13 @@
+pub mod codec;
+pub mod connection;
+pub mod encryption;
+pub mod forwarding;
 pub mod handler;
 pub mod packet_id;
+pub mod packets;
+pub mod proxy_protocol;
+pub mod session;
+pub mod version;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConnectionState {
@@ -0,0 +1,90 @@
+use craftflow_nbt::DynNBT;
+
+use crate::{
+	codec,
+	data::component::TextComponent,
+	protocol::codec::{RemainingBytes, VarInt},
+};
+
+codec! {
+	#[derive(PartialEq)]
+	pub struct KnownPack {
+		pub namespace: String,
+		pub id: String,
+		pub version: String,
+	}
+}
+
+codec! {
+	pub struct RegistryDataEntry {
+		pub entry_id: String,
+		/// None если клиент знает запись из известного пака
+		pub data: Option<DynNBT>,
+	}
+}
+
+codec! {
+	pub struct Tag {
+		pub name: String,
+		/// Айди записей реестра
+		pub entries: Vec<VarInt>,
+	}
+}
+
+codec! {
+	pub struct RegistryTags {
+		pub registry: String,
+		pub tags: Vec<Tag>,
+	}
+}
+
+packets! {
+	clientbound, configuration, ClientboundConfigurationPacket {
+		COOKIE_REQUEST => CookieRequest { key: String },
+		PLUGIN_MESSAGE => PluginMessage { channel: String, data: RemainingBytes },
+		DISCONNECT => Disconnect { reason: TextComponent },
+		FINISH => Finish {},
+		KEEP_ALIVE => KeepAlive { keep_alive_id: i64 },
+		PING => Ping { id: i32 },
+		RESET_CHAT => ResetChat {},
+		REGISTRY_DATA => RegistryData { registry_id: String, entries: Vec<RegistryDataEntry> },
+		STORE_COOKIE => StoreCookie { key: String, payload: Vec<u8> },
+		FEATURE_FLAGS => FeatureFlags { feature_flags: Vec<String> },
+		UPDATE_TAGS => UpdateTags { registries: Vec<RegistryTags> },
+		KNOWN_PACKS => KnownPacks { known_packs: Vec<KnownPack> },
+	}
+}
+
+packets! {
+	serverbound, configuration, ServerboundConfigurationPacket {
+		CLIENT_INFORMATION => ClientInformation {
+			/// Например en_us
+			locale: String,
+			/// Дальность прорисовки в чанках на клиенте
+			view_distance: i8,
+			/// 0: enabled, 1: commands only, 2: hidden
+			chat_mode: VarInt,
+			/// Ничего не делает на клиенте, но может использоваться на сервере
+			chat_colors: bool,
+			/// Битовая маска, https://minecraft.wiki/w/Java_Edition_protocol#Client_Information_(configuration)
+			displayed_skin_parts: u8,
+			/// 0 - левая, 1 - правая
+			main_hand: VarInt,
+			/// Фильтрация текста, в оффлайне всегда false
+			enable_text_filtering: bool,
+			/// Разрешает показывать игрока в статусе сервера
+			allow_server_listings: bool,
+			/// 0: all, 1: decreased, 2: minimal
+			particle_status: VarInt,
+		},
+		COOKIE_RESPONSE => CookieResponse {
+			key: String,
+			payload: Option<Vec<u8>>,
+		},
+		PLUGIN_MESSAGE => PluginMessage { channel: String, data: RemainingBytes },
+		ACKNOWLEDGE_FINISH => AcknowledgeFinish {},
+		KEEP_ALIVE => KeepAlive { keep_alive_id: i64 },
+		PONG => Pong { id: i32 },
+		KNOWN_PACKS => KnownPacks { known_packs: Vec<KnownPack> },
+	}
+}
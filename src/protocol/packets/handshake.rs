@@ -0,0 +1,15 @@
+use crate::protocol::codec::VarInt;
+
+packets! {
+	serverbound, handshake, ServerboundHandshakePacket {
+		HANDSHAKE => Handshake {
+			/// Может быть отрицательным если клиент дэбил
+			protocol_version: VarInt,
+			/// Домен/адрес к которому подключается клиент, например "play.example.com"
+			server_address: String,
+			server_port: u16,
+			/// 1 - статус, 2 - логин, 3 - трансфер
+			next_state: VarInt,
+		},
+	}
+}
@@ -0,0 +1,56 @@
+use uuid::Uuid;
+
+use crate::{
+	data::Property,
+	protocol::codec::{RemainingBytes, VarInt},
+};
+
+packets! {
+	clientbound, login, ClientboundLoginPacket {
+		DISCONNECT => Disconnect {
+			/// Текст-компонент в виде JSON
+			reason: String,
+		},
+		ENCRYPTION_REQUEST => EncryptionRequest {
+			server_id: String,
+			/// Публичный ключ в формате ASN.1 DER
+			public_key: Vec<u8>,
+			verify_token: Vec<u8>,
+			should_authenticate: bool,
+		},
+		SUCCESS => Success {
+			uuid: Uuid,
+			name: String,
+			properties: Vec<Property>,
+		},
+		SET_COMPRESSION => SetCompression { threshold: VarInt },
+		PLUGIN_REQUEST => PluginRequest {
+			message_id: VarInt,
+			channel: String,
+			data: RemainingBytes,
+		},
+		COOKIE_REQUEST => CookieRequest { key: String },
+	}
+}
+
+packets! {
+	serverbound, login, ServerboundLoginPacket {
+		START => Start { name: String, uuid: Uuid },
+		ENCRYPTION_RESPONSE => EncryptionResponse {
+			/// Зашифрован публичным ключом сервера
+			shared_secret: Vec<u8>,
+			/// Зашифрован публичным ключом сервера
+			verify_token: Vec<u8>,
+		},
+		PLUGIN_RESPONSE => PluginResponse {
+			message_id: VarInt,
+			/// None если клиент не понял канал
+			data: Option<RemainingBytes>,
+		},
+		ACKNOWLEDGED => Acknowledged {},
+		COOKIE_RESPONSE => CookieResponse {
+			key: String,
+			payload: Option<Vec<u8>>,
+		},
+	}
+}
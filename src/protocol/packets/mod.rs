@@ -0,0 +1,112 @@
+// Типизированные пакеты
+// На каждый пакет своя структура в модуле serverbound/clientbound нужного стейта,
+// плюс енам со всеми пакетами стейта чтобы удобно матчить в хандлерах
+//
+// Пакеты которых тут нет можно собирать руками через Packet::empty как раньше
+
+/// Генерирует структуры пакетов одного стейта и направления, а также енам со всеми ними
+///
+/// Айди берутся из packet_id.rs по имени константы
+///
+/// ```ignore
+/// packets! {
+///     serverbound, play, ServerboundPlayPacket {
+///         SET_PLAYER_POSITION => SetPlayerPosition { x: f64, y: f64, z: f64, flags: u8 },
+///     }
+/// }
+/// ```
+macro_rules! packets {
+	(
+		$bound:ident, $state:ident, $enum_name:ident {
+			$(
+				$(#[$meta:meta])*
+				$id:ident => $name:ident {
+					$( $(#[$field_meta:meta])* $field:ident : $ty:ty ),* $(,)?
+				}
+			),* $(,)?
+		}
+	) => {
+		pub mod $bound {
+			#[allow(unused_imports)]
+			use super::*;
+
+			$(
+				$crate::codec! {
+					$(#[$meta])*
+					pub struct $name {
+						$( $(#[$field_meta])* pub $field: $ty ),*
+					}
+				}
+
+				impl $crate::protocol::codec::PacketData for $name {
+					const ID: u8 = $crate::protocol::packet_id::$bound::$state::$id;
+				}
+			)*
+		}
+
+		#[derive(Debug, Clone)]
+		#[allow(clippy::large_enum_variant)]
+		pub enum $enum_name {
+			$(
+				$(#[$meta])*
+				$name {
+					$( $(#[$field_meta])* $field: $ty ),*
+				}
+			),*
+		}
+
+		impl $enum_name {
+			pub fn id(&self) -> u8 {
+				match self {
+					$( Self::$name { .. } => $crate::protocol::packet_id::$bound::$state::$id ),*
+				}
+			}
+
+			/// Читает пакет по его айди, на неизвестный айди возвращает UnexpectedPacket
+			pub fn decode(
+				packet: &mut rust_mc_proto::Packet,
+			) -> Result<Self, $crate::ServerError> {
+				match packet.id() {
+					$(
+						$crate::protocol::packet_id::$bound::$state::$id => {
+							let $bound::$name { $( $field ),* } =
+								<$bound::$name as $crate::protocol::codec::Decode>::decode(packet)?;
+							Ok(Self::$name { $( $field ),* })
+						}
+					)*
+					id => Err($crate::ServerError::UnexpectedPacket(id)),
+				}
+			}
+
+			pub fn to_packet(&self) -> Result<rust_mc_proto::Packet, $crate::ServerError> {
+				#[allow(unused_mut)]
+				match self {
+					$(
+						Self::$name { $( $field ),* } => {
+							let mut packet = rust_mc_proto::Packet::empty(
+								$crate::protocol::packet_id::$bound::$state::$id,
+							);
+							$( $crate::protocol::codec::Encode::encode($field, &mut packet)?; )*
+							Ok(packet)
+						}
+					)*
+				}
+			}
+		}
+
+		$(
+			impl From<$bound::$name> for $enum_name {
+				fn from(packet: $bound::$name) -> Self {
+					let $bound::$name { $( $field ),* } = packet;
+					Self::$name { $( $field ),* }
+				}
+			}
+		)*
+	};
+}
+
+pub mod configuration;
+pub mod handshake;
+pub mod login;
+pub mod play;
+pub mod status;
@@ -0,0 +1,729 @@
+use craftflow_nbt::DynNBT;
+use rust_mc_proto::{DataReader, DataWriter, Packet};
+use uuid::Uuid;
+
+use crate::{
+	ServerError, codec,
+	command::argument::ArgumentParser,
+	data::{
+		IdOr, Position, Property,
+		component::TextComponent,
+		metadata::EntityMetadata,
+		slot::{HashedSlot, Slot},
+		sound::SoundEvent,
+	},
+	protocol::codec::{Angle, Decode, Encode, RemainingBytes, VarInt},
+};
+
+codec! {
+	pub struct DeathLocation {
+		pub dimension_name: String,
+		pub location: Position,
+	}
+}
+
+codec! {
+	pub struct SourcePosition {
+		pub x: f64,
+		pub y: f64,
+		pub z: f64,
+	}
+}
+
+codec! {
+	pub struct Heightmap {
+		/// 1 - WORLD_SURFACE, 4 - MOTION_BLOCKING, ...
+		pub heightmap_type: VarInt,
+		pub data: Vec<i64>,
+	}
+}
+
+codec! {
+	pub struct BlockEntity {
+		/// ((x & 15) << 4) | (z & 15)
+		pub packed_xz: u8,
+		pub y: i16,
+		pub block_entity_type: VarInt,
+		pub data: DynNBT,
+	}
+}
+
+codec! {
+	pub struct ChunkData {
+		pub heightmaps: Vec<Heightmap>,
+		/// Секции чанка подряд, см. https://minecraft.wiki/w/Java_Edition_protocol/Chunk_format
+		pub data: Vec<u8>,
+		pub block_entities: Vec<BlockEntity>,
+	}
+}
+
+codec! {
+	pub struct LightData {
+		pub sky_light_mask: Vec<i64>,
+		pub block_light_mask: Vec<i64>,
+		pub empty_sky_light_mask: Vec<i64>,
+		pub empty_block_light_mask: Vec<i64>,
+		/// По 2048 байт на каждую секцию из маски
+		pub sky_light_arrays: Vec<Vec<u8>>,
+		pub block_light_arrays: Vec<Vec<u8>>,
+	}
+}
+
+codec! {
+	pub struct ArgumentSignature {
+		pub argument_name: String,
+		pub signature: [u8; 256],
+	}
+}
+
+codec! {
+	pub struct ChatSession {
+		pub session_id: Uuid,
+		pub expires_at: i64,
+		pub public_key: Vec<u8>,
+		pub key_signature: Vec<u8>,
+	}
+}
+
+codec! {
+	pub struct ChangedSlot {
+		pub slot: i16,
+		/// Предмет который клиент предсказал в слоте
+		pub item: Option<HashedSlot>,
+	}
+}
+
+codec! {
+	pub struct SuggestionMatch {
+		pub text: String,
+		pub tooltip: Option<TextComponent>,
+	}
+}
+
+// Узел дерева команд в пакете Commands, дети и редирект - индексы в общем списке узлов
+#[derive(Debug, Clone, Default)]
+pub struct CommandNodeData {
+	/// 0x03 - тип (0 - корень, 1 - литерал, 2 - аргумент), 0x04 - исполняемый,
+	/// 0x08 - есть редирект, 0x10 - есть тип подсказок
+	pub flags: u8,
+	pub children: Vec<VarInt>,
+	pub redirect_node: Option<VarInt>,
+	/// Только у литералов и аргументов
+	pub name: Option<String>,
+	/// Только у аргументов
+	pub parser: Option<ArgumentParser>,
+	/// Например minecraft:ask_server
+	pub suggestions_type: Option<String>,
+}
+
+impl Encode for CommandNodeData {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		packet.write_byte(self.flags)?;
+		self.children.encode(packet)?;
+
+		// Наличие полей определяется флагами и типом узла, поэтому без префиксов Option
+		if let Some(redirect) = &self.redirect_node {
+			redirect.encode(packet)?;
+		}
+		if let Some(name) = &self.name {
+			name.encode(packet)?;
+		}
+		if let Some(parser) = &self.parser {
+			parser.encode(packet)?;
+		}
+		if let Some(suggestions) = &self.suggestions_type {
+			suggestions.encode(packet)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Decode for CommandNodeData {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let flags = packet.read_byte()?;
+		let node_type = flags & 0x03;
+
+		Ok(CommandNodeData {
+			flags,
+			children: Vec::decode(packet)?,
+			redirect_node: (flags & 0x08 != 0)
+				.then(|| VarInt::decode(packet))
+				.transpose()?,
+			name: (node_type == 1 || node_type == 2)
+				.then(|| String::decode(packet))
+				.transpose()?,
+			parser: (node_type == 2)
+				.then(|| ArgumentParser::decode(packet))
+				.transpose()?,
+			suggestions_type: (node_type == 2 && flags & 0x10 != 0)
+				.then(|| String::decode(packet))
+				.transpose()?,
+		})
+	}
+}
+
+// Действие в Player Info Update, битовая маска строится по тому какие действия есть в записях
+#[derive(Debug, Clone)]
+pub enum PlayerInfoAction {
+	AddPlayer {
+		name: String,
+		properties: Vec<Property>,
+	},
+	InitializeChat(Option<ChatSession>),
+	/// 0: survival, 1: creative, 2: adventure, 3: spectator
+	UpdateGameMode(VarInt),
+	UpdateListed(bool),
+	/// Пинг в миллисекундах
+	UpdateLatency(VarInt),
+	UpdateDisplayName(Option<TextComponent>),
+	UpdateListPriority(VarInt),
+	UpdateHat(bool),
+}
+
+impl PlayerInfoAction {
+	pub fn mask(&self) -> u8 {
+		match self {
+			PlayerInfoAction::AddPlayer { .. } => 0x01,
+			PlayerInfoAction::InitializeChat(_) => 0x02,
+			PlayerInfoAction::UpdateGameMode(_) => 0x04,
+			PlayerInfoAction::UpdateListed(_) => 0x08,
+			PlayerInfoAction::UpdateLatency(_) => 0x10,
+			PlayerInfoAction::UpdateDisplayName(_) => 0x20,
+			PlayerInfoAction::UpdateListPriority(_) => 0x40,
+			PlayerInfoAction::UpdateHat(_) => 0x80,
+		}
+	}
+
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			PlayerInfoAction::AddPlayer { name, properties } => {
+				name.encode(packet)?;
+				properties.encode(packet)
+			}
+			PlayerInfoAction::InitializeChat(session) => session.encode(packet),
+			PlayerInfoAction::UpdateGameMode(game_mode) => game_mode.encode(packet),
+			PlayerInfoAction::UpdateListed(listed) => listed.encode(packet),
+			PlayerInfoAction::UpdateLatency(latency) => latency.encode(packet),
+			PlayerInfoAction::UpdateDisplayName(name) => name.encode(packet),
+			PlayerInfoAction::UpdateListPriority(priority) => priority.encode(packet),
+			PlayerInfoAction::UpdateHat(hat) => hat.encode(packet),
+		}
+	}
+
+	fn decode(mask: u8, packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(match mask {
+			0x01 => PlayerInfoAction::AddPlayer {
+				name: String::decode(packet)?,
+				properties: Vec::decode(packet)?,
+			},
+			0x02 => PlayerInfoAction::InitializeChat(Option::decode(packet)?),
+			0x04 => PlayerInfoAction::UpdateGameMode(VarInt::decode(packet)?),
+			0x08 => PlayerInfoAction::UpdateListed(bool::decode(packet)?),
+			0x10 => PlayerInfoAction::UpdateLatency(VarInt::decode(packet)?),
+			0x20 => PlayerInfoAction::UpdateDisplayName(Option::decode(packet)?),
+			0x40 => PlayerInfoAction::UpdateListPriority(VarInt::decode(packet)?),
+			0x80 => PlayerInfoAction::UpdateHat(bool::decode(packet)?),
+			_ => return Err(ServerError::WrongPacket),
+		})
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerInfoEntry {
+	pub uuid: Uuid,
+	pub actions: Vec<PlayerInfoAction>,
+}
+
+// Записи Player Info Update
+// Во всех записях должен быть одинаковый набор действий, так требует протокол
+#[derive(Debug, Clone, Default)]
+pub struct PlayerInfoEntries(pub Vec<PlayerInfoEntry>);
+
+impl Encode for PlayerInfoEntries {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		let mask = self
+			.0
+			.iter()
+			.flat_map(|o| o.actions.iter())
+			.fold(0u8, |mask, action| mask | action.mask());
+
+		packet.write_byte(mask)?;
+		packet.write_usize_varint(self.0.len())?;
+
+		for entry in &self.0 {
+			entry.uuid.encode(packet)?;
+
+			// Действия пишутся строго в порядке битов маски
+			for bit in (0..8).map(|i| 1u8 << i).filter(|bit| mask & bit != 0) {
+				let Some(action) = entry.actions.iter().find(|o| o.mask() == bit) else {
+					return Err(ServerError::WrongPacket);
+				};
+				action.encode(packet)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Decode for PlayerInfoEntries {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		let mask = packet.read_byte()?;
+		let length = packet.read_usize_varint()?;
+
+		let mut entries = Vec::new();
+
+		for _ in 0..length {
+			let uuid = Uuid::decode(packet)?;
+			let mut actions = Vec::new();
+			for bit in (0..8).map(|i| 1u8 << i).filter(|bit| mask & bit != 0) {
+				actions.push(PlayerInfoAction::decode(bit, packet)?);
+			}
+			entries.push(PlayerInfoEntry { uuid, actions });
+		}
+
+		Ok(PlayerInfoEntries(entries))
+	}
+}
+
+// Что игрок сделал с сущностью в пакете Interact
+#[derive(Debug, Clone)]
+pub enum InteractAction {
+	/// Правый клик
+	Interact { hand: VarInt },
+	/// Левый клик, удар
+	Attack,
+	/// Правый клик в точку на хитбоксе, относительно позиции сущности
+	InteractAt {
+		x: f32,
+		y: f32,
+		z: f32,
+		hand: VarInt,
+	},
+}
+
+impl Encode for InteractAction {
+	fn encode(&self, packet: &mut Packet) -> Result<(), ServerError> {
+		match self {
+			InteractAction::Interact { hand } => {
+				VarInt(0).encode(packet)?;
+				hand.encode(packet)
+			}
+			InteractAction::Attack => VarInt(1).encode(packet),
+			InteractAction::InteractAt { x, y, z, hand } => {
+				VarInt(2).encode(packet)?;
+				x.encode(packet)?;
+				y.encode(packet)?;
+				z.encode(packet)?;
+				hand.encode(packet)
+			}
+		}
+	}
+}
+
+impl Decode for InteractAction {
+	fn decode(packet: &mut Packet) -> Result<Self, ServerError> {
+		Ok(match VarInt::decode(packet)?.0 {
+			0 => InteractAction::Interact {
+				hand: VarInt::decode(packet)?,
+			},
+			1 => InteractAction::Attack,
+			2 => InteractAction::InteractAt {
+				x: f32::decode(packet)?,
+				y: f32::decode(packet)?,
+				z: f32::decode(packet)?,
+				hand: VarInt::decode(packet)?,
+			},
+			_ => return Err(ServerError::WrongPacket),
+		})
+	}
+}
+
+packets! {
+	clientbound, play, ClientboundPlayPacket {
+		SPAWN_ENTITY => SpawnEntity {
+			entity_id: VarInt,
+			entity_uuid: Uuid,
+			/// Айди в реестре minecraft:entity_type
+			entity_type: VarInt,
+			x: f64,
+			y: f64,
+			z: f64,
+			pitch: Angle,
+			yaw: Angle,
+			head_yaw: Angle,
+			/// Зависит от типа сущности
+			data: VarInt,
+			velocity_x: i16,
+			velocity_y: i16,
+			velocity_z: i16,
+		},
+		ENTITY_ANIMATION => EntityAnimation {
+			entity_id: VarInt,
+			/// 0 - взмах основной рукой, 3 - второй рукой, ...
+			animation: u8,
+		},
+		ACKNOWLEDGE_BLOCK_CHANGE => AcknowledgeBlockChange { sequence: VarInt },
+		BLOCK_UPDATE => BlockUpdate {
+			location: Position,
+			/// Айди состояния блока
+			block_id: VarInt,
+		},
+		COMMAND_SUGGESTIONS_RESPONSE => CommandSuggestionsResponse {
+			transaction_id: VarInt,
+			/// Откуда начинается заменяемый текст, считая слеш
+			start: VarInt,
+			length: VarInt,
+			matches: Vec<SuggestionMatch>,
+		},
+		COMMANDS => Commands {
+			nodes: Vec<CommandNodeData>,
+			root_index: VarInt,
+		},
+		CLOSE_CONTAINER => CloseContainer { window_id: VarInt },
+		SET_CONTAINER_CONTENT => SetContainerContent {
+			window_id: VarInt,
+			state_id: VarInt,
+			slots: Vec<Option<Slot>>,
+			carried_item: Option<Slot>,
+		},
+		SET_CONTAINER_PROPERTY => SetContainerProperty {
+			window_id: VarInt,
+			/// Зависит от окна, например 0 у наковальни - цена ремонта
+			property: i16,
+			value: i16,
+		},
+		SET_CONTAINER_SLOT => SetContainerSlot {
+			window_id: VarInt,
+			state_id: VarInt,
+			slot: i16,
+			item: Option<Slot>,
+		},
+		COOKIE_REQUEST => CookieRequest { key: String },
+		PLUGIN_MESSAGE => PluginMessage { channel: String, data: RemainingBytes },
+		DAMAGE_EVENT => DamageEvent {
+			entity_id: VarInt,
+			/// Айди в реестре minecraft:damage_type
+			source_type_id: VarInt,
+			/// Айди сущности-причины + 1, 0 если ее нет
+			source_cause_id: VarInt,
+			/// Айди сущности которая ударила напрямую + 1, например стрелы
+			source_direct_id: VarInt,
+			source_position: Option<SourcePosition>,
+		},
+		DISCONNECT => Disconnect { reason: TextComponent },
+		ENTITY_EVENT => EntityEvent { entity_id: i32, entity_status: u8 },
+		TELEPORT_ENTITY => TeleportEntity {
+			entity_id: VarInt,
+			x: f64,
+			y: f64,
+			z: f64,
+			velocity_x: f64,
+			velocity_y: f64,
+			velocity_z: f64,
+			yaw: f32,
+			pitch: f32,
+			on_ground: bool,
+		},
+		UNLOAD_CHUNK => UnloadChunk { chunk_z: i32, chunk_x: i32 },
+		GAME_EVENT => GameEvent { event: u8, value: f32 },
+		HURT_ANIMATION => HurtAnimation {
+			entity_id: VarInt,
+			/// Откуда пришел удар относительно взгляда, наклоняет камеру
+			yaw: f32,
+		},
+		KEEP_ALIVE => KeepAlive { keep_alive_id: i64 },
+		CHUNK_DATA_AND_UPDATE_LIGHT => ChunkDataAndUpdateLight {
+			chunk_x: i32,
+			chunk_z: i32,
+			data: ChunkData,
+			light: LightData,
+		},
+		LOGIN => Login {
+			entity_id: i32,
+			is_hardcore: bool,
+			dimension_names: Vec<String>,
+			max_players: VarInt,
+			view_distance: VarInt,
+			simulation_distance: VarInt,
+			reduced_debug_info: bool,
+			enable_respawn_screen: bool,
+			do_limited_crafting: bool,
+			/// Айди в реестре minecraft:dimension_type
+			dimension_type: VarInt,
+			dimension_name: String,
+			/// Первые 8 байт SHA-256 от сида
+			hashed_seed: i64,
+			game_mode: u8,
+			previous_game_mode: i8,
+			is_debug: bool,
+			is_flat: bool,
+			death_location: Option<DeathLocation>,
+			portal_cooldown: VarInt,
+			sea_level: VarInt,
+			enforces_secure_chat: bool,
+		},
+		UPDATE_ENTITY_POSITION => UpdateEntityPosition {
+			entity_id: VarInt,
+			/// currentX * 4096 - prevX * 4096
+			delta_x: i16,
+			delta_y: i16,
+			delta_z: i16,
+			on_ground: bool,
+		},
+		UPDATE_ENTITY_POSITION_AND_ROTATION => UpdateEntityPositionAndRotation {
+			entity_id: VarInt,
+			delta_x: i16,
+			delta_y: i16,
+			delta_z: i16,
+			yaw: Angle,
+			pitch: Angle,
+			on_ground: bool,
+		},
+		UPDATE_ENTITY_ROTATION => UpdateEntityRotation {
+			entity_id: VarInt,
+			yaw: Angle,
+			pitch: Angle,
+			on_ground: bool,
+		},
+		OPEN_SCREEN => OpenScreen {
+			window_id: VarInt,
+			/// Айди в реестре minecraft:menu
+			window_type: VarInt,
+			title: TextComponent,
+		},
+		PING => Ping { id: i32 },
+		PLAYER_ABILITIES => PlayerAbilities {
+			/// 0x01 - неуязвим, 0x02 - летит, 0x04 - может летать, 0x08 - креатив
+			flags: u8,
+			flying_speed: f32,
+			/// Модификатор FOV, ванилла шлет сюда скорость ходьбы
+			fov_modifier: f32,
+		},
+		COMBAT_DEATH => CombatDeath {
+			player_id: VarInt,
+			/// Показывается на экране смерти
+			message: TextComponent,
+		},
+		PLAYER_INFO_REMOVE => PlayerInfoRemove { uuids: Vec<Uuid> },
+		PLAYER_INFO_UPDATE => PlayerInfoUpdate { entries: PlayerInfoEntries },
+		SYNCHRONIZE_PLAYER_POSITION => SynchronizePlayerPosition {
+			teleport_id: VarInt,
+			x: f64,
+			y: f64,
+			z: f64,
+			velocity_x: f64,
+			velocity_y: f64,
+			velocity_z: f64,
+			yaw: f32,
+			pitch: f32,
+			/// Битовая маска относительных полей
+			flags: i32,
+		},
+		REMOVE_ENTITIES => RemoveEntities { entity_ids: Vec<VarInt> },
+		RESPAWN => Respawn {
+			/// Айди в реестре minecraft:dimension_type
+			dimension_type: VarInt,
+			dimension_name: String,
+			hashed_seed: i64,
+			game_mode: u8,
+			previous_game_mode: i8,
+			is_debug: bool,
+			is_flat: bool,
+			death_location: Option<DeathLocation>,
+			portal_cooldown: VarInt,
+			sea_level: VarInt,
+			/// 0x01 - оставить атрибуты, 0x02 - оставить метаданные
+			data_kept: u8,
+		},
+		SET_HEAD_ROTATION => SetHeadRotation { entity_id: VarInt, head_yaw: Angle },
+		SET_CENTER_CHUNK => SetCenterChunk { chunk_x: VarInt, chunk_z: VarInt },
+		SET_CURSOR_ITEM => SetCursorItem { item: Option<Slot> },
+		SET_ENTITY_METADATA => SetEntityMetadata {
+			entity_id: VarInt,
+			/// Только измененные индексы, остальные клиент помнит
+			metadata: EntityMetadata,
+		},
+		SET_ENTITY_VELOCITY => SetEntityVelocity {
+			entity_id: VarInt,
+			/// В 1/8000 блока за тик
+			velocity_x: i16,
+			velocity_y: i16,
+			velocity_z: i16,
+		},
+		SET_HEALTH => SetHealth {
+			/// 0 и меньше - игрок мертв
+			health: f32,
+			food: VarInt,
+			food_saturation: f32,
+		},
+		SET_HELD_ITEM => SetHeldItem {
+			/// Слот хотбара от 0 до 8
+			slot: VarInt,
+		},
+		SET_PLAYER_INVENTORY_SLOT => SetPlayerInventorySlot {
+			/// 0-8 хотбар, 9-35 инвентарь, 36-39 броня от ботинок до шлема, 40 - вторая рука
+			slot: VarInt,
+			item: Option<Slot>,
+		},
+		ENTITY_SOUND_EFFECT => EntitySoundEffect {
+			sound: IdOr<SoundEvent>,
+			/// 0 - master
+			category: VarInt,
+			entity_id: VarInt,
+			volume: f32,
+			pitch: f32,
+			seed: i64,
+		},
+		UPDATE_TIME => UpdateTime {
+			world_age: i64,
+			/// 0 - рассвет, 6000 - полдень, 12000 - закат, 18000 - полночь
+			time_of_day: i64,
+			/// false - время стоит на месте
+			time_of_day_increasing: bool,
+		},
+		START_CONFIGURATION => StartConfiguration {},
+		STORE_COOKIE => StoreCookie { key: String, payload: Vec<u8> },
+		SYSTEM_CHAT_MESSAGE => SystemChatMessage {
+			content: TextComponent,
+			/// true - показывается над хотбаром (action bar)
+			overlay: bool,
+		},
+		PICKUP_ITEM => PickupItem {
+			collected_entity_id: VarInt,
+			collector_entity_id: VarInt,
+			/// Сколько предметов подобрано, у опыта и стрел 1
+			pickup_item_count: VarInt,
+		},
+	}
+}
+
+packets! {
+	serverbound, play, ServerboundPlayPacket {
+		CONFIRM_TELEPORTATION => ConfirmTeleportation { teleport_id: VarInt },
+		CHAT_COMMAND => ChatCommand {
+			/// Без слеша в начале
+			command: String,
+		},
+		SIGNED_CHAT_COMMAND => SignedChatCommand {
+			command: String,
+			timestamp: i64,
+			salt: i64,
+			argument_signatures: Vec<ArgumentSignature>,
+			message_count: VarInt,
+			acknowledged: [u8; 3],
+			checksum: u8,
+		},
+		CHAT_MESSAGE => ChatMessage {
+			message: String,
+			timestamp: i64,
+			salt: i64,
+			signature: Option<[u8; 256]>,
+			message_count: VarInt,
+			acknowledged: [u8; 3],
+			checksum: u8,
+		},
+		CLIENT_STATUS => ClientStatus {
+			/// 0 - возродиться, 1 - запросить статистику
+			action_id: VarInt,
+		},
+		CLIENT_TICK_END => ClientTickEnd {},
+		COMMAND_SUGGESTIONS_REQUEST => CommandSuggestionsRequest {
+			transaction_id: VarInt,
+			/// Весь текст из чата вместе со слешем
+			text: String,
+		},
+		ACKNOWLEDGE_CONFIGURATION => AcknowledgeConfiguration {},
+		CLICK_CONTAINER => ClickContainer {
+			window_id: VarInt,
+			state_id: VarInt,
+			slot: i16,
+			button: i8,
+			/// 0 - клик, 1 - шифт, 2 - цифра, 3 - средняя кнопка, 4 - выкинуть,
+			/// 5 - растягивание, 6 - двойной клик
+			mode: VarInt,
+			/// Что клиент предсказал, сервер только сверяет
+			changed_slots: Vec<ChangedSlot>,
+			carried_item: Option<HashedSlot>,
+		},
+		CLOSE_CONTAINER => CloseContainer { window_id: VarInt },
+		COOKIE_RESPONSE => CookieResponse {
+			key: String,
+			payload: Option<Vec<u8>>,
+		},
+		PLUGIN_MESSAGE => PluginMessage { channel: String, data: RemainingBytes },
+		INTERACT => Interact {
+			entity_id: VarInt,
+			action: InteractAction,
+			sneaking: bool,
+		},
+		KEEP_ALIVE => KeepAlive { keep_alive_id: i64 },
+		SET_PLAYER_POSITION => SetPlayerPosition {
+			x: f64,
+			y: f64,
+			z: f64,
+			/// 0x01 - на земле, 0x02 - уперся в стену
+			flags: u8,
+		},
+		SET_PLAYER_POSITION_AND_ROTATION => SetPlayerPositionAndRotation {
+			x: f64,
+			y: f64,
+			z: f64,
+			yaw: f32,
+			pitch: f32,
+			flags: u8,
+		},
+		SET_PLAYER_ROTATION => SetPlayerRotation { yaw: f32, pitch: f32, flags: u8 },
+		SET_PLAYER_MOVEMENT_FLAGS => SetPlayerMovementFlags { flags: u8 },
+		PLAYER_ABILITIES => PlayerAbilities {
+			/// Клиент шлет только 0x02 - начал или перестал летать
+			flags: u8,
+		},
+		PLAYER_ACTION => PlayerAction {
+			/// 0 - начал копать, 1 - отменил, 2 - докопал, 3/4 - выкинул предмет, ...
+			status: VarInt,
+			location: Position,
+			/// 0 - низ, 1 - верх, 2 - север, 3 - юг, 4 - запад, 5 - восток
+			face: i8,
+			sequence: VarInt,
+		},
+		PLAYER_COMMAND => PlayerCommand {
+			entity_id: VarInt,
+			/// 0 - нажал шифт, 1 - отпустил шифт, ...
+			action_id: VarInt,
+			/// Прыжок на лошади, от 0 до 100
+			jump_boost: VarInt,
+		},
+		PLAYER_LOADED => PlayerLoaded {},
+		PONG => Pong { id: i32 },
+		RENAME_ITEM => RenameItem {
+			/// Текст в поле наковальни
+			item_name: String,
+		},
+		SET_HELD_ITEM => SetHeldItem {
+			/// Слот хотбара от 0 до 8
+			slot: i16,
+		},
+		SET_CREATIVE_MODE_SLOT => SetCreativeModeSlot {
+			/// Слот окна инвентаря, -1 - выкинуть предмет
+			slot: i16,
+			item: Option<Slot>,
+		},
+		SWING_ARM => SwingArm {
+			/// 0 - основная рука, 1 - вторая
+			hand: VarInt,
+		},
+		USE_ITEM_ON => UseItemOn {
+			hand: VarInt,
+			location: Position,
+			face: VarInt,
+			cursor_x: f32,
+			cursor_y: f32,
+			cursor_z: f32,
+			inside_block: bool,
+			world_border_hit: bool,
+			sequence: VarInt,
+		},
+	}
+}
@@ -0,0 +1,13 @@
+packets! {
+	clientbound, status, ClientboundStatusPacket {
+		RESPONSE => Response { json_response: String },
+		PONG_RESPONSE => PongResponse { timestamp: i64 },
+	}
+}
+
+packets! {
+	serverbound, status, ServerboundStatusPacket {
+		REQUEST => Request {},
+		PING_REQUEST => PingRequest { timestamp: i64 },
+	}
+}
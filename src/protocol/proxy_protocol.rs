@@ -0,0 +1,143 @@
+// HAProxy PROXY protocol v1 (текст) и v2 (бинарный)
+// Балансировщик перед рукопожатием пишет заголовок с настоящим адресом клиента
+// https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use tokio::{io::AsyncReadExt, net::TcpStream};
+
+use crate::ServerError;
+
+// Сигнатура v2, первые 12 байт
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+// Заголовок v1 не длиннее 107 байт вместе с \r\n
+const V1_MAX_LENGTH: usize = 107;
+
+/// Читает заголовок PROXY протокола и возвращает настоящий адрес клиента
+///
+/// Читает ровно заголовок, дальше в сокете остается рукопожатие майнкрафта.
+/// Для проверок здоровья (LOCAL, UNKNOWN) возвращает адрес самого соединения
+pub async fn read_proxy_header(
+	stream: &mut TcpStream,
+	peer: SocketAddr,
+) -> Result<SocketAddr, ServerError> {
+	// Любой v1 заголовок длиннее 12 байт, так что читать столько можно всегда
+	let mut header = [0; 12];
+	stream.read_exact(&mut header).await?;
+
+	if header == V2_SIGNATURE {
+		read_v2(stream, peer).await
+	} else if header.starts_with(b"PROXY ") {
+		read_v1(stream, peer, &header).await
+	} else {
+		Err(ServerError::ProxyProtocol)
+	}
+}
+
+async fn read_v1(
+	stream: &mut TcpStream,
+	peer: SocketAddr,
+	start: &[u8],
+) -> Result<SocketAddr, ServerError> {
+	let mut line = start.to_vec();
+
+	while !line.ends_with(b"\r\n") {
+		if line.len() >= V1_MAX_LENGTH {
+			return Err(ServerError::ProxyProtocol);
+		}
+		line.push(stream.read_u8().await?);
+	}
+
+	let line =
+		std::str::from_utf8(&line[..line.len() - 2]).map_err(|_| ServerError::ProxyProtocol)?;
+
+	// PROXY TCP4 <src> <dst> <src port> <dst port>
+	let parts = line.split(' ').collect::<Vec<&str>>();
+
+	match parts.as_slice() {
+		["PROXY", "UNKNOWN", ..] => Ok(peer),
+		["PROXY", "TCP4" | "TCP6", source, _, source_port, _] => {
+			let ip = source
+				.parse::<IpAddr>()
+				.map_err(|_| ServerError::ProxyProtocol)?;
+			let port = source_port
+				.parse::<u16>()
+				.map_err(|_| ServerError::ProxyProtocol)?;
+			Ok(SocketAddr::new(ip, port))
+		}
+		_ => Err(ServerError::ProxyProtocol),
+	}
+}
+
+async fn read_v2(stream: &mut TcpStream, peer: SocketAddr) -> Result<SocketAddr, ServerError> {
+	let version_command = stream.read_u8().await?;
+	let family = stream.read_u8().await?;
+	let length = stream.read_u16().await? as usize;
+
+	// Адреса и TLV читаем целиком, даже если они нам не нужны
+	let mut data = vec![0; length];
+	stream.read_exact(&mut data).await?;
+
+	if version_command >> 4 != 2 {
+		return Err(ServerError::ProxyProtocol);
+	}
+
+	// 0 - LOCAL, соединение от самого балансировщика
+	if version_command & 0x0F == 0 {
+		return Ok(peer);
+	}
+
+	match family {
+		// TCP over IPv4: src, dst, src port, dst port
+		0x11 if length >= 12 => {
+			let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&data[0..4]).unwrap());
+			let port = u16::from_be_bytes([data[8], data[9]]);
+			Ok(SocketAddr::new(IpAddr::V4(ip), port))
+		}
+		// TCP over IPv6
+		0x21 if length >= 36 => {
+			let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&data[0..16]).unwrap());
+			let port = u16::from_be_bytes([data[32], data[33]]);
+			Ok(SocketAddr::new(IpAddr::V6(ip), port))
+		}
+		// UNSPEC и unix сокеты
+		0x00 | 0x31 | 0x32 => Ok(peer),
+		_ => Err(ServerError::ProxyProtocol),
+	}
+}
+
+/// Проверяет что адрес есть в списке доверенных, пустой список не доверяет никому
+///
+/// В списке айпи адреса или подсети вида `10.0.0.0/8`
+pub fn is_trusted(trusted: &[String], ip: IpAddr) -> bool {
+	trusted.iter().any(|o| matches_network(o, ip))
+}
+
+fn matches_network(network: &str, ip: IpAddr) -> bool {
+	let (address, prefix) = match network.split_once('/') {
+		Some((address, prefix)) => match prefix.parse::<u32>() {
+			Ok(prefix) => (address, Some(prefix)),
+			Err(_) => return false,
+		},
+		None => (network, None),
+	};
+
+	let Ok(address) = address.parse::<IpAddr>() else {
+		return false;
+	};
+
+	match (address, ip.to_canonical()) {
+		(IpAddr::V4(address), IpAddr::V4(ip)) => {
+			let prefix = prefix.unwrap_or(32).min(32);
+			let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+			u32::from(address) & mask == u32::from(ip) & mask
+		}
+		(IpAddr::V6(address), IpAddr::V6(ip)) => {
+			let prefix = prefix.unwrap_or(128).min(128);
+			let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+			u128::from(address) & mask == u128::from(ip) & mask
+		}
+		_ => false,
+	}
+}
@@ -0,0 +1,90 @@
+use std::{net::IpAddr, time::Duration};
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{ServerError, data::Property};
+
+// Профиль игрока, полученный от сессионного сервера
+#[derive(Debug, Clone)]
+pub struct GameProfile {
+	pub uuid: Uuid,
+	pub name: String,
+	pub properties: Vec<Property>,
+}
+
+// Бэкенд проверки сессий для online-mode
+// Можно подменить через ServerContext::set_session_service, например на мок в тестах
+pub trait SessionService: Sync + Send {
+	/// Проверяет что игрок действительно зашел на сервер с таким хешем
+	///
+	/// Возвращает None если сессия не найдена
+	fn has_joined(
+		&self,
+		name: &str,
+		server_hash: &str,
+		ip: Option<IpAddr>,
+	) -> Result<Option<GameProfile>, ServerError>;
+}
+
+#[derive(Deserialize)]
+struct HasJoinedResponse {
+	id: String,
+	name: String,
+	#[serde(default)]
+	properties: Vec<Property>,
+}
+
+// Сессионный сервер в формате Mojang (yggdrasil)
+// Базовый адрес берется из конфига, так что сюда можно подсунуть любой совместимый сервер
+pub struct MojangSessionService {
+	base_url: String,
+	agent: ureq::Agent,
+}
+
+impl MojangSessionService {
+	pub fn new(base_url: &str, timeout: Duration) -> MojangSessionService {
+		MojangSessionService {
+			base_url: base_url.trim_end_matches('/').to_string(),
+			agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+		}
+	}
+}
+
+impl SessionService for MojangSessionService {
+	fn has_joined(
+		&self,
+		name: &str,
+		server_hash: &str,
+		ip: Option<IpAddr>,
+	) -> Result<Option<GameProfile>, ServerError> {
+		let mut request = self
+			.agent
+			.get(&format!("{}/session/minecraft/hasJoined", self.base_url))
+			.query("username", name)
+			.query("serverId", server_hash);
+
+		if let Some(ip) = ip {
+			request = request.query("ip", &ip.to_string());
+		}
+
+		let response = request.call().map_err(|_| ServerError::SessionServer)?;
+
+		// 204 No Content - сессия не найдена
+		if response.status() != 200 {
+			return Ok(None);
+		}
+
+		let body = response
+			.into_string()
+			.map_err(|_| ServerError::SessionServer)?;
+		let profile: HasJoinedResponse =
+			serde_json::from_str(&body).map_err(|_| ServerError::SessionServer)?;
+
+		Ok(Some(GameProfile {
+			uuid: Uuid::parse_str(&profile.id).map_err(|_| ServerError::SessionServer)?,
+			name: profile.name,
+			properties: profile.properties,
+		}))
+	}
+}
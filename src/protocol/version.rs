@@ -0,0 +1,57 @@
+// Версия протокола клиента
+//
+// Сервер говорит только на 1.21.5, под нее написаны packet_id.rs и пакеты в protocol::packets.
+// Клиентов других версий выкидываем еще в Login с понятным сообщением.
+// Версия всё же хранится у клиента, потому что реестры и pack_format датапаков от нее зависят
+
+/// Версия протокола клиента, выбирается по protocol_version из рукопожатия
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+	/// 1.21.5
+	V1_21_5,
+}
+
+/// Все поддерживаемые версии, от старой к новой
+pub const SUPPORTED_VERSIONS: [ProtocolVersion; 1] = [ProtocolVersion::V1_21_5];
+
+impl ProtocolVersion {
+	/// Версия под которую написаны packet_id.rs и пакеты в protocol::packets
+	pub const LATEST: ProtocolVersion = ProtocolVersion::V1_21_5;
+
+	/// None если такую версию мы не поддерживаем
+	pub fn from_protocol(protocol: i32) -> Option<ProtocolVersion> {
+		SUPPORTED_VERSIONS
+			.into_iter()
+			.find(|o| o.protocol() == protocol)
+	}
+
+	/// Номер протокола, как в рукопожатии
+	pub fn protocol(self) -> i32 {
+		match self {
+			ProtocolVersion::V1_21_5 => 770,
+		}
+	}
+
+	/// Название версии для игрока, например "1.21.5"
+	pub fn name(self) -> &'static str {
+		match self {
+			ProtocolVersion::V1_21_5 => "1.21.5",
+		}
+	}
+
+	/// pack_format датапаков для этой версии
+	pub fn data_pack_format(self) -> i32 {
+		match self {
+			ProtocolVersion::V1_21_5 => 71,
+		}
+	}
+}
+
+/// Названия всех поддерживаемых версий через запятую, для сообщений игроку
+pub fn supported_version_names() -> String {
+	SUPPORTED_VERSIONS
+		.iter()
+		.map(|o| o.name())
+		.collect::<Vec<&str>>()
+		.join(", ")
+}
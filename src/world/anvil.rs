@@ -0,0 +1,550 @@
+// Чтение и запись чанков в формате Anvil (region/r.x.z.mca)
+// https://minecraft.wiki/w/Region_file_format
+// https://minecraft.wiki/w/Chunk_format
+
+use std::{
+	collections::{BTreeMap, HashMap, HashSet},
+	fs::{self, File, OpenOptions},
+	io::{ErrorKind, Read, Seek, SeekFrom, Write},
+	path::{Path, PathBuf},
+	sync::Mutex,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use craftflow_nbt::DynNBT;
+use flate2::{
+	Compression,
+	read::{GzDecoder, ZlibDecoder},
+	write::ZlibEncoder,
+};
+
+use crate::ServerError;
+
+use super::{
+	block::{BlockRegistry, BlockState},
+	chunk::{
+		AIR, Chunk, ChunkSection, PaletteKind, PalettedContainer, SECTION_SIZE, bits_for, pack_values,
+		unpack_values,
+	},
+};
+
+// DataVersion для 1.21.5
+pub const DATA_VERSION: i32 = 4325;
+
+const SECTOR_SIZE: usize = 4096;
+// Заголовок региона: 1024 локации и 1024 таймстемпа
+const HEADER_SECTORS: usize = 2;
+// Если чанк не влезает в 255 секторов, он пишется в отдельный файл c.x.z.mcc
+const MAX_CHUNK_SECTORS: usize = 255;
+const EXTERNAL_FLAG: u8 = 0x80;
+
+const COMPRESSION_GZIP: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+const COMPRESSION_NONE: u8 = 3;
+
+const DEFAULT_BIOME: &str = "minecraft:plains";
+
+// Хранилище регионов одного мира
+pub struct RegionStorage {
+	path: PathBuf, // Папка region внутри папки мира
+	min_y: i32,
+	height: usize,
+	blocks: BlockRegistry,
+	biomes: Vec<String>,
+	biome_ids: HashMap<String, u32>,
+	lock: Mutex<()>, // Чтобы два потока не писали в один файл одновременно
+	// Чанки которые не смогли прочитать, их нельзя перезаписывать сгенерированными
+	unreadable: Mutex<HashSet<(i32, i32)>>,
+}
+
+impl RegionStorage {
+	/// biomes - имена биомов по порядку их айди в реестре
+	pub fn new(
+		world_path: &Path,
+		min_y: i32,
+		height: usize,
+		blocks: BlockRegistry,
+		biomes: Vec<String>,
+	) -> RegionStorage {
+		let biome_ids = biomes
+			.iter()
+			.enumerate()
+			.map(|(i, o)| (o.clone(), i as u32))
+			.collect();
+
+		RegionStorage {
+			path: world_path.join("region"),
+			min_y,
+			height,
+			blocks,
+			biomes,
+			biome_ids,
+			lock: Mutex::new(()),
+			unreadable: Mutex::new(HashSet::new()),
+		}
+	}
+
+	/// Без полного реестра блоков чужой мир прочитается воздухом и затрется при сохранении,
+	/// поэтому регионы тогда не читаются и не пишутся
+	pub fn is_enabled(&self) -> bool {
+		self.blocks.is_complete()
+	}
+
+	pub fn blocks(&self) -> &BlockRegistry {
+		&self.blocks
+	}
+
+	fn region_path(&self, x: i32, z: i32) -> PathBuf {
+		self.path.join(format!("r.{}.{}.mca", x >> 5, z >> 5))
+	}
+
+	fn external_path(&self, x: i32, z: i32) -> PathBuf {
+		self.path.join(format!("c.{x}.{z}.mcc"))
+	}
+
+	// Индекс чанка в заголовке региона
+	fn header_index(x: i32, z: i32) -> u64 {
+		((x & 31) + (z & 31) * 32) as u64
+	}
+
+	/// Читает сырой NBT чанка, None если чанк еще не сохранялся
+	pub fn read_chunk_nbt(&self, x: i32, z: i32) -> Result<Option<DynNBT>, ServerError> {
+		let _lock = self.lock.lock().unwrap();
+
+		let mut file = match File::open(self.region_path(x, z)) {
+			Ok(file) => file,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+
+		let mut location = [0; 4];
+		file.seek(SeekFrom::Start(Self::header_index(x, z) * 4))?;
+		if file.read_exact(&mut location).is_err() {
+			return Ok(None); // Файл короче заголовка, считаем что чанка нет
+		}
+
+		let offset = u32::from_be_bytes([0, location[0], location[1], location[2]]) as u64;
+		if offset == 0 || location[3] == 0 {
+			return Ok(None);
+		}
+
+		let mut header = [0; 5];
+		file.seek(SeekFrom::Start(offset * SECTOR_SIZE as u64))?;
+		file.read_exact(&mut header)?;
+
+		let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+		let compression = header[4];
+
+		let data = if compression & EXTERNAL_FLAG != 0 {
+			fs::read(self.external_path(x, z))?
+		} else {
+			if length == 0 {
+				return Err(ServerError::Region);
+			}
+			let mut data = vec![0; length - 1];
+			file.read_exact(&mut data)?;
+			data
+		};
+
+		let data = match compression & !EXTERNAL_FLAG {
+			COMPRESSION_GZIP => {
+				let mut out = Vec::new();
+				GzDecoder::new(data.as_slice()).read_to_end(&mut out)?;
+				out
+			}
+			COMPRESSION_ZLIB => {
+				let mut out = Vec::new();
+				ZlibDecoder::new(data.as_slice()).read_to_end(&mut out)?;
+				out
+			}
+			COMPRESSION_NONE => data,
+			_ => return Err(ServerError::Region), // LZ4 и кастомные не поддерживаются
+		};
+
+		let (_, (_, nbt)) =
+			craftflow_nbt::from_slice_named::<DynNBT>(&data).map_err(|_| ServerError::DeNbt)?;
+
+		Ok(Some(nbt))
+	}
+
+	/// Записывает сырой NBT чанка, сжимая его zlib'ом
+	pub fn write_chunk_nbt(&self, x: i32, z: i32, nbt: &DynNBT) -> Result<(), ServerError> {
+		let mut raw = Vec::new();
+		craftflow_nbt::to_writer_named(&mut raw, "", nbt).map_err(|_| ServerError::SerNbt)?;
+
+		let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+		encoder.write_all(&raw)?;
+		let compressed = encoder.finish()?;
+
+		let _lock = self.lock.lock().unwrap();
+
+		fs::create_dir_all(&self.path)?;
+
+		let mut payload = Vec::new();
+
+		if (compressed.len() + 5).div_ceil(SECTOR_SIZE) > MAX_CHUNK_SECTORS {
+			// Слишком большой чанк, выносим его в отдельный файл
+			fs::write(self.external_path(x, z), &compressed)?;
+			payload.extend_from_slice(&1u32.to_be_bytes());
+			payload.push(COMPRESSION_ZLIB | EXTERNAL_FLAG);
+		} else {
+			let _ = fs::remove_file(self.external_path(x, z));
+			payload.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+			payload.push(COMPRESSION_ZLIB);
+			payload.extend_from_slice(&compressed);
+		}
+
+		let sectors = payload.len().div_ceil(SECTOR_SIZE);
+		payload.resize(sectors * SECTOR_SIZE, 0);
+
+		let mut file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(false)
+			.open(self.region_path(x, z))?;
+
+		let mut header = vec![0; HEADER_SECTORS * SECTOR_SIZE];
+		let file_len = file.metadata()?.len() as usize;
+		if file_len >= header.len() {
+			file.read_exact(&mut header)?;
+		}
+
+		// Ищем свободное место, не считая старое место этого чанка
+		let index = Self::header_index(x, z) as usize;
+		let mut used = vec![true; HEADER_SECTORS];
+		for i in 0..1024 {
+			if i == index {
+				continue;
+			}
+			let offset =
+				u32::from_be_bytes([0, header[i * 4], header[i * 4 + 1], header[i * 4 + 2]]) as usize;
+			let count = header[i * 4 + 3] as usize;
+			if offset == 0 {
+				continue;
+			}
+			if used.len() < offset + count {
+				used.resize(offset + count, false);
+			}
+			for sector in used.iter_mut().skip(offset).take(count) {
+				*sector = true;
+			}
+		}
+
+		let mut offset = HEADER_SECTORS;
+		while (offset..offset + sectors).any(|o| used.get(o).copied().unwrap_or(false)) {
+			offset += 1;
+		}
+
+		file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+		file.write_all(&payload)?;
+
+		let location = ((offset as u32) << 8) | sectors as u32;
+		header[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+
+		let timestamp = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|o| o.as_secs() as u32)
+			.unwrap_or_default();
+		header[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4]
+			.copy_from_slice(&timestamp.to_be_bytes());
+
+		file.seek(SeekFrom::Start(0))?;
+		file.write_all(&header)?;
+
+		Ok(())
+	}
+
+	/// Загружает чанк из региона, None если его там нет или регионы выключены
+	///
+	/// Чанк который не прочитался (поломан или с неизвестными блоками) потом не сохранится
+	pub fn load_chunk(&self, x: i32, z: i32) -> Result<Option<Chunk>, ServerError> {
+		if !self.is_enabled() {
+			return Ok(None);
+		}
+
+		let result = match self.read_chunk_nbt(x, z) {
+			Ok(Some(nbt)) => self.chunk_from_nbt(x, z, &nbt).map(Some),
+			Ok(None) => Ok(None),
+			Err(error) => Err(error),
+		};
+
+		if result.is_err() {
+			self.unreadable.lock().unwrap().insert((x, z));
+		}
+
+		result
+	}
+
+	/// Сохраняет чанк в регион, без полного реестра блоков ничего не пишется
+	///
+	/// Все что мы не понимаем (энтити, блок-энтити, структуры) берется из старой версии чанка
+	pub fn save_chunk(&self, chunk: &Chunk) -> Result<(), ServerError> {
+		if !self.is_enabled() {
+			return Ok(());
+		}
+
+		if self
+			.unreadable
+			.lock()
+			.unwrap()
+			.contains(&(chunk.x, chunk.z))
+		{
+			return Err(ServerError::Region);
+		}
+
+		let base = self.read_chunk_nbt(chunk.x, chunk.z).unwrap_or(None);
+		let nbt = self.chunk_to_nbt(chunk, base);
+		self.write_chunk_nbt(chunk.x, chunk.z, &nbt)
+	}
+
+	/// Переводит NBT чанка (формат 1.18+) в наш чанк
+	pub fn chunk_from_nbt(&self, x: i32, z: i32, nbt: &DynNBT) -> Result<Chunk, ServerError> {
+		let root = nbt.as_compound().ok_or(ServerError::Region)?;
+		let sections_nbt = root
+			.get("sections")
+			.and_then(|o| o.as_list())
+			.ok_or(ServerError::Region)?;
+
+		let default_biome = self
+			.biome_ids
+			.get(DEFAULT_BIOME)
+			.copied()
+			.unwrap_or_default();
+
+		let mut sections = vec![ChunkSection::new(default_biome); self.height / SECTION_SIZE];
+
+		for section in sections_nbt {
+			let section = section.as_compound().ok_or(ServerError::Region)?;
+			let y = section
+				.get("Y")
+				.and_then(|o| o.as_byte())
+				.ok_or(ServerError::Region)? as i32;
+
+			// Секции только со светом бывают за пределами мира
+			let index = y - self.min_y.div_euclid(SECTION_SIZE as i32);
+			if index < 0 || index as usize >= sections.len() {
+				continue;
+			}
+
+			let blocks = match section.get("block_states").and_then(|o| o.as_compound()) {
+				Some(states) => {
+					let palette = states
+						.get("palette")
+						.and_then(|o| o.as_list())
+						.ok_or(ServerError::Region)?
+						.iter()
+						.map(|o| self.block_from_nbt(o))
+						.collect::<Result<Vec<u32>, ServerError>>()?;
+					self.read_container(PaletteKind::Blocks, palette, states.get("data"))
+				}
+				None => PalettedContainer::new(PaletteKind::Blocks, AIR),
+			};
+
+			let biomes = match section.get("biomes").and_then(|o| o.as_compound()) {
+				Some(biomes) => {
+					let palette = biomes
+						.get("palette")
+						.and_then(|o| o.as_list())
+						.ok_or(ServerError::Region)?
+						.iter()
+						.map(|o| {
+							o.as_string()
+								.and_then(|o| self.biome_ids.get(o))
+								.copied()
+								.unwrap_or(default_biome)
+						})
+						.collect::<Vec<u32>>();
+					self.read_container(PaletteKind::Biomes, palette, biomes.get("data"))
+				}
+				None => PalettedContainer::new(PaletteKind::Biomes, default_biome),
+			};
+
+			sections[index as usize] = ChunkSection::from_containers(blocks, biomes);
+		}
+
+		Ok(Chunk::from_sections(x, z, self.min_y, sections))
+	}
+
+	fn block_from_nbt(&self, nbt: &DynNBT) -> Result<u32, ServerError> {
+		let compound = nbt.as_compound().ok_or(ServerError::Region)?;
+		let name = compound
+			.get("Name")
+			.and_then(|o| o.as_string())
+			.ok_or(ServerError::Region)?;
+
+		let properties = compound
+			.get("Properties")
+			.and_then(|o| o.as_compound())
+			.map(|o| {
+				o.iter()
+					.filter_map(|(k, v)| Some((k.clone(), v.as_string()?.clone())))
+					.collect::<BTreeMap<String, String>>()
+			})
+			.unwrap_or_default();
+
+		// Неизвестный блок нельзя заменить воздухом, иначе он пропадет при сохранении
+		self
+			.blocks
+			.get_exact_id(&BlockState {
+				name: name.clone(),
+				properties,
+			})
+			.ok_or(ServerError::Region)
+	}
+
+	// На диске палитра всегда непрямая, битность считается от размера палитры
+	fn read_container(
+		&self,
+		kind: PaletteKind,
+		palette: Vec<u32>,
+		data: Option<&DynNBT>,
+	) -> PalettedContainer {
+		let data = data.and_then(|o| o.as_long_array());
+
+		match data {
+			Some(data) if palette.len() > 1 => {
+				let bits = disk_bits(kind, palette.len());
+				let data = data.iter().map(|o| *o as u64).collect::<Vec<u64>>();
+				let values = unpack_values(&data, bits, kind.size())
+					.into_iter()
+					.map(|o| palette.get(o as usize).copied().unwrap_or(palette[0]))
+					.collect::<Vec<u32>>();
+				PalettedContainer::from_values(kind, &values)
+			}
+			_ => PalettedContainer::new(kind, palette.first().copied().unwrap_or_default()),
+		}
+	}
+
+	// Собирает палитру и данные для записи на диск
+	fn write_container(&self, container: &PalettedContainer, name: impl Fn(u32) -> DynNBT) -> DynNBT {
+		let kind = container.kind();
+		let values = container.values();
+
+		let mut palette = Vec::new();
+		for value in &values {
+			if !palette.contains(value) {
+				palette.push(*value);
+			}
+		}
+
+		let mut compound = HashMap::new();
+
+		if palette.len() > 1 {
+			let bits = disk_bits(kind, palette.len());
+			let indices = values
+				.iter()
+				.map(|value| palette.iter().position(|o| o == value).unwrap_or(0) as u32)
+				.collect::<Vec<u32>>();
+			compound.insert(
+				"data".to_string(),
+				DynNBT::LongArray(
+					pack_values(&indices, bits)
+						.into_iter()
+						.map(|o| o as i64)
+						.collect(),
+				),
+			);
+		}
+
+		let palette = palette.into_iter().map(name).collect();
+
+		compound.insert("palette".to_string(), DynNBT::List(palette));
+
+		DynNBT::Compound(compound)
+	}
+
+	fn block_to_nbt(&self, id: u32) -> DynNBT {
+		let state = self
+			.blocks
+			.get_state(id)
+			.cloned()
+			.unwrap_or_else(|| BlockState::new("minecraft:air"));
+
+		let mut compound = HashMap::new();
+		compound.insert("Name".to_string(), DynNBT::String(state.name));
+		if !state.properties.is_empty() {
+			compound.insert(
+				"Properties".to_string(),
+				DynNBT::Compound(
+					state
+						.properties
+						.into_iter()
+						.map(|(k, v)| (k, DynNBT::String(v)))
+						.collect(),
+				),
+			);
+		}
+
+		DynNBT::Compound(compound)
+	}
+
+	/// Переводит наш чанк в NBT, поверх base если он есть
+	pub fn chunk_to_nbt(&self, chunk: &Chunk, base: Option<DynNBT>) -> DynNBT {
+		let mut root = base
+			.and_then(|o| o.as_compound().cloned())
+			.unwrap_or_default();
+
+		root.insert("DataVersion".to_string(), DynNBT::Int(DATA_VERSION));
+		root.insert("xPos".to_string(), DynNBT::Int(chunk.x));
+		root.insert("zPos".to_string(), DynNBT::Int(chunk.z));
+		root.insert(
+			"yPos".to_string(),
+			DynNBT::Int(chunk.min_y().div_euclid(SECTION_SIZE as i32)),
+		);
+		root.insert(
+			"Status".to_string(),
+			DynNBT::String("minecraft:full".to_string()),
+		);
+		root
+			.entry("LastUpdate".to_string())
+			.or_insert(DynNBT::Long(0));
+		root
+			.entry("InhabitedTime".to_string())
+			.or_insert(DynNBT::Long(0));
+
+		// Хейтмапы и свет у нас не хранятся, пусть ванилла пересчитает их сама
+		root.remove("Heightmaps");
+		root.insert("isLightOn".to_string(), DynNBT::Byte(0));
+
+		let mut sections = Vec::new();
+
+		for (index, section) in chunk.sections().iter().enumerate() {
+			let mut compound = HashMap::new();
+			compound.insert(
+				"Y".to_string(),
+				DynNBT::Byte((chunk.min_y().div_euclid(SECTION_SIZE as i32) + index as i32) as i8),
+			);
+			compound.insert(
+				"block_states".to_string(),
+				self.write_container(section.blocks(), |o| self.block_to_nbt(o)),
+			);
+			compound.insert(
+				"biomes".to_string(),
+				self.write_container(section.biomes(), |o| {
+					DynNBT::String(
+						self
+							.biomes
+							.get(o as usize)
+							.cloned()
+							.unwrap_or_else(|| DEFAULT_BIOME.to_string()),
+					)
+				}),
+			);
+
+			sections.push(DynNBT::Compound(compound));
+		}
+
+		root.insert("sections".to_string(), DynNBT::List(sections));
+
+		DynNBT::Compound(root)
+	}
+}
+
+// Битность на диске: блоки минимум 4 бита, биомы сколько надо
+fn disk_bits(kind: PaletteKind, palette_len: usize) -> u8 {
+	match kind {
+		PaletteKind::Blocks => bits_for(palette_len).max(4),
+		PaletteKind::Biomes => bits_for(palette_len),
+	}
+}
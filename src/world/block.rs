@@ -0,0 +1,184 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs,
+	path::Path,
+};
+
+use serde::Deserialize;
+
+use crate::ServerError;
+
+use super::chunk::AIR;
+
+// Состояние блока как оно хранится в мире: имя блока и его свойства
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BlockState {
+	pub name: String,
+	pub properties: BTreeMap<String, String>,
+}
+
+impl BlockState {
+	pub fn new(name: &str) -> BlockState {
+		BlockState {
+			name: name.to_string(),
+			properties: BTreeMap::new(),
+		}
+	}
+
+	pub fn with_property(mut self, key: &str, value: &str) -> BlockState {
+		self.properties.insert(key.to_string(), value.to_string());
+		self
+	}
+}
+
+// Формат отчета blocks.json из ванильного генератора данных
+// java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports
+#[derive(Deserialize)]
+struct ReportBlock {
+	states: Vec<ReportState>,
+}
+
+#[derive(Deserialize)]
+struct ReportState {
+	id: u32,
+	#[serde(default)]
+	default: bool,
+	#[serde(default)]
+	properties: BTreeMap<String, String>,
+}
+
+// Реестр состояний блоков, переводит имена со свойствами в айди и обратно
+#[derive(Debug, Clone, Default)]
+pub struct BlockRegistry {
+	states: HashMap<u32, BlockState>,
+	ids: HashMap<BlockState, u32>,
+	defaults: HashMap<String, u32>,
+	complete: bool,
+}
+
+impl BlockRegistry {
+	/// Загружает реестр из ванильного отчета blocks.json
+	pub fn load_report(path: &Path) -> Result<BlockRegistry, ServerError> {
+		let content = fs::read_to_string(path)?;
+		let report: HashMap<String, ReportBlock> =
+			serde_json::from_str(&content).map_err(|e| ServerError::Io(e.into()))?;
+
+		let mut registry = BlockRegistry {
+			complete: true,
+			..Default::default()
+		};
+
+		for (name, block) in report {
+			for state in block.states {
+				registry.register(
+					BlockState {
+						name: name.clone(),
+						properties: state.properties,
+					},
+					state.id,
+					state.default,
+				);
+			}
+		}
+
+		Ok(registry)
+	}
+
+	/// Встроенный реестр с самыми базовыми блоками, на случай если отчета нет
+	///
+	/// Айди для 1.21.5
+	pub fn builtin() -> BlockRegistry {
+		let mut registry = BlockRegistry::default();
+
+		for (id, name) in [
+			"minecraft:air",
+			"minecraft:stone",
+			"minecraft:granite",
+			"minecraft:polished_granite",
+			"minecraft:diorite",
+			"minecraft:polished_diorite",
+			"minecraft:andesite",
+			"minecraft:polished_andesite",
+		]
+		.into_iter()
+		.enumerate()
+		{
+			registry.register(BlockState::new(name), id as u32, true);
+		}
+
+		registry.register(
+			BlockState::new("minecraft:grass_block").with_property("snowy", "true"),
+			8,
+			false,
+		);
+		registry.register(
+			BlockState::new("minecraft:grass_block").with_property("snowy", "false"),
+			9,
+			true,
+		);
+		registry.register(BlockState::new("minecraft:dirt"), 10, true);
+		registry.register(BlockState::new("minecraft:coarse_dirt"), 11, true);
+		registry.register(
+			BlockState::new("minecraft:podzol").with_property("snowy", "true"),
+			12,
+			false,
+		);
+		registry.register(
+			BlockState::new("minecraft:podzol").with_property("snowy", "false"),
+			13,
+			true,
+		);
+		registry.register(BlockState::new("minecraft:cobblestone"), 14, true);
+
+		registry
+	}
+
+	pub fn register(&mut self, state: BlockState, id: u32, default: bool) {
+		if default {
+			self.defaults.insert(state.name.clone(), id);
+		}
+		self.states.insert(id, state.clone());
+		self.ids.insert(state, id);
+	}
+
+	pub fn len(&self) -> usize {
+		self.states.len()
+	}
+
+	/// Реестр из отчета знает все ванильные блоки, встроенный - только несколько
+	pub fn is_complete(&self) -> bool {
+		self.complete
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.states.is_empty()
+	}
+
+	/// Айди состояния, если свойства не совпали то берется дефолтное состояние блока
+	pub fn get_id(&self, state: &BlockState) -> Option<u32> {
+		self
+			.ids
+			.get(state)
+			.or_else(|| self.defaults.get(&state.name))
+			.copied()
+	}
+
+	/// Айди состояния только при точном совпадении свойств
+	pub fn get_exact_id(&self, state: &BlockState) -> Option<u32> {
+		self.ids.get(state).copied()
+	}
+
+	/// Айди дефолтного состояния блока по имени
+	pub fn get_default_id(&self, name: &str) -> Option<u32> {
+		self.defaults.get(name).copied()
+	}
+
+	pub fn get_state(&self, id: u32) -> Option<&BlockState> {
+		self.states.get(&id)
+	}
+
+	/// Как get_id, но неизвестные блоки превращаются в воздух
+	pub fn get_id_or_air(&self, state: &BlockState) -> u32 {
+		self.get_id(state).unwrap_or(AIR)
+	}
+}
@@ -0,0 +1,680 @@
+use rust_mc_proto::DataWriter;
+
+use crate::{
+	ServerError,
+	protocol::{
+		codec::VarInt,
+		packets::play::{ChunkData, Heightmap, LightData, clientbound},
+	},
+};
+
+// Айди блока воздуха в реестре состояний блоков
+pub const AIR: u32 = 0;
+
+// Размер секции по каждой оси
+pub const SECTION_SIZE: usize = 16;
+// Количество блоков в секции, 16 * 16 * 16
+pub const SECTION_VOLUME: usize = SECTION_SIZE * SECTION_SIZE * SECTION_SIZE;
+// Биомы хранятся кусками 4x4x4, так что в секции их 4 * 4 * 4
+pub const BIOME_VOLUME: usize = 64;
+
+// Типы хейтмап, айди из протокола
+pub const HEIGHTMAP_WORLD_SURFACE: i32 = 1;
+pub const HEIGHTMAP_MOTION_BLOCKING: i32 = 4;
+
+// Вид палитрового контейнера, от него зависят размеры и битность палитр
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteKind {
+	Blocks,
+	Biomes,
+}
+
+impl PaletteKind {
+	/// Количество значений в контейнере
+	pub fn size(&self) -> usize {
+		match self {
+			PaletteKind::Blocks => SECTION_VOLUME,
+			PaletteKind::Biomes => BIOME_VOLUME,
+		}
+	}
+
+	/// Минимальная битность непрямой палитры
+	pub fn min_indirect_bits(&self) -> u8 {
+		match self {
+			PaletteKind::Blocks => 4,
+			PaletteKind::Biomes => 1,
+		}
+	}
+
+	/// Максимальная битность непрямой палитры, дальше палитра прямая
+	pub fn max_indirect_bits(&self) -> u8 {
+		match self {
+			PaletteKind::Blocks => 8,
+			PaletteKind::Biomes => 3,
+		}
+	}
+
+	/// Битность прямой палитры в памяти
+	///
+	/// Клиенту прямая палитра пишется с битностью ceil(log2(размер реестра)), см. write.
+	/// Биомы датапаки могут добавить, поэтому в памяти они хранятся с запасом
+	pub fn direct_bits(&self) -> u8 {
+		match self {
+			PaletteKind::Blocks => 15, // 27914 состояний блоков в 1.21.5
+			PaletteKind::Biomes => 16,
+		}
+	}
+
+	/// Битность прямой палитры для реестра из registry_size значений, как ее считает клиент
+	pub fn registry_bits(&self, registry_size: usize) -> u8 {
+		// Меньше нельзя, иначе клиент примет прямую палитру за непрямую
+		bits_for(registry_size).max(self.max_indirect_bits() + 1)
+	}
+}
+
+// Палитровый контейнер
+// https://minecraft.wiki/w/Java_Edition_protocol/Chunk_format#Paletted_Container_structure
+#[derive(Debug, Clone, PartialEq)]
+pub enum PalettedContainer {
+	/// Весь контейнер заполнен одним значением
+	Single { kind: PaletteKind, value: u32 },
+	/// Данные хранят индексы в палитре
+	Indirect {
+		kind: PaletteKind,
+		bits: u8,
+		palette: Vec<u32>,
+		data: Vec<u64>,
+	},
+	/// Данные хранят сами айди из реестра
+	Direct { kind: PaletteKind, data: Vec<u64> },
+}
+
+// Длина массива long для заданной битности
+// Начиная с 1.16 значения не переходят через границу long
+fn packed_length(size: usize, bits: u8) -> usize {
+	let per_long = 64 / bits as usize;
+	size.div_ceil(per_long)
+}
+
+/// Минимальное количество бит чтобы уместить count разных значений
+pub fn bits_for(count: usize) -> u8 {
+	if count <= 1 {
+		0
+	} else {
+		(usize::BITS - (count - 1).leading_zeros()) as u8
+	}
+}
+
+/// Распаковывает значения из массива long, значения не переходят через границу long
+pub fn unpack_values(data: &[u64], bits: u8, size: usize) -> Vec<u32> {
+	if bits == 0 {
+		return vec![0; size];
+	}
+	(0..size)
+		.map(|i| {
+			if i / (64 / bits as usize) < data.len() {
+				get_packed(data, bits, i)
+			} else {
+				0
+			}
+		})
+		.collect()
+}
+
+/// Упаковывает значения в массив long, значения не переходят через границу long
+pub fn pack_values(values: &[u32], bits: u8) -> Vec<u64> {
+	let mut data = vec![0; packed_length(values.len(), bits)];
+	for (i, value) in values.iter().enumerate() {
+		set_packed(&mut data, bits, i, *value);
+	}
+	data
+}
+
+fn get_packed(data: &[u64], bits: u8, index: usize) -> u32 {
+	let per_long = 64 / bits as usize;
+	let long = data[index / per_long];
+	let offset = (index % per_long) * bits as usize;
+	((long >> offset) & ((1u64 << bits) - 1)) as u32
+}
+
+fn set_packed(data: &mut [u64], bits: u8, index: usize, value: u32) {
+	let per_long = 64 / bits as usize;
+	let offset = (index % per_long) * bits as usize;
+	let mask = ((1u64 << bits) - 1) << offset;
+	let long = &mut data[index / per_long];
+	*long = (*long & !mask) | (((value as u64) << offset) & mask);
+}
+
+impl PalettedContainer {
+	pub fn new(kind: PaletteKind, value: u32) -> PalettedContainer {
+		PalettedContainer::Single { kind, value }
+	}
+
+	/// Собирает контейнер из всех его значений, выбирая самую компактную палитру
+	pub fn from_values(kind: PaletteKind, values: &[u32]) -> PalettedContainer {
+		let mut palette = Vec::new();
+		for value in values {
+			if !palette.contains(value) {
+				palette.push(*value);
+			}
+		}
+
+		if palette.len() <= 1 {
+			return PalettedContainer::Single {
+				kind,
+				value: palette.first().copied().unwrap_or_default(),
+			};
+		}
+
+		let bits = bits_for(palette.len()).max(kind.min_indirect_bits());
+
+		if bits > kind.max_indirect_bits() {
+			return PalettedContainer::Direct {
+				kind,
+				data: pack_values(values, kind.direct_bits()),
+			};
+		}
+
+		let indices = values
+			.iter()
+			.map(|value| palette.iter().position(|o| o == value).unwrap_or(0) as u32)
+			.collect::<Vec<u32>>();
+
+		PalettedContainer::Indirect {
+			kind,
+			bits,
+			data: pack_values(&indices, bits),
+			palette,
+		}
+	}
+
+	/// Все значения контейнера по порядку
+	pub fn values(&self) -> Vec<u32> {
+		(0..self.kind().size()).map(|i| self.get(i)).collect()
+	}
+
+	pub fn kind(&self) -> PaletteKind {
+		match self {
+			PalettedContainer::Single { kind, .. }
+			| PalettedContainer::Indirect { kind, .. }
+			| PalettedContainer::Direct { kind, .. } => *kind,
+		}
+	}
+
+	pub fn get(&self, index: usize) -> u32 {
+		match self {
+			PalettedContainer::Single { value, .. } => *value,
+			PalettedContainer::Indirect {
+				bits,
+				palette,
+				data,
+				..
+			} => palette[get_packed(data, *bits, index) as usize],
+			PalettedContainer::Direct { kind, data } => get_packed(data, kind.direct_bits(), index),
+		}
+	}
+
+	/// Ставит значение и возвращает предыдущее
+	pub fn set(&mut self, index: usize, value: u32) -> u32 {
+		let previous = self.get(index);
+
+		if previous == value {
+			return previous;
+		}
+
+		match self {
+			PalettedContainer::Single {
+				kind,
+				value: single,
+			} => {
+				let kind = *kind;
+				let bits = kind.min_indirect_bits();
+				*self = PalettedContainer::Indirect {
+					kind,
+					bits,
+					palette: vec![*single],
+					data: vec![0; packed_length(kind.size(), bits)],
+				};
+				self.set(index, value);
+			}
+			PalettedContainer::Indirect {
+				bits,
+				palette,
+				data,
+				..
+			} => {
+				let palette_index = match palette.iter().position(|o| *o == value) {
+					Some(i) => i,
+					None => {
+						if palette.len() >= 1 << *bits {
+							// Палитра переполнена, пересобираем контейнер с большей битностью
+							self.resize();
+							return self.set(index, value);
+						}
+						palette.push(value);
+						palette.len() - 1
+					}
+				};
+				set_packed(data, *bits, index, palette_index as u32);
+			}
+			PalettedContainer::Direct { kind, data } => {
+				set_packed(data, kind.direct_bits(), index, value);
+			}
+		}
+
+		previous
+	}
+
+	// Увеличивает битность непрямой палитры, либо переводит ее в прямую
+	fn resize(&mut self) {
+		let PalettedContainer::Indirect {
+			kind,
+			bits,
+			palette,
+			data,
+		} = self
+		else {
+			return;
+		};
+
+		let kind = *kind;
+		let values = (0..kind.size())
+			.map(|i| palette[get_packed(data, *bits, i) as usize])
+			.collect::<Vec<u32>>();
+
+		let new_bits = *bits + 1;
+
+		if new_bits > kind.max_indirect_bits() {
+			let bits = kind.direct_bits();
+			let mut data = vec![0; packed_length(kind.size(), bits)];
+			for (i, value) in values.into_iter().enumerate() {
+				set_packed(&mut data, bits, i, value);
+			}
+			*self = PalettedContainer::Direct { kind, data };
+		} else {
+			let palette = palette.clone();
+			let mut data = vec![0; packed_length(kind.size(), new_bits)];
+			for (i, value) in values.into_iter().enumerate() {
+				let palette_index = palette.iter().position(|o| *o == value).unwrap_or(0);
+				set_packed(&mut data, new_bits, i, palette_index as u32);
+			}
+			*self = PalettedContainer::Indirect {
+				kind,
+				bits: new_bits,
+				palette,
+				data,
+			};
+		}
+	}
+
+	/// Заполняет весь контейнер одним значением
+	pub fn fill(&mut self, value: u32) {
+		*self = PalettedContainer::Single {
+			kind: self.kind(),
+			value,
+		};
+	}
+
+	/// Записывает контейнер в формате протокола
+	///
+	/// Начиная с 1.21.5 длина массива данных не пишется, она вычисляется из битности.
+	/// direct_bits - битность прямой палитры у клиента, см. PaletteKind::registry_bits
+	pub fn write(&self, buf: &mut Vec<u8>, direct_bits: u8) -> Result<(), ServerError> {
+		match self {
+			PalettedContainer::Single { value, .. } => {
+				buf.write_byte(0)?; // Bits Per Entry, single valued
+				buf.write_u32_varint(*value)?;
+			}
+			PalettedContainer::Indirect {
+				bits,
+				palette,
+				data,
+				..
+			} => {
+				buf.write_byte(*bits)?;
+				buf.write_usize_varint(palette.len())?;
+				for value in palette {
+					buf.write_u32_varint(*value)?;
+				}
+				for long in data {
+					buf.write_long(*long as i64)?;
+				}
+			}
+			PalettedContainer::Direct { kind, data } => {
+				let repacked;
+				let data = if direct_bits == kind.direct_bits() {
+					data
+				} else {
+					repacked = pack_values(&self.values(), direct_bits);
+					&repacked
+				};
+
+				buf.write_byte(direct_bits)?;
+				for long in data {
+					buf.write_long(*long as i64)?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+// Секция чанка 16x16x16
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSection {
+	block_count: i16,
+	blocks: PalettedContainer,
+	biomes: PalettedContainer,
+}
+
+impl ChunkSection {
+	pub fn new(biome: u32) -> ChunkSection {
+		ChunkSection {
+			block_count: 0,
+			blocks: PalettedContainer::new(PaletteKind::Blocks, AIR),
+			biomes: PalettedContainer::new(PaletteKind::Biomes, biome),
+		}
+	}
+
+	/// Секция из готовых контейнеров, количество блоков считается само
+	pub fn from_containers(blocks: PalettedContainer, biomes: PalettedContainer) -> ChunkSection {
+		let block_count = blocks.values().iter().filter(|o| **o != AIR).count() as i16;
+		ChunkSection {
+			block_count,
+			blocks,
+			biomes,
+		}
+	}
+
+	pub fn blocks(&self) -> &PalettedContainer {
+		&self.blocks
+	}
+
+	pub fn biomes(&self) -> &PalettedContainer {
+		&self.biomes
+	}
+
+	fn block_index(x: usize, y: usize, z: usize) -> usize {
+		(y * SECTION_SIZE + z) * SECTION_SIZE + x
+	}
+
+	fn biome_index(x: usize, y: usize, z: usize) -> usize {
+		((y / 4) * 4 + z / 4) * 4 + x / 4
+	}
+
+	/// Количество не-воздушных блоков
+	pub fn block_count(&self) -> i16 {
+		self.block_count
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.block_count == 0
+	}
+
+	/// Координаты локальные, от 0 до 15
+	pub fn get_block(&self, x: usize, y: usize, z: usize) -> u32 {
+		self.blocks.get(Self::block_index(x, y, z))
+	}
+
+	/// Координаты локальные, от 0 до 15. Возвращает предыдущий блок
+	pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: u32) -> u32 {
+		let previous = self.blocks.set(Self::block_index(x, y, z), state);
+		if previous == AIR && state != AIR {
+			self.block_count += 1;
+		} else if previous != AIR && state == AIR {
+			self.block_count -= 1;
+		}
+		previous
+	}
+
+	pub fn fill(&mut self, state: u32) {
+		self.blocks.fill(state);
+		self.block_count = if state == AIR {
+			0
+		} else {
+			SECTION_VOLUME as i16
+		};
+	}
+
+	/// Координаты локальные, от 0 до 15 (биомы хранятся кусками 4x4x4)
+	pub fn get_biome(&self, x: usize, y: usize, z: usize) -> u32 {
+		self.biomes.get(Self::biome_index(x, y, z))
+	}
+
+	pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: u32) -> u32 {
+		self.biomes.set(Self::biome_index(x, y, z), biome)
+	}
+
+	pub fn fill_biome(&mut self, biome: u32) {
+		self.biomes.fill(biome);
+	}
+
+	/// biome_bits - битность прямой палитры биомов, см. PaletteKind::registry_bits
+	pub fn write(&self, buf: &mut Vec<u8>, biome_bits: u8) -> Result<(), ServerError> {
+		buf.write_short(self.block_count)?;
+		self.blocks.write(buf, PaletteKind::Blocks.direct_bits())?;
+		self.biomes.write(buf, biome_bits)
+	}
+}
+
+// Чанк, столб секций 16 блоков шириной
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+	pub x: i32,
+	pub z: i32,
+	min_y: i32,
+	sections: Vec<ChunkSection>,
+	dirty: bool, // Чанк менялся с последнего сохранения
+}
+
+impl Chunk {
+	/// Пустой чанк из воздуха, высота должна быть кратна 16
+	pub fn new(x: i32, z: i32, min_y: i32, height: usize, biome: u32) -> Chunk {
+		Chunk {
+			x,
+			z,
+			min_y,
+			sections: vec![ChunkSection::new(biome); height / SECTION_SIZE],
+			dirty: true,
+		}
+	}
+
+	/// Чанк из готовых секций, например загруженных из региона
+	pub fn from_sections(x: i32, z: i32, min_y: i32, sections: Vec<ChunkSection>) -> Chunk {
+		Chunk {
+			x,
+			z,
+			min_y,
+			sections,
+			dirty: false,
+		}
+	}
+
+	/// Менялся ли чанк с последнего сохранения
+	pub fn is_dirty(&self) -> bool {
+		self.dirty
+	}
+
+	pub fn set_dirty(&mut self, dirty: bool) {
+		self.dirty = dirty;
+	}
+
+	pub fn min_y(&self) -> i32 {
+		self.min_y
+	}
+
+	pub fn height(&self) -> usize {
+		self.sections.len() * SECTION_SIZE
+	}
+
+	pub fn sections(&self) -> &[ChunkSection] {
+		&self.sections
+	}
+
+	pub fn sections_mut(&mut self) -> &mut [ChunkSection] {
+		self.dirty = true;
+		&mut self.sections
+	}
+
+	fn section_index(&self, y: i32) -> Option<usize> {
+		let index = (y - self.min_y).div_euclid(SECTION_SIZE as i32);
+		if index < 0 || index as usize >= self.sections.len() {
+			None
+		} else {
+			Some(index as usize)
+		}
+	}
+
+	/// x и z - локальные (0..16), y - мировая координата
+	///
+	/// Вне мира всегда воздух
+	pub fn get_block(&self, x: usize, y: i32, z: usize) -> u32 {
+		match self.section_index(y) {
+			Some(index) => self.sections[index].get_block(
+				x,
+				(y - self.min_y).rem_euclid(SECTION_SIZE as i32) as usize,
+				z,
+			),
+			None => AIR,
+		}
+	}
+
+	/// x и z - локальные (0..16), y - мировая координата
+	///
+	/// Возвращает предыдущий блок, либо None если y вне мира
+	pub fn set_block(&mut self, x: usize, y: i32, z: usize, state: u32) -> Option<u32> {
+		let index = self.section_index(y)?;
+		let local_y = (y - self.min_y).rem_euclid(SECTION_SIZE as i32) as usize;
+		self.dirty = true;
+		Some(self.sections[index].set_block(x, local_y, z, state))
+	}
+
+	pub fn get_biome(&self, x: usize, y: i32, z: usize) -> u32 {
+		match self.section_index(y) {
+			Some(index) => self.sections[index].get_biome(
+				x,
+				(y - self.min_y).rem_euclid(SECTION_SIZE as i32) as usize,
+				z,
+			),
+			None => 0,
+		}
+	}
+
+	pub fn set_biome(&mut self, x: usize, y: i32, z: usize, biome: u32) -> Option<u32> {
+		let index = self.section_index(y)?;
+		let local_y = (y - self.min_y).rem_euclid(SECTION_SIZE as i32) as usize;
+		self.dirty = true;
+		Some(self.sections[index].set_biome(x, local_y, z, biome))
+	}
+
+	/// Заполняет слои блоков от from_y до to_y включительно
+	pub fn fill_layers(&mut self, from_y: i32, to_y: i32, state: u32) {
+		let mut y = from_y;
+		while y <= to_y {
+			// Секцию которая покрывается целиком заполняем сразу, без палитр
+			if let Some(index) = self.section_index(y) {
+				let section_min = self.min_y + (index * SECTION_SIZE) as i32;
+				let section_max = section_min + SECTION_SIZE as i32 - 1;
+				if y == section_min && to_y >= section_max {
+					self.sections[index].fill(state);
+					self.dirty = true;
+					y = section_max + 1;
+					continue;
+				}
+			}
+			for x in 0..SECTION_SIZE {
+				for z in 0..SECTION_SIZE {
+					self.set_block(x, y, z, state);
+				}
+			}
+			y += 1;
+		}
+	}
+
+	/// Высота самого верхнего не-воздушного блока + 1 для каждого столба, относительно min_y
+	///
+	/// 0 если столб пустой
+	pub fn compute_heightmap(&self) -> [u16; 256] {
+		let mut heights = [0u16; 256];
+
+		for x in 0..SECTION_SIZE {
+			for z in 0..SECTION_SIZE {
+				'column: for (index, section) in self.sections.iter().enumerate().rev() {
+					if section.is_empty() {
+						continue;
+					}
+					for y in (0..SECTION_SIZE).rev() {
+						if section.get_block(x, y, z) != AIR {
+							heights[z * SECTION_SIZE + x] = (index * SECTION_SIZE + y + 1) as u16;
+							break 'column;
+						}
+					}
+				}
+			}
+		}
+
+		heights
+	}
+
+	/// Хейтмапа в формате протокола, битность ceil(log2(высота + 1))
+	pub fn heightmap_data(&self) -> Vec<i64> {
+		let bits = (usize::BITS - self.height().leading_zeros()) as u8;
+		let heights = self.compute_heightmap();
+		let mut data = vec![0u64; packed_length(heights.len(), bits)];
+		for (i, height) in heights.iter().enumerate() {
+			set_packed(&mut data, bits, i, *height as u32);
+		}
+		data.into_iter().map(|o| o as i64).collect()
+	}
+
+	/// Данные всех секций подряд
+	pub fn sections_data(&self, biome_bits: u8) -> Result<Vec<u8>, ServerError> {
+		let mut data = Vec::new();
+		for section in &self.sections {
+			section.write(&mut data, biome_bits)?;
+		}
+		Ok(data)
+	}
+
+	/// Собирает пакет Chunk Data and Update Light из текущего состояния чанка
+	///
+	/// Свет пока не считается, небо везде светит на полную.
+	/// biome_bits - битность прямой палитры биомов, см. WorldContext::biome_bits
+	pub fn to_packet(
+		&self,
+		biome_bits: u8,
+	) -> Result<clientbound::ChunkDataAndUpdateLight, ServerError> {
+		let heightmap = self.heightmap_data();
+
+		// Секций света на две больше: одна под миром и одна над ним
+		let light_sections = self.sections.len() + 2;
+		let light_mask = vec![((1u128 << light_sections) - 1) as i64];
+
+		Ok(clientbound::ChunkDataAndUpdateLight {
+			chunk_x: self.x,
+			chunk_z: self.z,
+			data: ChunkData {
+				heightmaps: vec![
+					Heightmap {
+						heightmap_type: VarInt(HEIGHTMAP_WORLD_SURFACE),
+						data: heightmap.clone(),
+					},
+					Heightmap {
+						heightmap_type: VarInt(HEIGHTMAP_MOTION_BLOCKING),
+						data: heightmap,
+					},
+				],
+				data: self.sections_data(biome_bits)?,
+				block_entities: Vec::new(),
+			},
+			light: LightData {
+				sky_light_mask: light_mask,
+				block_light_mask: Vec::new(),
+				empty_sky_light_mask: Vec::new(),
+				empty_block_light_mask: vec![((1u128 << light_sections) - 1) as i64],
+				sky_light_arrays: vec![vec![0xFF; 2048]; light_sections],
+				block_light_arrays: Vec::new(),
+			},
+		})
+	}
+}
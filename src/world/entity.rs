@@ -0,0 +1,349 @@
+// Сущности мира кроме игроков: предметы на земле, стойки для брони, мобы, дисплеи
+//
+// Все сущности, и игроки тоже, берут айди из одного счетчика WorldContext, так что
+// айди не пересекаются. Пакеты, отслеживание и тик в play::entity
+
+use std::sync::{Arc, RwLock};
+
+use dashmap::DashMap;
+use uuid::{Builder, Uuid};
+
+use crate::{
+	data::{
+		metadata::{EntityMetadata, ITEM_INDEX, MetadataValue, NO_GRAVITY_INDEX},
+		slot::Slot,
+	},
+	player::{
+		combat::{PLAYER_HEIGHT, PLAYER_WIDTH},
+		context::ClientContext,
+	},
+};
+
+// Ключи реестра minecraft:entity_type
+pub const PLAYER: &str = "minecraft:player";
+pub const ITEM: &str = "minecraft:item";
+pub const ARMOR_STAND: &str = "minecraft:armor_stand";
+pub const MARKER: &str = "minecraft:marker";
+pub const INTERACTION: &str = "minecraft:interaction";
+pub const BLOCK_DISPLAY: &str = "minecraft:block_display";
+pub const ITEM_DISPLAY: &str = "minecraft:item_display";
+pub const TEXT_DISPLAY: &str = "minecraft:text_display";
+
+// Айди самых нужных типов на случай если отчета registries.json нет, для 1.21.5
+pub const BUILTIN_ENTITY_TYPES: [(&str, i32); 13] = [
+	(ARMOR_STAND, 5),
+	(BLOCK_DISPLAY, 15),
+	("minecraft:cow", 28),
+	(INTERACTION, 66),
+	(ITEM, 68),
+	(ITEM_DISPLAY, 69),
+	(MARKER, 80),
+	("minecraft:pig", 94),
+	("minecraft:sheep", 105),
+	("minecraft:skeleton", 109),
+	(TEXT_DISPLAY, 125),
+	("minecraft:zombie", 144),
+	(PLAYER, 148),
+];
+
+// Эти сущности висят на месте, физики у них нет
+const STATIC_ENTITY_TYPES: [&str; 5] = [
+	MARKER,
+	INTERACTION,
+	BLOCK_DISPLAY,
+	ITEM_DISPLAY,
+	TEXT_DISPLAY,
+];
+
+/// Ускорение падения предметов за тик, у остальных сущностей в два раза больше
+pub const ITEM_GRAVITY: f64 = 0.04;
+pub const GRAVITY: f64 = 0.08;
+/// Сопротивление воздуха, скорость умножается на него каждый тик
+pub const DRAG: f64 = 0.98;
+/// Трение о землю по горизонтали
+pub const GROUND_FRICTION: f64 = 0.6;
+
+/// Через сколько тиков предмет на земле исчезает, 5 минут
+pub const ITEM_LIFETIME: u32 = 6000;
+/// Сколько тиков выкинутый игроком предмет нельзя подобрать
+pub const DROP_PICKUP_DELAY: u32 = 40;
+
+// Хитбокс предмета
+pub const ITEM_SIZE: f64 = 0.25;
+
+/// Айди типа сущности из встроенной таблицы, см. play::entity::entity_type_id
+pub fn builtin_entity_type_id(entity_type: &str) -> Option<i32> {
+	BUILTIN_ENTITY_TYPES
+		.iter()
+		.find(|(name, _)| *name == entity_type)
+		.map(|(_, id)| *id)
+}
+
+/// Может ли игрок в (x, y, z) подобрать предмет в точке item,
+/// хитбокс игрока для этого расширяется на 1 блок в стороны и на полблока вверх и вниз
+pub fn can_pick_up(item: (f64, f64, f64), player: (f64, f64, f64)) -> bool {
+	let reach = PLAYER_WIDTH / 2.0 + 1.0 + ITEM_SIZE / 2.0;
+
+	(item.0 - player.0).abs() <= reach
+		&& (item.2 - player.2).abs() <= reach
+		&& item.1 + ITEM_SIZE >= player.1 - 0.5
+		&& item.1 <= player.1 + PLAYER_HEIGHT + 0.5
+}
+
+/// Сущность мира, не игрок
+///
+/// Поля меняются без отправки пакетов, клиентам изменения рассылает play::entity
+pub struct Entity {
+	pub id: i32,
+	pub uuid: Uuid,
+	/// Ключ в реестре minecraft:entity_type, например minecraft:item
+	pub entity_type: String,
+	/// Поле data из Spawn Entity, зависит от типа, например направление у картины
+	pub data: i32,
+	position: RwLock<(f64, f64, f64)>,
+	velocity: RwLock<(f64, f64, f64)>,
+	rotation: RwLock<(f32, f32)>,
+	on_ground: RwLock<bool>,
+	metadata: RwLock<EntityMetadata>,
+	age: RwLock<u32>,
+	pickup_delay: RwLock<u32>,
+	removed: RwLock<bool>,
+}
+
+impl Entity {
+	/// Айди берется из WorldContext::next_entity_id, UUID случайный
+	pub fn new(id: i32, entity_type: &str, position: (f64, f64, f64)) -> Entity {
+		Entity {
+			id,
+			uuid: Builder::from_random_bytes(rand::random()).into_uuid(),
+			entity_type: entity_type.to_string(),
+			data: 0,
+			position: RwLock::new(position),
+			velocity: RwLock::new((0.0, 0.0, 0.0)),
+			rotation: RwLock::new((0.0, 0.0)),
+			on_ground: RwLock::new(false),
+			metadata: RwLock::new(EntityMetadata::new()),
+			age: RwLock::new(0),
+			pickup_delay: RwLock::new(0),
+			removed: RwLock::new(false),
+		}
+	}
+
+	/// Предмет на земле
+	pub fn item(id: i32, item: Slot, position: (f64, f64, f64)) -> Entity {
+		let mut entity = Entity::new(id, ITEM, position);
+		entity
+			.metadata
+			.get_mut()
+			.unwrap()
+			.set(ITEM_INDEX, MetadataValue::Slot(Some(item)));
+		entity
+	}
+
+	pub fn with_data(mut self, data: i32) -> Entity {
+		self.data = data;
+		self
+	}
+
+	pub fn with_velocity(mut self, velocity: (f64, f64, f64)) -> Entity {
+		*self.velocity.get_mut().unwrap() = velocity;
+		self
+	}
+
+	pub fn with_rotation(mut self, rotation: (f32, f32)) -> Entity {
+		*self.rotation.get_mut().unwrap() = rotation;
+		self
+	}
+
+	pub fn with_pickup_delay(mut self, delay: u32) -> Entity {
+		*self.pickup_delay.get_mut().unwrap() = delay;
+		self
+	}
+
+	pub fn is_item(self: &Arc<Self>) -> bool {
+		self.entity_type == ITEM
+	}
+
+	/// Предмет у сущности minecraft:item
+	pub fn item_stack(self: &Arc<Self>) -> Option<Slot> {
+		match self.metadata.read().unwrap().get(ITEM_INDEX) {
+			Some(MetadataValue::Slot(item)) => item.clone(),
+			_ => None,
+		}
+	}
+
+	/// Падает ли сущность, выключается метаданными no_gravity
+	pub fn has_gravity(self: &Arc<Self>) -> bool {
+		!STATIC_ENTITY_TYPES.contains(&self.entity_type.as_str())
+			&& !matches!(
+				self.metadata.read().unwrap().get(NO_GRAVITY_INDEX),
+				Some(MetadataValue::Boolean(true))
+			)
+	}
+
+	pub fn gravity(self: &Arc<Self>) -> f64 {
+		if self.is_item() {
+			ITEM_GRAVITY
+		} else {
+			GRAVITY
+		}
+	}
+
+	pub fn metadata(self: &Arc<Self>) -> EntityMetadata {
+		self.metadata.read().unwrap().clone()
+	}
+
+	pub fn set_metadata(self: &Arc<Self>, index: u8, value: MetadataValue) {
+		self.metadata.write().unwrap().set(index, value);
+	}
+
+	/// Сколько тиков сущность живет в мире
+	pub fn age(self: &Arc<Self>) -> u32 {
+		*self.age.read().unwrap()
+	}
+
+	pub fn set_age(self: &Arc<Self>, age: u32) {
+		*self.age.write().unwrap() = age;
+	}
+
+	/// Сколько еще тиков предмет нельзя подобрать
+	pub fn pickup_delay(self: &Arc<Self>) -> u32 {
+		*self.pickup_delay.read().unwrap()
+	}
+
+	pub fn set_pickup_delay(self: &Arc<Self>, delay: u32) {
+		*self.pickup_delay.write().unwrap() = delay;
+	}
+
+	/// Сущность убрана из мира, но кто-то еще держит ее Arc
+	pub fn is_removed(self: &Arc<Self>) -> bool {
+		*self.removed.read().unwrap()
+	}
+
+	pub fn set_removed(self: &Arc<Self>) {
+		*self.removed.write().unwrap() = true;
+	}
+
+	pub fn set_position(self: &Arc<Self>, position: (f64, f64, f64)) {
+		*self.position.write().unwrap() = position;
+	}
+
+	pub fn set_velocity(self: &Arc<Self>, velocity: (f64, f64, f64)) {
+		*self.velocity.write().unwrap() = velocity;
+	}
+
+	pub fn set_rotation(self: &Arc<Self>, rotation: (f32, f32)) {
+		*self.rotation.write().unwrap() = rotation;
+	}
+
+	pub fn set_on_ground(self: &Arc<Self>, on_ground: bool) {
+		*self.on_ground.write().unwrap() = on_ground;
+	}
+
+	pub fn position(self: &Arc<Self>) -> (f64, f64, f64) {
+		*self.position.read().unwrap()
+	}
+
+	pub fn velocity(self: &Arc<Self>) -> (f64, f64, f64) {
+		*self.velocity.read().unwrap()
+	}
+
+	pub fn rotation(self: &Arc<Self>) -> (f32, f32) {
+		*self.rotation.read().unwrap()
+	}
+
+	pub fn on_ground(self: &Arc<Self>) -> bool {
+		*self.on_ground.read().unwrap()
+	}
+}
+
+// Сущности одного мира по айди
+#[derive(Default)]
+pub struct EntityStorage {
+	entities: DashMap<i32, Arc<Entity>>,
+}
+
+impl EntityStorage {
+	pub fn insert(&self, entity: Arc<Entity>) {
+		self.entities.insert(entity.id, entity);
+	}
+
+	pub fn remove(&self, id: i32) -> Option<Arc<Entity>> {
+		self.entities.remove(&id).map(|(_, o)| o)
+	}
+
+	pub fn get(&self, id: i32) -> Option<Arc<Entity>> {
+		self.entities.get(&id).map(|o| o.clone())
+	}
+
+	pub fn all(&self) -> Vec<Arc<Entity>> {
+		self.entities.iter().map(|o| o.clone()).collect()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entities.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entities.is_empty()
+	}
+}
+
+/// Любая сущность мира, игрок - тоже сущность
+#[derive(Clone)]
+pub enum WorldEntity {
+	Player(Arc<ClientContext>),
+	Entity(Arc<Entity>),
+}
+
+impl WorldEntity {
+	pub fn id(&self) -> i32 {
+		match self {
+			WorldEntity::Player(client) => client.entity_info().unwrap().entity_id,
+			WorldEntity::Entity(entity) => entity.id,
+		}
+	}
+
+	pub fn uuid(&self) -> Uuid {
+		match self {
+			WorldEntity::Player(client) => client.entity_info().unwrap().uuid,
+			WorldEntity::Entity(entity) => entity.uuid,
+		}
+	}
+
+	/// Ключ в реестре minecraft:entity_type
+	pub fn entity_type(&self) -> &str {
+		match self {
+			WorldEntity::Player(_) => PLAYER,
+			WorldEntity::Entity(entity) => &entity.entity_type,
+		}
+	}
+
+	pub fn position(&self) -> (f64, f64, f64) {
+		match self {
+			WorldEntity::Player(client) => client.entity_info().unwrap().position(),
+			WorldEntity::Entity(entity) => entity.position(),
+		}
+	}
+
+	pub fn velocity(&self) -> (f64, f64, f64) {
+		match self {
+			WorldEntity::Player(client) => client.entity_info().unwrap().velocity(),
+			WorldEntity::Entity(entity) => entity.velocity(),
+		}
+	}
+
+	pub fn rotation(&self) -> (f32, f32) {
+		match self {
+			WorldEntity::Player(client) => client.entity_info().unwrap().rotation(),
+			WorldEntity::Entity(entity) => entity.rotation(),
+		}
+	}
+
+	pub fn on_ground(&self) -> bool {
+		match self {
+			WorldEntity::Player(client) => client.entity_info().unwrap().on_ground(),
+			WorldEntity::Entity(entity) => entity.on_ground(),
+		}
+	}
+}
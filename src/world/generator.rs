@@ -0,0 +1,367 @@
+use std::{fs, path::Path};
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::ServerError;
+
+use super::{
+	WORLD_HEIGHT, WORLD_MIN_Y,
+	block::BlockRegistry,
+	chunk::{Chunk, SECTION_SIZE},
+};
+
+// Генератор мира, вызывается для каждого чанка которого еще нет ни в памяти, ни в регионах
+pub trait WorldGenerator: Sync + Send {
+	fn generate(&self, x: i32, z: i32) -> Chunk;
+}
+
+// Настройки генератора в server.toml
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GeneratorConfig {
+	/// Плоский мир, preset в формате ванильного суперплоского мира
+	Flat {
+		#[serde(default = "default_flat_preset")]
+		preset: String,
+	},
+	/// Пустой мир
+	Void {
+		#[serde(default = "default_biome")]
+		biome: String,
+	},
+	/// Холмы по шуму Перлина. Без seed он выбирается один раз и хранится в папке мира
+	Noise {
+		#[serde(default)]
+		seed: Option<u64>,
+		#[serde(default = "default_base_height")]
+		base_height: i32,
+		#[serde(default = "default_amplitude")]
+		amplitude: f64,
+		#[serde(default = "default_scale")]
+		scale: f64,
+		#[serde(default = "default_biome")]
+		biome: String,
+	},
+}
+
+impl Default for GeneratorConfig {
+	fn default() -> Self {
+		GeneratorConfig::Flat {
+			preset: default_flat_preset(),
+		}
+	}
+}
+
+fn default_flat_preset() -> String {
+	"61*minecraft:stone,2*minecraft:dirt,minecraft:grass_block;minecraft:plains".to_string()
+}
+fn default_biome() -> String {
+	"minecraft:plains".to_string()
+}
+fn default_base_height() -> i32 {
+	64
+}
+fn default_amplitude() -> f64 {
+	24.0
+}
+fn default_scale() -> f64 {
+	0.01
+}
+
+// Айди биома по имени, индекс в реестре биомов
+fn biome_id(biomes: &[String], name: &str) -> Result<u32, ServerError> {
+	biomes
+		.iter()
+		.position(|o| o == name)
+		.map(|o| o as u32)
+		.ok_or_else(|| ServerError::Generator(format!("Неизвестный биом {name}")))
+}
+
+fn block_id(blocks: &BlockRegistry, name: &str) -> Result<u32, ServerError> {
+	blocks
+		.get_default_id(name)
+		.ok_or_else(|| ServerError::Generator(format!("Неизвестный блок {name}")))
+}
+
+// Файл с сидом в папке мира, без него мир после перезапуска генерировался бы заново
+const SEED_FILE: &str = "seed.txt";
+
+// Сид из конфига, иначе сохраненный в мире, иначе новый случайный, который сразу сохраняется
+fn world_seed(world_path: &Path, seed: Option<u64>) -> Result<u64, ServerError> {
+	if let Some(seed) = seed {
+		return Ok(seed);
+	}
+
+	let path = world_path.join(SEED_FILE);
+	if path.exists() {
+		let content = fs::read_to_string(&path)?;
+		return content.trim().parse().map_err(|_| {
+			ServerError::Generator(format!("Неверный сид в {}: {content}", path.display()))
+		});
+	}
+
+	let seed: u64 = rand::thread_rng().r#gen();
+	fs::create_dir_all(world_path)?;
+	fs::write(&path, seed.to_string())?;
+	Ok(seed)
+}
+
+/// Создает генератор по настройкам из конфига, world_path - папка мира для сида
+pub fn create_generator(
+	config: &GeneratorConfig,
+	world_path: &Path,
+	blocks: &BlockRegistry,
+	biomes: &[String],
+) -> Result<Box<dyn WorldGenerator>, ServerError> {
+	Ok(match config {
+		GeneratorConfig::Flat { preset } => {
+			Box::new(FlatGenerator::from_preset(preset, blocks, biomes)?)
+		}
+		GeneratorConfig::Void { biome } => Box::new(VoidGenerator::new(biome_id(biomes, biome)?)),
+		GeneratorConfig::Noise {
+			seed,
+			base_height,
+			amplitude,
+			scale,
+			biome,
+		} => Box::new(NoiseGenerator {
+			noise: PerlinNoise::new(world_seed(world_path, *seed)?),
+			base_height: *base_height,
+			amplitude: *amplitude,
+			scale: *scale,
+			biome: biome_id(biomes, biome)?,
+			stone: block_id(blocks, "minecraft:stone")?,
+			dirt: block_id(blocks, "minecraft:dirt")?,
+			grass: block_id(blocks, "minecraft:grass_block")?,
+		}),
+	})
+}
+
+// Пустой мир, один воздух
+pub struct VoidGenerator {
+	biome: u32,
+}
+
+impl VoidGenerator {
+	pub fn new(biome: u32) -> VoidGenerator {
+		VoidGenerator { biome }
+	}
+}
+
+impl WorldGenerator for VoidGenerator {
+	fn generate(&self, x: i32, z: i32) -> Chunk {
+		Chunk::new(x, z, WORLD_MIN_Y, WORLD_HEIGHT, self.biome)
+	}
+}
+
+// Плоский мир из слоев, снизу вверх начиная с минимальной высоты
+pub struct FlatGenerator {
+	layers: Vec<(u32, usize)>, // Блок и толщина слоя
+	biome: u32,
+}
+
+impl FlatGenerator {
+	pub fn new(layers: Vec<(u32, usize)>, biome: u32) -> FlatGenerator {
+		FlatGenerator { layers, biome }
+	}
+
+	/// Парсит строку суперплоского мира, например
+	/// `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains`
+	///
+	/// Биом можно не указывать, тогда будут равнины. Все после биома игнорируется
+	pub fn from_preset(
+		preset: &str,
+		blocks: &BlockRegistry,
+		biomes: &[String],
+	) -> Result<FlatGenerator, ServerError> {
+		let mut parts = preset.split(';');
+
+		let mut layers = Vec::new();
+
+		for layer in parts.next().unwrap_or_default().split(',') {
+			let layer = layer.trim();
+			if layer.is_empty() {
+				continue;
+			}
+
+			let (count, name) = match layer.split_once('*') {
+				Some((count, name)) => (
+					count
+						.trim()
+						.parse::<usize>()
+						.map_err(|_| ServerError::Generator(format!("Неверный слой {layer}")))?,
+					name.trim(),
+				),
+				None => (1, layer),
+			};
+
+			layers.push((block_id(blocks, name)?, count));
+		}
+
+		let biome = parts
+			.next()
+			.map(|o| o.trim())
+			.filter(|o| !o.is_empty())
+			.unwrap_or("minecraft:plains");
+
+		Ok(FlatGenerator::new(layers, biome_id(biomes, biome)?))
+	}
+}
+
+impl WorldGenerator for FlatGenerator {
+	fn generate(&self, x: i32, z: i32) -> Chunk {
+		let mut chunk = Chunk::new(x, z, WORLD_MIN_Y, WORLD_HEIGHT, self.biome);
+
+		let mut y = WORLD_MIN_Y;
+		for (block, count) in &self.layers {
+			chunk.fill_layers(y, y + *count as i32 - 1, *block);
+			y += *count as i32;
+		}
+
+		chunk
+	}
+}
+
+// Классический шум Перлина с таблицей перестановок из сида
+pub struct PerlinNoise {
+	permutation: [u8; 512],
+}
+
+impl PerlinNoise {
+	pub fn new(seed: u64) -> PerlinNoise {
+		let mut table = (0..=255u8).collect::<Vec<u8>>();
+		table.shuffle(&mut StdRng::seed_from_u64(seed));
+
+		let mut permutation = [0; 512];
+		for i in 0..512 {
+			permutation[i] = table[i % 256];
+		}
+
+		PerlinNoise { permutation }
+	}
+
+	fn fade(t: f64) -> f64 {
+		t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+	}
+
+	fn lerp(t: f64, a: f64, b: f64) -> f64 {
+		a + t * (b - a)
+	}
+
+	fn grad(hash: u8, x: f64, y: f64) -> f64 {
+		match hash & 7 {
+			0 => x + y,
+			1 => -x + y,
+			2 => x - y,
+			3 => -x - y,
+			4 => x,
+			5 => -x,
+			6 => y,
+			_ => -y,
+		}
+	}
+
+	/// Значение шума в точке, примерно от -1 до 1
+	pub fn noise(&self, x: f64, y: f64) -> f64 {
+		let p = &self.permutation;
+
+		let xi = (x.floor() as i64 & 255) as usize;
+		let yi = (y.floor() as i64 & 255) as usize;
+		let xf = x - x.floor();
+		let yf = y - y.floor();
+
+		let u = Self::fade(xf);
+		let v = Self::fade(yf);
+
+		let aa = p[p[xi] as usize + yi];
+		let ab = p[p[xi] as usize + yi + 1];
+		let ba = p[p[xi + 1] as usize + yi];
+		let bb = p[p[xi + 1] as usize + yi + 1];
+
+		Self::lerp(
+			v,
+			Self::lerp(u, Self::grad(aa, xf, yf), Self::grad(ba, xf - 1.0, yf)),
+			Self::lerp(
+				u,
+				Self::grad(ab, xf, yf - 1.0),
+				Self::grad(bb, xf - 1.0, yf - 1.0),
+			),
+		)
+	}
+
+	/// Несколько октав шума, каждая в два раза мельче и слабее
+	pub fn fractal(&self, x: f64, y: f64, octaves: usize) -> f64 {
+		let mut total = 0.0;
+		let mut frequency = 1.0;
+		let mut amplitude = 1.0;
+		let mut max = 0.0;
+
+		for _ in 0..octaves {
+			total += self.noise(x * frequency, y * frequency) * amplitude;
+			max += amplitude;
+			frequency *= 2.0;
+			amplitude *= 0.5;
+		}
+
+		total / max
+	}
+}
+
+// Холмистый мир по карте высот из шума
+pub struct NoiseGenerator {
+	noise: PerlinNoise,
+	base_height: i32,
+	amplitude: f64,
+	scale: f64,
+	biome: u32,
+	stone: u32,
+	dirt: u32,
+	grass: u32,
+}
+
+impl NoiseGenerator {
+	/// Высота поверхности в мировых координатах
+	pub fn height(&self, x: i32, z: i32) -> i32 {
+		let value = self
+			.noise
+			.fractal(x as f64 * self.scale, z as f64 * self.scale, 4);
+		let height = self.base_height + (value * self.amplitude) as i32;
+		height.clamp(WORLD_MIN_Y, WORLD_MIN_Y + WORLD_HEIGHT as i32 - 1)
+	}
+}
+
+impl WorldGenerator for NoiseGenerator {
+	fn generate(&self, chunk_x: i32, chunk_z: i32) -> Chunk {
+		let mut chunk = Chunk::new(chunk_x, chunk_z, WORLD_MIN_Y, WORLD_HEIGHT, self.biome);
+
+		// Все что ниже самого низкого столба заполняем целыми секциями
+		let heights = (0..SECTION_SIZE * SECTION_SIZE)
+			.map(|i| {
+				self.height(
+					chunk_x * 16 + (i % SECTION_SIZE) as i32,
+					chunk_z * 16 + (i / SECTION_SIZE) as i32,
+				)
+			})
+			.collect::<Vec<i32>>();
+
+		let lowest = heights.iter().copied().min().unwrap_or(WORLD_MIN_Y);
+		chunk.fill_layers(WORLD_MIN_Y, lowest - 4, self.stone);
+
+		for (i, height) in heights.into_iter().enumerate() {
+			let (x, z) = (i % SECTION_SIZE, i / SECTION_SIZE);
+			for y in (lowest - 3).max(WORLD_MIN_Y)..=height {
+				let block = if y == height {
+					self.grass
+				} else if y >= height - 3 {
+					self.dirt
+				} else {
+					self.stone
+				};
+				chunk.set_block(x, y, z, block);
+			}
+		}
+
+		chunk
+	}
+}
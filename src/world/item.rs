@@ -0,0 +1,155 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::ServerError;
+
+// Формат отчета registries.json из ванильного генератора данных
+#[derive(Deserialize)]
+struct ReportRegistry {
+	entries: HashMap<String, ReportEntry>,
+}
+
+#[derive(Deserialize)]
+struct ReportEntry {
+	protocol_id: u32,
+}
+
+// Формат отчета items.json, компоненты предметов по умолчанию
+#[derive(Deserialize)]
+struct ItemReport {
+	components: HashMap<String, Value>,
+}
+
+/// Размер стака если не знаем предмет
+pub const DEFAULT_MAX_STACK_SIZE: i32 = 64;
+
+// Слоты экипировки как в компоненте minecraft:equippable
+const EQUIPMENT_SLOTS: [&str; 8] = [
+	"mainhand", "feet", "legs", "chest", "head", "offhand", "body", "saddle",
+];
+
+// Реестр предметов, переводит айди предмета в его имя и обратно
+//
+// Имя блочного предмета совпадает с именем блока, так мы узнаем что ставить
+#[derive(Debug, Clone, Default)]
+pub struct ItemRegistry {
+	names: HashMap<u32, String>,
+	ids: HashMap<String, u32>,
+	max_stack_sizes: HashMap<u32, i32>,
+	equipment_slots: HashMap<u32, u8>,
+}
+
+impl ItemRegistry {
+	/// Загружает minecraft:item из ванильного отчета registries.json
+	pub fn load_report(path: &Path) -> Result<ItemRegistry, ServerError> {
+		let content = fs::read_to_string(path)?;
+		let mut report: HashMap<String, ReportRegistry> =
+			serde_json::from_str(&content).map_err(|e| ServerError::Io(e.into()))?;
+
+		let mut registry = ItemRegistry::default();
+
+		if let Some(items) = report.remove("minecraft:item") {
+			for (name, entry) in items.entries {
+				registry.register(&name, entry.protocol_id);
+			}
+		}
+
+		Ok(registry)
+	}
+
+	/// Встроенный реестр с самыми базовыми предметами, на случай если отчета нет
+	///
+	/// Айди для 1.21.5
+	pub fn builtin() -> ItemRegistry {
+		let mut registry = ItemRegistry::default();
+
+		for (id, name) in [
+			"minecraft:air",
+			"minecraft:stone",
+			"minecraft:granite",
+			"minecraft:polished_granite",
+			"minecraft:diorite",
+			"minecraft:polished_diorite",
+			"minecraft:andesite",
+			"minecraft:polished_andesite",
+		]
+		.into_iter()
+		.enumerate()
+		{
+			registry.register(name, id as u32);
+		}
+
+		registry
+	}
+
+	/// Берет размеры стаков и слоты экипировки из ванильного отчета items.json
+	///
+	/// Без него все предметы стакаются по 64 и надеваются в любой слот брони
+	pub fn load_components_report(&mut self, path: &Path) -> Result<(), ServerError> {
+		let content = fs::read_to_string(path)?;
+		let report: HashMap<String, ItemReport> =
+			serde_json::from_str(&content).map_err(|e| ServerError::Io(e.into()))?;
+
+		for (name, item) in report {
+			let Some(id) = self.get_id(&name) else {
+				continue;
+			};
+
+			if let Some(size) = item
+				.components
+				.get("minecraft:max_stack_size")
+				.and_then(|o| o.as_i64())
+			{
+				self.max_stack_sizes.insert(id, size as i32);
+			}
+
+			if let Some(slot) = item
+				.components
+				.get("minecraft:equippable")
+				.and_then(|o| o.get("slot"))
+				.and_then(|o| o.as_str())
+				.and_then(|o| EQUIPMENT_SLOTS.iter().position(|s| *s == o))
+			{
+				self.equipment_slots.insert(id, slot as u8);
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn register(&mut self, name: &str, id: u32) {
+		self.names.insert(id, name.to_string());
+		self.ids.insert(name.to_string(), id);
+	}
+
+	pub fn get_name(&self, id: u32) -> Option<&str> {
+		self.names.get(&id).map(|o| o.as_str())
+	}
+
+	pub fn get_id(&self, name: &str) -> Option<u32> {
+		self.ids.get(name).copied()
+	}
+
+	/// Размер стака предмета по умолчанию, без учета компонентов в слоте
+	pub fn max_stack_size(&self, id: u32) -> i32 {
+		self
+			.max_stack_sizes
+			.get(&id)
+			.copied()
+			.unwrap_or(DEFAULT_MAX_STACK_SIZE)
+	}
+
+	/// Загружен ли отчет items.json, без него про компоненты предметов мы ничего не знаем
+	pub fn has_components(&self) -> bool {
+		!self.max_stack_sizes.is_empty()
+	}
+
+	/// Слот экипировки по умолчанию: 1 - ноги, 2 - штаны, 3 - нагрудник, 4 - голова, ...
+	///
+	/// None если предмет не надевается или мы про него не знаем
+	pub fn equipment_slot(&self, id: u32) -> Option<u8> {
+		self.equipment_slots.get(&id).copied()
+	}
+}
@@ -0,0 +1,22 @@
+// Мир: чанки, секции, палитры
+// Блоки и биомы хранятся как айди из реестров, никаких имен
+
+pub mod anvil;
+pub mod block;
+pub mod chunk;
+pub mod entity;
+pub mod generator;
+pub mod item;
+pub mod playerdata;
+
+// Высота мира как в дименшене minecraft:overworld
+pub const WORLD_MIN_Y: i32 = -64;
+pub const WORLD_HEIGHT: usize = 384;
+
+/// Переводит мировые координаты блока в координаты чанка и локальные координаты внутри него
+pub fn block_to_chunk(x: i32, z: i32) -> ((i32, i32), (usize, usize)) {
+	(
+		(x.div_euclid(16), z.div_euclid(16)),
+		(x.rem_euclid(16) as usize, z.rem_euclid(16) as usize),
+	)
+}
@@ -0,0 +1,83 @@
+// Данные игроков в папке мира: playerdata/<uuid>.dat
+// https://minecraft.wiki/w/Player.dat_format
+//
+// Файл - это сжатый gzip'ом именованный NBT, как у ваниллы
+
+use std::{
+	fs,
+	io::{ErrorKind, Read, Write},
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+use craftflow_nbt::DynNBT;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use uuid::Uuid;
+
+use crate::ServerError;
+
+// Хранилище файлов игроков одного мира
+pub struct PlayerDataStorage {
+	path: PathBuf, // Папка playerdata внутри папки мира
+	lock: Mutex<()>,
+}
+
+impl PlayerDataStorage {
+	pub fn new(world_path: &Path) -> PlayerDataStorage {
+		PlayerDataStorage {
+			path: world_path.join("playerdata"),
+			lock: Mutex::new(()),
+		}
+	}
+
+	fn data_path(&self, uuid: Uuid, extension: &str) -> PathBuf {
+		self.path.join(format!("{}.{extension}", uuid.hyphenated()))
+	}
+
+	/// Читает сырой NBT игрока, None если игрок тут еще не был
+	pub fn read_nbt(&self, uuid: Uuid) -> Result<Option<DynNBT>, ServerError> {
+		let _lock = self.lock.lock().unwrap();
+
+		let data = match fs::read(self.data_path(uuid, "dat")) {
+			Ok(data) => data,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e.into()),
+		};
+
+		let mut raw = Vec::new();
+		GzDecoder::new(data.as_slice()).read_to_end(&mut raw)?;
+
+		let (_, (_, nbt)) =
+			craftflow_nbt::from_slice_named::<DynNBT>(&raw).map_err(|_| ServerError::DeNbt)?;
+
+		Ok(Some(nbt))
+	}
+
+	/// Записывает сырой NBT игрока
+	///
+	/// Как и ванилла, пишем во временный файл, а прошлую версию оставляем в .dat_old,
+	/// чтобы падение посреди записи не съело данные
+	pub fn write_nbt(&self, uuid: Uuid, nbt: &DynNBT) -> Result<(), ServerError> {
+		let mut raw = Vec::new();
+		craftflow_nbt::to_writer_named(&mut raw, "", nbt).map_err(|_| ServerError::SerNbt)?;
+
+		let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+		encoder.write_all(&raw)?;
+		let compressed = encoder.finish()?;
+
+		let _lock = self.lock.lock().unwrap();
+
+		fs::create_dir_all(&self.path)?;
+
+		let path = self.data_path(uuid, "dat");
+		let temp = self.data_path(uuid, "dat_tmp");
+
+		fs::write(&temp, &compressed)?;
+		if path.exists() {
+			fs::rename(&path, self.data_path(uuid, "dat_old"))?;
+		}
+		fs::rename(&temp, &path)?;
+
+		Ok(())
+	}
+}
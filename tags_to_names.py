@@ -0,0 +1,44 @@
+#!/usr/bin/env python
+
+# Переводит айди в тегах встроенных реестров (блоки, предметы и тд) в имена
+# по отчету registries.json из ванильного генератора данных той же версии
+#
+# Использование:
+#
+#   java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports
+#   ./tags_to_names.py generated/reports/registries.json < src/play/data/1.21.5/tags.json > tags.json
+
+import json
+import sys
+
+def main():
+    with open(sys.argv[1]) as file:
+        report = json.load(file)
+    tags = json.load(sys.stdin)
+
+    missing = 0
+
+    for registry, registry_tags in tags.items():
+        names = {
+            entry["protocol_id"]: name
+            for name, entry in report.get(registry, {}).get("entries", {}).items()
+        }
+
+        for values in registry_tags.values():
+            for i, value in enumerate(values):
+                if not isinstance(value, int):
+                    continue
+                if value not in names:
+                    print(f"{registry}: нет имени для айди {value}", file=sys.stderr)
+                    missing += 1
+                    continue
+                values[i] = names[value]
+
+    json.dump(tags, sys.stdout, indent="\t")
+    print()
+
+    if missing:
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,120 @@
+// Set Container Slot и Set Container Content с предметами посложнее: чары, модификаторы
+// атрибутов, custom_data и вложенные контейнеры. Читаем пакет и пишем обратно
+//
+// Это не перехваченные у ванильного сервера пакеты, байты собраны вручную по описанию
+// протокола 1.21.5 (minecraft.wiki, Java Edition protocol и Slot Data). Айди чар из
+// data/1.21.5/registries.json, айди предметов и атрибутов тут ни на что не влияют
+
+use rust_mc_proto::Packet;
+use rust_mc_serv::{
+	data::slot::StructuredComponent,
+	protocol::{packet_id::clientbound::play, packets::play::ClientboundPlayPacket},
+};
+
+fn round_trip(id: u8, bytes: &[u8]) -> ClientboundPlayPacket {
+	let mut packet = Packet::from_bytes(id, bytes);
+	let decoded = ClientboundPlayPacket::decode(&mut packet).expect("пакет не прочитался");
+	assert_eq!(
+		packet.get_ref().position() as usize,
+		bytes.len(),
+		"прочитаны не все байты"
+	);
+	assert_eq!(decoded.to_packet().unwrap().get_bytes(), bytes);
+	decoded
+}
+
+// Алмазный меч (891): острота V и прочность III, +7 урона в главной руке,
+// custom_data = {owner: {name: "alpha"}}
+fn enchanted_sword() -> Vec<u8> {
+	[
+		&[0x01, 0xFB, 0x06, 0x03, 0x00][..],
+		// enchantments (10): sharpness (32) 5, unbreaking (39) 3
+		&[10, 0x02, 32, 0x05, 39, 0x03],
+		// attribute_modifiers (13): attack_damage (2), minecraft:base_attack_damage,
+		// 7.0, add_value (0), mainhand (1)
+		&[13, 0x01, 0x02, 28],
+		b"minecraft:base_attack_damage",
+		&[0x40, 0x1C, 0, 0, 0, 0, 0, 0, 0x00, 0x01],
+		// custom_data (0): безымянный компаунд как в сетевом NBT
+		&[0, 0x0A, 0x0A, 0x00, 0x05],
+		b"owner",
+		&[0x08, 0x00, 0x04],
+		b"name",
+		&[0x00, 0x05],
+		b"alpha",
+		&[0x00, 0x00],
+	]
+	.concat()
+}
+
+#[test]
+fn set_container_slot() {
+	// Окно 0 (инвентарь), state id 7, слот 36 - первый слот хотбара
+	let bytes = [&[0x00, 0x07, 0x00, 36][..], &enchanted_sword()].concat();
+
+	let ClientboundPlayPacket::SetContainerSlot { slot, item, .. } =
+		round_trip(play::SET_CONTAINER_SLOT, &bytes)
+	else {
+		panic!("ожидался Set Container Slot");
+	};
+	assert_eq!(slot, 36);
+
+	let item = item.unwrap();
+	assert_eq!((item.id, item.amount), (891, 1));
+	assert!(matches!(
+		&item.components[0],
+		StructuredComponent::Enchantments(o) if *o == [(32, 5), (39, 3)]
+	));
+	match &item.components[1] {
+		StructuredComponent::AttributeModifiers(modifiers) => {
+			assert_eq!(modifiers[0].modifier_id, "minecraft:base_attack_damage");
+			assert_eq!(modifiers[0].value, 7.0);
+		}
+		_ => panic!("ожидался attribute_modifiers"),
+	}
+	assert!(matches!(
+		item.components[2],
+		StructuredComponent::CustomData(_)
+	));
+}
+
+#[test]
+fn set_container_content() {
+	// Окно 1, state id 5, три слота и пустой предмет на курсоре
+	let bytes = [
+		&[0x01, 0x05, 0x03][..],
+		// Пустой слот
+		&[0x00],
+		// Шалкер (0x3E5) с container (66): меч, пустой слот, бандл (41) с 16 камнями
+		&[0x01, 0xE5, 0x07, 0x01, 0x00, 66, 0x03],
+		&enchanted_sword(),
+		&[0x00],
+		&[0x01, 0x01, 0x01, 0x00, 41, 0x01, 0x10, 0x01, 0x00, 0x00],
+		// 64 камня
+		&[0x40, 0x01, 0x00, 0x00],
+		// Курсор
+		&[0x00],
+	]
+	.concat();
+
+	let ClientboundPlayPacket::SetContainerContent {
+		slots,
+		carried_item,
+		..
+	} = round_trip(play::SET_CONTAINER_CONTENT, &bytes)
+	else {
+		panic!("ожидался Set Container Content");
+	};
+	assert_eq!(slots.len(), 3);
+	assert!(slots[0].is_none() && carried_item.is_none());
+
+	match &slots[1].as_ref().unwrap().components[0] {
+		StructuredComponent::Container(items) => {
+			assert_eq!(items.len(), 3);
+			assert_eq!(items[0].as_ref().unwrap().components.len(), 3);
+			assert!(items[1].is_none());
+		}
+		_ => panic!("ожидался container"),
+	}
+	assert_eq!(slots[2].as_ref().unwrap().amount, 64);
+}
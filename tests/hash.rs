@@ -0,0 +1,103 @@
+// Хеши компонентов для Hashed Slot против заранее посчитанных значений
+//
+// Ожидаемые числа посчитаны отдельно от сервера: CRC32C (Castagnoli) от байтов,
+// которые пишет ванильный HashOps. Теги 1 байтом, числа и длины в little endian,
+// строки - длина и символы в UTF-16, у списка хеши элементов между 4 и 5, у мапы
+// пары хешей ключа и значения, отсортированные без знака, между 2 и 3
+
+use std::collections::HashMap;
+
+use craftflow_nbt::DynNBT;
+use rust_mc_serv::{
+	data::{
+		component::TextComponent,
+		hash::{ComponentHasher, HashValue},
+		slot::{HashedSlot, Slot, StructuredComponent},
+	},
+	play::registry::Registries,
+	protocol::version::ProtocolVersion,
+};
+
+fn hash(component: StructuredComponent) -> i32 {
+	let registries = Registries::vanilla(ProtocolVersion::V1_21_5).unwrap();
+	ComponentHasher::new(&registries).hash(&component)
+}
+
+#[test]
+fn crc32c_check_value() {
+	// Проверочное значение CRC-32C из RFC 3720
+	assert_eq!(crc32c::crc32c(b"123456789"), 0xE3069283);
+}
+
+#[test]
+fn empty_collections() {
+	assert_eq!(HashValue::List(Vec::new()).hash() as i32, -1978007022);
+	assert_eq!(HashValue::Map(Vec::new()).hash() as i32, -982207288);
+}
+
+#[test]
+fn int_component() {
+	assert_eq!(hash(StructuredComponent::MaxStackSize(16)), 1769065625);
+	assert_eq!(hash(StructuredComponent::Damage(5)), 645064431);
+}
+
+#[test]
+fn string_component() {
+	assert_eq!(
+		hash(StructuredComponent::ItemModel(
+			"minecraft:stick".to_string()
+		)),
+		-1153337500
+	);
+}
+
+#[test]
+fn list_component() {
+	// Текст без стиля кодек сворачивает в строку
+	let lore = vec![
+		TextComponent::new("Hi".to_string()),
+		TextComponent::new("there".to_string()),
+	];
+	assert_eq!(hash(StructuredComponent::Lore(lore)), -955964613);
+}
+
+#[test]
+fn map_component() {
+	// custom_data = {a: 1b, name: "x"}, порядок полей на хеш не влияет
+	let nbt = DynNBT::Compound(HashMap::from([
+		("a".to_string(), DynNBT::Byte(1)),
+		("name".to_string(), DynNBT::String("x".to_string())),
+	]));
+	assert_eq!(hash(StructuredComponent::CustomData(nbt)), 665091205);
+}
+
+#[test]
+fn registry_map_component() {
+	// enchantments = {"minecraft:sharpness": 5}, айди берется из реестра
+	let registries = Registries::vanilla(ProtocolVersion::V1_21_5).unwrap();
+	let sharpness = registries
+		.entry_id("minecraft:enchantment", "minecraft:sharpness")
+		.unwrap();
+	let component = StructuredComponent::Enchantments(vec![(sharpness as u64, 5)]);
+	assert_eq!(
+		ComponentHasher::new(&registries).hash(&component),
+		1722377199
+	);
+}
+
+#[test]
+fn hashed_slot() {
+	let registries = Registries::vanilla(ProtocolVersion::V1_21_5).unwrap();
+	let mut slot = Slot::new(1, 3);
+	slot.components = vec![
+		StructuredComponent::MaxStackSize(16),
+		StructuredComponent::ItemModel("minecraft:stick".to_string()),
+	];
+	slot.removed_components = vec![3];
+
+	let hashed = HashedSlot::from_slot(&slot, &registries);
+	assert_eq!((hashed.id, hashed.amount), (1, 3));
+	assert_eq!(hashed.components, vec![(1, 1769065625), (7, -1153337500)]);
+	assert_eq!(hashed.removed_components, vec![3]);
+	assert!(hashed.matches(&slot, &registries));
+}
@@ -0,0 +1,159 @@
+// Слоты в формате 1.21.5: читаем байты и пишем обратно, должно получиться то же самое
+//
+// Байты собраны по описанию протокола (minecraft.wiki, Slot Data и Data components)
+// в том порядке, в каком их пишет ванильный ItemStack.OPTIONAL_STREAM_CODEC
+
+use rust_mc_proto::Packet;
+use rust_mc_serv::{
+	ServerError,
+	data::{
+		MAX_NBT_DEPTH,
+		slot::{MAX_NESTED_ITEMS, MAX_SLOT_DEPTH, ReadWriteSlot, Slot, StructuredComponent},
+	},
+};
+
+fn read(bytes: &[u8]) -> Result<Option<Slot>, ServerError> {
+	let mut packet = Packet::from_bytes(0, bytes);
+	let slot = packet.read_slot()?;
+	assert_eq!(
+		packet.get_ref().position() as usize,
+		bytes.len(),
+		"прочитаны не все байты"
+	);
+	Ok(slot)
+}
+
+fn round_trip(bytes: &[u8]) -> Option<Slot> {
+	let slot = read(bytes).expect("слот не прочитался");
+	let mut packet = Packet::empty(0);
+	packet.write_slot(slot.clone()).unwrap();
+	assert_eq!(packet.get_bytes(), bytes);
+	slot
+}
+
+// Бандл (bundle_contents, 41) с одним предметом внутри, вложенный depth раз
+fn nested_bundles(depth: usize) -> Vec<u8> {
+	let mut bytes = vec![0x01, 0x01, 0x00, 0x00];
+	for _ in 0..depth {
+		let mut outer = vec![0x01, 0x01, 0x01, 0x00, 41, 0x01];
+		outer.extend(bytes);
+		bytes = outer;
+	}
+	bytes
+}
+
+#[test]
+fn empty_slot() {
+	assert!(round_trip(&[0x00]).is_none());
+}
+
+#[test]
+fn plain_stack() {
+	// 64 камня (айди 1), без компонентов
+	let slot = round_trip(&[0x40, 0x01, 0x00, 0x00]).unwrap();
+	assert_eq!((slot.id, slot.amount), (1, 64));
+	assert!(slot.components.is_empty());
+}
+
+#[test]
+fn simple_components() {
+	// Предмет 926: damage = 5, unbreakable, убран компонент max_stack_size
+	let slot = round_trip(&[0x01, 0x9E, 0x07, 0x02, 0x01, 0x03, 0x05, 0x04, 0x01]).unwrap();
+	assert_eq!(slot.id, 926);
+	assert!(matches!(slot.components[0], StructuredComponent::Damage(5)));
+	assert!(matches!(
+		slot.components[1],
+		StructuredComponent::Unbreakable
+	));
+	assert_eq!(slot.removed_components, vec![1]);
+}
+
+#[test]
+fn custom_data() {
+	// custom_data = {a: 1b}, безымянный компаунд как в сетевом NBT
+	round_trip(&[
+		0x01, 0x01, 0x01, 0x00, 0x00, 0x0A, 0x01, 0x00, 0x01, b'a', 0x01, 0x00,
+	]);
+}
+
+#[test]
+fn bundle_contents() {
+	// Бандл с 16 камнями и 3 палками
+	let slot = round_trip(&[
+		0x01, 0x01, 0x01, 0x00, 41, 0x02, 0x10, 0x01, 0x00, 0x00, 0x03, 0x01, 0x00, 0x00,
+	])
+	.unwrap();
+	match &slot.components[0] {
+		StructuredComponent::BundleContents(items) => assert_eq!(items.len(), 2),
+		_ => panic!("ожидался bundle_contents"),
+	}
+}
+
+#[test]
+fn container_with_empty_slots() {
+	// container (66): пустой слот, затем бандл с камнем
+	round_trip(&[
+		0x01, 0x01, 0x01, 0x00, 66, 0x02, 0x00, 0x01, 0x01, 0x01, 0x00, 41, 0x01, 0x01, 0x01, 0x00,
+		0x00,
+	]);
+}
+
+#[test]
+fn nesting_up_to_limit() {
+	round_trip(&nested_bundles(MAX_SLOT_DEPTH));
+}
+
+#[test]
+fn nesting_over_limit() {
+	assert!(matches!(
+		read(&nested_bundles(MAX_SLOT_DEPTH + 1)),
+		Err(ServerError::WrongPacket)
+	));
+}
+
+#[test]
+fn deep_nesting_does_not_overflow() {
+	// Около 12 КБ вложенных бандлов, раньше это клало сервер переполнением стека
+	let bytes = nested_bundles(2000);
+	let mut packet = Packet::from_bytes(0, &bytes);
+	assert!(matches!(packet.read_slot(), Err(ServerError::WrongPacket)));
+}
+
+#[test]
+fn too_many_nested_items() {
+	let mut bytes = vec![0x01, 0x01, 0x01, 0x00, 41];
+	let length = MAX_NESTED_ITEMS + 1;
+	bytes.extend([(length as u8 & 0x7F) | 0x80, (length >> 7) as u8]);
+	for _ in 0..length {
+		bytes.extend([0x01, 0x01, 0x00, 0x00]);
+	}
+	let mut packet = Packet::from_bytes(0, &bytes);
+	assert!(matches!(packet.read_slot(), Err(ServerError::WrongPacket)));
+}
+
+#[test]
+fn too_many_components() {
+	let mut packet = Packet::from_bytes(0, &[0x01, 0x01, 0x7F, 0x7F]);
+	assert!(matches!(packet.read_slot(), Err(ServerError::WrongPacket)));
+}
+
+// custom_data из depth вложенных друг в друга списков
+fn nested_nbt_lists(depth: usize) -> Vec<u8> {
+	let mut bytes = vec![0x01, 0x01, 0x01, 0x00, 0x00, 0x09];
+	for _ in 1..depth {
+		bytes.extend([0x09, 0x00, 0x00, 0x00, 0x01]);
+	}
+	bytes.extend([0x00, 0x00, 0x00, 0x00, 0x00]);
+	bytes
+}
+
+#[test]
+fn nbt_up_to_limit() {
+	round_trip(&nested_nbt_lists(MAX_NBT_DEPTH));
+}
+
+#[test]
+fn deep_nbt_does_not_overflow() {
+	let mut packet = Packet::from_bytes(0, &nested_nbt_lists(100_000));
+	assert!(matches!(packet.read_slot(), Err(ServerError::DeNbt)));
+}